@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Threads each background load runs.
+const BACKGROUND_THREADS: u32 = 2;
+
+/// Bytes in each write of the fsync-storm load.
+const FSYNC_WRITE_BYTES: usize = 4096;
+
+/// The fsync-storm load starts its files over at this size.
+const FSYNC_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Bytes in each write of the writeback load.
+const WRITEBACK_WRITE_BYTES: usize = 1024 * 1024;
+
+/// The writeback load wraps around to the start of its file at this size.
+const WRITEBACK_FILE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Canned I/O that runs alongside the measured phases, for
+/// `--background-load`, so reflinks are timed while the journal, commit, or
+/// writeback machinery they share is already busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BackgroundLoad {
+    /// Small appends, each followed by fsync, like a busy database log
+    FsyncStorm,
+    /// Creating, fsyncing, and unlinking empty files, keeping the metadata
+    /// journal busy
+    MetadataStorm,
+    /// Streaming 1 MB buffered writes without syncing, keeping writeback busy
+    Writeback,
+}
+
+impl Display for BackgroundLoad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackgroundLoad::FsyncStorm => write!(f, "fsync-storm"),
+            BackgroundLoad::MetadataStorm => write!(f, "metadata-storm"),
+            BackgroundLoad::Writeback => write!(f, "writeback"),
+        }
+    }
+}
+
+/// How much background work was done while the phases ran.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundLoadResult {
+    pub load: String,
+    pub operations: u64,
+    pub operations_per_sec: f64,
+}
+
+/// A running background load. Its threads stop when it is
+/// [finished](Self::finish) or dropped, so an error partway through a run
+/// doesn't leave them holding the filesystem busy.
+pub struct BackgroundTask {
+    load: BackgroundLoad,
+    dir: PathBuf,
+    stop: Arc<AtomicBool>,
+    operations: Arc<AtomicU64>,
+    started: Instant,
+    threads: Vec<JoinHandle<Result<()>>>,
+}
+
+impl BackgroundLoad {
+    /// Starts the load's threads in a new directory `dir`.
+    pub fn start(self, dir: &Path) -> Result<BackgroundTask> {
+        std::fs::create_dir_all(dir).context("Failed to create background load directory")?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let operations = Arc::new(AtomicU64::new(0));
+        let threads = (0..BACKGROUND_THREADS)
+            .map(|thread| {
+                let (dir, stop, operations) = (
+                    dir.to_path_buf(),
+                    Arc::clone(&stop),
+                    Arc::clone(&operations),
+                );
+                std::thread::spawn(move || self.run(&dir, thread, &stop, &operations))
+            })
+            .collect();
+        Ok(BackgroundTask {
+            load: self,
+            dir: dir.to_path_buf(),
+            stop,
+            operations,
+            started: Instant::now(),
+            threads,
+        })
+    }
+
+    fn run(self, dir: &Path, thread: u32, stop: &AtomicBool, operations: &AtomicU64) -> Result<()> {
+        match self {
+            BackgroundLoad::FsyncStorm => {
+                let file = std::fs::File::create(dir.join(format!("fsync_{}.log", thread)))
+                    .context("Failed to create fsync-storm file")?;
+                let data = vec![0x5A; FSYNC_WRITE_BYTES];
+                let mut offset = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    if offset >= FSYNC_FILE_BYTES {
+                        file.set_len(0)
+                            .context("Failed to truncate fsync-storm file")?;
+                        offset = 0;
+                    }
+                    file.write_all_at(&data, offset)
+                        .context("Failed to write fsync-storm file")?;
+                    file.sync_all().context("Failed to fsync")?;
+                    offset += FSYNC_WRITE_BYTES as u64;
+                    operations.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            BackgroundLoad::MetadataStorm => {
+                let mut i = 0u64;
+                while !stop.load(Ordering::Relaxed) {
+                    let path = dir.join(format!("meta_{}_{}", thread, i % 64));
+                    let file = std::fs::File::create(&path)
+                        .context("Failed to create metadata-storm file")?;
+                    file.sync_all().context("Failed to fsync")?;
+                    drop(file);
+                    std::fs::remove_file(&path).context("Failed to unlink metadata-storm file")?;
+                    i += 1;
+                    operations.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            BackgroundLoad::Writeback => {
+                let file = std::fs::File::create(dir.join(format!("writeback_{}.dat", thread)))
+                    .context("Failed to create writeback file")?;
+                let data = vec![0x5A; WRITEBACK_WRITE_BYTES];
+                let mut offset = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    file.write_all_at(&data, offset)
+                        .context("Failed to write writeback file")?;
+                    offset = (offset + WRITEBACK_WRITE_BYTES as u64) % WRITEBACK_FILE_BYTES;
+                    operations.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BackgroundTask {
+    /// Stops the load, removes its files, and reports how much it did.
+    pub fn finish(mut self) -> Result<BackgroundLoadResult> {
+        let elapsed = self.started.elapsed();
+        self.stop_threads()?;
+        let operations = self.operations.load(Ordering::Relaxed);
+        std::fs::remove_dir_all(&self.dir).context("Failed to remove background load files")?;
+        Ok(BackgroundLoadResult {
+            load: self.load.to_string(),
+            operations,
+            operations_per_sec: operations as f64
+                / elapsed.max(Duration::from_millis(1)).as_secs_f64(),
+        })
+    }
+
+    fn stop_threads(&mut self) -> Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        let mut result = Ok(());
+        for thread in self.threads.drain(..) {
+            match thread.join() {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    result = Err(e.context(format!("{} background load failed", self.load)))
+                }
+                Err(_) => {
+                    result = Err(anyhow::anyhow!(
+                        "{} background load thread panicked",
+                        self.load
+                    ))
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Drop for BackgroundTask {
+    fn drop(&mut self) {
+        let _ = self.stop_threads();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_run_until_finished() {
+        let dir =
+            std::env::temp_dir().join(format!("reflink-bench-background-{}", std::process::id()));
+        for load in [
+            BackgroundLoad::FsyncStorm,
+            BackgroundLoad::MetadataStorm,
+            BackgroundLoad::Writeback,
+        ] {
+            let task = load.start(&dir).unwrap();
+            while task.operations.load(Ordering::Relaxed) == 0 {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            let result = task.finish().unwrap();
+            assert_eq!(result.load, load.to_string());
+            assert!(result.operations > 0);
+            assert!(!dir.exists());
+        }
+    }
+}
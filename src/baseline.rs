@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use crate::compare;
+use crate::results::ComparisonResults;
+
+/// Directory named baselines are stored in:
+/// `$XDG_DATA_HOME/reflink-bench/baselines`, falling back to
+/// `~/.local/share/reflink-bench/baselines`.
+pub fn baseline_dir() -> Result<PathBuf> {
+    let data_home = match std::env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home = std::env::var_os("HOME").context("Neither XDG_DATA_HOME nor HOME is set")?;
+            PathBuf::from(home).join(".local/share")
+        }
+    };
+    Ok(data_home.join("reflink-bench").join("baselines"))
+}
+
+pub fn baseline_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        anyhow::bail!("Invalid baseline name: {:?}", name);
+    }
+    Ok(baseline_dir()?.join(format!("{}.json", name)))
+}
+
+pub fn load(name: &str) -> Result<ComparisonResults> {
+    let path = baseline_path(name)?;
+    if !path.exists() {
+        anyhow::bail!(
+            "Baseline '{}' not found; save one with --save-baseline {}",
+            name,
+            name
+        );
+    }
+    compare::load_results(&path)
+}
@@ -1,62 +1,1371 @@
 use anyhow::{Context, Result};
-use rand::Rng;
+use clap::ValueEnum;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::fs::File;
+use tokio::fs::{self, File};
 use tokio::io::AsyncWriteExt;
-use tokio::sync::Semaphore;
-use tokio::task::JoinSet;
 
+use crate::background::{BackgroundLoad, BackgroundLoadResult};
+use crate::blktrace::BlktraceCapture;
+use crate::cgroup::Cgroup;
+use crate::copy::{self, CopyResult, CrossFsResult};
+use crate::cpu::{CpuSnapshot, CpuUsage};
+use crate::diskstats::{DeviceStats, DiskStats, WriteAmplification};
+use crate::environment::FilesystemEnvironment;
+use crate::external::{ExternalReflinkWrite, ExternalTool, ExternalToolResult};
+use crate::filesystem::{self, FilesystemType};
+use crate::interleave::Turns;
+use crate::ioctl;
+use crate::iosched::IoScheduler;
+use crate::kernel_trace::{KernelFunctionLatency, KernelTracer};
+use crate::perf::{PerfCounters, PerfStat};
+use crate::progress::ProgressBar;
+use crate::results;
+use crate::shutdown;
+use crate::space::{self, ExtentUsage, SpaceReport};
+use crate::stats::{LatencyStats, Summary};
+use crate::stream::EventStream;
+use crate::timeseries::{ThroughputSampler, ThroughputSeries};
+use crate::tui::LiveMonitor;
+use crate::verify::{PhaseTargets, Verifier, VerifyReport};
+use crate::workloads::{self, OperationMix, ReflinkWrite, Workload, WorkloadKind, WorkloadResult};
+
+/// Bytes written after each reflink to trigger copy-on-write.
+pub const DEFAULT_WRITE_SIZE: usize = 4096;
+
+/// Byte every CoW write fills its region with.
+pub const WRITE_PATTERN: u8 = 0xAA;
+
+/// Windows a soak run is divided into for drift, each at least a second.
+const SOAK_WINDOWS: u32 = 20;
+
+/// What a [`BenchmarkRunner`] measures. [`Default`] matches the `run`
+/// subcommand's defaults.
 #[derive(Debug, Clone)]
 pub struct BenchmarkConfig {
     pub file_size_mb: u64,
     pub reflink_count: u32,
+    pub workloads: Vec<WorkloadKind>,
+    pub write_sizes: Vec<u64>,
+    /// Sweep clone latency over sources written in extents of these sizes
+    pub source_extent_sizes: Vec<u64>,
+    pub small_file_count: u32,
+    pub source_count: u32,
+    /// Write each source file as this many separately allocated extents
+    /// instead of one contiguous run
+    pub fragment_source: Option<u32>,
+    pub mix: OperationMix,
+    pub mixed_workers: u32,
+    /// Percentage of the dataset's blocks the backup workload rewrites
+    /// between generations
+    pub backup_change_percent: u32,
+    /// Worker counts for the concurrent scaling benchmark
+    pub thread_counts: Vec<u32>,
+    /// Seeds source data and every randomized choice, so runs with the
+    /// same seed write identical bytes
+    pub seed: u64,
+    pub space_report: bool,
+    /// Add up the extent maps of the sequential phase's and each workload's
+    /// files afterwards, to report shared and exclusive bytes
+    pub extent_usage: bool,
+    pub warmup: u32,
+    pub trim_outliers: bool,
+    pub drop_caches: bool,
+    /// Discard the filesystem's free space between benchmark phases
+    pub fstrim: bool,
+    pub cache_mode: Option<CacheMode>,
+    /// Run reflink + write for this long as a soak phase
+    pub duration: Option<Duration>,
+    /// Issue reflink + write on a fixed schedule at this rate as an
+    /// open-loop phase
+    pub rate: Option<Rate>,
+    /// Also run reflink + write in an fscrypt-encrypted directory, where
+    /// the filesystem supports it
+    pub fscrypt: bool,
+    /// Also clone a sparse source of this apparent size, with data
+    /// scattered through it
+    pub sparse_source_bytes: Option<u64>,
+    /// Also try cloning the source into this directory on another
+    /// filesystem, and time the copies that fall back from it
+    pub cross_fs_dir: Option<PathBuf>,
+    /// Also time copying the source within the filesystem without reflink,
+    /// as a baseline for the clones
+    pub copy_baselines: bool,
+    /// Also run reflink + write with the clone done by each of these tools
+    pub external_tools: Vec<ExternalTool>,
+    /// Enable quota groups first when the filesystem is btrfs
+    pub btrfs_qgroups: bool,
+    /// Put the mount point under an XFS project quota first when the
+    /// filesystem is XFS (mounted with `prjquota`)
+    pub xfs_project_quota: bool,
+    /// I/O scheduler to switch the filesystem's block device to
+    pub io_scheduler: Option<IoScheduler>,
+    /// I/O kept running on the filesystem while the phases are measured
+    pub background_load: Option<BackgroundLoad>,
+    /// What the blocking reflink + write operations run on
+    pub runner: Runner,
+    /// Directory to record a blktrace of each phase into
+    pub blktrace_dir: Option<PathBuf>,
+    pub perf_stat: bool,
+    /// Histogram kernel reflink remap functions with bpftrace
+    pub trace_kernel: bool,
+    /// Read back every clone afterwards and check it against its source
+    pub verify: bool,
+    /// JSON Lines sink for per-operation and per-phase events
+    pub stream: Option<EventStream>,
+    /// Live dashboard to report progress to
+    pub monitor: Option<LiveMonitor>,
+    /// Progress bar to draw each phase's progress and ETA on
+    pub progress: Option<ProgressBar>,
+    /// Alternate phases with the scenario's other filesystems, all mounted
+    /// at once, instead of running each filesystem's phases back to back
+    pub interleave: bool,
+    /// This filesystem's seat when interleaving
+    pub turns: Option<Turns>,
+    /// The limited cgroup the run is in, to throttle this filesystem's
+    /// device with
+    pub cgroup: Option<Arc<Cgroup>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Default for BenchmarkConfig {
+    /// The `run` subcommand's defaults, with seed 0 and no outputs attached.
+    fn default() -> Self {
+        Self {
+            file_size_mb: 100,
+            reflink_count: 1000,
+            workloads: Vec::new(),
+            write_sizes: Vec::new(),
+            source_extent_sizes: Vec::new(),
+            small_file_count: 20000,
+            source_count: 1,
+            fragment_source: None,
+            mix: OperationMix::default(),
+            mixed_workers: 8,
+            backup_change_percent: 5,
+            thread_counts: vec![1, 2, 4, 8, 16, 32, 64, 128],
+            seed: 0,
+            space_report: false,
+            extent_usage: false,
+            warmup: 0,
+            trim_outliers: false,
+            drop_caches: false,
+            fstrim: false,
+            cache_mode: None,
+            duration: None,
+            rate: None,
+            fscrypt: false,
+            sparse_source_bytes: None,
+            cross_fs_dir: None,
+            copy_baselines: false,
+            external_tools: Vec::new(),
+            btrfs_qgroups: false,
+            xfs_project_quota: false,
+            io_scheduler: None,
+            background_load: None,
+            runner: Runner::Tokio,
+            blktrace_dir: None,
+            perf_stat: false,
+            trace_kernel: false,
+            verify: false,
+            stream: None,
+            monitor: None,
+            progress: None,
+            interleave: false,
+            turns: None,
+            cgroup: None,
+        }
+    }
+}
+
+/// A random number generator for one consumer of randomness (a source
+/// file, a worker), derived from the run's seed. Each consumer gets its own
+/// ChaCha stream, so what one draws doesn't shift another's sequence.
+pub fn seeded_rng(seed: u64, stream: &str) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stable_hash(stream));
+    rng
+}
+
+/// Data written at the start of every [`SPARSE_DATA_STRIDE`] of a
+/// `--sparse-source`; the rest is holes.
+pub const SPARSE_DATA_BYTES: u64 = 64 * 1024;
+
+/// Spacing of the data regions in a `--sparse-source`, so a 1 TB source
+/// holds 1024 extents and 64 MB of data.
+pub const SPARSE_DATA_STRIDE: u64 = 1 << 30;
+
+/// Bytes of a file that's too small to hold a fragmented source's extents
+/// apart, appended to between them.
+const FRAGMENT_SPACER_BYTES: usize = 4096;
+
+/// Writes `size` bytes of `rng` data to `path` as `extents` separately
+/// allocated extents: the chunks are written in shuffled order, each
+/// fdatasync'd, with a small write to a spacer file synced in between so the
+/// allocator can't place neighbouring chunks next to each other on disk.
+/// The spacer is deleted afterwards, leaving free space between extents.
+/// Every chunk gets the bytes a sequential write would have, so the data
+/// matches an unfragmented source with the same seed.
+pub fn write_fragmented(path: &Path, size: u64, extents: u32, mut rng: ChaCha8Rng) -> Result<()> {
+    use rand::seq::SliceRandom;
+    use std::os::unix::fs::FileExt;
+
+    let chunk = (size / extents.max(1) as u64)
+        .next_multiple_of(4096)
+        .max(4096);
+    let file = std::fs::File::create(path).context("Failed to create source file")?;
+    let spacer_path = path.with_extension("spacer");
+    let spacer = std::fs::File::create(&spacer_path).context("Failed to create spacer file")?;
+
+    let mut order: Vec<u64> = (0..size).step_by(chunk as usize).collect();
+    order.shuffle(&mut rng);
+    let mut data = vec![0u8; chunk as usize];
+    for (i, offset) in order.into_iter().enumerate() {
+        let len = chunk.min(size - offset) as usize;
+        // ChaCha words are 4 bytes, so seek the stream to this chunk's data
+        rng.set_word_pos(offset as u128 / 4);
+        rng.fill(&mut data[..len]);
+        file.write_all_at(&data[..len], offset)
+            .context("Failed to write to source file")?;
+        file.sync_data().context("Failed to sync source file")?;
+        spacer
+            .write_all_at(
+                &[0u8; FRAGMENT_SPACER_BYTES],
+                (i * FRAGMENT_SPACER_BYTES) as u64,
+            )
+            .context("Failed to write spacer file")?;
+        spacer.sync_data().context("Failed to sync spacer file")?;
+    }
+    file.sync_all().context("Failed to sync source file")?;
+    std::fs::remove_file(&spacer_path).context("Failed to remove spacer file")
+}
+
+/// Parses a byte size with an optional binary suffix (k, m, g), e.g. `64k`.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let lower = s.trim().to_lowercase();
+    let lower = lower.trim_end_matches('b');
+    let (digits, multiplier) = match lower.char_indices().last() {
+        Some((i, 'k')) => (&lower[..i], 1 << 10),
+        Some((i, 'm')) => (&lower[..i], 1 << 20),
+        Some((i, 'g')) => (&lower[..i], 1 << 30),
+        Some((i, 't')) => (&lower[..i], 1 << 40),
+        _ => (lower, 1),
+    };
+
+    let value = digits
+        .parse::<u64>()
+        .with_context(|| format!("Invalid size: {}", s))?;
+    value
+        .checked_mul(multiplier)
+        .with_context(|| format!("Size too large: {}", s))
+}
+
+/// FNV-1a, which unlike std's hasher is stable across Rust releases.
+pub fn stable_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Slowest `--rate` accepted: one operation every ~17 minutes. Slower rates
+/// would schedule operations past what `Duration` and `Instant` can hold.
+pub const MIN_RATE: f64 = 0.001;
+
+/// Operations per second to issue in the open-loop phase, parsed from
+/// `500`, `500/s`, or `500ops/s`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate(pub f64);
+
+impl FromStr for Rate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        let number = trimmed
+            .strip_suffix("ops/s")
+            .or_else(|| trimmed.strip_suffix("/s"))
+            .unwrap_or(trimmed);
+        let rate: f64 = number
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid rate (expected e.g. 500ops/s): {}", s))?;
+        if !rate.is_finite() || rate <= 0.0 {
+            anyhow::bail!("Rate must be positive, got: {}", s);
+        }
+        if rate < MIN_RATE {
+            anyhow::bail!("Rate must be at least {}ops/s, got: {}", MIN_RATE, s);
+        }
+        Ok(Self(rate))
+    }
+}
+
+impl std::fmt::Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}ops/s", self.0)
+    }
+}
+
+/// Worker counts for the concurrent benchmark, parsed from a list such as
+/// `1,4,16,64` or `auto`.
+#[derive(Debug, Clone)]
+pub struct ThreadCounts(pub Vec<u32>);
+
+impl ThreadCounts {
+    /// Powers of two up to twice the CPU count, so scaling is measured just
+    /// past saturation without piling 128 threads onto a small machine.
+    pub fn auto() -> Self {
+        let cpus = std::thread::available_parallelism()
+            .map(|n| n.get() as u32)
+            .unwrap_or(1);
+        let limit = cpus.saturating_mul(2);
+        Self(
+            std::iter::successors(Some(1u32), |&n| n.checked_mul(2))
+                .take_while(|&n| n <= limit)
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for ThreadCounts {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.trim().eq_ignore_ascii_case("auto") {
+            return Ok(Self::auto());
+        }
+
+        let counts = s
+            .split(',')
+            .map(|part| part.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| {
+                format!(
+                    "Invalid thread counts (expected e.g. 1,4,16 or auto): {}",
+                    s
+                )
+            })?;
+        if counts.is_empty() || counts.contains(&0) {
+            anyhow::bail!("Thread counts must be positive, got: {}", s);
+        }
+        Ok(Self(counts))
+    }
+}
+
+/// What runs the blocking reflink + write operations of the sequential
+/// and concurrent phases.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Runner {
+    /// Tokio's blocking pool, via `spawn_blocking`
+    #[default]
+    Tokio,
+    /// A plain `std::thread` per worker, with no async runtime in between
+    Threads,
+}
+
+impl std::fmt::Display for Runner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Runner::Tokio => write!(f, "tokio"),
+            Runner::Threads => write!(f, "threads"),
+        }
+    }
+}
+
+impl Runner {
+    /// Starts `work` on a blocking thread, from the pool or a new one of
+    /// its own.
+    fn start<T, F>(self, work: F) -> Result<WorkerHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        Ok(match self {
+            Runner::Tokio => WorkerHandle::Task(tokio::task::spawn_blocking(work)),
+            Runner::Threads => {
+                // The receiver lets the runtime wait without parking one of
+                // its own threads in join()
+                let (sender, receiver) = tokio::sync::oneshot::channel();
+                std::thread::Builder::new()
+                    .name("reflink-bench-worker".to_string())
+                    .spawn(move || {
+                        let _ = sender.send(work());
+                    })
+                    .context("Failed to start worker thread")?;
+                WorkerHandle::Thread(receiver)
+            }
+        })
+    }
+}
+
+/// Work started by [`Runner::start`].
+enum WorkerHandle<T> {
+    Task(tokio::task::JoinHandle<T>),
+    Thread(tokio::sync::oneshot::Receiver<T>),
+}
+
+impl<T> WorkerHandle<T> {
+    async fn join(self) -> Result<T> {
+        match self {
+            WorkerHandle::Task(task) => task.await.context("Task panicked"),
+            WorkerHandle::Thread(receiver) => receiver.await.context("Worker thread panicked"),
+        }
+    }
+}
+
+/// Which page-cache conditions to measure reflink + write under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CacheMode {
+    /// Source data and extent metadata cached by a prior read and clone
+    Warm,
+    /// Page cache, dentries, and inodes dropped immediately before
+    Cold,
+    /// Measure warm and cold separately
+    Both,
+}
+
+impl CacheMode {
+    fn conditions(self) -> &'static [CacheMode] {
+        match self {
+            CacheMode::Warm => &[CacheMode::Warm],
+            CacheMode::Cold => &[CacheMode::Cold],
+            CacheMode::Both => &[CacheMode::Warm, CacheMode::Cold],
+        }
+    }
+}
+
+impl std::fmt::Display for CacheMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheMode::Warm => write!(f, "warm"),
+            CacheMode::Cold => write!(f, "cold"),
+            CacheMode::Both => write!(f, "both"),
+        }
+    }
+}
+
+/// Everything measured on one filesystem by [`BenchmarkRunner::run_benchmark`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BenchmarkResult {
     pub filesystem: String,
+    /// Device and mount options the benchmark ran on
+    pub environment: FilesystemEnvironment,
     pub file_size_mb: u64,
     pub reflink_count: u32,
+    pub source_count: u32,
+    /// Extents the (first) source file is mapped to, where FIEMAP works
+    pub source_extents: Option<u32>,
+    #[serde(default)]
+    pub runner: Runner,
+    pub warmup: u32,
     pub total_duration: Duration,
     pub avg_reflink_time: Duration,
     pub concurrent_results: ConcurrentBenchmarkResult,
     pub throughput_mb_per_sec: f64,
     pub operations_per_sec: f64,
+    pub reflink_latency: LatencyStats,
+    pub write_latency: LatencyStats,
+    /// Latency with 3×IQR outliers removed, when requested
+    pub reflink_latency_trimmed: Option<LatencyStats>,
+    pub write_latency_trimmed: Option<LatencyStats>,
+    pub sequential_throughput: ThroughputSeries,
+    pub sequential_cpu: CpuUsage,
+    pub workload_results: Vec<WorkloadResult>,
+    pub write_size_results: Vec<WriteSizeResult>,
+    /// Clone latency of sources written in each of --source-extent-sizes
+    #[serde(default)]
+    pub source_extent_results: Vec<SourceExtentResult>,
+    pub space_report: Option<SpaceReport>,
+    /// Where the sequential phase's and each workload's bytes live, with
+    /// --extent-usage
+    #[serde(default)]
+    pub extent_usage: Vec<ExtentUsage>,
+    pub write_amplification: Vec<WriteAmplification>,
+    pub device_stats: Vec<DeviceStats>,
+    /// blktrace file prefixes, one per traced phase
+    pub blktraces: Vec<PathBuf>,
+    pub perf_counters: Vec<PerfCounters>,
+    pub kernel_latency: Vec<KernelFunctionLatency>,
+    /// Sequential reflink + write under each requested cache condition
+    pub cache_results: Vec<CacheResult>,
+    /// Reflink + write sustained for a fixed time, with --duration
+    pub soak: Option<SoakResult>,
+    /// Reflink + write issued on a schedule, with --rate
+    pub open_loop: Option<OpenLoopResult>,
+    /// Reflink + write in an encrypted directory, with --fscrypt on a
+    /// filesystem that supports it
+    pub fscrypt: Option<FscryptResult>,
+    /// Reflink + write of a huge sparse source, with --sparse-source
+    pub sparse_source: Option<SparseSourceResult>,
+    /// The cross-filesystem clone attempt and its fallback copies, with
+    /// --cross-fs
+    pub cross_fs: Option<CrossFsResult>,
+    /// Copies of the source without reflink, with --copy-baselines
+    #[serde(default)]
+    pub copy_baselines: Vec<CopyResult>,
+    /// Reflink + write through each of --external-tools
+    #[serde(default)]
+    pub external_tools: Vec<ExternalToolResult>,
+    /// Work done by --background-load while the phases ran
+    pub background_load: Option<BackgroundLoadResult>,
+    /// Each trim between phases, with --fstrim
+    #[serde(default)]
+    pub fstrim: Vec<TrimResult>,
+    /// Statistics across runs when the benchmark was repeated
+    pub repetitions: Option<RepetitionSummary>,
+    /// Data integrity check of every clone, with --verify
+    pub verification: Option<VerifyReport>,
+}
+
+/// Reflink + write issued at a fixed rate whether or not earlier operations
+/// have finished. Response latency counts from when each operation was due,
+/// so time spent waiting behind a slow operation is included rather than
+/// silently skipped the way a closed loop does (coordinated omission).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenLoopResult {
+    pub target_rate: f64,
+    /// Operations completed over `duration`
+    pub achieved_rate: f64,
+    pub operations: u32,
+    /// From the first operation's start until the last one finished
+    pub duration: Duration,
+    /// From each operation's scheduled start to its completion
+    pub response_latency: LatencyStats,
+    /// Reflink plus CoW write alone, as the closed-loop phases time it
+    pub service_latency: LatencyStats,
+    /// Most operations in flight at once
+    pub max_in_flight: u64,
+}
+
+impl OpenLoopResult {
+    /// Whether the filesystem kept up with the target rate (within 5%).
+    pub fn kept_up(&self) -> bool {
+        self.achieved_rate >= self.target_rate * 0.95
+    }
+}
+
+/// Reflink + write sustained for a fixed wall-clock time, in windows, so
+/// slowdowns that only show up minutes in (btrfs transaction commits,
+/// metadata growth, writeback throttling) are visible as drift.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakResult {
+    pub duration: Duration,
+    pub operations: u64,
+    /// Sustained rate over the whole run
+    pub operations_per_sec: f64,
+    pub windows: Vec<SoakWindow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakWindow {
+    /// When the window started, from the start of the run
+    pub offset: Duration,
+    pub operations_per_sec: f64,
+    pub reflink_p50: Duration,
+    pub reflink_p99: Duration,
+    pub write_p50: Duration,
+    pub write_p99: Duration,
+}
+
+impl SoakWindow {
+    fn new(offset: Duration, length: Duration, timings: &[OpTiming]) -> Self {
+        let reflink =
+            LatencyStats::from_samples(&timings.iter().map(|t| t.reflink).collect::<Vec<_>>());
+        let write =
+            LatencyStats::from_samples(&timings.iter().map(|t| t.write).collect::<Vec<_>>());
+        Self {
+            offset,
+            operations_per_sec: timings.len() as f64 / length.as_secs_f64(),
+            reflink_p50: reflink.p50,
+            reflink_p99: reflink.p99,
+            write_p50: write.p50,
+            write_p99: write.p99,
+        }
+    }
+}
+
+impl SoakResult {
+    /// Relative change in ops/sec from the first quarter of the run to the
+    /// last, e.g. -0.3 for 30% slower by the end.
+    pub fn throughput_drift(&self) -> f64 {
+        drift(&self.series(|w| w.operations_per_sec))
+    }
+
+    /// Relative change in reflink p99 from the first quarter to the last.
+    pub fn reflink_p99_drift(&self) -> f64 {
+        drift(&self.series(|w| w.reflink_p99.as_secs_f64()))
+    }
+
+    /// Relative change in CoW write p99 from the first quarter to the last.
+    pub fn write_p99_drift(&self) -> f64 {
+        drift(&self.series(|w| w.write_p99.as_secs_f64()))
+    }
+
+    /// The lowest ops/sec of any window.
+    pub fn slowest_window(&self) -> f64 {
+        self.series(|w| w.operations_per_sec)
+            .into_iter()
+            .reduce(f64::min)
+            .unwrap_or(0.0)
+    }
+
+    pub fn series(&self, value: impl Fn(&SoakWindow) -> f64) -> Vec<f64> {
+        self.windows.iter().map(value).collect()
+    }
+}
+
+/// Mean of the last quarter of `values` relative to the first quarter.
+fn drift(values: &[f64]) -> f64 {
+    let quarter = (values.len() / 4).max(1);
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let first = values[..quarter].iter().sum::<f64>() / quarter as f64;
+    let last = values[values.len() - quarter..].iter().sum::<f64>() / quarter as f64;
+    if first == 0.0 {
+        return 0.0;
+    }
+    last / first - 1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheResult {
+    pub cache_mode: String,
+    pub total_duration: Duration,
+    pub operations_per_sec: f64,
+    pub reflink_latency: LatencyStats,
+    pub write_latency: LatencyStats,
 }
 
+/// Sequential reflink + write with source and clones in an fscrypt
+/// directory, where every CoW write is encrypted per file.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FscryptResult {
+    pub total_duration: Duration,
+    pub operations_per_sec: f64,
+    pub reflink_latency: LatencyStats,
+    pub write_latency: LatencyStats,
+}
+
+/// Sequential reflink + write of a sparse source whose apparent size can
+/// be far larger than the filesystem, like a VM or image server's disk
+/// images. Clone cost follows the extent map, not the apparent size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SparseSourceResult {
+    pub apparent_bytes: u64,
+    pub allocated_bytes: u64,
+    /// Extents the source was mapped to, where FIEMAP works
+    pub extents: Option<u32>,
+    pub total_duration: Duration,
+    pub operations_per_sec: f64,
+    pub reflink_latency: LatencyStats,
+    pub write_latency: LatencyStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepetitionSummary {
+    pub runs: u32,
+    pub operations_per_sec: Summary,
+    pub throughput_mb_per_sec: Summary,
+    pub reflink_p99_us: Summary,
+    pub write_p99_us: Summary,
+    /// Ops/sec for each entry of `ConcurrentBenchmarkResult::thread_counts`
+    pub concurrent_operations_per_sec: Vec<Summary>,
+}
+
+impl RepetitionSummary {
+    pub fn from_results(results: &[BenchmarkResult]) -> Self {
+        let summarize = |metric: &dyn Fn(&BenchmarkResult) -> f64| -> Summary {
+            Summary::from_samples(results.iter().map(metric).collect())
+        };
+
+        let thread_levels = results
+            .first()
+            .map(|r| r.concurrent_results.thread_counts.len())
+            .unwrap_or(0);
+
+        Self {
+            runs: results.len() as u32,
+            operations_per_sec: summarize(&|r| r.operations_per_sec),
+            throughput_mb_per_sec: summarize(&|r| r.throughput_mb_per_sec),
+            reflink_p99_us: summarize(&|r| r.reflink_latency.p99.as_secs_f64() * 1e6),
+            write_p99_us: summarize(&|r| r.write_latency.p99.as_secs_f64() * 1e6),
+            concurrent_operations_per_sec: (0..thread_levels)
+                .map(|i| summarize(&|r| r.concurrent_results.operations_per_sec[i]))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ConcurrentBenchmarkResult {
     pub thread_counts: Vec<u32>,
     pub durations: Vec<Duration>,
     pub operations_per_sec: Vec<f64>,
     pub contention_ratios: Vec<f64>,
+    /// Per-operation (reflink + write) latency for each thread count
+    pub latencies: Vec<LatencyStats>,
+    /// Ops/sec over time for each thread count
+    pub throughput_series: Vec<ThroughputSeries>,
+    /// CPU time and context switches for each thread count
+    pub cpu_usage: Vec<CpuUsage>,
+    /// Per-worker start/end times and op counts for each thread count
+    pub worker_balance: Vec<WorkerBalance>,
+}
+
+/// One `FITRIM` of the filesystem's free space between phases.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrimResult {
+    pub trimmed_bytes: u64,
+    pub duration: Duration,
+}
+
+/// When one concurrent worker ran, relative to the start of its run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerTiming {
+    pub worker: u32,
+    pub start: Duration,
+    pub end: Duration,
+    pub operations: u32,
+}
+
+/// How evenly work was spread across the workers of one concurrent run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerBalance {
+    pub workers: Vec<WorkerTiming>,
+    pub median_finish: Duration,
+    pub last_finish: Duration,
+    /// Last worker's finish time over the median worker's
+    pub straggler_ratio: f64,
+    /// Share of total worker time spent idle after finishing early
+    pub idle_share: f64,
+}
+
+impl WorkerBalance {
+    pub fn new(mut workers: Vec<WorkerTiming>, duration: Duration) -> Self {
+        workers.sort_by_key(|w| w.worker);
+
+        let mut finishes: Vec<Duration> = workers.iter().map(|w| w.end).collect();
+        finishes.sort_unstable();
+        let median_finish = finishes
+            .get(finishes.len() / 2)
+            .copied()
+            .unwrap_or_default();
+        let last_finish = finishes.last().copied().unwrap_or_default();
+
+        let busy: f64 = workers
+            .iter()
+            .map(|w| w.end.saturating_sub(w.start).as_secs_f64())
+            .sum();
+        let available = workers.len() as f64 * duration.as_secs_f64();
+
+        Self {
+            workers,
+            median_finish,
+            last_finish,
+            straggler_ratio: last_finish.as_secs_f64()
+                / median_finish.as_secs_f64().max(f64::EPSILON),
+            idle_share: if available > 0.0 {
+                (1.0 - busy / available).max(0.0)
+            } else {
+                0.0
+            },
+        }
+    }
+
+    pub fn min_operations(&self) -> u32 {
+        self.workers.iter().map(|w| w.operations).min().unwrap_or(0)
+    }
+
+    pub fn max_operations(&self) -> u32 {
+        self.workers.iter().map(|w| w.operations).max().unwrap_or(0)
+    }
+}
+
+/// Time spent in each half of a single reflink + write operation. Other
+/// [`Workload`]s name what the two times measure with
+/// [`Workload::steps`].
+#[derive(Debug, Clone, Copy)]
+pub struct OpTiming {
+    pub reflink: Duration,
+    pub write: Duration,
+}
+
+impl OpTiming {
+    pub fn total(&self) -> Duration {
+        self.reflink + self.write
+    }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteSizeResult {
+    pub write_size_bytes: u64,
+    pub total_duration: Duration,
+    pub avg_op_time: Duration,
+    pub operations_per_sec: f64,
+}
+
+/// Clones of a source written in extents of one size, without the CoW
+/// write, so the latency is `FICLONE` sharing that many extents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceExtentResult {
+    pub extent_size_bytes: u64,
+    /// Extents the source was actually mapped to, where FIEMAP works
+    pub extents: Option<u32>,
+    pub total_duration: Duration,
+    pub operations_per_sec: f64,
+    pub clone_latency: LatencyStats,
+}
+
+/// Runs the benchmark phases against an already mounted filesystem.
 pub struct BenchmarkRunner {
     mount_point: PathBuf,
     config: BenchmarkConfig,
+    block_device: Option<String>,
+    trims: std::sync::Mutex<Vec<TrimResult>>,
 }
 
 impl BenchmarkRunner {
+    /// Benchmarks the filesystem mounted at `mount_point`, which must be
+    /// writable and support reflinks.
     pub fn new(mount_point: &Path, config: BenchmarkConfig) -> Self {
         Self {
             mount_point: mount_point.to_path_buf(),
             config,
+            block_device: filesystem::block_device_name(mount_point),
+            trims: Default::default(),
         }
     }
 
+    /// Creates the source files, then runs the sequential, concurrent, and
+    /// every configured optional phase in turn. Files are left in place for
+    /// the caller to clean up with the filesystem.
     pub async fn run_benchmark(&self) -> Result<BenchmarkResult> {
-        println!("📁 Creating source file...");
-        let source_file = self.create_source_file().await?;
+        if let Some(cgroup) = &self.config.cgroup {
+            match &self.block_device {
+                Some(device) => cgroup.limit_device(device)?,
+                None if cgroup.io_max().is_some() => println!(
+                    "⚠️  {} is not on a block device; io.max limits don't apply",
+                    self.mount_point.display()
+                ),
+                None => {}
+            }
+        }
+        if let Some(scheduler) = self.config.io_scheduler {
+            match &self.block_device {
+                Some(device) => scheduler.apply(device)?,
+                None => println!(
+                    "⚠️  {} is not on a block device; keeping the I/O scheduler",
+                    self.mount_point.display()
+                ),
+            }
+        }
+        let qgroups = self.config.btrfs_qgroups && filesystem::is_btrfs(&self.mount_point);
+        if qgroups {
+            println!("📏 Enabling btrfs quota groups...");
+            filesystem::enable_btrfs_qgroups(&self.mount_point)?;
+        }
+        let project_quota = self.config.xfs_project_quota
+            && matches!(
+                FilesystemType::detect(&self.mount_point),
+                Ok(FilesystemType::Xfs)
+            );
+        if project_quota {
+            println!("📏 Enabling XFS project quota...");
+            filesystem::enable_xfs_project_quota(&self.mount_point)?;
+        }
+        self.take_turn().await;
+        self.begin_phase("creating source files", 0);
+        let source_files = self.create_source_files().await?;
+        let source_file = source_files[0].clone();
+        let source_extents = std::fs::File::open(&source_file)
+            .and_then(|file| ioctl::fiemap_extent_count(&file))
+            .ok();
+        if let (Some(requested), Some(extents)) = (self.config.fragment_source, source_extents) {
+            println!(
+                "    Source file is in {} extents ({} requested)",
+                extents, requested
+            );
+        }
+        let verifier = if self.config.verify {
+            let sources = source_files.clone();
+            Some(Arc::new(
+                tokio::task::spawn_blocking(move || Verifier::new(&sources))
+                    .await
+                    .context("Task panicked")??,
+            ))
+        } else {
+            None
+        };
+
+        if self.config.warmup > 0 {
+            self.take_turn().await;
+            println!("🔥 Running {} warmup operations...", self.config.warmup);
+            self.begin_phase("warmup", self.config.warmup as u64);
+            self.run_warmup(&source_files).await?;
+        }
+
+        let background = match self.config.background_load {
+            Some(load) => {
+                println!("🌪️  Starting {} background load...", load);
+                Some(load.start(&self.mount_point.join("background"))?)
+            }
+            None => None,
+        };
 
+        let kernel_tracer = if self.config.trace_kernel {
+            println!("🐝 Attaching kernel tracer...");
+            Some(KernelTracer::start().await?)
+        } else {
+            None
+        };
+
+        let mut write_amplification = Vec::new();
+        let mut device_stats = Vec::new();
+        let mut blktraces = Vec::new();
+        let mut perf_counters = Vec::new();
+
+        self.take_turn().await;
+        self.settle_between_phases()?;
         println!("🔗 Running sequential reflink + write benchmark...");
-        let sequential_result = self.run_sequential_benchmark(&source_file).await?;
+        self.begin_phase("sequential", self.config.reflink_count as u64);
+        let trace = self.start_blktrace("sequential").await?;
+        let disk_before = self.sample_disk_stats()?;
+        let sequential_throughput = ThroughputSampler::start("sequential");
+        let completed = sequential_throughput.counter();
+        let perf = self.start_perf_stat("sequential").await?;
+        let cpu_before = CpuSnapshot::take()?;
+        let (sequential_result, sequential_timings) = self
+            .run_sequential(&self.reflink_write("sequential", &source_files), &completed)
+            .await?;
+        self.stream_phase(
+            "sequential",
+            self.config.reflink_count as u64,
+            sequential_result,
+        )?;
+        perf_counters.extend(perf.map(PerfStat::stop).transpose()?);
+        let sequential_cpu = CpuUsage::between("sequential", &cpu_before, &CpuSnapshot::take()?);
+        let sequential_throughput = sequential_throughput.finish().await;
+        let reflink_samples: Vec<Duration> = sequential_timings.iter().map(|t| t.reflink).collect();
+        let write_samples: Vec<Duration> = sequential_timings.iter().map(|t| t.write).collect();
+        let reflink_latency = LatencyStats::from_samples(&reflink_samples);
+        let write_latency = LatencyStats::from_samples(&write_samples);
+        for (operation, stats) in [("reflink", &reflink_latency), ("CoW write", &write_latency)] {
+            if stats.outliers_dominate() {
+                println!(
+                    "    ⚠️  {} {} outliers take {:.0}% of total time; background activity may have interfered",
+                    stats.outliers,
+                    operation,
+                    stats.outlier_time_share * 100.0
+                );
+            }
+        }
+        let (reflink_latency_trimmed, write_latency_trimmed) = if self.config.trim_outliers {
+            (
+                Some(LatencyStats::trimmed(&reflink_samples)),
+                Some(LatencyStats::trimmed(&write_samples)),
+            )
+        } else {
+            (None, None)
+        };
+        println!(
+            "    Reflink p50/p99: {:?}/{:?}, Write p50/p99: {:?}/{:?}",
+            reflink_latency.p50, reflink_latency.p99, write_latency.p50, write_latency.p99
+        );
+        device_stats.extend(self.measure_device_stats("sequential", disk_before)?);
+        let logical_bytes = self.config.reflink_count as u64 * DEFAULT_WRITE_SIZE as u64;
+        write_amplification.extend(self.measure_write_amplification(
+            "sequential",
+            logical_bytes,
+            disk_before,
+        )?);
+        blktraces.extend(trace.map(BlktraceCapture::stop).transpose()?);
 
+        self.take_turn().await;
+        self.settle_between_phases()?;
         println!("⚡ Running concurrent reflink + write benchmarks...");
-        let concurrent_result = self.run_concurrent_benchmarks(&source_file).await?;
+        let trace = self.start_blktrace("concurrent").await?;
+        let disk_before = self.sample_disk_stats()?;
+        let perf = self.start_perf_stat("concurrent").await?;
+        let concurrent_result = self.run_concurrent_benchmarks(&source_files).await?;
+        perf_counters.extend(perf.map(PerfStat::stop).transpose()?);
+        device_stats.extend(self.measure_device_stats("concurrent", disk_before)?);
+        // The single-thread baseline run writes as much as each thread count
+        let concurrent_runs = concurrent_result.thread_counts.len() as u64 + 1;
+        write_amplification.extend(self.measure_write_amplification(
+            "concurrent",
+            concurrent_runs * logical_bytes,
+            disk_before,
+        )?);
+        blktraces.extend(trace.map(BlktraceCapture::stop).transpose()?);
+
+        let soak = match self.config.duration {
+            Some(duration) => {
+                self.take_turn().await;
+                self.settle_between_phases()?;
+                println!(
+                    "⏱️  Running reflink + write for {}...",
+                    humantime::format_duration(duration)
+                );
+                let disk_before = self.sample_disk_stats()?;
+                let result = self.run_soak(&source_files, duration).await?;
+                println!(
+                    "    Sustained ops/sec: {:.2} ({} ops), drift {:+.1}%, write p99 drift {:+.1}%",
+                    result.operations_per_sec,
+                    result.operations,
+                    result.throughput_drift() * 100.0,
+                    result.write_p99_drift() * 100.0
+                );
+                device_stats.extend(self.measure_device_stats("soak", disk_before)?);
+                write_amplification.extend(self.measure_write_amplification(
+                    "soak",
+                    result.operations * DEFAULT_WRITE_SIZE as u64,
+                    disk_before,
+                )?);
+                self.stream_phase("soak", result.operations, result.duration)?;
+                Some(result)
+            }
+            None => None,
+        };
+
+        let open_loop = match self.config.rate {
+            Some(rate) => {
+                self.take_turn().await;
+                self.settle_between_phases()?;
+                println!("🎯 Running open-loop reflink + write at {}...", rate);
+                self.begin_phase("open-loop", self.config.reflink_count as u64);
+                let disk_before = self.sample_disk_stats()?;
+                let result = self.run_open_loop(&source_files, rate).await?;
+                println!(
+                    "    Achieved {:.2} ops/s, response p50/p99: {:?}/{:?}, service p99: {:?}, max in flight: {}",
+                    result.achieved_rate,
+                    result.response_latency.p50,
+                    result.response_latency.p99,
+                    result.service_latency.p99,
+                    result.max_in_flight
+                );
+                if !result.kept_up() {
+                    println!("    ⚠️  Fell behind the target rate; operations queued up");
+                }
+                device_stats.extend(self.measure_device_stats("open-loop", disk_before)?);
+                write_amplification.extend(self.measure_write_amplification(
+                    "open-loop",
+                    logical_bytes,
+                    disk_before,
+                )?);
+                self.stream_phase("open_loop", result.operations as u64, result.duration)?;
+                Some(result)
+            }
+            None => None,
+        };
+
+        let fscrypt = if self.config.fscrypt {
+            self.take_turn().await;
+            self.settle_between_phases()?;
+            println!("🔐 Running reflink + write in an fscrypt-encrypted directory...");
+            let result = self.run_fscrypt(&source_file).await?;
+            match &result {
+                Some(result) => println!(
+                    "    Ops/sec: {:.2}, Reflink p99: {:?}, Write p99: {:?}",
+                    result.operations_per_sec, result.reflink_latency.p99, result.write_latency.p99
+                ),
+                None => println!("    ⚠️  This filesystem doesn't support fscrypt; skipped"),
+            }
+            result
+        } else {
+            None
+        };
+
+        let sparse_source = match self.config.sparse_source_bytes {
+            Some(bytes) => {
+                self.take_turn().await;
+                self.settle_between_phases()?;
+                println!(
+                    "🕳️  Running reflink + write of a {} sparse source...",
+                    results::format_bytes(bytes)
+                );
+                let result = self.run_sparse_source(bytes).await?;
+                println!(
+                    "    {} allocated in {} extents, Ops/sec: {:.2}, Reflink p99: {:?}",
+                    results::format_bytes(result.allocated_bytes),
+                    result
+                        .extents
+                        .map_or_else(|| "?".to_string(), |e| e.to_string()),
+                    result.operations_per_sec,
+                    result.reflink_latency.p99
+                );
+                Some(result)
+            }
+            None => None,
+        };
+
+        let cross_fs = match &self.config.cross_fs_dir {
+            Some(dir) => {
+                self.take_turn().await;
+                self.settle_between_phases()?;
+                println!(
+                    "🌉 Cloning the source into {}, then copying it there...",
+                    dir.display()
+                );
+                let (source, dir) = (source_file.clone(), dir.clone());
+                let result = tokio::task::spawn_blocking(move || copy::run_cross_fs(&source, &dir))
+                    .await
+                    .context("Task panicked")??;
+                println!(
+                    "    FICLONE: {} after {:?}",
+                    result.ficlone_error.as_deref().unwrap_or("cloned"),
+                    result.ficlone_latency.p50
+                );
+                for fallback in &result.fallbacks {
+                    match &fallback.error {
+                        Some(error) => println!("    {}: {}", fallback.strategy, error),
+                        None => println!(
+                            "    {}: p50 {:?}, {:.1} MB/s",
+                            fallback.strategy, fallback.latency.p50, fallback.throughput_mb_per_sec
+                        ),
+                    }
+                }
+                Some(result)
+            }
+            None => None,
+        };
+
+        let copy_baselines = if self.config.copy_baselines {
+            self.take_turn().await;
+            self.settle_between_phases()?;
+            println!("📋 Copying the source without reflink...");
+            let (source, dir) = (source_file.clone(), self.mount_point.join("copies"));
+            let results =
+                tokio::task::spawn_blocking(move || copy::run_copy_baselines(&source, &dir))
+                    .await
+                    .context("Task panicked")??;
+            for copy in &results {
+                match &copy.error {
+                    Some(error) => println!("    {}: {}", copy.strategy, error),
+                    None => println!(
+                        "    {}: p50 {:?}, {:.1} MB/s",
+                        copy.strategy, copy.latency.p50, copy.throughput_mb_per_sec
+                    ),
+                }
+            }
+            results
+        } else {
+            Vec::new()
+        };
+
+        let mut external_tools = Vec::new();
+        for &tool in &self.config.external_tools {
+            self.take_turn().await;
+            self.settle_between_phases()?;
+            println!("🛠️  Running reflink + write through {}...", tool);
+            let result = self.run_external_tool(tool, &source_file).await?;
+            println!(
+                "    Ops/sec: {:.2}, Clone p50/p99: {:?}/{:?}",
+                result.operations_per_sec, result.reflink_latency.p50, result.reflink_latency.p99
+            );
+            external_tools.push(result);
+        }
+
+        let mut cache_results = Vec::new();
+        if let Some(cache_mode) = self.config.cache_mode {
+            for &condition in cache_mode.conditions() {
+                self.take_turn().await;
+                println!(
+                    "🧊 Running {}-cache reflink + write benchmark...",
+                    condition
+                );
+                self.begin_phase(
+                    &format!("{}-cache", condition),
+                    self.config.reflink_count as u64,
+                );
+                let result = self.run_cache_condition(&source_files, condition).await?;
+                println!(
+                    "    Ops/sec: {:.2}, Reflink p99: {:?}",
+                    result.operations_per_sec, result.reflink_latency.p99
+                );
+                self.stream_phase(
+                    &format!("cache_{}", condition),
+                    self.config.reflink_count as u64,
+                    result.total_duration,
+                )?;
+                cache_results.push(result);
+            }
+        }
+
+        let mut write_size_results = Vec::new();
+        let mut write_size_verification: Option<VerifyReport> = None;
+        if !self.config.write_sizes.is_empty() {
+            self.take_turn().await;
+            self.settle_between_phases()?;
+            println!("✍️  Running write-size sweep...");
+            for &write_size in &self.config.write_sizes {
+                self.begin_phase(
+                    &format!("write-size {}", write_size),
+                    self.config.reflink_count as u64,
+                );
+                let trace = self
+                    .start_blktrace(&format!("write-size-{}", write_size))
+                    .await?;
+                let disk_before = self.sample_disk_stats()?;
+                let perf = self
+                    .start_perf_stat(&format!("write-size {}", write_size))
+                    .await?;
+                let (result, verification) = self
+                    .run_write_size(&source_file, write_size, verifier.clone())
+                    .await?;
+                if let Some(verification) = verification {
+                    match &mut write_size_verification {
+                        Some(total) => total.add(&verification),
+                        None => write_size_verification = Some(verification),
+                    }
+                }
+                perf_counters.extend(perf.map(PerfStat::stop).transpose()?);
+                device_stats.extend(self.measure_device_stats(
+                    &format!("write-size {}", result.write_size_bytes),
+                    disk_before,
+                )?);
+                println!(
+                    "  {} bytes: Avg per op: {:?}, Ops/sec: {:.2}",
+                    write_size, result.avg_op_time, result.operations_per_sec
+                );
+                write_amplification.extend(self.measure_write_amplification(
+                    &format!("write-size {}", result.write_size_bytes),
+                    self.config.reflink_count as u64 * result.write_size_bytes,
+                    disk_before,
+                )?);
+                blktraces.extend(trace.map(BlktraceCapture::stop).transpose()?);
+                self.stream_phase(
+                    &format!("write_size_{}", result.write_size_bytes),
+                    self.config.reflink_count as u64,
+                    result.total_duration,
+                )?;
+                write_size_results.push(result);
+            }
+        }
+
+        let mut source_extent_results = Vec::new();
+        if !self.config.source_extent_sizes.is_empty() {
+            self.take_turn().await;
+            self.settle_between_phases()?;
+            println!("📐 Running source extent-size sweep...");
+            for &extent_size in &self.config.source_extent_sizes {
+                self.begin_phase(
+                    &format!("source extents {}", extent_size),
+                    self.config.reflink_count as u64,
+                );
+                let result = self.run_source_extent_size(extent_size).await?;
+                println!(
+                    "  {} bytes: {} extents, clone p50: {:?}, Ops/sec: {:.2}",
+                    extent_size,
+                    result
+                        .extents
+                        .map_or_else(|| "?".to_string(), |e| e.to_string()),
+                    result.clone_latency.p50,
+                    result.operations_per_sec
+                );
+                self.stream_phase(
+                    &format!("source_extents_{}", extent_size),
+                    self.config.reflink_count as u64,
+                    result.total_duration,
+                )?;
+                source_extent_results.push(result);
+            }
+        }
+
+        let verification = match verifier {
+            Some(verifier) => {
+                self.take_turn().await;
+                let phases = self.verify_phases(
+                    source_files.len(),
+                    &concurrent_result.thread_counts,
+                    &cache_results,
+                );
+                println!(
+                    "🔍 Verifying {} clones...",
+                    phases.iter().map(|p| p.count as u64).sum::<u64>()
+                        + write_size_verification.as_ref().map_or(0, |v| v.files)
+                );
+                let dir = self.mount_point.clone();
+                let mut report =
+                    tokio::task::spawn_blocking(move || verifier.verify(&dir, &phases))
+                        .await
+                        .context("Task panicked")??;
+                // The write-size sweep's clones were checked before removal
+                if let Some(verification) = &write_size_verification {
+                    report.add(verification);
+                }
+                println!(
+                    "    ✅ {} clones intact ({} MB read in {:?})",
+                    report.files,
+                    report.bytes_read / (1024 * 1024),
+                    report.duration
+                );
+                Some(report)
+            }
+            None => None,
+        };
+
+        let space_report = if self.config.space_report {
+            self.take_turn().await;
+            self.settle_between_phases()?;
+            println!("💾 Measuring space usage...");
+            let report = self.measure_space(&source_file).await?;
+            println!(
+                "    Apparent: {} bytes, Actual: {} bytes",
+                report.apparent_bytes,
+                report.actual_used_bytes()
+            );
+            Some(report)
+        } else {
+            None
+        };
+
+        let mut workload_results = Vec::new();
+        for &workload in &self.config.workloads {
+            self.take_turn().await;
+            self.settle_between_phases()?;
+            println!("🧪 Running {} workload...", workload);
+            self.begin_phase(&format!("{} workload", workload), 0);
+            let result = self.run_workload(workload, &source_files).await?;
+            println!(
+                "    Duration: {:?}, Ops/sec: {:.2}",
+                result.total_duration, result.operations_per_sec
+            );
+            self.stream_phase(
+                &workload.to_string(),
+                result.operations as u64,
+                result.total_duration,
+            )?;
+            workload_results.push(result);
+        }
+
+        let extent_usage = if self.config.extent_usage {
+            println!("🧮 Mapping extents of the sequential phase and workloads...");
+            let usage = self.measure_extent_usage(&source_files).await?;
+            for phase in &usage {
+                println!(
+                    "    {}: {} referenced, {} on disk ({:.1}% saved)",
+                    phase.phase,
+                    results::format_bytes(phase.referenced_bytes),
+                    results::format_bytes(phase.disk_bytes),
+                    phase.savings() * 100.0
+                );
+            }
+            usage
+        } else {
+            Vec::new()
+        };
+
+        let kernel_latency = match kernel_tracer {
+            Some(tracer) => tracer.stop()?,
+            None => Vec::new(),
+        };
+        let background_load = background.map(|task| task.finish()).transpose()?;
 
         let total_data_mb = self.config.file_size_mb * self.config.reflink_count as u64;
         let throughput_mb_per_sec = total_data_mb as f64 / sequential_result.as_secs_f64();
@@ -64,25 +1373,470 @@ impl BenchmarkRunner {
 
         Ok(BenchmarkResult {
             filesystem: "unknown".to_string(), // Will be set by caller
+            environment: FilesystemEnvironment {
+                qgroups,
+                project_quota,
+                ..FilesystemEnvironment::capture(&self.mount_point)
+            },
             file_size_mb: self.config.file_size_mb,
             reflink_count: self.config.reflink_count,
+            source_count: source_files.len() as u32,
+            source_extents,
+            runner: self.config.runner,
+            warmup: self.config.warmup,
             total_duration: sequential_result,
             avg_reflink_time: sequential_result / self.config.reflink_count,
             concurrent_results: concurrent_result,
             throughput_mb_per_sec,
             operations_per_sec,
+            reflink_latency,
+            write_latency,
+            reflink_latency_trimmed,
+            write_latency_trimmed,
+            sequential_throughput,
+            sequential_cpu,
+            workload_results,
+            write_size_results,
+            source_extent_results,
+            space_report,
+            extent_usage,
+            write_amplification,
+            device_stats,
+            blktraces,
+            perf_counters,
+            kernel_latency,
+            cache_results,
+            soak,
+            open_loop,
+            fscrypt,
+            sparse_source,
+            cross_fs,
+            copy_baselines,
+            external_tools,
+            background_load,
+            fstrim: std::mem::take(&mut *self.trims.lock().unwrap()),
+            repetitions: None,
+            verification,
+        })
+    }
+
+    /// The clones left by each timed phase, for --verify. The write-size
+    /// sweep removes its clones, so checks them itself.
+    fn verify_phases(
+        &self,
+        source_count: usize,
+        thread_counts: &[u32],
+        cache_results: &[CacheResult],
+    ) -> Vec<PhaseTargets> {
+        let count = self.config.reflink_count;
+        let mut prefixes = vec!["sequential".to_string(), "concurrent".to_string()];
+        prefixes.extend(thread_counts.iter().map(|n| format!("concurrent_{}", n)));
+        prefixes.extend(
+            cache_results
+                .iter()
+                .map(|result| format!("cache_{}", result.cache_mode)),
+        );
+
+        prefixes
+            .into_iter()
+            .map(|prefix| PhaseTargets {
+                prefix,
+                count,
+                write_size: DEFAULT_WRITE_SIZE as u64,
+                sources: source_count,
+            })
+            .collect()
+    }
+
+    fn settle_between_phases(&self) -> Result<()> {
+        if self.config.fstrim {
+            let start = Instant::now();
+            let trimmed_bytes = filesystem::fstrim(&self.mount_point)?;
+            let duration = start.elapsed();
+            println!(
+                "✂️  Trimmed {} in {:?}",
+                results::format_bytes(trimmed_bytes),
+                duration
+            );
+            self.trims.lock().unwrap().push(TrimResult {
+                trimmed_bytes,
+                duration,
+            });
+        }
+        if self.config.drop_caches {
+            println!("🧊 Dropping page caches...");
+            filesystem::drop_caches()?;
+        }
+        Ok(())
+    }
+
+    async fn start_blktrace(&self, phase: &str) -> Result<Option<BlktraceCapture>> {
+        let Some(dir) = &self.config.blktrace_dir else {
+            return Ok(None);
+        };
+        let device = self
+            .block_device
+            .as_deref()
+            .context("Cannot blktrace: block device for the benchmark filesystem not found")?;
+
+        BlktraceCapture::start(device, dir, phase).await.map(Some)
+    }
+
+    /// With `--interleave`, waits for the other filesystems to run a phase
+    /// each before running the next one here.
+    async fn take_turn(&self) {
+        if let Some(turns) = &self.config.turns {
+            turns.next().await;
+        }
+    }
+
+    fn begin_phase(&self, phase: &str, total_operations: u64) {
+        if let Some(monitor) = &self.config.monitor {
+            monitor.begin_phase(phase, total_operations);
+        }
+        if let Some(progress) = &self.config.progress {
+            progress.begin_phase(phase, total_operations, "ops");
+        }
+    }
+
+    fn stream_phase(&self, phase: &str, operations: u64, duration: Duration) -> Result<()> {
+        match &self.config.stream {
+            Some(stream) => stream.phase(phase, operations, duration),
+            None => Ok(()),
+        }
+    }
+
+    async fn start_perf_stat(&self, phase: &str) -> Result<Option<PerfStat>> {
+        if !self.config.perf_stat {
+            return Ok(None);
+        }
+        PerfStat::start(phase).await.map(Some)
+    }
+
+    fn sample_disk_stats(&self) -> Result<Option<DiskStats>> {
+        self.block_device
+            .as_deref()
+            .map(DiskStats::sample)
+            .transpose()
+    }
+
+    /// Device activity since `before`, sampled before any flush so it only
+    /// covers IO the phase itself issued.
+    fn measure_device_stats(
+        &self,
+        phase: &str,
+        before: Option<DiskStats>,
+    ) -> Result<Option<DeviceStats>> {
+        let Some(before) = before else {
+            return Ok(None);
+        };
+
+        let after = self
+            .sample_disk_stats()?
+            .context("Block device disappeared during benchmark")?;
+
+        let stats = DeviceStats::new(phase, &before, &after);
+        println!(
+            "    Device: {:.0} IOPS, queue depth {:.2}, await {:.2}ms, {:.0}% util",
+            stats.iops(),
+            stats.avg_queue_depth,
+            stats.await_ms,
+            stats.utilization * 100.0
+        );
+
+        Ok(Some(stats))
+    }
+
+    /// Flushes the phase's dirty data and compares device writes against
+    /// the bytes the phase logically modified.
+    fn measure_write_amplification(
+        &self,
+        phase: &str,
+        logical_bytes: u64,
+        before: Option<DiskStats>,
+    ) -> Result<Option<WriteAmplification>> {
+        let Some(before) = before else {
+            return Ok(None);
+        };
+
+        filesystem::sync_filesystem(&self.mount_point)?;
+        let after = self
+            .sample_disk_stats()?
+            .context("Block device disappeared during benchmark")?;
+
+        let amplification = WriteAmplification::new(phase, logical_bytes, &before, &after);
+        println!(
+            "    Write amplification: {:.2}x ({} physical / {} logical bytes)",
+            amplification.amplification, amplification.physical_bytes, amplification.logical_bytes
+        );
+
+        Ok(Some(amplification))
+    }
+
+    async fn measure_space(&self, source_file: &Path) -> Result<SpaceReport> {
+        let dir = self.mount_point.join("space");
+        let source_file = source_file.to_path_buf();
+        let count = self.config.reflink_count;
+        tokio::task::spawn_blocking(move || space::measure_space(&dir, &source_file, count))
+            .await
+            .context("Task panicked")?
+            .context("Space accounting failed")
+    }
+
+    /// Times clones with `write_size`-byte writes in their own directory,
+    /// checks them with `verifier` if there is one, then removes them.
+    async fn run_write_size(
+        &self,
+        source_file: &Path,
+        write_size: u64,
+        verifier: Option<Arc<Verifier>>,
+    ) -> Result<(WriteSizeResult, Option<VerifyReport>)> {
+        // Writes past the end of the clone would extend it rather than CoW
+        let file_size = self.config.file_size_mb * 1024 * 1024;
+        let write_size = write_size.min(file_size).max(1) as usize;
+        let dir = self.mount_point.join(format!("write_{}", write_size));
+        fs::create_dir_all(&dir)
+            .await
+            .context("Failed to create write size directory")?;
+        let workload: Arc<dyn Workload> = Arc::new(ReflinkWrite::new(
+            &format!("write_{}", write_size),
+            &dir,
+            &[source_file.to_path_buf()],
+            self.config.reflink_count,
+            write_size,
+        ));
+
+        let (total_duration, _) = self.run_sequential(&workload, &Arc::default()).await?;
+        let verification = match verifier {
+            // The sweep only clones the first source
+            Some(verifier) => {
+                let phases = vec![PhaseTargets {
+                    prefix: format!("write_{}", write_size),
+                    count: self.config.reflink_count,
+                    write_size: write_size as u64,
+                    sources: 1,
+                }];
+                let dir = dir.clone();
+                Some(
+                    tokio::task::spawn_blocking(move || verifier.verify_clones(&dir, &phases))
+                        .await
+                        .context("Task panicked")??,
+                )
+            }
+            None => None,
+        };
+        // Each clone holds up to write_size of unshared data; later sizes
+        // would otherwise run on a fuller filesystem, or out of space
+        fs::remove_dir_all(&dir)
+            .await
+            .context("Failed to remove write size directory")?;
+        Ok((
+            WriteSizeResult {
+                write_size_bytes: write_size as u64,
+                total_duration,
+                avg_op_time: total_duration / self.config.reflink_count,
+                operations_per_sec: self.config.reflink_count as f64 / total_duration.as_secs_f64(),
+            },
+            verification,
+        ))
+    }
+
+    /// Writes a source in `extent_size` extents with [`write_fragmented`],
+    /// times `reflink_count` clones of it, and removes the source and
+    /// clones again so each size starts from the same free space.
+    async fn run_source_extent_size(&self, extent_size: u64) -> Result<SourceExtentResult> {
+        let dir = self.mount_point.join(format!("extents_{}", extent_size));
+        fs::create_dir_all(&dir)
+            .await
+            .context("Failed to create extent sweep directory")?;
+        let source = dir.join("source.dat");
+        let size = self.config.file_size_mb * 1024 * 1024;
+        let extents = size.div_ceil(extent_size.max(4096)) as u32;
+        let (path, rng) = (
+            source.clone(),
+            seeded_rng(self.config.seed, "source_file.dat"),
+        );
+        let measured = tokio::task::spawn_blocking(move || {
+            write_fragmented(&path, size, extents, rng)?;
+            Ok::<_, anyhow::Error>(
+                std::fs::File::open(&path)
+                    .and_then(|file| ioctl::fiemap_extent_count(&file))
+                    .ok(),
+            )
+        })
+        .await
+        .context("Task panicked")??;
+
+        let mut workload = WorkloadKind::CloneOnly
+            .build(&dir.join("clones"), &[source], &self.config)
+            .context("clone-only runs through the Workload trait")?;
+        let workload: Arc<dyn Workload> = tokio::task::spawn_blocking(move || {
+            workload.prepare()?;
+            Ok::<_, anyhow::Error>(workload)
+        })
+        .await
+        .context("Task panicked")??
+        .into();
+        let (duration, timings) = self.run_sequential(&workload, &Arc::default()).await?;
+        let samples: Vec<Duration> = timings.iter().map(|t| t.reflink).collect();
+
+        fs::remove_dir_all(&dir)
+            .await
+            .context("Failed to remove extent sweep directory")?;
+        Ok(SourceExtentResult {
+            extent_size_bytes: extent_size,
+            extents: measured,
+            total_duration: duration,
+            operations_per_sec: self.config.reflink_count as f64 / duration.as_secs_f64(),
+            clone_latency: LatencyStats::from_samples(&samples),
+        })
+    }
+
+    /// FIEMAP totals for the sources with the sequential phase's clones, and
+    /// for each workload's directory that still has files in it.
+    async fn measure_extent_usage(&self, source_files: &[PathBuf]) -> Result<Vec<ExtentUsage>> {
+        let mount_point = self.mount_point.clone();
+        let mut sequential = source_files.to_vec();
+        sequential.extend(
+            (0..self.config.reflink_count)
+                .map(|i| mount_point.join(format!("sequential_{}.dat", i))),
+        );
+        let workloads = self.config.workloads.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut usage = vec![space::extent_usage("sequential", &sequential)?];
+            for workload in workloads {
+                let dir = mount_point.join(workload.to_string());
+                let files = if dir.is_dir() {
+                    space::files_under(&dir)?
+                } else {
+                    Vec::new()
+                };
+                if !files.is_empty() {
+                    usage.push(space::extent_usage(&workload.to_string(), &files)?);
+                }
+            }
+            Ok(usage)
+        })
+        .await
+        .context("Task panicked")?
+    }
+
+    async fn run_workload(
+        &self,
+        workload: WorkloadKind,
+        source_files: &[PathBuf],
+    ) -> Result<WorkloadResult> {
+        let dir = self.mount_point.join(workload.to_string());
+        if let Some(built) = workload.build(&dir, source_files, &self.config) {
+            return self
+                .run_prepared(built)
+                .await
+                .context(format!("{} workload failed", workload));
+        }
+
+        let mount_point = self.mount_point.clone();
+        let source_file = source_files[0].clone();
+        let config = self.config.clone();
+        tokio::task::spawn_blocking(move || {
+            workloads::run_workload(workload, &mount_point, &source_file, &config)
+        })
+        .await
+        .context("Task panicked")?
+        .context(format!("{} workload failed", workload))
+    }
+
+    /// Prepares `workload`, runs its operations in order, and tears it down.
+    async fn run_prepared(&self, mut workload: Box<dyn Workload>) -> Result<WorkloadResult> {
+        let workload: Arc<dyn Workload> = tokio::task::spawn_blocking(move || {
+            workload.prepare()?;
+            Ok::<_, anyhow::Error>(workload)
         })
+        .await
+        .context("Task panicked")??
+        .into();
+
+        self.begin_phase(
+            &format!("{} workload", workload.name()),
+            workload.operations() as u64,
+        );
+        let (duration, timings) = self.run_sequential(&workload, &Arc::default()).await?;
+
+        let teardown = Arc::clone(&workload);
+        tokio::task::spawn_blocking(move || teardown.teardown())
+            .await
+            .context("Task panicked")??;
+
+        Ok(WorkloadResult::from_samples(
+            workload.as_ref(),
+            &timings,
+            duration,
+        ))
+    }
+
+    /// The core reflink + write operation over the run's source files, in
+    /// the mount point's root.
+    fn reflink_write(&self, name: &str, source_files: &[PathBuf]) -> Arc<dyn Workload> {
+        Arc::new(ReflinkWrite::new(
+            name,
+            &self.mount_point,
+            source_files,
+            self.config.reflink_count,
+            DEFAULT_WRITE_SIZE,
+        ))
     }
 
-    async fn create_source_file(&self) -> Result<PathBuf> {
-        let source_path = self.mount_point.join("source_file.dat");
+    async fn create_source_files(&self) -> Result<Vec<PathBuf>> {
+        let source_count = self.config.source_count.max(1);
+        if let Some(progress) = &self.config.progress {
+            progress.begin_phase(
+                "creating source files",
+                self.config.file_size_mb * source_count as u64,
+                "MB",
+            );
+        }
+        if source_count == 1 {
+            println!("📁 Creating source file...");
+            return Ok(vec![self.create_source_file("source_file.dat").await?]);
+        }
+
+        println!("📁 Creating {} source files...", source_count);
+        let mut source_files = Vec::with_capacity(source_count as usize);
+        for i in 0..source_count {
+            source_files.push(
+                self.create_source_file(&format!("source_file_{}.dat", i))
+                    .await?,
+            );
+        }
+
+        Ok(source_files)
+    }
+
+    async fn create_source_file(&self, name: &str) -> Result<PathBuf> {
+        let source_path = self.mount_point.join(name);
+        if let Some(extents) = self.config.fragment_source {
+            let (path, size_bytes, seed, name) = (
+                source_path.clone(),
+                self.config.file_size_mb * 1024 * 1024,
+                self.config.seed,
+                name.to_string(),
+            );
+            tokio::task::spawn_blocking(move || {
+                write_fragmented(&path, size_bytes, extents, seeded_rng(seed, &name))
+            })
+            .await
+            .context("Task panicked")??;
+            if let Some(progress) = &self.config.progress {
+                progress.inc(self.config.file_size_mb);
+            }
+            return Ok(source_path);
+        }
         let mut file = File::create(&source_path)
             .await
             .context("Failed to create source file")?;
 
         let size_bytes = self.config.file_size_mb * 1024 * 1024;
         let chunk_size = 1024 * 1024; // 1MB chunks
-        let mut rng = rand::thread_rng();
+        let mut rng = seeded_rng(self.config.seed, name);
 
         for _ in 0..(size_bytes / chunk_size) {
             let mut chunk = vec![0u8; chunk_size as usize];
@@ -90,6 +1844,9 @@ impl BenchmarkRunner {
             file.write_all(&chunk)
                 .await
                 .context("Failed to write to source file")?;
+            if let Some(progress) = &self.config.progress {
+                progress.inc(1);
+            }
         }
 
         file.sync_all()
@@ -99,30 +1856,420 @@ impl BenchmarkRunner {
         Ok(source_path)
     }
 
-    async fn run_sequential_benchmark(&self, source_file: &Path) -> Result<Duration> {
+    /// Performs untimed reflink + write operations, then deletes them and
+    /// syncs so the allocator and journal have been exercised before any
+    /// timed phase starts.
+    async fn run_warmup(&self, source_files: &[PathBuf]) -> Result<()> {
+        let warmup_dir = self.mount_point.join("warmup");
+        fs::create_dir_all(&warmup_dir)
+            .await
+            .context("Failed to create warmup directory")?;
+
+        for i in 0..self.config.warmup {
+            let target_path = warmup_dir.join(format!("warmup_{}.dat", i));
+            let source_file = &source_files[i as usize % source_files.len()];
+            let timing = self
+                .create_reflink(source_file, &target_path)
+                .await
+                .context(format!("Failed to run warmup operation {}", i))?;
+            if let Some(monitor) = &self.config.monitor {
+                monitor.record(&timing);
+            }
+            if let Some(progress) = &self.config.progress {
+                progress.inc(1);
+            }
+        }
+
+        fs::remove_dir_all(&warmup_dir)
+            .await
+            .context("Failed to remove warmup directory")?;
+        filesystem::sync_filesystem(&self.mount_point)?;
+
+        Ok(())
+    }
+
+    /// Puts the caches into the requested state, then runs a sequential
+    /// reflink + write pass.
+    async fn run_cache_condition(
+        &self,
+        source_files: &[PathBuf],
+        condition: CacheMode,
+    ) -> Result<CacheResult> {
+        match condition {
+            CacheMode::Cold => filesystem::drop_caches()?,
+            _ => {
+                // Read every source and clone it once so both data pages and
+                // extent metadata are cached
+                for (i, source_file) in source_files.iter().enumerate() {
+                    fs::read(source_file)
+                        .await
+                        .context("Failed to read source file into cache")?;
+                    let scratch = self
+                        .mount_point
+                        .join(format!("cache_warm_scratch_{}.dat", i));
+                    self.create_reflink(source_file, &scratch).await?;
+                    fs::remove_file(&scratch)
+                        .await
+                        .context("Failed to remove cache warming clone")?;
+                }
+            }
+        }
+
+        let (total_duration, timings) = self
+            .run_sequential(
+                &self.reflink_write(&format!("cache_{}", condition), source_files),
+                &Arc::default(),
+            )
+            .await?;
+
+        Ok(CacheResult {
+            cache_mode: condition.to_string(),
+            total_duration,
+            operations_per_sec: self.config.reflink_count as f64 / total_duration.as_secs_f64(),
+            reflink_latency: LatencyStats::from_samples(
+                &timings.iter().map(|t| t.reflink).collect::<Vec<_>>(),
+            ),
+            write_latency: LatencyStats::from_samples(
+                &timings.iter().map(|t| t.write).collect::<Vec<_>>(),
+            ),
+        })
+    }
+
+    /// Copies `source_file` into a new fscrypt-encrypted directory and runs
+    /// a sequential reflink + write pass there, or returns `None` when the
+    /// filesystem doesn't support fscrypt. The key is removed afterwards,
+    /// leaving the clones locked until the filesystem is cleaned up.
+    async fn run_fscrypt(&self, source_file: &Path) -> Result<Option<FscryptResult>> {
+        let dir = self.mount_point.join("fscrypt");
+        fs::create_dir(&dir)
+            .await
+            .context("Failed to create fscrypt directory")?;
+        let Some(key) = filesystem::enable_fscrypt(&dir)? else {
+            fs::remove_dir(&dir)
+                .await
+                .context("Failed to remove fscrypt directory")?;
+            return Ok(None);
+        };
+
+        let result = async {
+            let source = dir.join("source.dat");
+            fs::copy(source_file, &source)
+                .await
+                .context("Failed to copy source file into fscrypt directory")?;
+            filesystem::sync_filesystem(&self.mount_point)?;
+
+            self.begin_phase("fscrypt", self.config.reflink_count as u64);
+            let workload: Arc<dyn Workload> = Arc::new(ReflinkWrite::new(
+                "fscrypt",
+                &dir,
+                &[source],
+                self.config.reflink_count,
+                DEFAULT_WRITE_SIZE,
+            ));
+            let (total_duration, timings) = self.run_sequential(&workload, &Arc::default()).await?;
+            self.stream_phase("fscrypt", self.config.reflink_count as u64, total_duration)?;
+            anyhow::Ok(FscryptResult {
+                total_duration,
+                operations_per_sec: self.config.reflink_count as f64 / total_duration.as_secs_f64(),
+                reflink_latency: LatencyStats::from_samples(
+                    &timings.iter().map(|t| t.reflink).collect::<Vec<_>>(),
+                ),
+                write_latency: LatencyStats::from_samples(
+                    &timings.iter().map(|t| t.write).collect::<Vec<_>>(),
+                ),
+            })
+        }
+        .await;
+        filesystem::remove_fscrypt_key(&dir, key)?;
+        result.map(Some)
+    }
+
+    /// Creates a sparse source of `apparent_bytes` with
+    /// [`SPARSE_DATA_BYTES`] of data at the start of every
+    /// [`SPARSE_DATA_STRIDE`], runs a sequential reflink + write pass over
+    /// it, and deletes the source and clones again.
+    async fn run_sparse_source(&self, apparent_bytes: u64) -> Result<SparseSourceResult> {
+        let dir = self.mount_point.join("sparse");
+        fs::create_dir_all(&dir)
+            .await
+            .context("Failed to create sparse source directory")?;
+        let source = dir.join("source.dat");
+        let (path, mut rng) = (source.clone(), seeded_rng(self.config.seed, "sparse"));
+        let (allocated_bytes, extents) = tokio::task::spawn_blocking(move || {
+            use std::os::unix::fs::{FileExt, MetadataExt};
+
+            let file = std::fs::File::create(&path).context("Failed to create sparse source")?;
+            file.set_len(apparent_bytes)
+                .context("Failed to size sparse source (is it past the filesystem's limit?)")?;
+            let mut data = vec![0u8; SPARSE_DATA_BYTES as usize];
+            for offset in (0..apparent_bytes).step_by(SPARSE_DATA_STRIDE as usize) {
+                rng.fill(&mut data[..]);
+                let len = SPARSE_DATA_BYTES.min(apparent_bytes - offset) as usize;
+                file.write_all_at(&data[..len], offset)
+                    .context("Failed to write sparse source")?;
+            }
+            file.sync_all().context("Failed to sync sparse source")?;
+            let allocated = file
+                .metadata()
+                .context("Failed to stat sparse source")?
+                .blocks()
+                * 512;
+            anyhow::Ok((allocated, ioctl::fiemap_extent_count(&file).ok()))
+        })
+        .await
+        .context("Task panicked")??;
+
+        self.begin_phase("sparse source", self.config.reflink_count as u64);
+        let workload: Arc<dyn Workload> = Arc::new(ReflinkWrite::new(
+            "sparse",
+            &dir,
+            &[source],
+            self.config.reflink_count,
+            DEFAULT_WRITE_SIZE,
+        ));
+        let (total_duration, timings) = self.run_sequential(&workload, &Arc::default()).await?;
+        self.stream_phase("sparse", self.config.reflink_count as u64, total_duration)?;
+        fs::remove_dir_all(&dir)
+            .await
+            .context("Failed to remove sparse source directory")?;
+
+        Ok(SparseSourceResult {
+            apparent_bytes,
+            allocated_bytes,
+            extents,
+            total_duration,
+            operations_per_sec: self.config.reflink_count as f64 / total_duration.as_secs_f64(),
+            reflink_latency: LatencyStats::from_samples(
+                &timings.iter().map(|t| t.reflink).collect::<Vec<_>>(),
+            ),
+            write_latency: LatencyStats::from_samples(
+                &timings.iter().map(|t| t.write).collect::<Vec<_>>(),
+            ),
+        })
+    }
+
+    /// Runs a sequential reflink + write pass with `tool` doing each clone.
+    async fn run_external_tool(
+        &self,
+        tool: ExternalTool,
+        source_file: &Path,
+    ) -> Result<ExternalToolResult> {
+        let name = format!("external_{}", tool);
+        self.begin_phase(&name, self.config.reflink_count as u64);
+        let workload: Arc<dyn Workload> = Arc::new(ExternalReflinkWrite::new(
+            tool,
+            &self.mount_point,
+            source_file,
+            self.config.reflink_count,
+            DEFAULT_WRITE_SIZE,
+        ));
+        let (total_duration, timings) = self.run_sequential(&workload, &Arc::default()).await?;
+        self.stream_phase(&name, self.config.reflink_count as u64, total_duration)?;
+
+        Ok(ExternalToolResult {
+            tool: tool.to_string(),
+            total_duration,
+            operations_per_sec: self.config.reflink_count as f64 / total_duration.as_secs_f64(),
+            reflink_latency: LatencyStats::from_samples(
+                &timings.iter().map(|t| t.reflink).collect::<Vec<_>>(),
+            ),
+            write_latency: LatencyStats::from_samples(
+                &timings.iter().map(|t| t.write).collect::<Vec<_>>(),
+            ),
+        })
+    }
+
+    /// Runs reflink + write back to back until `duration` has passed,
+    /// recycling a ring of `reflink_count` clones so the filesystem doesn't
+    /// fill up, and measures each window separately.
+    async fn run_soak(&self, source_files: &[PathBuf], duration: Duration) -> Result<SoakResult> {
+        let ring = self.config.reflink_count.max(1);
+        let workload: Arc<dyn Workload> = Arc::new(ReflinkWrite::new(
+            "soak",
+            &self.mount_point,
+            source_files,
+            ring,
+            DEFAULT_WRITE_SIZE,
+        ));
+        let window = (duration / SOAK_WINDOWS).max(Duration::from_secs(1));
+        self.begin_phase("soak", 0);
+        if let Some(progress) = &self.config.progress {
+            progress.begin_phase("soak", duration.as_secs(), "s");
+        }
+
+        let mut windows = Vec::new();
+        let mut timings = Vec::new();
+        let mut operations = 0u64;
+        let mut seconds_shown = 0;
+        let start = Instant::now();
+        let mut window_start = start;
+        while start.elapsed() < duration {
+            let index = (operations % ring as u64) as u32;
+            let op = Arc::clone(&workload);
+            let timing = tokio::task::spawn_blocking(move || op.run(index))
+                .await
+                .context("Task panicked")?
+                .context(format!("Failed to run soak operation {}", operations))?;
+            if let Some(stream) = &self.config.stream {
+                stream.operation("soak", index, &timing)?;
+            }
+            if let Some(monitor) = &self.config.monitor {
+                monitor.record(&timing);
+            }
+            timings.push(timing);
+            operations += 1;
+
+            let now = Instant::now();
+            if now - window_start >= window {
+                windows.push(SoakWindow::new(
+                    window_start - start,
+                    now - window_start,
+                    &timings,
+                ));
+                timings.clear();
+                window_start = now;
+            }
+            if let Some(progress) = &self.config.progress {
+                let seconds = (now - start).as_secs().min(duration.as_secs());
+                progress.inc(seconds - seconds_shown);
+                seconds_shown = seconds;
+            }
+        }
+        let elapsed = start.elapsed();
+        // A last sliver of a window would skew the drift
+        if !timings.is_empty() && elapsed - (window_start - start) >= window / 2 {
+            windows.push(SoakWindow::new(
+                window_start - start,
+                start + elapsed - window_start,
+                &timings,
+            ));
+        }
+
+        Ok(SoakResult {
+            duration: elapsed,
+            operations,
+            operations_per_sec: operations as f64 / elapsed.as_secs_f64(),
+            windows,
+        })
+    }
+
+    /// Starts `reflink_count` reflink + write operations `1 / rate` apart,
+    /// each on its own blocking thread, without waiting for earlier ones to
+    /// finish.
+    async fn run_open_loop(&self, source_files: &[PathBuf], rate: Rate) -> Result<OpenLoopResult> {
+        let workload = self.reflink_write("open_loop", source_files);
+        let operations = workload.operations();
+        let interval = Duration::from_secs_f64(1.0 / rate.0);
+        let in_flight = Arc::new(AtomicU64::new(0));
+        let mut max_in_flight = 0;
+
+        let start = Instant::now();
+        let mut tasks = Vec::with_capacity(operations as usize);
+        for i in 0..operations {
+            let scheduled = start + interval.mul_f64(i as f64);
+            tokio::time::sleep_until(scheduled.into()).await;
+            let op = Arc::clone(&workload);
+            let running = Arc::clone(&in_flight);
+            max_in_flight = max_in_flight.max(running.fetch_add(1, Ordering::Relaxed) + 1);
+            tasks.push(tokio::task::spawn_blocking(move || {
+                let timing = op.run(i);
+                running.fetch_sub(1, Ordering::Relaxed);
+                timing.map(|timing| (timing, scheduled.elapsed()))
+            }));
+        }
+
+        let mut response = Vec::with_capacity(tasks.len());
+        let mut service = Vec::with_capacity(tasks.len());
+        for (i, task) in tasks.into_iter().enumerate() {
+            let (timing, latency) = task
+                .await
+                .context("Task panicked")?
+                .context(format!("Failed to run open-loop operation {}", i))?;
+            if let Some(stream) = &self.config.stream {
+                stream.operation("open_loop", i as u32, &timing)?;
+            }
+            if let Some(monitor) = &self.config.monitor {
+                monitor.record(&timing);
+            }
+            if let Some(progress) = &self.config.progress {
+                progress.inc(1);
+            }
+            service.push(timing.reflink + timing.write);
+            response.push(latency);
+        }
+        let elapsed = start.elapsed();
+
+        Ok(OpenLoopResult {
+            target_rate: rate.0,
+            achieved_rate: operations as f64 / elapsed.as_secs_f64(),
+            operations,
+            duration: elapsed,
+            response_latency: LatencyStats::from_samples(&response),
+            service_latency: LatencyStats::from_samples(&service),
+            max_in_flight,
+        })
+    }
+
+    /// Runs each of `workload`'s operations in turn.
+    async fn run_sequential(
+        &self,
+        workload: &Arc<dyn Workload>,
+        completed: &Arc<AtomicU64>,
+    ) -> Result<(Duration, Vec<OpTiming>)> {
+        if self.config.runner == Runner::Threads {
+            // One thread for the whole pass rather than a pool hop per
+            // operation; a lone worker still runs them in order
+            let (duration, timings, _) = self
+                .run_concurrent_with_threads(workload, 1, Arc::clone(completed))
+                .await?;
+            return Ok((duration, timings));
+        }
+        let operations = workload.operations();
+        let mut timings = Vec::with_capacity(operations as usize);
         let start = Instant::now();
 
-        for i in 0..self.config.reflink_count {
-            let target_path = self.mount_point.join(format!("reflink_{}.dat", i));
-            self.create_reflink(source_file, &target_path)
+        for i in 0..operations {
+            let op = Arc::clone(workload);
+            let timing = tokio::task::spawn_blocking(move || op.run(i))
                 .await
-                .context(format!("Failed to create reflink {}", i))?;
+                .context("Task panicked")?
+                .context(format!("Failed to run {} operation {}", workload.name(), i))?;
+            if let Some(stream) = &self.config.stream {
+                stream.operation(workload.name(), i, &timing)?;
+            }
+            if let Some(monitor) = &self.config.monitor {
+                monitor.record(&timing);
+            }
+            if let Some(progress) = &self.config.progress {
+                progress.inc(1);
+            }
+            timings.push(timing);
+            completed.fetch_add(1, Ordering::Relaxed);
         }
 
-        Ok(start.elapsed())
+        Ok((start.elapsed(), timings))
     }
 
     async fn run_concurrent_benchmarks(
         &self,
-        source_file: &Path,
+        source_files: &[PathBuf],
     ) -> Result<ConcurrentBenchmarkResult> {
-        let thread_counts = vec![1, 2, 4, 8, 16, 32, 64, 128];
+        let thread_counts = self.config.thread_counts.clone();
         let mut durations = Vec::new();
         let mut operations_per_sec = Vec::new();
         let mut contention_ratios = Vec::new();
+        let mut latencies = Vec::new();
+        let mut throughput_series = Vec::new();
+        let mut cpu_usage = Vec::new();
+        let mut worker_balance = Vec::new();
 
-        let baseline_duration = self
-            .run_concurrent_with_threads(source_file, 1, "concurrent")
+        self.begin_phase("concurrent x1 (baseline)", self.config.reflink_count as u64);
+        let (baseline_duration, _, _) = self
+            .run_concurrent_with_threads(
+                &self.reflink_write("concurrent", source_files),
+                1,
+                Arc::default(),
+            )
             .await?;
         let baseline_ops_per_sec =
             self.config.reflink_count as f64 / baseline_duration.as_secs_f64();
@@ -130,13 +2277,28 @@ impl BenchmarkRunner {
         for &thread_count in &thread_counts {
             println!("  Testing with {} concurrent threads...", thread_count);
 
-            let duration = self
+            let phase = format!("concurrent x{}", thread_count);
+            self.begin_phase(&phase, self.config.reflink_count as u64);
+            let sampler = ThroughputSampler::start(&phase);
+            let cpu_before = CpuSnapshot::take()?;
+            let (duration, timings, workers) = self
                 .run_concurrent_with_threads(
-                    source_file,
+                    &self.reflink_write(&format!("concurrent_{}", thread_count), source_files),
                     thread_count,
-                    &format!("concurrent_{}", thread_count),
+                    sampler.counter(),
                 )
                 .await?;
+            cpu_usage.push(CpuUsage::between(
+                &phase,
+                &cpu_before,
+                &CpuSnapshot::take()?,
+            ));
+            throughput_series.push(sampler.finish().await);
+            self.stream_phase(
+                &format!("concurrent_{}", thread_count),
+                self.config.reflink_count as u64,
+                duration,
+            )?;
 
             let ops_per_sec = self.config.reflink_count as f64 / duration.as_secs_f64();
             let contention_ratio = baseline_ops_per_sec / ops_per_sec;
@@ -144,11 +2306,17 @@ impl BenchmarkRunner {
             durations.push(duration);
             operations_per_sec.push(ops_per_sec);
             contention_ratios.push(contention_ratio);
+            let latency = LatencyStats::from_samples(
+                &timings.iter().map(OpTiming::total).collect::<Vec<_>>(),
+            );
+            let balance = WorkerBalance::new(workers, duration);
 
             println!(
-                "    Duration: {:?}, Ops/sec: {:.2}, Contention ratio: {:.2}x",
-                duration, ops_per_sec, contention_ratio
+                "    Duration: {:?}, Ops/sec: {:.2}, Contention ratio: {:.2}x, p99: {:?}, Straggler: {:.2}x",
+                duration, ops_per_sec, contention_ratio, latency.p99, balance.straggler_ratio
             );
+            latencies.push(latency);
+            worker_balance.push(balance);
         }
 
         Ok(ConcurrentBenchmarkResult {
@@ -156,64 +2324,98 @@ impl BenchmarkRunner {
             durations,
             operations_per_sec,
             contention_ratios,
+            latencies,
+            throughput_series,
+            cpu_usage,
+            worker_balance,
         })
     }
 
+    /// Runs `workload`'s operations across `thread_count` workers.
     async fn run_concurrent_with_threads(
         &self,
-        source_file: &Path,
+        workload: &Arc<dyn Workload>,
         thread_count: u32,
-        prefix: &str,
-    ) -> Result<Duration> {
-        let semaphore = Arc::new(Semaphore::new(thread_count as usize));
-        let mut join_set = JoinSet::new();
-        let operations_per_thread = (self.config.reflink_count + thread_count - 1) / thread_count;
+        completed: Arc<AtomicU64>,
+    ) -> Result<(Duration, Vec<OpTiming>, Vec<WorkerTiming>)> {
+        // Workers pull the next operation index from a shared queue, so
+        // exactly `thread_count` operations are in flight and a slow worker
+        // simply takes fewer of them
+        let next_index = Arc::new(AtomicU32::new(0));
+        let operations = workload.operations();
+        let mut handles = Vec::new();
 
         let start = Instant::now();
 
-        for thread_id in 0..thread_count {
-            let semaphore = Arc::clone(&semaphore);
-            let source_file = source_file.to_path_buf();
-            let mount_point = self.mount_point.clone();
-            let start_idx = thread_id * operations_per_thread;
-            let end_idx = ((thread_id + 1) * operations_per_thread).min(self.config.reflink_count);
-            let prefix = prefix.to_string();
-
-            if start_idx >= self.config.reflink_count {
-                break;
-            }
+        for thread_id in 0..thread_count.min(operations) {
+            let next_index = Arc::clone(&next_index);
+            let workload = Arc::clone(workload);
+            let completed = Arc::clone(&completed);
+            let stream = self.config.stream.clone();
+            let monitor = self.config.monitor.clone();
+            let progress = self.config.progress.clone();
 
-            join_set.spawn(async move {
-                let _permit = semaphore.acquire().await.unwrap();
+            handles.push(self.config.runner.start(
+                move || -> Result<(Vec<OpTiming>, WorkerTiming)> {
+                    let worker_start = start.elapsed();
+                    let mut timings = Vec::new();
 
-                for i in start_idx..end_idx {
-                    let target_path = mount_point.join(format!("{}_{}.dat", prefix, i));
-                    let source_file_clone = source_file.clone();
+                    loop {
+                        let i = next_index.fetch_add(1, Ordering::Relaxed);
+                        if i >= operations || shutdown::requested() {
+                            break;
+                        }
 
-                    if let Err(e) = tokio::task::spawn_blocking(move || {
-                        Self::create_reflink_and_write_blocking(&source_file_clone, &target_path)
-                    })
-                    .await
-                    .context("Task panicked")?
-                    {
-                        eprintln!("Failed to create reflink and write {}: {}", i, e);
-                        return Err(e);
+                        match workload.run(i) {
+                            Ok(timing) => {
+                                if let Some(stream) = &stream {
+                                    stream.operation(workload.name(), i, &timing)?;
+                                }
+                                if let Some(monitor) = &monitor {
+                                    monitor.record(&timing);
+                                }
+                                if let Some(progress) = &progress {
+                                    progress.inc(1);
+                                }
+                                timings.push(timing);
+                                completed.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Err(e) => {
+                                eprintln!(
+                                    "Failed to run {} operation {}: {}",
+                                    workload.name(),
+                                    i,
+                                    e
+                                );
+                                return Err(e);
+                            }
+                        }
                     }
-                }
 
-                Ok(())
-            });
+                    let worker = WorkerTiming {
+                        worker: thread_id,
+                        start: worker_start,
+                        end: start.elapsed(),
+                        operations: timings.len() as u32,
+                    };
+                    Ok((timings, worker))
+                },
+            )?);
         }
 
-        // Wait for all tasks to complete
-        while let Some(result) = join_set.join_next().await {
-            result.context("Task panicked")??;
+        // Wait for all workers to complete
+        let mut timings = Vec::with_capacity(operations as usize);
+        let mut workers = Vec::with_capacity(thread_count as usize);
+        for handle in handles {
+            let (worker_timings, worker) = handle.join().await??;
+            timings.extend(worker_timings);
+            workers.push(worker);
         }
 
-        Ok(start.elapsed())
+        Ok((start.elapsed(), timings, workers))
     }
 
-    async fn create_reflink(&self, source: &Path, target: &Path) -> Result<()> {
+    async fn create_reflink(&self, source: &Path, target: &Path) -> Result<OpTiming> {
         let source = source.to_path_buf();
         let target = target.to_path_buf();
         tokio::task::spawn_blocking(move || {
@@ -223,9 +2425,21 @@ impl BenchmarkRunner {
         .context("Task panicked")?
     }
 
-    fn create_reflink_and_write_blocking(source: &Path, target: &Path) -> Result<()> {
+    pub(crate) fn create_reflink_and_write_blocking(
+        source: &Path,
+        target: &Path,
+    ) -> Result<OpTiming> {
+        Self::create_reflink_and_write_sized_blocking(source, target, DEFAULT_WRITE_SIZE)
+    }
+
+    pub(crate) fn create_reflink_and_write_sized_blocking(
+        source: &Path,
+        target: &Path,
+        write_size: usize,
+    ) -> Result<OpTiming> {
         use std::io::{Seek, SeekFrom, Write};
-        use std::os::unix::io::AsRawFd;
+
+        let reflink_start = Instant::now();
 
         // Open source file
         let source_file = std::fs::File::open(source).context("Failed to open source file")?;
@@ -239,42 +2453,35 @@ impl BenchmarkRunner {
             .open(target)
             .context("Failed to create target file")?;
 
-        // Use FICLONE ioctl for reflink operation
-        let result = unsafe {
-            libc::ioctl(
-                target_file.as_raw_fd(),
-                0x40049409, // FICLONE
-                source_file.as_raw_fd(),
-            )
-        };
-
-        if result != 0 {
-            let errno = std::io::Error::last_os_error();
+        if let Err(errno) = ioctl::ficlone(&target_file, &source_file) {
             anyhow::bail!(
                 "Reflink operation failed: {}. Filesystem may not support reflinks.",
                 errno
             );
         }
 
+        let reflink = reflink_start.elapsed();
+        let write_start = Instant::now();
+
         // Write some data to trigger copy-on-write
         // Write to the beginning of the file to ensure CoW is triggered
         target_file
             .seek(SeekFrom::Start(0))
             .context("Failed to seek to beginning of target file")?;
 
-        // Write a small amount of data (4KB) to trigger CoW without significantly affecting timing
-        let write_data = vec![0xAA; 4096];
+        // Defaults to a small amount of data (4KB) to trigger CoW without significantly affecting timing
+        let write_data = vec![WRITE_PATTERN; write_size];
         target_file
             .write_all(&write_data)
             .context("Failed to write to target file")?;
 
-        Ok(())
+        Ok(OpTiming {
+            reflink,
+            write: write_start.elapsed(),
+        })
     }
 
-    #[allow(dead_code)]
-    fn create_reflink_blocking(source: &Path, target: &Path) -> Result<()> {
-        use std::os::unix::io::AsRawFd;
-
+    pub(crate) fn create_reflink_blocking(source: &Path, target: &Path) -> Result<()> {
         // Open source file
         let source_file = std::fs::File::open(source).context("Failed to open source file")?;
 
@@ -286,17 +2493,7 @@ impl BenchmarkRunner {
             .open(target)
             .context("Failed to create target file")?;
 
-        // Use FICLONE ioctl for reflink operation
-        let result = unsafe {
-            libc::ioctl(
-                target_file.as_raw_fd(),
-                0x40049409, // FICLONE
-                source_file.as_raw_fd(),
-            )
-        };
-
-        if result != 0 {
-            let errno = std::io::Error::last_os_error();
+        if let Err(errno) = ioctl::ficlone(&target_file, &source_file) {
             anyhow::bail!(
                 "Reflink operation failed: {}. Filesystem may not support reflinks.",
                 errno
@@ -306,3 +2503,107 @@ impl BenchmarkRunner {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soak_drift_compares_first_and_last_quarters() {
+        assert_eq!(
+            drift(&[100.0, 100.0, 90.0, 80.0, 60.0, 40.0, 50.0, 50.0]),
+            -0.5
+        );
+        assert_eq!(drift(&[10.0, 15.0]), 0.5);
+        assert_eq!(drift(&[10.0]), 0.0);
+        assert_eq!(drift(&[]), 0.0);
+    }
+
+    #[test]
+    fn rates_accept_optional_units() {
+        assert_eq!("500".parse::<Rate>().unwrap(), Rate(500.0));
+        assert_eq!("500ops/s".parse::<Rate>().unwrap(), Rate(500.0));
+        assert_eq!("2.5/s".parse::<Rate>().unwrap(), Rate(2.5));
+        assert_eq!(Rate(500.0).to_string(), "500ops/s");
+        assert!("0".parse::<Rate>().is_err());
+        assert!("1e-20".parse::<Rate>().is_err());
+        assert!("fast".parse::<Rate>().is_err());
+    }
+
+    #[test]
+    fn parse_size_accepts_binary_suffixes() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("64k").unwrap(), 64 << 10);
+        assert_eq!(parse_size("64KB").unwrap(), 64 << 10);
+        assert_eq!(parse_size(" 1m ").unwrap(), 1 << 20);
+        assert_eq!(parse_size("2G").unwrap(), 2 << 30);
+        assert_eq!(parse_size("4t").unwrap(), 4 << 40);
+        assert!(parse_size("").is_err());
+        assert!(parse_size("4x").is_err());
+        assert!(parse_size("99999999999t").is_err());
+    }
+
+    #[test]
+    fn thread_counts_parse_lists_and_auto() {
+        assert_eq!("1, 4,16".parse::<ThreadCounts>().unwrap().0, vec![1, 4, 16]);
+        assert!("1,0".parse::<ThreadCounts>().is_err());
+        assert!("four".parse::<ThreadCounts>().is_err());
+
+        let auto = "auto".parse::<ThreadCounts>().unwrap().0;
+        assert_eq!(auto[0], 1);
+        assert!(auto.windows(2).all(|pair| pair[1] == pair[0] * 2));
+    }
+
+    #[test]
+    fn stable_hash_is_fnv1a() {
+        assert_eq!(stable_hash(""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash("a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn seeded_rng_streams_are_reproducible_and_independent() {
+        let draw = |seed, stream| {
+            let mut rng = seeded_rng(seed, stream);
+            (0..4).map(|_| rng.gen::<u64>()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(draw(7, "source_file.dat"), draw(7, "source_file.dat"));
+        assert_ne!(draw(7, "source_file.dat"), draw(8, "source_file.dat"));
+        assert_ne!(draw(7, "source_file.dat"), draw(7, "small-files"));
+    }
+
+    #[test]
+    fn repetition_summary_covers_each_metric() {
+        let runs: Vec<BenchmarkResult> = [100.0, 110.0, 120.0]
+            .into_iter()
+            .map(|ops| BenchmarkResult {
+                operations_per_sec: ops,
+                ..Default::default()
+            })
+            .collect();
+
+        let summary = RepetitionSummary::from_results(&runs);
+        assert_eq!(summary.runs, 3);
+        assert_eq!(summary.operations_per_sec.mean, 110.0);
+    }
+
+    #[test]
+    fn fragmented_sources_hold_the_same_data() {
+        let path = std::env::temp_dir().join(format!(
+            "reflink-bench-fragment-test-{}.dat",
+            std::process::id()
+        ));
+        let size = 3 * 1024 * 1024 + 8192;
+        write_fragmented(&path, size, 7, seeded_rng(42, "source")).unwrap();
+        let written = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut expected = vec![0u8; size as usize];
+        let mut rng = seeded_rng(42, "source");
+        for chunk in expected.chunks_mut(1024 * 1024) {
+            rng.fill(chunk);
+        }
+        assert!(written == expected);
+        assert!(!path.with_extension("spacer").exists());
+    }
+}
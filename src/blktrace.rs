@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// How long blktrace gets to attach to the device before the phase starts.
+const STARTUP_DELAY: Duration = Duration::from_millis(500);
+
+/// A running `blktrace` process recording one benchmark phase.
+pub struct BlktraceCapture {
+    child: Child,
+    /// Prefix of the per-CPU `<phase>.blktrace.<cpu>` files
+    output: PathBuf,
+}
+
+impl BlktraceCapture {
+    pub async fn start(device: &str, output_dir: &Path, phase: &str) -> Result<Self> {
+        std::fs::create_dir_all(output_dir).with_context(|| {
+            format!(
+                "Failed to create blktrace directory {}",
+                output_dir.display()
+            )
+        })?;
+
+        let mut child = Command::new("blktrace")
+            .arg("-d")
+            .arg(format!("/dev/{}", device))
+            .arg("-D")
+            .arg(output_dir)
+            .arg("-o")
+            .arg(phase)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start blktrace; is it installed?")?;
+
+        tokio::time::sleep(STARTUP_DELAY).await;
+        if child.try_wait()?.is_some() {
+            let output = child.wait_with_output()?;
+            anyhow::bail!(
+                "blktrace exited early: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(Self {
+            child,
+            output: output_dir.join(phase),
+        })
+    }
+
+    /// Stops tracing and returns the trace file prefix, suitable for
+    /// `blkparse -i`.
+    pub fn stop(mut self) -> Result<PathBuf> {
+        // blktrace flushes its per-CPU buffers on SIGINT
+        let result = unsafe { libc::kill(self.child.id() as libc::pid_t, libc::SIGINT) };
+        if result != 0 {
+            let errno = std::io::Error::last_os_error();
+            let _ = self.child.kill();
+            anyhow::bail!("Failed to signal blktrace: {}", errno);
+        }
+
+        let output = self.child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "blktrace failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(self.output)
+    }
+}
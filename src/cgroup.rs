@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::benchmark::parse_size;
+
+/// `io.max` limits for the benchmark's block device, e.g.
+/// `wbps=100m,riops=1000`. Byte rates take the same suffixes as `--write-sizes`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IoMax {
+    pub rbps: Option<u64>,
+    pub wbps: Option<u64>,
+    pub riops: Option<u64>,
+    pub wiops: Option<u64>,
+}
+
+impl FromStr for IoMax {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut limits = Self::default();
+        for part in s.split([',', ' ']).filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .with_context(|| format!("Expected key=value in io.max limits: {}", part))?;
+            match key {
+                "rbps" => limits.rbps = Some(parse_size(value)?),
+                "wbps" => limits.wbps = Some(parse_size(value)?),
+                "riops" => limits.riops = Some(value.parse().context("Invalid riops")?),
+                "wiops" => limits.wiops = Some(value.parse().context("Invalid wiops")?),
+                _ => anyhow::bail!(
+                    "Unknown io.max limit '{}' (expected rbps, wbps, riops, or wiops)",
+                    key
+                ),
+            }
+        }
+        if limits == Self::default() {
+            anyhow::bail!("No io.max limits given: {}", s);
+        }
+        Ok(limits)
+    }
+}
+
+impl Display for IoMax {
+    /// The kernel's `io.max` format, without the device: `wbps=104857600 riops=1000`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let limits: Vec<String> = [
+            ("rbps", self.rbps),
+            ("wbps", self.wbps),
+            ("riops", self.riops),
+            ("wiops", self.wiops),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some(format!("{}={}", key, value?)))
+        .collect();
+        write!(f, "{}", limits.join(" "))
+    }
+}
+
+/// A cgroup v2 group the benchmark moves itself into for
+/// `--cgroup-io-max` / `--cgroup-mem-max`, so reflinks are measured under
+/// the I/O throttling and memory pressure of a limited container. Page
+/// cache is charged to the group, so `memory.max` squeezes it as well.
+/// Dropping it moves the process back and removes the group.
+#[derive(Debug)]
+pub struct Cgroup {
+    path: PathBuf,
+    original: PathBuf,
+    io_max: Option<IoMax>,
+}
+
+impl Cgroup {
+    /// Creates a group under the cgroup v2 root with these limits and moves
+    /// this process, every thread included, into it. `io_max` is applied
+    /// per device with [`limit_device`](Self::limit_device).
+    pub fn enter(io_max: Option<IoMax>, memory_max: Option<u64>) -> Result<Self> {
+        let root = cgroup2_root()
+            .context("cgroup limits need the unified (v2) cgroup hierarchy mounted")?;
+        let current = std::fs::read_to_string("/proc/self/cgroup")
+            .context("Failed to read /proc/self/cgroup")?;
+        let current = current
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .context("Not in a cgroup v2 group")?;
+        let original = root.join(current.trim_start_matches('/'));
+
+        let mut controllers = Vec::new();
+        if io_max.is_some() {
+            controllers.push("io");
+        }
+        if memory_max.is_some() {
+            controllers.push("memory");
+        }
+        let available = std::fs::read_to_string(root.join("cgroup.controllers"))
+            .context("Failed to read cgroup.controllers")?;
+        for controller in &controllers {
+            if !available.split_whitespace().any(|c| c == *controller) {
+                anyhow::bail!("The cgroup {} controller is not available", controller);
+            }
+        }
+        let enable: Vec<String> = controllers.iter().map(|c| format!("+{}", c)).collect();
+        write(&root.join("cgroup.subtree_control"), &enable.join(" "))?;
+
+        let path = root.join(format!("reflink-bench-{}", std::process::id()));
+        std::fs::create_dir(&path)
+            .with_context(|| format!("Failed to create cgroup {}", path.display()))?;
+        let cgroup = Self {
+            path,
+            original,
+            io_max,
+        };
+        if let Some(bytes) = memory_max {
+            write(&cgroup.path.join("memory.max"), &bytes.to_string())?;
+        }
+        write(
+            &cgroup.path.join("cgroup.procs"),
+            &std::process::id().to_string(),
+        )?;
+        Ok(cgroup)
+    }
+
+    /// Applies the `io.max` limits, if any, to block device `device`
+    /// (e.g. `loop0`).
+    pub fn limit_device(&self, device: &str) -> Result<()> {
+        let Some(io_max) = &self.io_max else {
+            return Ok(());
+        };
+        let dev = std::fs::read_to_string(format!("/sys/class/block/{}/dev", device))
+            .with_context(|| format!("Failed to find device number of {}", device))?;
+        write(
+            &self.path.join("io.max"),
+            &format!("{} {}", dev.trim(), io_max),
+        )
+    }
+
+    pub fn io_max(&self) -> Option<&IoMax> {
+        self.io_max.as_ref()
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        let moved = write(
+            &self.original.join("cgroup.procs"),
+            &std::process::id().to_string(),
+        );
+        if let Err(e) = moved.and_then(|()| {
+            std::fs::remove_dir(&self.path)
+                .with_context(|| format!("Failed to remove {}", self.path.display()))
+        }) {
+            println!(
+                "⚠️  Failed to clean up cgroup {}: {:#}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Where the unified cgroup hierarchy is mounted, if it is.
+fn cgroup2_root() -> Option<PathBuf> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    mounts.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        (fields.get(2) == Some(&"cgroup2")).then(|| PathBuf::from(fields[1]))
+    })
+}
+
+fn write(path: &Path, value: &str) -> Result<()> {
+    std::fs::write(path, value)
+        .with_context(|| format!("Failed to write '{}' to {}", value, path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_max_parses_into_kernel_format() {
+        let limits: IoMax = "wbps=100m,riops=1000".parse().unwrap();
+        assert_eq!(limits.wbps, Some(100 << 20));
+        assert_eq!(limits.to_string(), "wbps=104857600 riops=1000");
+        assert!("wbps".parse::<IoMax>().is_err());
+        assert!("rate=5".parse::<IoMax>().is_err());
+        assert!("".parse::<IoMax>().is_err());
+    }
+}
@@ -0,0 +1,38 @@
+/// Eighth-block characters for sub-character bar resolution.
+const PARTIAL_BLOCKS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A horizontal bar `width` characters long at `max`.
+pub fn bar(value: f64, max: f64, width: usize) -> String {
+    if max <= 0.0 || value <= 0.0 {
+        return String::new();
+    }
+
+    let eighths = (value / max * width as f64 * 8.0).round() as usize;
+    let (full, partial) = (eighths / 8, eighths % 8);
+    let mut bar = "█".repeat(full);
+    if partial > 0 {
+        bar.push(PARTIAL_BLOCKS[partial]);
+    }
+    bar
+}
+
+/// A one-line sparkline scaled between the series' minimum and maximum.
+pub fn sparkline(values: &[f64]) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+
+    values
+        .iter()
+        .map(|&value| {
+            if range <= 0.0 {
+                SPARK_LEVELS[SPARK_LEVELS.len() / 2]
+            } else {
+                let level = ((value - min) / range * (SPARK_LEVELS.len() - 1) as f64).round();
+                SPARK_LEVELS[level as usize]
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::benchmark::{stable_hash, BenchmarkResult};
+use crate::filesystem::FilesystemType;
+use crate::suite::Scenario;
+
+/// File name used for the checkpoint when `--checkpoint` isn't given.
+pub const DEFAULT_FILE_NAME: &str = "reflink-bench-checkpoint.json";
+
+/// Progress of a run, saved after each filesystem finishes so an
+/// interrupted run can pick up where it stopped with `--resume`.
+pub struct Checkpoint {
+    path: PathBuf,
+    state: CheckpointState,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CheckpointState {
+    /// Identifies the scenarios the checkpoint was written for
+    fingerprint: String,
+    /// Seed given to (or drawn for) the run, reused on resume
+    seed: u64,
+    completed: Vec<CompletedFilesystem>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompletedFilesystem {
+    scenario: Option<String>,
+    filesystem: String,
+    result: BenchmarkResult,
+}
+
+impl Checkpoint {
+    /// Reads an existing checkpoint to resume from.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            anyhow::bail!(
+                "No checkpoint at {} to resume from; start the run without --resume",
+                path.display()
+            );
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read checkpoint {}", path.display()))?;
+        let state = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse checkpoint {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            state,
+        })
+    }
+
+    /// Starts a fresh checkpoint for `scenarios`, replacing any left at `path`.
+    pub fn create(path: &Path, scenarios: &[Scenario], seed: u64) -> Result<Self> {
+        let checkpoint = Self {
+            path: path.to_path_buf(),
+            state: CheckpointState {
+                fingerprint: fingerprint(scenarios),
+                seed,
+                completed: Vec::new(),
+            },
+        };
+        checkpoint.save()?;
+        Ok(checkpoint)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.state.seed
+    }
+
+    /// Fails unless the checkpoint was written for the same scenarios.
+    pub fn check_matches(&self, scenarios: &[Scenario]) -> Result<()> {
+        if self.state.fingerprint != fingerprint(scenarios) {
+            anyhow::bail!(
+                "Checkpoint {} was written for a different configuration; resume with the same flags and --config, or drop --resume to start over",
+                self.path.display()
+            );
+        }
+        Ok(())
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.state.completed.len()
+    }
+
+    /// The saved result for one filesystem of a scenario, if it finished.
+    pub fn completed(
+        &self,
+        scenario: &Scenario,
+        fs_type: FilesystemType,
+    ) -> Option<&BenchmarkResult> {
+        self.state
+            .completed
+            .iter()
+            .find(|done| done.scenario == scenario.name && done.filesystem == fs_type.to_string())
+            .map(|done| &done.result)
+    }
+
+    /// Records a finished filesystem and writes the checkpoint out.
+    pub fn record(
+        &mut self,
+        scenario: &Scenario,
+        fs_type: FilesystemType,
+        result: &BenchmarkResult,
+    ) -> Result<()> {
+        self.state.completed.push(CompletedFilesystem {
+            scenario: scenario.name.clone(),
+            filesystem: fs_type.to_string(),
+            result: result.clone(),
+        });
+        self.save()
+    }
+
+    /// Removes the checkpoint once the whole run has finished.
+    pub fn finish(self) -> Result<()> {
+        std::fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to remove checkpoint {}", self.path.display()))
+    }
+
+    fn save(&self) -> Result<()> {
+        // Write then rename, so an interruption mid-write leaves the old one
+        let temp = self.path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_string_pretty(&self.state)?)
+            .with_context(|| format!("Failed to write checkpoint {}", temp.display()))?;
+        std::fs::rename(&temp, &self.path)
+            .with_context(|| format!("Failed to write checkpoint {}", self.path.display()))
+    }
+}
+
+fn fingerprint(scenarios: &[Scenario]) -> String {
+    format!("{:016x}", stable_hash(&format!("{:?}", scenarios)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::BenchmarkConfig;
+
+    fn scenario(name: &str, file_size_mb: u64) -> Scenario {
+        Scenario {
+            name: Some(name.to_string()),
+            filesystems: vec![FilesystemType::Xfs, FilesystemType::Btrfs],
+            fs_size_gb: 2,
+            repeat: 1,
+            mount_options: Default::default(),
+            backing_paths: Default::default(),
+            sweep: None,
+            preset: None,
+            config: BenchmarkConfig {
+                file_size_mb,
+                ..BenchmarkConfig::default()
+            },
+        }
+    }
+
+    #[test]
+    fn progress_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!(
+            "reflink-bench-checkpoint-test-{}.json",
+            std::process::id()
+        ));
+        let scenarios = vec![scenario("small", 10)];
+
+        let mut checkpoint = Checkpoint::create(&path, &scenarios, 42).unwrap();
+        let result = BenchmarkResult {
+            operations_per_sec: 123.0,
+            ..Default::default()
+        };
+        checkpoint
+            .record(&scenarios[0], FilesystemType::Xfs, &result)
+            .unwrap();
+
+        let resumed = Checkpoint::load(&path).unwrap();
+        resumed.check_matches(&scenarios).unwrap();
+        assert_eq!(resumed.seed(), 42);
+        assert_eq!(resumed.completed_count(), 1);
+        assert_eq!(
+            resumed
+                .completed(&scenarios[0], FilesystemType::Xfs)
+                .map(|r| r.operations_per_sec),
+            Some(123.0)
+        );
+        assert!(resumed
+            .completed(&scenarios[0], FilesystemType::Btrfs)
+            .is_none());
+
+        assert!(resumed.check_matches(&[scenario("small", 20)]).is_err());
+
+        resumed.finish().unwrap();
+        assert!(!path.exists());
+        assert!(Checkpoint::load(&path).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use tabled::{Table, Tabled};
+
+use crate::benchmark::BenchmarkResult;
+use crate::results::ComparisonResults;
+
+/// Whether an increase in a metric is an improvement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    HigherIsBetter,
+    LowerIsBetter,
+}
+
+/// One metric of one filesystem in two result sets.
+#[derive(Debug, Clone)]
+pub struct MetricDelta {
+    pub filesystem: String,
+    pub metric: String,
+    pub old: f64,
+    pub new: f64,
+    pub direction: Direction,
+}
+
+impl MetricDelta {
+    /// Change from old to new as a percentage of old.
+    pub fn change_percent(&self) -> f64 {
+        (self.new - self.old) / self.old.abs().max(f64::EPSILON) * 100.0
+    }
+
+    /// How much worse the new value is, in percent; negative when it improved.
+    pub fn regression_percent(&self) -> f64 {
+        match self.direction {
+            Direction::HigherIsBetter => -self.change_percent(),
+            Direction::LowerIsBetter => self.change_percent(),
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct DeltaRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Metric")]
+    metric: String,
+    #[tabled(rename = "Old")]
+    old: String,
+    #[tabled(rename = "New")]
+    new: String,
+    #[tabled(rename = "Change")]
+    change: String,
+}
+
+pub fn load_results(path: &Path) -> Result<ComparisonResults> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read results file {}", path.display()))?;
+    ComparisonResults::from_json(&json)
+        .with_context(|| format!("Failed to parse results file {}", path.display()))
+}
+
+/// Deltas for every key metric present for the same filesystem in both
+/// result sets.
+pub fn metric_deltas(old: &ComparisonResults, new: &ComparisonResults) -> Vec<MetricDelta> {
+    let mut deltas = Vec::new();
+
+    for (filesystem, new_result) in &new.results {
+        let Some((_, old_result)) = old.results.iter().find(|(fs, _)| fs == filesystem) else {
+            continue;
+        };
+
+        let old_metrics = key_metrics(old_result);
+        for (metric, new_value, direction) in key_metrics(new_result) {
+            if let Some((_, old_value, _)) = old_metrics.iter().find(|(m, _, _)| *m == metric) {
+                deltas.push(MetricDelta {
+                    filesystem: filesystem.clone(),
+                    metric,
+                    old: *old_value,
+                    new: new_value,
+                    direction,
+                });
+            }
+        }
+    }
+
+    deltas
+}
+
+/// Headline numbers of one run, named so they can be matched across runs.
+pub fn key_metrics(result: &BenchmarkResult) -> Vec<(String, f64, Direction)> {
+    use Direction::*;
+
+    let micros = |d: std::time::Duration| d.as_secs_f64() * 1_000_000.0;
+    let mut metrics = vec![
+        (
+            "sequential ops/sec".to_string(),
+            result.operations_per_sec,
+            HigherIsBetter,
+        ),
+        (
+            "sequential MB/s".to_string(),
+            result.throughput_mb_per_sec,
+            HigherIsBetter,
+        ),
+    ];
+
+    // Results upgraded from before latencies were recorded have no samples
+    for (operation, stats) in [
+        ("reflink", &result.reflink_latency),
+        ("CoW write", &result.write_latency),
+    ] {
+        if stats.count == 0 {
+            continue;
+        }
+        metrics.push((
+            format!("{} p50 (µs)", operation),
+            micros(stats.p50),
+            LowerIsBetter,
+        ));
+        metrics.push((
+            format!("{} p99 (µs)", operation),
+            micros(stats.p99),
+            LowerIsBetter,
+        ));
+    }
+
+    let concurrent = &result.concurrent_results;
+    for (threads, ops) in concurrent
+        .thread_counts
+        .iter()
+        .zip(&concurrent.operations_per_sec)
+    {
+        metrics.push((format!("{} threads ops/sec", threads), *ops, HigherIsBetter));
+    }
+
+    for w in &result.write_size_results {
+        metrics.push((
+            format!("write-size {} ops/sec", w.write_size_bytes),
+            w.operations_per_sec,
+            HigherIsBetter,
+        ));
+    }
+
+    for w in &result.workload_results {
+        metrics.push((
+            format!("{} ops/sec", w.workload),
+            w.operations_per_sec,
+            HigherIsBetter,
+        ));
+    }
+
+    metrics
+}
+
+pub fn print_deltas(deltas: &[MetricDelta]) {
+    let rows: Vec<DeltaRow> = deltas
+        .iter()
+        .map(|d| {
+            let marker = if d.regression_percent() > 0.0 {
+                "📉"
+            } else {
+                "📈"
+            };
+            DeltaRow {
+                filesystem: d.filesystem.clone(),
+                metric: d.metric.clone(),
+                old: format!("{:.2}", d.old),
+                new: format!("{:.2}", d.new),
+                change: format!("{} {:+.1}%", marker, d.change_percent()),
+            }
+        })
+        .collect();
+
+    let table = Table::new(rows);
+    println!("{}", table);
+}
+
+/// Parses a percentage such as `10%` or `2.5`.
+pub fn parse_percent(s: &str) -> Result<f64> {
+    let value: f64 = s
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .with_context(|| format!("Invalid percentage: {}", s))?;
+    if value < 0.0 {
+        anyhow::bail!("Percentage must not be negative: {}", s);
+    }
+    Ok(value)
+}
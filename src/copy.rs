@@ -0,0 +1,310 @@
+use anyhow::{Context, Result};
+use nix::errno::Errno;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs::File;
+use std::os::unix::fs::{FileExt, MetadataExt};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::ioctl;
+use crate::stats::LatencyStats;
+
+/// Copies of the source each strategy makes, in the cross-filesystem and
+/// copy baseline phases.
+pub const COPIES_PER_STRATEGY: u32 = 8;
+
+/// Bytes per read and write in the read/write loop.
+const COPY_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// How an application copies a file it can't reflink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyStrategy {
+    /// `copy_file_range(2)`, which copies in the kernel and may offload
+    /// or reflink where the filesystems allow it
+    CopyFileRange,
+    /// `sendfile(2)`, which copies in the kernel through the page cache
+    Sendfile,
+    /// `pread`/`pwrite` in 1 MB chunks through a userspace buffer
+    ReadWrite,
+}
+
+impl Display for CopyStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CopyStrategy::CopyFileRange => write!(f, "copy_file_range"),
+            CopyStrategy::Sendfile => write!(f, "sendfile"),
+            CopyStrategy::ReadWrite => write!(f, "read/write"),
+        }
+    }
+}
+
+impl CopyStrategy {
+    pub const ALL: [CopyStrategy; 3] = [
+        CopyStrategy::CopyFileRange,
+        CopyStrategy::Sendfile,
+        CopyStrategy::ReadWrite,
+    ];
+
+    /// Strategies that always copy the data. `copy_file_range` is left out
+    /// because within xfs or btrfs it reflinks instead.
+    pub const BASELINES: [CopyStrategy; 2] = [CopyStrategy::Sendfile, CopyStrategy::ReadWrite];
+
+    /// Copies the first `len` bytes of `source` to the start of `target`.
+    pub fn copy(self, source: &File, target: &File, len: u64) -> std::io::Result<()> {
+        match self {
+            CopyStrategy::CopyFileRange => {
+                let (mut off_in, mut off_out): (libc::loff_t, libc::loff_t) = (0, 0);
+                while (off_in as u64) < len {
+                    let copied = unsafe {
+                        libc::copy_file_range(
+                            source.as_raw_fd(),
+                            &mut off_in,
+                            target.as_raw_fd(),
+                            &mut off_out,
+                            (len - off_in as u64) as usize,
+                            0,
+                        )
+                    };
+                    match copied {
+                        0 => break,
+                        n if n < 0 => return Err(std::io::Error::last_os_error()),
+                        _ => {}
+                    }
+                }
+            }
+            CopyStrategy::Sendfile => {
+                let mut offset: libc::off_t = 0;
+                while (offset as u64) < len {
+                    let sent = unsafe {
+                        libc::sendfile(
+                            target.as_raw_fd(),
+                            source.as_raw_fd(),
+                            &mut offset,
+                            (len - offset as u64) as usize,
+                        )
+                    };
+                    match sent {
+                        0 => break,
+                        n if n < 0 => return Err(std::io::Error::last_os_error()),
+                        _ => {}
+                    }
+                }
+            }
+            CopyStrategy::ReadWrite => {
+                let mut buf = vec![0u8; COPY_CHUNK_BYTES];
+                let mut offset = 0;
+                while offset < len {
+                    let want = (len - offset).min(COPY_CHUNK_BYTES as u64) as usize;
+                    let n = source.read_at(&mut buf[..want], offset)?;
+                    if n == 0 {
+                        break;
+                    }
+                    target.write_all_at(&buf[..n], offset)?;
+                    offset += n as u64;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An attempt to clone across filesystems and the copies an application
+/// falls back to, for `--cross-fs`. `FICLONE` between two mounts fails
+/// with `EXDEV`, so "reflink or copy" code pays for the attempt and then
+/// for a full copy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossFsResult {
+    /// The directory on the other filesystem copies went to
+    pub target: String,
+    /// What `FICLONE` failed with, e.g. `EXDEV`; `None` if it cloned
+    pub ficlone_error: Option<String>,
+    /// Time for each `FICLONE` attempt to return
+    pub ficlone_latency: LatencyStats,
+    pub fallbacks: Vec<CopyResult>,
+}
+
+/// One copy strategy's copies of the source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopyResult {
+    pub strategy: String,
+    /// What the first copy failed with, leaving no samples; `None` if it
+    /// copied
+    pub error: Option<String>,
+    /// Each copy through `fdatasync`, so the data has left the page cache
+    pub latency: LatencyStats,
+    pub throughput_mb_per_sec: f64,
+}
+
+/// Tries `FICLONE` from `source` into a new directory under `target_dir`
+/// [`COPIES_PER_STRATEGY`] times, then copies it there with each
+/// [`CopyStrategy`], and removes the directory again. `target_dir` has to
+/// be on another filesystem than `source`.
+///
+/// This is blocking and should be called from `spawn_blocking`.
+pub fn run_cross_fs(source: &Path, target_dir: &Path) -> Result<CrossFsResult> {
+    let source_dev = std::fs::metadata(source)
+        .context("Failed to stat source file")?
+        .dev();
+    let target_dev = std::fs::metadata(target_dir)
+        .with_context(|| format!("Failed to stat {}", target_dir.display()))?
+        .dev();
+    if source_dev == target_dev {
+        anyhow::bail!(
+            "{} is on the benchmarked filesystem; --cross-fs needs a directory on another mount",
+            target_dir.display()
+        );
+    }
+
+    let dir = target_dir.join(format!("reflink-bench-cross-fs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let result = copy_into(source, &dir);
+    std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to remove {}", dir.display()))?;
+    Ok(CrossFsResult {
+        target: target_dir.display().to_string(),
+        ..result?
+    })
+}
+
+fn copy_into(source_path: &Path, dir: &Path) -> Result<CrossFsResult> {
+    let source = File::open(source_path).context("Failed to open source file")?;
+    let len = source
+        .metadata()
+        .context("Failed to stat source file")?
+        .len();
+
+    let mut ficlone_error = None;
+    let mut ficlone_samples = Vec::new();
+    for i in 0..COPIES_PER_STRATEGY {
+        let target = create_target(dir, &format!("ficlone_{}.dat", i))?;
+        let start = Instant::now();
+        let cloned = ioctl::ficlone(&target, &source);
+        ficlone_samples.push(start.elapsed());
+        ficlone_error = cloned.err().map(|e| errno_name(&e));
+    }
+
+    let fallbacks = CopyStrategy::ALL
+        .into_iter()
+        .map(|strategy| time_copies(strategy, &source, len, dir, false))
+        .collect::<Result<_>>()?;
+
+    Ok(CrossFsResult {
+        target: String::new(),
+        ficlone_error,
+        ficlone_latency: LatencyStats::from_samples(&ficlone_samples),
+        fallbacks,
+    })
+}
+
+/// Copies `source` within its own filesystem [`COPIES_PER_STRATEGY`]
+/// times with each of the [baseline](CopyStrategy::BASELINES) strategies,
+/// for `--copy-baselines`, in a new directory `dir` that is removed again.
+/// Each copy is deleted once it's timed, so only one takes up space at a
+/// time.
+///
+/// This is blocking and should be called from `spawn_blocking`.
+pub fn run_copy_baselines(source_path: &Path, dir: &Path) -> Result<Vec<CopyResult>> {
+    std::fs::create_dir_all(dir).context("Failed to create copy baseline directory")?;
+    let source = File::open(source_path).context("Failed to open source file")?;
+    let len = source
+        .metadata()
+        .context("Failed to stat source file")?
+        .len();
+    let results = CopyStrategy::BASELINES
+        .into_iter()
+        .map(|strategy| time_copies(strategy, &source, len, dir, true))
+        .collect::<Result<Vec<_>>>()?;
+    std::fs::remove_dir_all(dir).context("Failed to remove copy baseline directory")?;
+    Ok(results)
+}
+
+/// Times [`COPIES_PER_STRATEGY`] copies of `source` into new files in
+/// `dir`, each through `fdatasync`, deleting each one afterwards with
+/// `remove`. Stops at the first copy that fails and reports its error.
+fn time_copies(
+    strategy: CopyStrategy,
+    source: &File,
+    len: u64,
+    dir: &Path,
+    remove: bool,
+) -> Result<CopyResult> {
+    let mut error = None;
+    let mut samples = Vec::new();
+    for i in 0..COPIES_PER_STRATEGY {
+        let name = format!("{}_{}.dat", strategy.to_string().replace('/', "_"), i);
+        let target = create_target(dir, &name)?;
+        let start = Instant::now();
+        let copied = strategy
+            .copy(source, &target, len)
+            .and_then(|()| target.sync_data());
+        if let Err(e) = copied {
+            error = Some(errno_name(&e));
+            break;
+        }
+        samples.push(start.elapsed());
+        if remove {
+            std::fs::remove_file(dir.join(&name))
+                .with_context(|| format!("Failed to remove {}", name))?;
+        }
+    }
+    let total: Duration = samples.iter().sum();
+    Ok(CopyResult {
+        strategy: strategy.to_string(),
+        error,
+        throughput_mb_per_sec: if samples.is_empty() {
+            0.0
+        } else {
+            (len * samples.len() as u64) as f64 / (1024.0 * 1024.0) / total.as_secs_f64()
+        },
+        latency: LatencyStats::from_samples(&samples),
+    })
+}
+
+fn create_target(dir: &Path, name: &str) -> Result<File> {
+    File::create(dir.join(name)).with_context(|| format!("Failed to create {}", name))
+}
+
+/// The errno's symbolic name, e.g. `EXDEV`, or the error's message when it
+/// has none.
+fn errno_name(error: &std::io::Error) -> String {
+    match error.raw_os_error() {
+        Some(errno) => format!("{:?}", Errno::from_i32(errno)),
+        None => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategies_copy_the_whole_file() {
+        let dir = std::env::temp_dir().join(format!("reflink-bench-copy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data: Vec<u8> = (0..3 * COPY_CHUNK_BYTES + 123)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let source_path = dir.join("source.dat");
+        std::fs::write(&source_path, &data).unwrap();
+        let source = File::open(&source_path).unwrap();
+        for strategy in CopyStrategy::ALL {
+            let target_path = dir.join("target.dat");
+            let target = File::create(&target_path).unwrap();
+            strategy.copy(&source, &target, data.len() as u64).unwrap();
+            assert_eq!(std::fs::read(&target_path).unwrap(), data, "{}", strategy);
+        }
+
+        let baselines = run_copy_baselines(&source_path, &dir.join("baselines")).unwrap();
+        assert_eq!(baselines.len(), CopyStrategy::BASELINES.len());
+        for baseline in &baselines {
+            assert_eq!(baseline.error, None);
+            assert_eq!(baseline.latency.count, COPIES_PER_STRATEGY as usize);
+        }
+        assert!(!dir.join("baselines").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let exdev = std::io::Error::from_raw_os_error(libc::EXDEV);
+        assert_eq!(errno_name(&exdev), "EXDEV");
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Process rusage and system-wide CPU counters at one point in time.
+#[derive(Debug, Clone, Copy)]
+pub struct CpuSnapshot {
+    taken_at: Instant,
+    user_time: Duration,
+    system_time: Duration,
+    voluntary_switches: i64,
+    involuntary_switches: i64,
+    /// Jiffies from the aggregate `cpu` line of /proc/stat
+    system_busy: u64,
+    system_total: u64,
+}
+
+impl CpuSnapshot {
+    pub fn take() -> Result<Self> {
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        let result = unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+        if result != 0 {
+            let errno = std::io::Error::last_os_error();
+            anyhow::bail!("getrusage failed: {}", errno);
+        }
+
+        let (system_busy, system_total) = read_proc_stat()?;
+
+        Ok(Self {
+            taken_at: Instant::now(),
+            user_time: timeval_to_duration(usage.ru_utime),
+            system_time: timeval_to_duration(usage.ru_stime),
+            voluntary_switches: usage.ru_nvcsw,
+            involuntary_switches: usage.ru_nivcsw,
+            system_busy,
+            system_total,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuUsage {
+    pub phase: String,
+    /// Process CPU time as a percentage of one core (800% = 8 busy cores)
+    pub process_cpu_percent: f64,
+    /// Fraction of process CPU time spent in the kernel
+    pub system_time_share: f64,
+    /// Machine-wide busy percentage from /proc/stat
+    pub machine_cpu_percent: f64,
+    pub voluntary_context_switches: i64,
+    pub involuntary_context_switches: i64,
+}
+
+impl CpuUsage {
+    pub fn between(phase: &str, before: &CpuSnapshot, after: &CpuSnapshot) -> Self {
+        let wall = after.taken_at.duration_since(before.taken_at).as_secs_f64();
+        let user = (after.user_time.saturating_sub(before.user_time)).as_secs_f64();
+        let system = (after.system_time.saturating_sub(before.system_time)).as_secs_f64();
+        let busy = after.system_busy.saturating_sub(before.system_busy) as f64;
+        let total = after.system_total.saturating_sub(before.system_total) as f64;
+
+        Self {
+            phase: phase.to_string(),
+            process_cpu_percent: (user + system) / wall.max(f64::EPSILON) * 100.0,
+            system_time_share: system / (user + system).max(f64::EPSILON),
+            machine_cpu_percent: busy / total.max(1.0) * 100.0,
+            voluntary_context_switches: after.voluntary_switches - before.voluntary_switches,
+            involuntary_context_switches: after.involuntary_switches - before.involuntary_switches,
+        }
+    }
+}
+
+fn timeval_to_duration(tv: libc::timeval) -> Duration {
+    Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+}
+
+/// Returns `(busy, total)` jiffies across all CPUs.
+fn read_proc_stat() -> Result<(u64, u64)> {
+    let contents = std::fs::read_to_string("/proc/stat").context("Failed to read /proc/stat")?;
+    let line = contents
+        .lines()
+        .find(|line| line.starts_with("cpu "))
+        .context("No aggregate cpu line in /proc/stat")?;
+
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|field| field.parse().ok())
+        .collect();
+
+    // user nice system idle iowait irq softirq steal ...
+    let total: u64 = values.iter().take(8).sum();
+    let idle = values.get(3).copied().unwrap_or(0) + values.get(4).copied().unwrap_or(0);
+
+    Ok((total.saturating_sub(idle), total))
+}
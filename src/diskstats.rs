@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// /proc/diskstats always counts in 512-byte sectors.
+const SECTOR_SIZE: u64 = 512;
+
+/// A snapshot of one block device's counters from `/proc/diskstats`.
+#[derive(Debug, Clone, Copy)]
+pub struct DiskStats {
+    pub taken_at: Instant,
+    pub reads_completed: u64,
+    pub writes_completed: u64,
+    pub sectors_written: u64,
+    /// Milliseconds spent on completed reads and writes
+    pub read_ticks_ms: u64,
+    pub write_ticks_ms: u64,
+    /// Milliseconds the device had at least one request in flight
+    pub io_ticks_ms: u64,
+    /// Milliseconds of request time summed over all in-flight requests
+    pub weighted_ticks_ms: u64,
+}
+
+impl DiskStats {
+    pub fn sample(device: &str) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string("/proc/diskstats").context("Failed to read /proc/diskstats")?;
+
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 14 || fields[2] != device {
+                continue;
+            }
+
+            let field = |index: usize| -> Result<u64> {
+                fields[index]
+                    .parse()
+                    .with_context(|| format!("Invalid field {} in /proc/diskstats", index))
+            };
+            return Ok(Self {
+                taken_at: Instant::now(),
+                reads_completed: field(3)?,
+                read_ticks_ms: field(6)?,
+                writes_completed: field(7)?,
+                sectors_written: field(9)?,
+                write_ticks_ms: field(10)?,
+                io_ticks_ms: field(12)?,
+                weighted_ticks_ms: field(13)?,
+            });
+        }
+
+        anyhow::bail!("Device {} not found in /proc/diskstats", device)
+    }
+
+    pub fn bytes_written_since(&self, earlier: &DiskStats) -> u64 {
+        self.sectors_written.saturating_sub(earlier.sectors_written) * SECTOR_SIZE
+    }
+}
+
+/// iostat-style device activity over one benchmark phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceStats {
+    pub phase: String,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    /// Average number of requests in flight (iostat's aqu-sz)
+    pub avg_queue_depth: f64,
+    /// Average time per completed request, including queueing
+    pub await_ms: f64,
+    /// Share of wall time the device was busy (iostat's %util)
+    pub utilization: f64,
+}
+
+impl DeviceStats {
+    pub fn new(phase: &str, before: &DiskStats, after: &DiskStats) -> Self {
+        let elapsed_ms = (after.taken_at.duration_since(before.taken_at).as_secs_f64() * 1000.0)
+            .max(f64::EPSILON);
+        let reads = after.reads_completed.saturating_sub(before.reads_completed);
+        let writes = after
+            .writes_completed
+            .saturating_sub(before.writes_completed);
+        let ticks = after.read_ticks_ms.saturating_sub(before.read_ticks_ms)
+            + after.write_ticks_ms.saturating_sub(before.write_ticks_ms);
+        let io_ticks = after.io_ticks_ms.saturating_sub(before.io_ticks_ms);
+        let weighted = after
+            .weighted_ticks_ms
+            .saturating_sub(before.weighted_ticks_ms);
+
+        Self {
+            phase: phase.to_string(),
+            read_iops: reads as f64 * 1000.0 / elapsed_ms,
+            write_iops: writes as f64 * 1000.0 / elapsed_ms,
+            avg_queue_depth: weighted as f64 / elapsed_ms,
+            await_ms: ticks as f64 / (reads + writes).max(1) as f64,
+            utilization: (io_ticks as f64 / elapsed_ms).min(1.0),
+        }
+    }
+
+    pub fn iops(&self) -> f64 {
+        self.read_iops + self.write_iops
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteAmplification {
+    pub phase: String,
+    /// Bytes the benchmark asked to modify
+    pub logical_bytes: u64,
+    /// Bytes the block device actually wrote
+    pub physical_bytes: u64,
+    pub amplification: f64,
+}
+
+impl WriteAmplification {
+    pub fn new(phase: &str, logical_bytes: u64, before: &DiskStats, after: &DiskStats) -> Self {
+        let physical_bytes = after.bytes_written_since(before);
+        Self {
+            phase: phase.to_string(),
+            logical_bytes,
+            physical_bytes,
+            amplification: physical_bytes as f64 / logical_bytes.max(1) as f64,
+        }
+    }
+}
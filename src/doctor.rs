@@ -0,0 +1,265 @@
+use anyhow::Result;
+use std::path::Path;
+use tabled::{Table, Tabled};
+
+use crate::environment;
+use crate::filesystem::{self, FilesystemType};
+
+/// CAP_SYS_ADMIN's bit in the capability sets of /proc/self/status.
+const CAP_SYS_ADMIN: u32 = 21;
+
+/// FICLONE first appeared in Linux 4.5.
+const FICLONE_SINCE: (u32, u32) = (4, 5);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+    fix: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            fix: String::new(),
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = fix.into();
+        self
+    }
+}
+
+#[derive(Tabled)]
+struct CheckRow {
+    #[tabled(rename = "")]
+    status: &'static str,
+    #[tabled(rename = "Check")]
+    name: String,
+    #[tabled(rename = "Result")]
+    detail: String,
+    #[tabled(rename = "Fix")]
+    fix: String,
+}
+
+/// Checks everything a run against `filesystems` needs, printing what's
+/// missing and how to fix it. Fails if the run would.
+pub fn run_checks(filesystems: &[FilesystemType], workdir: &Path, fs_size_gb: u64) -> Result<()> {
+    println!("🩺 PREFLIGHT CHECKS");
+    println!("===================");
+
+    let mut checks = vec![check_privileges()];
+    checks.push(check_command("losetup", "install util-linux"));
+    for fs_type in filesystems {
+        checks.push(check_command(
+            fs_type.mkfs_command(),
+            &format!("install {}", package_for(*fs_type)),
+        ));
+    }
+    checks.push(check_loop_support());
+    checks.push(check_ficlone());
+    for &fs_type in filesystems {
+        checks.push(check_kernel_filesystem(fs_type));
+    }
+    checks.extend(check_workdir(workdir, fs_size_gb, filesystems.len() as u64));
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    let warnings = checks.iter().filter(|c| c.status == Status::Warn).count();
+    let rows: Vec<CheckRow> = checks
+        .into_iter()
+        .map(|check| CheckRow {
+            status: match check.status {
+                Status::Ok => "✅",
+                Status::Warn => "⚠️",
+                Status::Fail => "❌",
+            },
+            name: check.name,
+            detail: check.detail,
+            fix: check.fix,
+        })
+        .collect();
+    println!("{}", Table::new(rows));
+
+    if failed > 0 {
+        anyhow::bail!(
+            "{} check(s) failed; fix them before running benchmarks",
+            failed
+        );
+    }
+    if warnings > 0 {
+        println!("\n⚠️  {} warning(s); runs may still fail", warnings);
+    } else {
+        println!("\n✅ Ready to run");
+    }
+    Ok(())
+}
+
+fn check_privileges() -> Check {
+    if unsafe { libc::geteuid() } == 0 {
+        return Check::new("Privileges", Status::Ok, "running as root");
+    }
+
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    let has_sys_admin = status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|caps| u64::from_str_radix(caps.trim(), 16).ok())
+        .is_some_and(|caps| caps & (1 << CAP_SYS_ADMIN) != 0);
+    if has_sys_admin {
+        Check::new("Privileges", Status::Ok, "CAP_SYS_ADMIN")
+    } else {
+        Check::new(
+            "Privileges",
+            Status::Fail,
+            "not root and no CAP_SYS_ADMIN; losetup and mount(2) will fail",
+        )
+        .fix("run with sudo")
+    }
+}
+
+fn check_command(command: &str, fix: &str) -> Check {
+    if filesystem::command_exists(command) {
+        Check::new(command, Status::Ok, "found on PATH")
+    } else {
+        Check::new(command, Status::Fail, "not found on PATH").fix(fix)
+    }
+}
+
+fn check_loop_support() -> Check {
+    if Path::new("/dev/loop-control").exists() {
+        Check::new("Loop devices", Status::Ok, "/dev/loop-control present")
+    } else if Path::new("/sys/module/loop").exists() {
+        Check::new(
+            "Loop devices",
+            Status::Fail,
+            "loop driver loaded but /dev/loop-control is missing",
+        )
+        .fix("mknod /dev/loop-control c 10 237, or expose it to the container")
+    } else {
+        Check::new("Loop devices", Status::Fail, "loop driver not loaded").fix("modprobe loop")
+    }
+}
+
+fn check_ficlone() -> Check {
+    let kernel = environment::kernel_version();
+    let version = parse_kernel_version(&kernel);
+    match version {
+        Some(version) if version >= FICLONE_SINCE => {
+            Check::new("FICLONE", Status::Ok, format!("kernel {}", kernel))
+        }
+        Some(_) => Check::new(
+            "FICLONE",
+            Status::Fail,
+            format!(
+                "kernel {} predates FICLONE ({}.{})",
+                kernel, FICLONE_SINCE.0, FICLONE_SINCE.1
+            ),
+        )
+        .fix("upgrade the kernel"),
+        None => Check::new(
+            "FICLONE",
+            Status::Warn,
+            format!("couldn't parse kernel version {:?}", kernel),
+        ),
+    }
+}
+
+fn check_kernel_filesystem(fs_type: FilesystemType) -> Check {
+    let name = format!("{} in kernel", fs_type);
+    let registered = std::fs::read_to_string("/proc/filesystems")
+        .unwrap_or_default()
+        .lines()
+        .any(|line| line.split_whitespace().last() == Some(fs_type.mount_type()));
+    if registered {
+        Check::new(name, Status::Ok, "registered in /proc/filesystems")
+    } else {
+        // Usually a module that mount(2) loads on demand
+        Check::new(
+            name,
+            Status::Warn,
+            "not registered yet; mount will fail unless the module loads",
+        )
+        .fix(format!("modprobe {}", fs_type.mount_type()))
+    }
+}
+
+fn check_workdir(workdir: &Path, fs_size_gb: u64, filesystems: u64) -> Vec<Check> {
+    // The run creates the workdir, so look at where it would be created
+    let Some(existing) = workdir.ancestors().find(|dir| dir.exists()) else {
+        return vec![Check::new(
+            "Free space",
+            Status::Fail,
+            format!("{} has no existing parent", workdir.display()),
+        )];
+    };
+
+    let needed_gb = fs_size_gb * filesystems;
+    let mut checks = vec![match filesystem::check_image_space(existing, needed_gb) {
+        Ok(()) => Check::new(
+            "Free space",
+            Status::Ok,
+            format!(
+                "room for {}GB of images in {}",
+                needed_gb,
+                existing.display()
+            ),
+        ),
+        Err(e) => Check::new("Free space", Status::Fail, e.to_string())
+            .fix("pass a different --workdir or a smaller --fs-size-gb"),
+    }];
+    if filesystem::is_tmpfs(existing) {
+        checks.push(
+            Check::new(
+                "Working directory",
+                Status::Warn,
+                format!(
+                    "{} is tmpfs; images will be held in memory",
+                    existing.display()
+                ),
+            )
+            .fix("pass --workdir on a disk"),
+        );
+    }
+    checks
+}
+
+/// `(major, minor)` from a release such as `6.8.0-45-generic`.
+fn parse_kernel_version(release: &str) -> Option<(u32, u32)> {
+    let mut parts = release.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+fn package_for(fs_type: FilesystemType) -> &'static str {
+    match fs_type {
+        FilesystemType::Xfs => "xfsprogs",
+        FilesystemType::Btrfs => "btrfs-progs",
+        FilesystemType::Bcachefs => "bcachefs-tools",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_kernel_releases() {
+        assert_eq!(parse_kernel_version("6.8.0-45-generic"), Some((6, 8)));
+        assert_eq!(parse_kernel_version("4.4.302"), Some((4, 4)));
+        assert_eq!(parse_kernel_version("5.15"), Some((5, 15)));
+        assert_eq!(parse_kernel_version(""), None);
+        assert!(parse_kernel_version("4.4.302").unwrap() < FICLONE_SINCE);
+    }
+}
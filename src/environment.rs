@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::filesystem::FilesystemType;
+use crate::iosched;
+use crate::placement;
+
+/// The machine and tools a set of results was produced with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvironmentInfo {
+    pub tool_version: String,
+    pub kernel_version: String,
+    pub cpu_model: String,
+    pub cpu_count: usize,
+    pub memory_bytes: u64,
+    /// `mkfs.* -V` output per filesystem
+    pub mkfs_versions: BTreeMap<String, String>,
+    /// Each NUMA node's CPUs, by node number
+    #[serde(default)]
+    pub numa_nodes: Vec<String>,
+    /// CPUs the run was pinned to with `--cpus` or `--numa-node`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_affinity: Option<String>,
+    /// NUMA node memory was bound to with `--numa-node`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numa_node: Option<u32>,
+    /// cgroup io.max limits the run was throttled with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_io_max: Option<String>,
+    /// cgroup memory.max the run was capped at, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_memory_max: Option<u64>,
+    /// I/O scheduling class and level the run was given with `--ionice`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_priority: Option<String>,
+    /// Size of tokio's blocking thread pool, from `--io-threads`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_threads: Option<usize>,
+}
+
+impl EnvironmentInfo {
+    /// Best-effort collection; anything unavailable is left empty.
+    pub fn collect(filesystems: &[FilesystemType]) -> Self {
+        let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+        let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            kernel_version: kernel_version(),
+            cpu_model: cpuinfo
+                .lines()
+                .find_map(|line| line.strip_prefix("model name"))
+                .and_then(|rest| rest.split_once(':'))
+                .map(|(_, model)| model.trim().to_string())
+                .unwrap_or_default(),
+            cpu_count: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(0),
+            memory_bytes: meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix("MemTotal:"))
+                .and_then(|rest| {
+                    rest.trim()
+                        .trim_end_matches("kB")
+                        .trim()
+                        .parse::<u64>()
+                        .ok()
+                })
+                .map(|kb| kb * 1024)
+                .unwrap_or(0),
+            mkfs_versions: filesystems
+                .iter()
+                .map(|fs| (fs.to_string(), mkfs_version(fs.mkfs_command())))
+                .collect(),
+            numa_nodes: placement::numa_topology(),
+            cpu_affinity: None,
+            numa_node: None,
+            cgroup_io_max: None,
+            cgroup_memory_max: None,
+            io_priority: None,
+            io_threads: None,
+        }
+    }
+}
+
+/// How one benchmarked filesystem was mounted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilesystemEnvironment {
+    pub device: String,
+    pub loop_device: bool,
+    /// Image file behind the loop device
+    pub backing_file: Option<PathBuf>,
+    pub mount_options: String,
+    /// The device's I/O scheduler
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_scheduler: Option<String>,
+    /// Whether btrfs quota groups were enabled
+    #[serde(default)]
+    pub qgroups: bool,
+    /// Whether the benchmark ran under an XFS project quota
+    #[serde(default)]
+    pub project_quota: bool,
+    /// Whether the device is a dm-crypt mapping
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Whether the loop device was attached with direct I/O
+    #[serde(default)]
+    pub loop_direct_io: bool,
+    /// The device's logical sector size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sector_size: Option<u32>,
+}
+
+impl FilesystemEnvironment {
+    pub fn capture(mount_point: &Path) -> Self {
+        let mounts = std::fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+        let mount_point = mount_point.to_string_lossy();
+        let Some((device, options)) = mounts.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            (fields.len() >= 4 && fields[1] == mount_point).then(|| (fields[0], fields[3]))
+        }) else {
+            return Self::default();
+        };
+
+        // /dev/mapper names are symlinks to the dm-N kernel name
+        let name = std::fs::canonicalize(device)
+            .ok()
+            .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| device.rsplit('/').next().unwrap_or(device).to_string());
+        let encrypted = std::fs::read_to_string(format!("/sys/block/{}/dm/uuid", name))
+            .is_ok_and(|uuid| uuid.starts_with("CRYPT-"));
+        // A crypt mapping's backing loop device is the one it sits on
+        let lower = if encrypted {
+            std::fs::read_dir(format!("/sys/block/{}/slaves", name))
+                .ok()
+                .and_then(|mut slaves| slaves.next()?.ok())
+                .map(|slave| slave.file_name().to_string_lossy().into_owned())
+                .unwrap_or_else(|| name.clone())
+        } else {
+            name.clone()
+        };
+        let backing_file =
+            std::fs::read_to_string(format!("/sys/block/{}/loop/backing_file", lower))
+                .ok()
+                .map(|path| PathBuf::from(path.trim()));
+        let loop_direct_io = std::fs::read_to_string(format!("/sys/block/{}/loop/dio", lower))
+            .is_ok_and(|dio| dio.trim() == "1");
+        let sector_size =
+            std::fs::read_to_string(format!("/sys/block/{}/queue/logical_block_size", name))
+                .ok()
+                .and_then(|size| size.trim().parse().ok());
+
+        Self {
+            device: device.to_string(),
+            loop_device: lower.starts_with("loop"),
+            backing_file,
+            mount_options: options.to_string(),
+            io_scheduler: iosched::current_scheduler(&name),
+            qgroups: false,
+            project_quota: false,
+            encrypted,
+            loop_direct_io,
+            sector_size,
+        }
+    }
+}
+
+pub fn kernel_version() -> String {
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return String::new();
+    }
+    let release = unsafe { std::ffi::CStr::from_ptr(uts.release.as_ptr()) };
+    release.to_string_lossy().into_owned()
+}
+
+fn mkfs_version(command: &str) -> String {
+    Command::new(command)
+        .arg("-V")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::benchmark::{OpTiming, WRITE_PATTERN};
+use crate::stats::LatencyStats;
+use crate::workloads::Workload;
+
+/// A command-line tool that can clone a whole file, for `--external-tools`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExternalTool {
+    /// `cp --reflink=always`, from coreutils
+    Cp,
+    /// `xfs_io -c reflink`, from xfsprogs; works on any reflink filesystem
+    XfsIo,
+}
+
+impl Display for ExternalTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExternalTool::Cp => write!(f, "cp"),
+            ExternalTool::XfsIo => write!(f, "xfs_io"),
+        }
+    }
+}
+
+impl ExternalTool {
+    pub fn program(self) -> &'static str {
+        match self {
+            ExternalTool::Cp => "cp",
+            ExternalTool::XfsIo => "xfs_io",
+        }
+    }
+
+    /// Arguments that clone `source` to a new `target`.
+    pub fn args(self, source: &Path, target: &Path) -> Vec<String> {
+        match self {
+            ExternalTool::Cp => vec![
+                "--reflink=always".to_string(),
+                source.display().to_string(),
+                target.display().to_string(),
+            ],
+            ExternalTool::XfsIo => vec![
+                "-f".to_string(),
+                "-c".to_string(),
+                format!("reflink {}", source.display()),
+                target.display().to_string(),
+            ],
+        }
+    }
+
+    /// The command line, for dry runs and output.
+    pub fn command_line(self, source: &Path, target: &Path) -> String {
+        let args: Vec<String> = self
+            .args(source, target)
+            .into_iter()
+            .map(|arg| {
+                if arg.contains(' ') {
+                    format!("'{}'", arg)
+                } else {
+                    arg
+                }
+            })
+            .collect();
+        format!("{} {}", self.program(), args.join(" "))
+    }
+}
+
+/// Reflink + write through an external tool: the run's sequential pass
+/// with the clone done by `tool` in a child process, so comparing it with
+/// the in-process `FICLONE` shows what exec and the tool add.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalToolResult {
+    pub tool: String,
+    pub total_duration: Duration,
+    pub operations_per_sec: f64,
+    /// Spawning the tool until it exits, clone included
+    pub reflink_latency: LatencyStats,
+    /// Reopening the clone and dirtying its first block
+    pub write_latency: LatencyStats,
+}
+
+/// Clones `{dir}/external_{tool}_{i}.dat` from `source` by running `tool`, then
+/// overwrites its first `write_size` bytes, like
+/// [`ReflinkWrite`](crate::workloads::ReflinkWrite) does in-process.
+pub struct ExternalReflinkWrite {
+    tool: ExternalTool,
+    name: String,
+    dir: PathBuf,
+    source: PathBuf,
+    count: u32,
+    write_size: usize,
+}
+
+impl ExternalReflinkWrite {
+    pub fn new(
+        tool: ExternalTool,
+        dir: &Path,
+        source: &Path,
+        count: u32,
+        write_size: usize,
+    ) -> Self {
+        Self {
+            tool,
+            name: format!("external_{}", tool),
+            dir: dir.to_path_buf(),
+            source: source.to_path_buf(),
+            count,
+            write_size,
+        }
+    }
+}
+
+impl Workload for ExternalReflinkWrite {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        let target = self.dir.join(format!("{}_{}.dat", self.name, index));
+
+        let reflink_start = Instant::now();
+        let output = Command::new(self.tool.program())
+            .args(self.tool.args(&self.source, &target))
+            .output()
+            .with_context(|| format!("Failed to run {}", self.tool.program()))?;
+        // xfs_io reports a failed command on stderr but still exits 0
+        if !output.status.success() || !output.stderr.is_empty() {
+            anyhow::bail!(
+                "{} failed: {}",
+                self.tool.command_line(&self.source, &target),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let reflink = reflink_start.elapsed();
+
+        let write_start = Instant::now();
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&target)
+            .context("Failed to open clone")?;
+        file.write_all(&vec![WRITE_PATTERN; self.write_size])
+            .context("Failed to write to clone")?;
+
+        Ok(OpTiming {
+            reflink,
+            write: write_start.elapsed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tools_clone_source_to_target() {
+        let (source, target) = (Path::new("/mnt/source.dat"), Path::new("/mnt/clone.dat"));
+        assert_eq!(
+            ExternalTool::Cp.command_line(source, target),
+            "cp --reflink=always /mnt/source.dat /mnt/clone.dat"
+        );
+        assert_eq!(
+            ExternalTool::XfsIo.command_line(source, target),
+            "xfs_io -f -c 'reflink /mnt/source.dat' /mnt/clone.dat"
+        );
+    }
+}
@@ -1,14 +1,24 @@
 use anyhow::{Context, Result};
-use nix::mount::{mount, umount, MsFlags};
+use clap::ValueEnum;
+use nix::mount::MsFlags;
 use std::fmt::Display;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use tokio::fs;
 
-#[derive(Debug, Clone, Copy)]
+use crate::ioctl;
+use crate::lock::DirLock;
+use crate::provider::{self, FilesystemProvider, LoopOptions};
+use crate::retry::RetryPolicy;
+use crate::run_dir;
+
+/// Where images and mount points go unless `--workdir` says otherwise.
+pub const DEFAULT_WORKDIR: &str = "/tmp";
+
+/// A filesystem that can be benchmarked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum FilesystemType {
     Xfs,
     Btrfs,
+    Bcachefs,
 }
 
 impl Display for FilesystemType {
@@ -16,221 +26,591 @@ impl Display for FilesystemType {
         match self {
             FilesystemType::Xfs => write!(f, "XFS"),
             FilesystemType::Btrfs => write!(f, "btrfs"),
+            FilesystemType::Bcachefs => write!(f, "bcachefs"),
         }
     }
 }
 
+impl FilesystemType {
+    pub fn mkfs_command(&self) -> &'static str {
+        match self {
+            FilesystemType::Xfs => "mkfs.xfs",
+            FilesystemType::Btrfs => "mkfs.btrfs",
+            FilesystemType::Bcachefs => "mkfs.bcachefs",
+        }
+    }
+
+    /// Filesystem type name passed to mount(2).
+    pub fn mount_type(&self) -> &'static str {
+        match self {
+            FilesystemType::Xfs => "xfs",
+            FilesystemType::Btrfs => "btrfs",
+            FilesystemType::Bcachefs => "bcachefs",
+        }
+    }
+
+    /// First kernel with FICLONE/FICLONERANGE support on this filesystem.
+    pub fn reflink_since(&self) -> &'static str {
+        match self {
+            FilesystemType::Xfs => "4.9 (reflink on by default since xfsprogs 5.1)",
+            FilesystemType::Btrfs => "4.5",
+            FilesystemType::Bcachefs => "6.7",
+        }
+    }
+
+    /// Whether this filesystem's mkfs tool is on `PATH`.
+    pub fn is_available(&self) -> bool {
+        command_exists(self.mkfs_command())
+    }
+
+    /// The filesystem holding `path`, if it's one that can be benchmarked.
+    pub fn detect(path: &Path) -> Result<Self> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path =
+            CString::new(path.as_os_str().as_bytes()).context("Invalid path for statfs")?;
+        let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+        let result = unsafe { libc::statfs(c_path.as_ptr(), &mut stat) };
+
+        if result != 0 {
+            let errno = std::io::Error::last_os_error();
+            anyhow::bail!("statfs {} failed: {}", path.display(), errno);
+        }
+
+        Self::from_magic(stat.f_type as u64).with_context(|| {
+            format!(
+                "{} is not on XFS, btrfs, or bcachefs (filesystem magic {:#x})",
+                path.display(),
+                stat.f_type
+            )
+        })
+    }
+
+    /// The filesystem with this statfs `f_type` magic number.
+    fn from_magic(magic: u64) -> Option<Self> {
+        match magic {
+            0x58465342 => Some(FilesystemType::Xfs),
+            0x9123683e => Some(FilesystemType::Btrfs),
+            0xca451a4e => Some(FilesystemType::Bcachefs),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an executable named `name` is on `PATH`.
+pub fn command_exists(name: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| dir.join(name).is_file())
+}
+
+/// A filesystem to benchmark, set up and torn down by its
+/// [`FilesystemProvider`]. [`new`](Self::new) uses the loopback provider: an
+/// image at `<dir>/<fs>.img` mounted on `<dir>/<fs>`, where `dir` is usually
+/// a [`RunDir`](crate::run_dir::RunDir). Setting up and cleaning up need
+/// root.
+///
+/// Once set up, the filesystem is torn down when the manager is dropped
+/// unless [`cleanup`](Self::cleanup) or [`keep`](Self::keep) already
+/// decided its fate, so an interrupted or failed run doesn't leave stale
+/// mounts behind.
 pub struct FilesystemManager {
-    fs_type: FilesystemType,
-    size_gb: u64,
-    image_path: PathBuf,
-    mount_point: PathBuf,
-    loop_device: Option<String>,
+    provider: Box<dyn FilesystemProvider>,
+    /// Whether dropping the manager should clean up
+    armed: bool,
 }
 
 impl FilesystemManager {
-    pub fn new(fs_type: FilesystemType, size_gb: u64) -> Result<Self> {
-        let image_path = PathBuf::from(format!(
-            "/tmp/reflink-bench-{}.img",
-            format!("{:?}", fs_type).to_lowercase()
-        ));
-        let mount_point = PathBuf::from(format!(
-            "/tmp/reflink-bench-{}",
-            format!("{:?}", fs_type).to_lowercase()
-        ));
-
-        Ok(Self {
+    pub fn new(fs_type: FilesystemType, size_gb: u64, dir: &Path) -> Result<Self> {
+        Ok(Self::with_provider(provider::loopback(
+            fs_type, size_gb, dir,
+        )))
+    }
+
+    /// Benchmarks `<dir>/<fs>` on whatever filesystem already holds `dir`,
+    /// without creating or mounting anything.
+    pub fn in_directory(fs_type: FilesystemType, dir: &Path) -> Self {
+        Self::with_provider(provider::directory(
             fs_type,
-            size_gb,
-            image_path,
-            mount_point,
-            loop_device: None,
-        })
+            &dir.join(fs_type.mount_type()),
+        ))
     }
 
-    pub async fn setup(&mut self) -> Result<()> {
-        self.cleanup_existing().await?;
-        self.create_image().await?;
-        self.setup_loop_device().await?;
-        self.format_filesystem().await?;
-        self.create_mount_point().await?;
-        self.mount_filesystem().await?;
-        Ok(())
+    /// A loopback filesystem like [`new`](Self::new)'s, but set up and torn
+    /// down by the privileged `command` (see `run --helper`), so this
+    /// process needs no root.
+    pub fn with_helper(command: &str, fs_type: FilesystemType, size_gb: u64, dir: &Path) -> Self {
+        Self::with_provider(provider::helper(command, fs_type, size_gb, dir))
     }
 
-    pub async fn cleanup(&self) -> Result<()> {
-        if self.mount_point.exists() {
-            let _ = self.unmount_filesystem().await;
+    /// Manages a filesystem from any provider.
+    pub fn with_provider(provider: Box<dyn FilesystemProvider>) -> Self {
+        Self {
+            provider,
+            armed: false,
         }
+    }
 
-        if let Some(loop_dev) = &self.loop_device {
-            let _ = self.detach_loop_device(loop_dev).await;
-        }
+    /// Mounts with these comma-separated options, e.g. `noatime,compress=zstd`.
+    pub fn with_mount_options(mut self, options: Option<String>) -> Self {
+        self.provider.set_mount_options(options);
+        self
+    }
+
+    /// Retries loop device attachment, mount, and umount with `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.provider.set_retry_policy(policy);
+        self
+    }
 
-        if self.image_path.exists() {
-            fs::remove_file(&self.image_path)
-                .await
-                .context("Failed to remove filesystem image")?;
+    /// Formats a dm-crypt mapping over the loop device rather than the
+    /// loop device itself.
+    pub fn with_encrypt_underlay(mut self, encrypt: bool) -> Self {
+        self.provider.set_encrypt_underlay(encrypt);
+        self
+    }
+
+    /// Creates the image in `dir` rather than the run directory, when given.
+    pub fn with_image_dir(mut self, dir: Option<&Path>) -> Self {
+        if let Some(dir) = dir {
+            self.provider.set_image_dir(dir);
         }
+        self
+    }
+
+    /// Attaches the loop device with direct I/O or a custom sector size.
+    pub fn with_loop_options(mut self, options: LoopOptions) -> Self {
+        self.provider.set_loop_options(options);
+        self
+    }
+
+    /// Replaces anything left at this filesystem's paths, then creates,
+    /// formats, and mounts it.
+    pub async fn setup(&mut self) -> Result<()> {
+        // A half-finished setup needs cleaning up too
+        self.armed = true;
+        self.provider.setup()?;
+        self.provider.mount()
+    }
 
-        if self.mount_point.exists() {
-            fs::remove_dir(&self.mount_point)
-                .await
-                .context("Failed to remove mount point")?;
+    /// Unmounts and removes everything setup created, skipping whatever
+    /// doesn't exist.
+    pub async fn cleanup(mut self) -> Result<()> {
+        self.armed = false;
+        self.provider.cleanup()
+    }
+
+    /// Leaves the filesystem mounted for inspection after the manager is
+    /// dropped.
+    pub fn keep(mut self) {
+        self.armed = false;
+    }
+
+    /// Sets up independent filesystems concurrently. Creating, attaching,
+    /// and formatting images is mostly waiting on I/O, so doing it for each
+    /// filesystem at once cuts setup time. If any fails, the others are
+    /// torn down again.
+    pub async fn setup_all(managers: Vec<Self>) -> Result<Vec<Self>> {
+        // Providers shell out synchronously, so give each its own thread
+        let tasks: Vec<_> = managers
+            .into_iter()
+            .map(|mut manager| {
+                tokio::task::spawn_blocking(move || {
+                    manager.armed = true;
+                    let result = manager
+                        .provider
+                        .setup()
+                        .and_then(|()| manager.provider.mount());
+                    (manager, result)
+                })
+            })
+            .collect();
+
+        let mut ready = Vec::with_capacity(tasks.len());
+        let mut first_error = None;
+        for task in tasks {
+            let (manager, result) = task.await.context("Filesystem setup task panicked")?;
+            match result {
+                Ok(()) => ready.push(manager),
+                Err(e) => {
+                    let fs_type = manager.fs_type();
+                    let _ = manager.cleanup().await;
+                    first_error
+                        .get_or_insert_with(|| e.context(format!("Failed to set up {}", fs_type)));
+                }
+            }
         }
 
-        Ok(())
+        if let Some(e) = first_error {
+            for manager in ready {
+                let _ = manager.cleanup().await;
+            }
+            return Err(e);
+        }
+        Ok(ready)
     }
 
-    pub async fn cleanup_all() -> Result<()> {
-        for fs_type in [FilesystemType::Xfs, FilesystemType::Btrfs] {
-            let manager = Self::new(fs_type, 1)?; // Size doesn't matter for cleanup
-            let _ = manager.cleanup().await; // Ignore errors during cleanup
+    /// Tears down the filesystems of every run under `workdir` that has
+    /// finished (or crashed), skipping runs still in progress, and removes
+    /// their run directories.
+    pub async fn cleanup_all(workdir: &Path) -> Result<()> {
+        for dir in run_dir::list(workdir)? {
+            let Ok(lock) = DirLock::acquire(&dir) else {
+                println!("⏭️  Skipping {} (run in progress)", dir.display());
+                continue;
+            };
+            for &fs_type in FilesystemType::value_variants() {
+                // Size doesn't matter for cleanup
+                let manager = Self::new(fs_type, 1, &dir)?;
+                let _ = manager.cleanup().await; // Ignore errors during cleanup
+            }
+
+            let _ = std::fs::remove_file(dir.join(crate::lock::LOCK_FILE_NAME));
+            drop(lock);
+            match std::fs::remove_dir(&dir) {
+                Ok(()) => println!("🗑️  Removed {}", dir.display()),
+                Err(e) => println!("⚠️  Couldn't remove {}: {}", dir.display(), e),
+            }
         }
         Ok(())
     }
 
+    pub fn fs_type(&self) -> FilesystemType {
+        self.provider.fs_type()
+    }
+
+    /// Whether the tools the provider needs are installed.
+    pub fn is_available(&self) -> bool {
+        self.provider.is_available()
+    }
+
     pub fn mount_point(&self) -> &Path {
-        &self.mount_point
+        self.provider.mount_point()
     }
 
-    async fn cleanup_existing(&self) -> Result<()> {
-        if self.mount_point.exists() {
-            let _ = umount(&self.mount_point);
-        }
+    pub fn image_path(&self) -> Option<&Path> {
+        self.provider.image_path()
+    }
 
-        if self.image_path.exists() {
-            fs::remove_file(&self.image_path).await.ok();
-        }
+    pub fn device(&self) -> Option<&str> {
+        self.provider.device()
+    }
 
-        Ok(())
+    /// The commands and syscalls setup would perform.
+    pub fn setup_plan(&self) -> Vec<String> {
+        self.provider.setup_plan()
     }
 
-    async fn create_image(&self) -> Result<()> {
-        let size_mb = self.size_gb * 1024;
+    /// The commands and syscalls cleanup would perform.
+    pub fn cleanup_plan(&self) -> Vec<String> {
+        self.provider.cleanup_plan()
+    }
+}
 
-        let output = Command::new("dd")
-            .args([
-                "if=/dev/zero",
-                &format!("of={}", self.image_path.display()),
-                "bs=1M",
-                &format!("count={}", size_mb),
-            ])
-            .output()
-            .context("Failed to create filesystem image")?;
+impl Drop for FilesystemManager {
+    fn drop(&mut self) {
+        if self.armed {
+            let _ = self.provider.cleanup();
+        }
+    }
+}
 
-        if !output.status.success() {
-            anyhow::bail!("dd failed: {}", String::from_utf8_lossy(&output.stderr));
+/// Splits `mount -o` style options into the generic flags mount(2) takes
+/// and the filesystem-specific data string.
+pub(crate) fn split_mount_options(options: &str) -> (MsFlags, String) {
+    let mut flags = MsFlags::empty();
+    let mut data = Vec::new();
+
+    for option in options.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+        match option {
+            "ro" => flags |= MsFlags::MS_RDONLY,
+            "noatime" => flags |= MsFlags::MS_NOATIME,
+            "nodiratime" => flags |= MsFlags::MS_NODIRATIME,
+            "relatime" => flags |= MsFlags::MS_RELATIME,
+            "strictatime" => flags |= MsFlags::MS_STRICTATIME,
+            "lazytime" => flags |= MsFlags::MS_LAZYTIME,
+            "sync" => flags |= MsFlags::MS_SYNCHRONOUS,
+            "dirsync" => flags |= MsFlags::MS_DIRSYNC,
+            "nosuid" => flags |= MsFlags::MS_NOSUID,
+            "nodev" => flags |= MsFlags::MS_NODEV,
+            "noexec" => flags |= MsFlags::MS_NOEXEC,
+            other => data.push(other),
         }
+    }
 
-        Ok(())
+    (flags, data.join(","))
+}
+
+/// Flushes all dirty data for the filesystem containing `path`.
+pub fn sync_filesystem(path: &Path) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = std::fs::File::open(path).context("Failed to open path for syncfs")?;
+    let result = unsafe { libc::syncfs(file.as_raw_fd()) };
+
+    if result != 0 {
+        let errno = std::io::Error::last_os_error();
+        anyhow::bail!("syncfs failed: {}", errno);
     }
 
-    async fn setup_loop_device(&mut self) -> Result<()> {
-        let output = Command::new("losetup")
-            .args(["-f", "--show", &self.image_path.to_string_lossy()])
-            .output()
-            .context("Failed to setup loop device")?;
+    Ok(())
+}
 
-        if !output.status.success() {
-            anyhow::bail!(
-                "losetup failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
-        }
+/// Returns the number of bytes available on the filesystem containing `path`.
+pub fn free_space_bytes(path: &Path) -> Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
 
-        let loop_device = String::from_utf8(output.stdout)
-            .context("Invalid UTF-8 in losetup output")?
-            .trim()
-            .to_string();
+    let c_path = CString::new(path.as_os_str().as_bytes()).context("Invalid path for statvfs")?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) };
 
-        self.loop_device = Some(loop_device);
-        Ok(())
+    if result != 0 {
+        let errno = std::io::Error::last_os_error();
+        anyhow::bail!("statvfs failed: {}", errno);
+    }
+
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Fails unless the filesystem holding `workdir` can fit a fully written
+/// `size_gb` image (images are zero-filled, not sparse).
+pub fn check_image_space(workdir: &Path, size_gb: u64) -> Result<()> {
+    let needed = size_gb * 1024 * 1024 * 1024;
+    let available = free_space_bytes(workdir)
+        .with_context(|| format!("Failed to check free space in {}", workdir.display()))?;
+
+    if available < needed {
+        anyhow::bail!(
+            "Not enough space in {} for a {}GB filesystem image: {:.1}GB available; \
+             free some space, lower --fs-size-gb, or pick another --workdir",
+            workdir.display(),
+            size_gb,
+            available as f64 / (1024.0 * 1024.0 * 1024.0)
+        );
     }
+    Ok(())
+}
 
-    async fn format_filesystem(&self) -> Result<()> {
-        let loop_device = self
-            .loop_device
-            .as_ref()
-            .context("Loop device not set up")?;
+/// Returns whether the filesystem containing `path` is tmpfs, i.e. backed
+/// by memory.
+pub fn is_tmpfs(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
 
-        let (cmd, args): (&str, Vec<&str>) = match self.fs_type {
-            FilesystemType::Xfs => ("mkfs.xfs", vec!["-f", loop_device]),
-            FilesystemType::Btrfs => ("mkfs.btrfs", vec!["-f", loop_device]),
-        };
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::statfs(c_path.as_ptr(), &mut stat) };
 
-        let output = Command::new(cmd)
+    result == 0 && stat.f_type == libc::TMPFS_MAGIC
+}
+
+/// Returns whether the filesystem containing `path` is btrfs.
+pub fn is_btrfs(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::statfs(c_path.as_ptr(), &mut stat) };
+
+    result == 0 && stat.f_type == libc::BTRFS_SUPER_MAGIC
+}
+
+/// Enables quota groups on the btrfs filesystem mounted at `mount_point`
+/// and waits for the initial rescan to finish.
+pub fn enable_btrfs_qgroups(mount_point: &Path) -> Result<()> {
+    let mount_point = mount_point.to_string_lossy();
+    for args in [&["quota", "enable"][..], &["quota", "rescan", "-W"]] {
+        let output = std::process::Command::new("btrfs")
             .args(args)
+            .arg(mount_point.as_ref())
             .output()
-            .context(format!("Failed to format {} filesystem", self.fs_type))?;
-
+            .with_context(|| format!("Failed to run btrfs {}", args.join(" ")))?;
         if !output.status.success() {
             anyhow::bail!(
-                "{} failed: {}",
-                cmd,
-                String::from_utf8_lossy(&output.stderr)
+                "btrfs {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
             );
         }
+    }
+    Ok(())
+}
 
-        Ok(())
+/// Project the benchmark directory is assigned to by
+/// [`enable_xfs_project_quota`].
+pub const XFS_PROJECT_ID: u32 = 1;
+
+/// Assigns the XFS filesystem mounted (with `prjquota`) at `mount_point` to
+/// project [`XFS_PROJECT_ID`], inherited by everything created under it,
+/// and gives the project a hard limit no run reaches, so usage is both
+/// accounted and checked against the limit.
+pub fn enable_xfs_project_quota(mount_point: &Path) -> Result<()> {
+    let limit = free_space_bytes(mount_point)? * 2;
+    let commands = [
+        format!("project -s -p {} {}", mount_point.display(), XFS_PROJECT_ID),
+        format!("limit -p bhard={} {}", limit, XFS_PROJECT_ID),
+    ];
+    for command in commands {
+        let output = std::process::Command::new("xfs_quota")
+            .args(["-x", "-c", &command])
+            .arg(mount_point)
+            .output()
+            .with_context(|| format!("Failed to run xfs_quota -c '{}'", command))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "xfs_quota -c '{}' failed (is the filesystem mounted with prjquota?): {}",
+                command,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
     }
+    Ok(())
+}
 
-    async fn create_mount_point(&self) -> Result<()> {
-        if !self.mount_point.exists() {
-            fs::create_dir_all(&self.mount_point)
-                .await
-                .context("Failed to create mount point")?;
+/// Adds a random fscrypt master key and sets a policy using it on the
+/// empty directory `dir`, so files created in it are encrypted, returning
+/// the key's identifier for [`remove_fscrypt_key`]. `None` when the
+/// filesystem doesn't support fscrypt, or has it disabled.
+pub fn enable_fscrypt(dir: &Path) -> Result<Option<ioctl::FscryptKeyIdentifier>> {
+    use rand::RngCore;
+
+    let handle =
+        std::fs::File::open(dir).with_context(|| format!("Failed to open {}", dir.display()))?;
+    let mut key = [0u8; ioctl::FSCRYPT_KEY_SIZE];
+    rand::thread_rng().fill_bytes(&mut key);
+
+    let unsupported = |e: &std::io::Error| {
+        matches!(
+            e.raw_os_error(),
+            Some(libc::EOPNOTSUPP | libc::ENOTTY | libc::EINVAL)
+        )
+    };
+    let identifier = match ioctl::fscrypt_add_key(&handle, &key) {
+        Ok(identifier) => identifier,
+        Err(e) if unsupported(&e) => return Ok(None),
+        Err(e) => return Err(e).context("Failed to add fscrypt key"),
+    };
+    match ioctl::fscrypt_set_policy(&handle, identifier) {
+        Ok(()) => Ok(Some(identifier)),
+        Err(e) => {
+            let _ = ioctl::fscrypt_remove_key(&handle, identifier);
+            if unsupported(&e) {
+                Ok(None)
+            } else {
+                Err(e).with_context(|| {
+                    format!("Failed to set an fscrypt policy on {}", dir.display())
+                })
+            }
         }
-        Ok(())
     }
+}
 
-    async fn mount_filesystem(&self) -> Result<()> {
-        let loop_device = self
-            .loop_device
-            .as_ref()
-            .context("Loop device not set up")?;
+/// Removes the key [`enable_fscrypt`] added, locking the directory's files.
+pub fn remove_fscrypt_key(dir: &Path, identifier: ioctl::FscryptKeyIdentifier) -> Result<()> {
+    let handle =
+        std::fs::File::open(dir).with_context(|| format!("Failed to open {}", dir.display()))?;
+    ioctl::fscrypt_remove_key(&handle, identifier).context("Failed to remove fscrypt key")
+}
 
-        let fs_type_str = match self.fs_type {
-            FilesystemType::Xfs => "xfs",
-            FilesystemType::Btrfs => "btrfs",
-        };
-
-        mount(
-            Some(loop_device.as_str()),
-            &self.mount_point,
-            Some(fs_type_str),
-            MsFlags::empty(),
-            None::<&str>,
+/// Returns the kernel name (e.g. `loop0`) of the block device mounted at
+/// `mount_point`, if it is mounted from one.
+pub fn block_device_name(mount_point: &Path) -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    let mount_point = mount_point.to_string_lossy();
+
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let source = fields.next()?;
+        let target = fields.next()?;
+        if target != mount_point {
+            return None;
+        }
+        source.strip_prefix("/dev/")?;
+        // /dev/mapper names are symlinks to the dm-N kernel name
+        let source = std::fs::canonicalize(source).unwrap_or_else(|_| PathBuf::from(source));
+        Some(source.file_name()?.to_string_lossy().into_owned())
+    })
+}
+
+/// Discards the free space of the filesystem mounted at `mount_point`, as
+/// `fstrim` does, returning the bytes trimmed.
+pub fn fstrim(mount_point: &Path) -> Result<u64> {
+    let dir = std::fs::File::open(mount_point)
+        .with_context(|| format!("Failed to open {}", mount_point.display()))?;
+    ioctl::fitrim(&dir).with_context(|| {
+        format!(
+            "FITRIM failed on {} (does its device support discard?)",
+            mount_point.display()
         )
-        .context("Failed to mount filesystem")?;
+    })
+}
 
-        // Set permissions for non-root access
-        Command::new("chmod")
-            .args(["777", &self.mount_point.to_string_lossy()])
-            .output()
-            .context("Failed to set mount point permissions")?;
+/// Syncs all filesystems and drops the page cache, dentries, and inodes so
+/// the next phase starts cold.
+pub fn drop_caches() -> Result<()> {
+    unsafe { libc::sync() };
+    std::fs::write("/proc/sys/vm/drop_caches", "3")
+        .context("Failed to write /proc/sys/vm/drop_caches")?;
+    Ok(())
+}
 
-        Ok(())
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    async fn unmount_filesystem(&self) -> Result<()> {
-        umount(&self.mount_point).context("Failed to unmount filesystem")?;
-        Ok(())
+    #[test]
+    fn split_mount_options_separates_flags_from_data() {
+        let (flags, data) = split_mount_options("noatime, compress=zstd,ro,discard=async");
+        assert_eq!(flags, MsFlags::MS_NOATIME | MsFlags::MS_RDONLY);
+        assert_eq!(data, "compress=zstd,discard=async");
+
+        let (flags, data) = split_mount_options("");
+        assert!(flags.is_empty());
+        assert!(data.is_empty());
     }
 
-    async fn detach_loop_device(&self, loop_device: &str) -> Result<()> {
-        let output = Command::new("losetup")
-            .args(["-d", loop_device])
-            .output()
-            .context("Failed to detach loop device")?;
+    #[test]
+    fn manager_paths_are_per_filesystem() {
+        let manager = FilesystemManager::new(FilesystemType::Btrfs, 1, Path::new("/work")).unwrap();
+        assert_eq!(manager.image_path(), Some(Path::new("/work/btrfs.img")));
+        assert_eq!(manager.mount_point(), Path::new("/work/btrfs"));
+        assert_eq!(manager.fs_type(), FilesystemType::Btrfs);
+        assert_eq!(manager.device(), None);
+    }
 
-        if !output.status.success() {
-            anyhow::bail!(
-                "losetup -d failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
-        }
+    #[test]
+    fn filesystems_are_detected_by_magic() {
+        assert_eq!(
+            FilesystemType::from_magic(0x58465342),
+            Some(FilesystemType::Xfs)
+        );
+        assert_eq!(
+            FilesystemType::from_magic(0x9123683e),
+            Some(FilesystemType::Btrfs)
+        );
+        // ext4 has no reflinks
+        assert_eq!(FilesystemType::from_magic(0xef53), None);
+    }
 
-        Ok(())
+    #[test]
+    fn dropping_a_manager_that_never_set_up_leaves_files_alone() {
+        let workdir =
+            std::env::temp_dir().join(format!("reflink-bench-drop-test-{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        // Stands in for another run's image, as a dry run would see it
+        let image = workdir.join("xfs.img");
+        std::fs::write(&image, b"image").unwrap();
+
+        drop(FilesystemManager::new(FilesystemType::Xfs, 1, &workdir).unwrap());
+        assert!(image.exists());
+        std::fs::remove_dir_all(&workdir).unwrap();
     }
 }
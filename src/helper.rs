@@ -0,0 +1,421 @@
+use anyhow::{Context, Result};
+use std::ffi::{CString, OsStr};
+use std::fs::File;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::filesystem::FilesystemType;
+use crate::provider::{self, LoopOptions};
+use crate::retry::RetryPolicy;
+use crate::run_dir::{HELPER_DIR_NAME, RUNS_DIR_NAME};
+
+/// PATH the helper runs tools with, whatever the caller's was.
+const SAFE_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Options every helper mount gets, so nothing on the image can gain
+/// privileges.
+const FORCED_MOUNT_OPTIONS: &str = "nosuid,nodev";
+
+/// Mount options the helper accepts from the caller, by name (the part
+/// before any `=`). Options that name other devices or files, like XFS's
+/// `logdev=` or btrfs's `device=`, and ones that undo the forced options,
+/// are left out.
+const ALLOWED_MOUNT_OPTIONS: &[&str] = &[
+    // Generic
+    "ro",
+    "rw",
+    "noatime",
+    "nodiratime",
+    "relatime",
+    "strictatime",
+    "lazytime",
+    "sync",
+    "dirsync",
+    "noexec",
+    "discard",
+    "nodiscard",
+    // XFS
+    "allocsize",
+    "inode32",
+    "inode64",
+    "largeio",
+    "nolargeio",
+    "logbufs",
+    "logbsize",
+    "noalign",
+    "swalloc",
+    "wsync",
+    "prjquota",
+    "pquota",
+    "uquota",
+    "usrquota",
+    "gquota",
+    "grpquota",
+    // btrfs
+    "autodefrag",
+    "noautodefrag",
+    "commit",
+    "compress",
+    "compress-force",
+    "datacow",
+    "nodatacow",
+    "datasum",
+    "nodatasum",
+    "space_cache",
+    "nospace_cache",
+    "clear_cache",
+    "ssd",
+    "nossd",
+    "ssd_spread",
+    "nossd_spread",
+    "max_inline",
+    "flushoncommit",
+    "noflushoncommit",
+    "barrier",
+    "nobarrier",
+    "treelog",
+    "notreelog",
+    // bcachefs
+    "compression",
+    "background_compression",
+    "data_checksum",
+    "metadata_checksum",
+];
+
+/// Largest image the helper creates.
+const MAX_SIZE_GB: u64 = 1024;
+
+/// Most tries, and longest first retry delay, the helper accepts. Delays
+/// double, so these bound root's retrying to a few minutes per step.
+const MAX_ATTEMPTS: u32 = 10;
+const MAX_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// How `mount-helper setup` creates and mounts a filesystem.
+pub struct SetupOptions<'a> {
+    pub mount_options: Option<&'a str>,
+    /// Put the filesystem on a dm-crypt mapping over the loop device
+    pub encrypt_underlay: bool,
+    pub loop_options: LoopOptions,
+    /// Another run directory of the caller's to create the image in
+    pub image_dir: Option<&'a Path>,
+}
+
+/// Privileged half of `run --helper`: creates, formats, and mounts one
+/// loopback filesystem in the caller's run directory, returning its device
+/// (the loop device, or the crypt mapping over it with `encrypt_underlay`).
+pub fn setup(
+    fs_type: FilesystemType,
+    size_gb: u64,
+    dir: &Path,
+    options: &SetupOptions,
+    retry: RetryPolicy,
+) -> Result<String> {
+    let caller = become_root()?;
+    if size_gb == 0 || size_gb > MAX_SIZE_GB {
+        anyhow::bail!("--size-gb must be between 1 and {}", MAX_SIZE_GB);
+    }
+    check_retry_policy(retry)?;
+    if let Some(own) = options.mount_options {
+        check_mount_options(own)?;
+    }
+
+    let (dir, run) = open_run_dir(dir, caller)?;
+    let files = HelperDir::open(&run)?;
+    let image_files = match options.image_dir {
+        Some(image_dir) => Some(HelperDir::open(&open_run_dir(image_dir, caller)?.1)?),
+        None => None,
+    };
+
+    let mount_options = match options.mount_options {
+        Some(own) => format!("{},{}", own, FORCED_MOUNT_OPTIONS),
+        None => FORCED_MOUNT_OPTIONS.to_string(),
+    };
+    let mut provider = provider::loopback_in(fs_type, size_gb, &dir, &files.path());
+    if let Some(image_files) = &image_files {
+        provider.set_image_dir(&image_files.path());
+    }
+    provider.set_mount_options(Some(mount_options));
+    provider.set_retry_policy(retry);
+    provider.set_encrypt_underlay(options.encrypt_underlay);
+    provider.set_loop_options(options.loop_options);
+    provider.setup()?;
+    if let Err(e) = provider.mount() {
+        let _ = provider.cleanup();
+        return Err(e);
+    }
+    provider
+        .device()
+        .map(str::to_string)
+        .context("Loop device not set up")
+}
+
+/// Privileged half of `run --helper`'s cleanup: unmounts and removes one
+/// loopback filesystem, and any crypt mapping under it, in the caller's run
+/// directory, with its image in `image_dir` if it was set up with one.
+pub fn cleanup(
+    fs_type: FilesystemType,
+    dir: &Path,
+    image_dir: Option<&Path>,
+    retry: RetryPolicy,
+) -> Result<()> {
+    let caller = become_root()?;
+    check_retry_policy(retry)?;
+
+    let (dir, run) = open_run_dir(dir, caller)?;
+    let files = HelperDir::open(&run)?;
+    let image_run = image_dir
+        .map(|image_dir| open_run_dir(image_dir, caller))
+        .transpose()?;
+    let image_files = image_run
+        .as_ref()
+        .map(|(_, image_run)| HelperDir::open(image_run))
+        .transpose()?;
+
+    // Size doesn't matter for cleanup
+    let mut provider = provider::loopback_in(fs_type, 1, &dir, &files.path());
+    if let Some(image_files) = &image_files {
+        provider.set_image_dir(&image_files.path());
+    }
+    provider.set_retry_policy(retry);
+    provider.cleanup()?;
+
+    // Still holds other filesystems unless this was the last one
+    files.remove_if_empty(&run);
+    if let (Some((_, image_run)), Some(image_files)) = (&image_run, &image_files) {
+        image_files.remove_if_empty(image_run);
+    }
+    Ok(())
+}
+
+fn check_retry_policy(retry: RetryPolicy) -> Result<()> {
+    if retry.attempts == 0 || retry.attempts > MAX_ATTEMPTS {
+        anyhow::bail!("--attempts must be between 1 and {}", MAX_ATTEMPTS);
+    }
+    if retry.delay > MAX_RETRY_DELAY {
+        anyhow::bail!(
+            "--retry-delay must be at most {}",
+            humantime::format_duration(MAX_RETRY_DELAY)
+        );
+    }
+    Ok(())
+}
+
+/// Only passes on [`ALLOWED_MOUNT_OPTIONS`], with plain values, so the
+/// caller can't point the mount at other devices or paths.
+fn check_mount_options(options: &str) -> Result<()> {
+    for option in options.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+        let (name, value) = option.split_once('=').unwrap_or((option, ""));
+        if !ALLOWED_MOUNT_OPTIONS.contains(&name) {
+            anyhow::bail!("mount option {} is not allowed through mount-helper", name);
+        }
+        if !value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '.' | '_' | '-'))
+        {
+            anyhow::bail!("mount option {} has an invalid value", option);
+        }
+    }
+    Ok(())
+}
+
+/// Switches fully to root and scrubs the environment the caller controls. Returns the calling user's uid.
+fn become_root() -> Result<u32> {
+    if unsafe { libc::geteuid() } != 0 {
+        anyhow::bail!("mount-helper must run as root: invoke it through sudo or pkexec");
+    }
+
+    let real = unsafe { libc::getuid() };
+    let caller = if real != 0 {
+        real
+    } else {
+        // pkexec and sudo run us with a real uid of 0 but say who asked
+        ["PKEXEC_UID", "SUDO_UID"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok()?.parse().ok())
+            .unwrap_or(0)
+    };
+
+    // mkfs and losetup would otherwise be looked up on the caller's PATH
+    for (key, _) in std::env::vars_os() {
+        std::env::remove_var(key);
+    }
+    std::env::set_var("PATH", SAFE_PATH);
+
+    if unsafe { libc::setresuid(0, 0, 0) } != 0 {
+        let errno = std::io::Error::last_os_error();
+        anyhow::bail!("Failed to switch to root: {}", errno);
+    }
+
+    Ok(caller)
+}
+
+/// Only touches `<workdir>/reflink-bench/<run-id>` directories the caller
+/// owns, so the helper can't be pointed at arbitrary paths. Returns the
+/// directory's path, for naming things after it, and the directory opened
+/// without following symlinks anywhere along the path, so the caller can't
+/// swap it out after the checks.
+fn open_run_dir(dir: &Path, caller: u32) -> Result<(PathBuf, File)> {
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", dir.display()))?;
+
+    let parent = dir.parent().and_then(Path::file_name);
+    if parent != Some(OsStr::new(RUNS_DIR_NAME)) {
+        anyhow::bail!(
+            "{} is not a run directory (<workdir>/{}/<run-id>)",
+            dir.display(),
+            RUNS_DIR_NAME
+        );
+    }
+
+    let mut run = File::open("/").context("Failed to open /")?;
+    for component in dir.components() {
+        if let Component::Normal(name) = component {
+            run = open_dir_at(&run, name)
+                .with_context(|| format!("Failed to open {} without symlinks", dir.display()))?;
+        }
+    }
+
+    let owner = run
+        .metadata()
+        .with_context(|| format!("Failed to stat {}", dir.display()))?
+        .uid();
+    if caller != 0 && owner != caller {
+        anyhow::bail!(
+            "{} is owned by uid {}, not the calling user (uid {})",
+            dir.display(),
+            owner,
+            caller
+        );
+    }
+
+    Ok((dir, run))
+}
+
+/// Opens directory `name` in `dir`, failing if it is a symlink.
+fn open_dir_at(dir: &File, name: &OsStr) -> std::io::Result<File> {
+    let name = CString::new(name.as_bytes())?;
+    let fd = unsafe {
+        libc::openat(
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// The root-owned [`HELPER_DIR_NAME`] directory in a run directory, where
+/// the helper puts images and mount points. The caller can't create or
+/// replace anything inside it, and the helper reaches it only through its
+/// open file descriptor, so no path root follows is under the caller's
+/// control.
+struct HelperDir {
+    dir: File,
+}
+
+impl HelperDir {
+    /// Creates the directory in `run`, or opens the one an earlier helper
+    /// call created.
+    fn open(run: &File) -> Result<Self> {
+        let name = CString::new(HELPER_DIR_NAME)?;
+        if unsafe { libc::mkdirat(run.as_raw_fd(), name.as_ptr(), 0o755) } != 0 {
+            let errno = std::io::Error::last_os_error();
+            if errno.kind() != std::io::ErrorKind::AlreadyExists {
+                return Err(errno).context("Failed to create helper directory");
+            }
+        }
+        let dir = open_dir_at(run, OsStr::new(HELPER_DIR_NAME))
+            .context("Failed to open helper directory")?;
+
+        let metadata = dir.metadata().context("Failed to stat helper directory")?;
+        if metadata.uid() != 0 || metadata.mode() & 0o022 != 0 {
+            anyhow::bail!(
+                "{} in the run directory wasn't created by mount-helper",
+                HELPER_DIR_NAME
+            );
+        }
+        // The caller's umask may have narrowed it, but the caller needs to
+        // reach the mount points inside
+        if unsafe { libc::fchmod(dir.as_raw_fd(), 0o755) } != 0 {
+            let errno = std::io::Error::last_os_error();
+            return Err(errno).context("Failed to set helper directory permissions");
+        }
+        Ok(Self { dir })
+    }
+
+    /// A path to the directory through this process's file descriptor, for
+    /// the helper and the tools it runs.
+    fn path(&self) -> PathBuf {
+        PathBuf::from(format!(
+            "/proc/{}/fd/{}",
+            std::process::id(),
+            self.dir.as_raw_fd()
+        ))
+    }
+
+    /// Removes the directory from `run` once nothing is left in it.
+    fn remove_if_empty(&self, run: &File) {
+        let Ok(name) = CString::new(HELPER_DIR_NAME) else {
+            return;
+        };
+        unsafe { libc::unlinkat(run.as_raw_fd(), name.as_ptr(), libc::AT_REMOVEDIR) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_run_directories_are_accepted() {
+        let workdir =
+            std::env::temp_dir().join(format!("reflink-bench-helper-test-{}", std::process::id()));
+        let run = workdir.join(RUNS_DIR_NAME).join("20240101-000000-1");
+        std::fs::create_dir_all(&run).unwrap();
+        let uid = unsafe { libc::getuid() };
+
+        assert_eq!(
+            open_run_dir(&run, uid).unwrap().0,
+            run.canonicalize().unwrap()
+        );
+        assert!(open_run_dir(&workdir, uid)
+            .unwrap_err()
+            .to_string()
+            .contains("not a run directory"));
+        assert!(open_run_dir(&run, uid + 1)
+            .unwrap_err()
+            .to_string()
+            .contains("not the calling user"));
+
+        // A symlink in the caller's directory is never followed
+        let elsewhere = workdir.join("elsewhere");
+        std::fs::create_dir_all(&elsewhere).unwrap();
+        let (_, run_dir) = open_run_dir(&run, uid).unwrap();
+        std::os::unix::fs::symlink(&elsewhere, run.join("link")).unwrap();
+        assert!(open_dir_at(&run_dir, OsStr::new("link")).is_err());
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[test]
+    fn only_plain_allowed_mount_options_pass() {
+        assert!(check_mount_options("noatime, compress=zstd:3,commit=120").is_ok());
+        assert!(check_mount_options("logdev=/dev/sda1").is_err());
+        assert!(check_mount_options("device=/dev/sdb").is_err());
+        assert!(check_mount_options("suid").is_err());
+        assert!(check_mount_options("compress=../../x").is_err());
+
+        let retry = RetryPolicy {
+            attempts: MAX_ATTEMPTS + 1,
+            ..RetryPolicy::default()
+        };
+        assert!(check_retry_policy(retry).is_err());
+        assert!(check_retry_policy(RetryPolicy::default()).is_ok());
+    }
+}
@@ -0,0 +1,278 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tabled::{Table, Tabled};
+
+use crate::benchmark::BenchmarkResult;
+use crate::charts;
+use crate::results::ComparisonResults;
+use crate::stats::LatencyStats;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    kernel_version TEXT NOT NULL,
+    environment TEXT NOT NULL,
+    results TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS metrics (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    filesystem TEXT NOT NULL,
+    workload TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    value REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS metrics_by_series ON metrics (filesystem, workload, metric);
+CREATE TABLE IF NOT EXISTS latency_histograms (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    filesystem TEXT NOT NULL,
+    operation TEXT NOT NULL,
+    upper_us INTEGER NOT NULL,
+    count INTEGER NOT NULL
+);
+";
+
+/// Filters for the `history` subcommand; `None` matches everything.
+pub struct HistoryQuery {
+    pub filesystem: Option<String>,
+    pub workload: Option<String>,
+    pub metric: Option<String>,
+    /// Most recent runs to include per series
+    pub limit: usize,
+}
+
+#[derive(Tabled)]
+struct TrendRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Workload")]
+    workload: String,
+    #[tabled(rename = "Metric")]
+    metric: String,
+    #[tabled(rename = "Runs")]
+    runs: usize,
+    #[tabled(rename = "First")]
+    first: String,
+    #[tabled(rename = "Latest")]
+    latest: String,
+    #[tabled(rename = "Change")]
+    change: String,
+    #[tabled(rename = "Trend")]
+    trend: String,
+}
+
+fn open(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open database {}", path.display()))?;
+    conn.execute_batch(SCHEMA)
+        .context("Failed to create database schema")?;
+    Ok(conn)
+}
+
+/// Appends one invocation's results to the history database.
+pub fn append(path: &Path, results: &ComparisonResults) -> Result<()> {
+    let mut conn = open(path)?;
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "INSERT INTO runs (timestamp, kernel_version, environment, results) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            results.timestamp,
+            results.environment.kernel_version,
+            serde_json::to_string(&results.environment)?,
+            serde_json::to_string(results)?,
+        ],
+    )?;
+    let run_id = tx.last_insert_rowid();
+
+    for (filesystem, result) in &results.results {
+        for (workload, metric, value) in metrics(result) {
+            // Keep each suite scenario's trends apart
+            let workload = match &results.scenario {
+                Some(scenario) => format!("{}/{}", scenario, workload),
+                None => workload,
+            };
+            tx.execute(
+                "INSERT INTO metrics (run_id, filesystem, workload, metric, value) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![run_id, filesystem, workload, metric, value],
+            )?;
+        }
+
+        for (operation, stats) in [
+            ("reflink", &result.reflink_latency),
+            ("CoW write", &result.write_latency),
+        ] {
+            for bucket in &stats.histogram {
+                tx.execute(
+                    "INSERT INTO latency_histograms (run_id, filesystem, operation, upper_us, count) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![run_id, filesystem, operation, bucket.upper_us, bucket.count],
+                )?;
+            }
+        }
+    }
+
+    tx.commit().context("Failed to save run to database")
+}
+
+/// Flattens a result into `(workload, metric, value)` rows.
+fn metrics(result: &BenchmarkResult) -> Vec<(String, String, f64)> {
+    let mut rows = Vec::new();
+    let mut push = |workload: &str, metric: &str, value: f64| {
+        rows.push((workload.to_string(), metric.to_string(), value));
+    };
+    let micros = |stats: &LatencyStats, p99: bool| {
+        let d = if p99 { stats.p99 } else { stats.p50 };
+        d.as_secs_f64() * 1_000_000.0
+    };
+
+    push("sequential", "ops_per_sec", result.operations_per_sec);
+    push(
+        "sequential",
+        "throughput_mb_per_sec",
+        result.throughput_mb_per_sec,
+    );
+    push(
+        "sequential",
+        "reflink_p50_us",
+        micros(&result.reflink_latency, false),
+    );
+    push(
+        "sequential",
+        "reflink_p99_us",
+        micros(&result.reflink_latency, true),
+    );
+    push(
+        "sequential",
+        "write_p50_us",
+        micros(&result.write_latency, false),
+    );
+    push(
+        "sequential",
+        "write_p99_us",
+        micros(&result.write_latency, true),
+    );
+
+    let concurrent = &result.concurrent_results;
+    for (i, threads) in concurrent.thread_counts.iter().enumerate() {
+        let workload = format!("concurrent x{}", threads);
+        push(&workload, "ops_per_sec", concurrent.operations_per_sec[i]);
+        if let Some(latency) = concurrent.latencies.get(i) {
+            push(&workload, "p99_us", micros(latency, true));
+        }
+    }
+
+    for c in &result.cache_results {
+        push(
+            &format!("{}-cache", c.cache_mode),
+            "ops_per_sec",
+            c.operations_per_sec,
+        );
+    }
+
+    for w in &result.write_size_results {
+        push(
+            &format!("write-size {}", w.write_size_bytes),
+            "ops_per_sec",
+            w.operations_per_sec,
+        );
+    }
+
+    for w in &result.workload_results {
+        push(&w.workload, "ops_per_sec", w.operations_per_sec);
+        for (name, value) in &w.metrics {
+            push(&w.workload, name, *value);
+        }
+    }
+
+    rows
+}
+
+/// Prints one trend row per matching (filesystem, workload, metric) series.
+pub fn print_history(path: &Path, query: &HistoryQuery) -> Result<()> {
+    if !path.exists() {
+        anyhow::bail!("Database {} does not exist", path.display());
+    }
+    let conn = open(path)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT m.filesystem, m.workload, m.metric, m.value
+         FROM metrics m JOIN runs r ON r.id = m.run_id
+         WHERE (?1 IS NULL OR lower(m.filesystem) = lower(?1))
+           AND (?2 IS NULL OR m.workload = ?2)
+           AND (?3 IS NULL OR m.metric = ?3)
+         ORDER BY r.timestamp, r.id",
+    )?;
+    let rows = stmt.query_map(
+        params![query.filesystem, query.workload, query.metric],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, f64>(3)?,
+            ))
+        },
+    )?;
+
+    let mut series: BTreeMap<(String, String, String), Vec<f64>> = BTreeMap::new();
+    for row in rows {
+        let (filesystem, workload, metric, value) = row?;
+        series
+            .entry((filesystem, workload, metric))
+            .or_default()
+            .push(value);
+    }
+
+    if series.is_empty() {
+        println!("No matching runs in {}", path.display());
+        return Ok(());
+    }
+
+    let trend_rows: Vec<TrendRow> = series
+        .into_iter()
+        .map(|((filesystem, workload, metric), mut values)| {
+            if values.len() > query.limit {
+                values.drain(..values.len() - query.limit);
+            }
+            let first = values[0];
+            let latest = values[values.len() - 1];
+            TrendRow {
+                filesystem,
+                workload,
+                metric,
+                runs: values.len(),
+                first: format!("{:.2}", first),
+                latest: format!("{:.2}", latest),
+                change: format!(
+                    "{:+.1}%",
+                    (latest - first) / first.abs().max(f64::EPSILON) * 100.0
+                ),
+                trend: charts::sparkline(&values),
+            }
+        })
+        .collect();
+
+    let table = Table::new(trend_rows);
+    println!("{}", table);
+    Ok(())
+}
+
+/// Every stored run with its row id, oldest first.
+pub fn load_runs(path: &Path) -> Result<Vec<(i64, ComparisonResults)>> {
+    let conn = open(path)?;
+    let mut stmt = conn.prepare("SELECT id, results FROM runs ORDER BY timestamp, id")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut runs = Vec::new();
+    for row in rows {
+        let (id, json) = row?;
+        let results = ComparisonResults::from_json(&json)
+            .with_context(|| format!("Failed to parse run {} in {}", id, path.display()))?;
+        runs.push((id, results));
+    }
+    Ok(runs)
+}
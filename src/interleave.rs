@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Whose turn it is, and who is still taking turns.
+struct State {
+    current: usize,
+    /// Whether `current` has started its turn, so its next call passes on
+    started: bool,
+    present: Vec<bool>,
+}
+
+impl State {
+    /// Hands the turn to the next seat still present, in order.
+    fn advance(&mut self) {
+        self.started = false;
+        let seats = self.present.len();
+        if let Some(next) = (1..=seats)
+            .map(|step| (self.current + step) % seats)
+            .find(|&seat| self.present[seat])
+        {
+            self.current = next;
+        }
+    }
+}
+
+/// One seat at a table of filesystems taking turns phase by phase, for
+/// `--interleave`. Each filesystem's runner calls [`next`](Self::next)
+/// before each phase, which ends its previous turn and waits for the others
+/// to have theirs, so phases run A, B, A, B rather than all of A first, and
+/// drift (thermal throttling, background daemons, device wear-leveling)
+/// lands on every filesystem alike.
+#[derive(Clone)]
+pub struct Turns {
+    state: Arc<watch::Sender<State>>,
+    seat: usize,
+    label: Arc<str>,
+}
+
+impl std::fmt::Debug for Turns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Turns")
+            .field("seat", &self.seat)
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Turns {
+    /// A seat for each of `labels`, taking turns in that order.
+    pub fn table(labels: &[String]) -> Vec<Self> {
+        let (state, _) = watch::channel(State {
+            current: 0,
+            started: false,
+            present: vec![true; labels.len()],
+        });
+        let state = Arc::new(state);
+        labels
+            .iter()
+            .enumerate()
+            .map(|(seat, label)| Self {
+                state: Arc::clone(&state),
+                seat,
+                label: label.as_str().into(),
+            })
+            .collect()
+    }
+
+    /// Ends this seat's turn, if it's having one, and waits for its next.
+    pub async fn next(&self) {
+        self.state.send_if_modified(|state| {
+            let mine = state.current == self.seat && state.started;
+            if mine {
+                state.advance();
+            }
+            mine
+        });
+
+        let mut turn = self.state.subscribe();
+        // The sender lives as long as `self`, so this can't fail
+        let _ = turn.wait_for(|state| state.current == self.seat).await;
+        self.state.send_modify(|state| state.started = true);
+        println!("🔀 {}'s turn", self.label);
+    }
+
+    /// Gives up this seat for good, passing the turn on if it's this seat's.
+    pub fn leave(&self) {
+        self.state.send_modify(|state| {
+            state.present[self.seat] = false;
+            if state.current == self.seat {
+                state.advance();
+            }
+        });
+    }
+
+    pub fn leave_on_drop(&self) -> LeaveOnDrop {
+        LeaveOnDrop(self.clone())
+    }
+}
+
+/// Leaves the table when dropped, so a runner that fails or panics doesn't
+/// keep the others waiting.
+pub struct LeaveOnDrop(Turns);
+
+impl Drop for LeaveOnDrop {
+    fn drop(&mut self) {
+        self.0.leave();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn seats_alternate_and_leaving_skips_a_seat() {
+        let labels = ["a".to_string(), "b".to_string()];
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let tasks: Vec<_> = Turns::table(&labels)
+            .into_iter()
+            .zip([3, 1])
+            .map(|(turns, phases)| {
+                let order = Arc::clone(&order);
+                tokio::spawn(async move {
+                    for phase in 0..phases {
+                        turns.next().await;
+                        order.lock().unwrap().push((turns.seat, phase));
+                        tokio::task::yield_now().await;
+                    }
+                    turns.leave();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec![(0, 0), (1, 0), (0, 1), (0, 2)]);
+    }
+}
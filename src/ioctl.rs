@@ -0,0 +1,452 @@
+use nix::sys::ioctl::ioctl_num_type;
+use nix::{request_code_read, request_code_readwrite, request_code_write};
+use std::fs::File;
+use std::mem::size_of;
+use std::os::unix::io::AsRawFd;
+
+// The clone and dedupe ioctls from linux/fs.h. Request numbers are encoded
+// with nix's `_IOW`/`_IOWR` equivalents rather than hard-coded, since the
+// direction bits differ between architectures (mips and powerpc put write
+// at bit 31, not 30). Adding another ioctl means a request code here, a
+// `nix::ioctl_*_bad!` wrapper in `raw`, and a safe function below.
+
+/// ioctl type of the `FI*` filesystem ioctls
+const FS_IOC_MAGIC: u8 = 0x94;
+
+/// `_IOW(0x94, 9, int)`
+pub const FICLONE: ioctl_num_type = request_code_write!(FS_IOC_MAGIC, 9, size_of::<libc::c_int>());
+
+/// `_IOW(0x94, 13, struct file_clone_range)`
+pub const FICLONERANGE: ioctl_num_type =
+    request_code_write!(FS_IOC_MAGIC, 13, size_of::<libc::file_clone_range>());
+
+/// `_IOWR(0x94, 54, struct file_dedupe_range)`. The size is the header's,
+/// without the trailing destination array.
+pub const FIDEDUPERANGE: ioctl_num_type =
+    request_code_readwrite!(FS_IOC_MAGIC, 54, size_of::<FileDedupeRangeHeader>());
+
+/// FILE_DEDUPE_RANGE_DIFFERS from linux/fs.h
+const FILE_DEDUPE_RANGE_DIFFERS: i32 = 1;
+
+// The fscrypt ioctls from linux/fscrypt.h
+
+/// ioctl type of the fscrypt ioctls
+const FSCRYPT_IOC_MAGIC: u8 = b'f';
+
+/// `_IOR('f', 19, struct fscrypt_policy_v1)`. Despite the direction and
+/// size, it sets a policy, and takes v2 policies too.
+pub const FS_IOC_SET_ENCRYPTION_POLICY: ioctl_num_type =
+    request_code_read!(FSCRYPT_IOC_MAGIC, 19, FSCRYPT_POLICY_V1_SIZE);
+
+/// `_IOWR('f', 23, struct fscrypt_add_key_arg)`. The size is the header's,
+/// without the trailing raw key.
+pub const FS_IOC_ADD_ENCRYPTION_KEY: ioctl_num_type =
+    request_code_readwrite!(FSCRYPT_IOC_MAGIC, 23, size_of::<FscryptAddKeyHeader>());
+
+/// `_IOWR('f', 24, struct fscrypt_remove_key_arg)`
+pub const FS_IOC_REMOVE_ENCRYPTION_KEY: ioctl_num_type =
+    request_code_readwrite!(FSCRYPT_IOC_MAGIC, 24, size_of::<FscryptRemoveKeyArg>());
+
+/// `_IOWR('f', 11, struct fiemap)`. The size is the header's, without the
+/// trailing extent array.
+pub const FS_IOC_FIEMAP: ioctl_num_type =
+    request_code_readwrite!(FSCRYPT_IOC_MAGIC, 11, size_of::<Fiemap>());
+
+/// FIEMAP_FLAG_SYNC from linux/fiemap.h: flush dirty data before mapping
+const FIEMAP_FLAG_SYNC: u32 = 1;
+
+/// FIEMAP_EXTENT_LAST and FIEMAP_EXTENT_SHARED from linux/fiemap.h
+const FIEMAP_EXTENT_LAST: u32 = 0x1;
+const FIEMAP_EXTENT_SHARED: u32 = 0x2000;
+
+/// Extents fetched per FIEMAP call
+const FIEMAP_BATCH: usize = 64;
+
+/// `_IOWR('X', 121, struct fstrim_range)`, from linux/fs.h
+pub const FITRIM: ioctl_num_type = request_code_readwrite!(b'X', 121, size_of::<FstrimRange>());
+
+/// `sizeof(struct fscrypt_policy_v1)`
+const FSCRYPT_POLICY_V1_SIZE: usize = 12;
+
+/// Bytes in an fscrypt master key, the most AES-256-XTS needs
+pub const FSCRYPT_KEY_SIZE: usize = 64;
+
+/// An fscrypt master key identifier, derived from the key by the kernel
+pub type FscryptKeyIdentifier = [u8; 16];
+
+/// FSCRYPT_KEY_SPEC_TYPE_IDENTIFIER, FSCRYPT_POLICY_V2,
+/// FSCRYPT_MODE_AES_256_XTS, FSCRYPT_MODE_AES_256_CTS, and
+/// FSCRYPT_POLICY_FLAGS_PAD_32 from linux/fscrypt.h
+const FSCRYPT_KEY_SPEC_TYPE_IDENTIFIER: u32 = 2;
+const FSCRYPT_POLICY_V2: u8 = 2;
+const FSCRYPT_MODE_AES_256_XTS: u8 = 1;
+const FSCRYPT_MODE_AES_256_CTS: u8 = 4;
+const FSCRYPT_POLICY_FLAGS_PAD_32: u8 = 3;
+
+mod raw {
+    use super::{
+        Fiemap, FiemapExtents, FileDedupeRange, FscryptAddKeyArg, FscryptPolicyV2,
+        FscryptRemoveKeyArg, FstrimRange, FICLONE, FICLONERANGE, FIDEDUPERANGE, FITRIM,
+        FS_IOC_ADD_ENCRYPTION_KEY, FS_IOC_FIEMAP, FS_IOC_REMOVE_ENCRYPTION_KEY,
+        FS_IOC_SET_ENCRYPTION_POLICY,
+    };
+
+    nix::ioctl_write_int_bad!(ficlone, FICLONE);
+    nix::ioctl_write_ptr_bad!(ficlonerange, FICLONERANGE, libc::file_clone_range);
+    nix::ioctl_readwrite_bad!(fideduperange, FIDEDUPERANGE, FileDedupeRange);
+    nix::ioctl_readwrite_bad!(fiemap, FS_IOC_FIEMAP, Fiemap);
+    nix::ioctl_readwrite_bad!(fiemap_extents, FS_IOC_FIEMAP, FiemapExtents);
+    nix::ioctl_readwrite_bad!(fitrim, FITRIM, FstrimRange);
+    nix::ioctl_write_ptr_bad!(
+        set_encryption_policy,
+        FS_IOC_SET_ENCRYPTION_POLICY,
+        FscryptPolicyV2
+    );
+    nix::ioctl_readwrite_bad!(
+        add_encryption_key,
+        FS_IOC_ADD_ENCRYPTION_KEY,
+        FscryptAddKeyArg
+    );
+    nix::ioctl_readwrite_bad!(
+        remove_encryption_key,
+        FS_IOC_REMOVE_ENCRYPTION_KEY,
+        FscryptRemoveKeyArg
+    );
+}
+
+/// `struct file_dedupe_range`, whose size goes into the request code.
+#[repr(C)]
+struct FileDedupeRangeHeader {
+    src_offset: u64,
+    src_length: u64,
+    dest_count: u16,
+    reserved1: u16,
+    reserved2: u32,
+}
+
+/// `struct file_dedupe_range` with room for a single destination.
+#[repr(C)]
+struct FileDedupeRange {
+    header: FileDedupeRangeHeader,
+    info: [FileDedupeRangeInfo; 1],
+}
+
+/// `struct file_dedupe_range_info`
+#[repr(C)]
+struct FileDedupeRangeInfo {
+    dest_fd: i64,
+    dest_offset: u64,
+    bytes_deduped: u64,
+    status: i32,
+    reserved: u32,
+}
+
+/// `struct fstrim_range`: the byte range to discard and the smallest free
+/// extent worth discarding. The kernel writes the bytes it trimmed back
+/// into `len`.
+#[repr(C)]
+struct FstrimRange {
+    start: u64,
+    len: u64,
+    minlen: u64,
+}
+
+/// `struct fiemap` without room for any extents, which asks only for the
+/// count.
+#[repr(C)]
+struct Fiemap {
+    start: u64,
+    length: u64,
+    flags: u32,
+    mapped_extents: u32,
+    extent_count: u32,
+    reserved: u32,
+}
+
+/// `struct fiemap` with room for a batch of extents.
+#[repr(C)]
+struct FiemapExtents {
+    header: Fiemap,
+    extents: [FiemapExtent; FIEMAP_BATCH],
+}
+
+/// `struct fiemap_extent`
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct FiemapExtent {
+    logical: u64,
+    physical: u64,
+    length: u64,
+    reserved64: [u64; 2],
+    flags: u32,
+    reserved: [u32; 3],
+}
+
+/// One extent of a file's data, from FIEMAP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    pub logical: u64,
+    pub physical: u64,
+    pub length: u64,
+    /// Other files (or other ranges of this one) map the same blocks
+    pub shared: bool,
+}
+
+/// `struct fscrypt_key_specifier`, with the union as raw bytes.
+#[repr(C)]
+struct FscryptKeySpecifier {
+    kind: u32,
+    reserved: u32,
+    u: [u8; 32],
+}
+
+impl FscryptKeySpecifier {
+    fn identifier(identifier: FscryptKeyIdentifier) -> Self {
+        let mut u = [0; 32];
+        u[..identifier.len()].copy_from_slice(&identifier);
+        Self {
+            kind: FSCRYPT_KEY_SPEC_TYPE_IDENTIFIER,
+            reserved: 0,
+            u,
+        }
+    }
+}
+
+/// `struct fscrypt_add_key_arg`, whose size goes into the request code.
+#[repr(C)]
+struct FscryptAddKeyHeader {
+    key_spec: FscryptKeySpecifier,
+    raw_size: u32,
+    key_id: u32,
+    reserved: [u32; 8],
+}
+
+/// `struct fscrypt_add_key_arg` with a raw key.
+#[repr(C)]
+struct FscryptAddKeyArg {
+    header: FscryptAddKeyHeader,
+    raw: [u8; FSCRYPT_KEY_SIZE],
+}
+
+/// `struct fscrypt_policy_v2`
+#[repr(C)]
+struct FscryptPolicyV2 {
+    version: u8,
+    contents_encryption_mode: u8,
+    filenames_encryption_mode: u8,
+    flags: u8,
+    log2_data_unit_size: u8,
+    reserved: [u8; 3],
+    master_key_identifier: FscryptKeyIdentifier,
+}
+
+/// `struct fscrypt_remove_key_arg`
+#[repr(C)]
+struct FscryptRemoveKeyArg {
+    key_spec: FscryptKeySpecifier,
+    removal_status_flags: u32,
+    reserved: [u32; 5],
+}
+
+/// What the kernel did with a dedupe request's destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dedupe {
+    /// This many bytes now share extents with the source
+    Shared(u64),
+    /// The ranges' contents differ, so nothing was shared
+    Differs,
+}
+
+/// Makes `target` share all of `source`'s extents, replacing its contents.
+pub fn ficlone(target: &File, source: &File) -> std::io::Result<()> {
+    unsafe { raw::ficlone(target.as_raw_fd(), source.as_raw_fd()) }?;
+    Ok(())
+}
+
+/// Makes `len` bytes of `target` at `dest_offset` share the extents of
+/// `source` at `src_offset`.
+pub fn ficlonerange(
+    target: &File,
+    dest_offset: u64,
+    source: &File,
+    src_offset: u64,
+    len: u64,
+) -> std::io::Result<()> {
+    let range = libc::file_clone_range {
+        src_fd: source.as_raw_fd() as i64,
+        src_offset,
+        src_length: len,
+        dest_offset,
+    };
+    unsafe { raw::ficlonerange(target.as_raw_fd(), &range) }?;
+    Ok(())
+}
+
+/// Shares `len` bytes of `source` at `src_offset` with `target` at
+/// `dest_offset` if their contents match.
+pub fn fideduperange(
+    source: &File,
+    src_offset: u64,
+    len: u64,
+    target: &File,
+    dest_offset: u64,
+) -> std::io::Result<Dedupe> {
+    let mut range = FileDedupeRange {
+        header: FileDedupeRangeHeader {
+            src_offset,
+            src_length: len,
+            dest_count: 1,
+            reserved1: 0,
+            reserved2: 0,
+        },
+        info: [FileDedupeRangeInfo {
+            dest_fd: target.as_raw_fd() as i64,
+            dest_offset,
+            bytes_deduped: 0,
+            status: 0,
+            reserved: 0,
+        }],
+    };
+    unsafe { raw::fideduperange(source.as_raw_fd(), &mut range) }?;
+
+    let info = &range.info[0];
+    match info.status {
+        FILE_DEDUPE_RANGE_DIFFERS => Ok(Dedupe::Differs),
+        status if status < 0 => Err(std::io::Error::from_raw_os_error(-status)),
+        _ => Ok(Dedupe::Shared(info.bytes_deduped)),
+    }
+}
+
+/// How many extents `file`'s data is mapped to, after flushing it.
+pub fn fiemap_extent_count(file: &File) -> std::io::Result<u32> {
+    let mut map = Fiemap {
+        start: 0,
+        length: u64::MAX,
+        flags: FIEMAP_FLAG_SYNC,
+        mapped_extents: 0,
+        extent_count: 0,
+        reserved: 0,
+    };
+    unsafe { raw::fiemap(file.as_raw_fd(), &mut map) }?;
+    Ok(map.mapped_extents)
+}
+
+/// Discards every free extent of the filesystem `dir` is on, as `fstrim`
+/// does, and returns the bytes trimmed.
+pub fn fitrim(dir: &File) -> std::io::Result<u64> {
+    let mut range = FstrimRange {
+        start: 0,
+        len: u64::MAX,
+        minlen: 0,
+    };
+    unsafe { raw::fitrim(dir.as_raw_fd(), &mut range) }?;
+    Ok(range.len)
+}
+
+/// Every extent of `file`'s data, after flushing it.
+pub fn fiemap(file: &File) -> std::io::Result<Vec<Extent>> {
+    let mut extents = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut map = FiemapExtents {
+            header: Fiemap {
+                start,
+                length: u64::MAX - start,
+                flags: FIEMAP_FLAG_SYNC,
+                mapped_extents: 0,
+                extent_count: FIEMAP_BATCH as u32,
+                reserved: 0,
+            },
+            extents: [FiemapExtent::default(); FIEMAP_BATCH],
+        };
+        unsafe { raw::fiemap_extents(file.as_raw_fd(), &mut map) }?;
+
+        let mapped = &map.extents[..map.header.mapped_extents as usize];
+        extents.extend(mapped.iter().map(|extent| Extent {
+            logical: extent.logical,
+            physical: extent.physical,
+            length: extent.length,
+            shared: extent.flags & FIEMAP_EXTENT_SHARED != 0,
+        }));
+        match mapped.last() {
+            Some(last) if last.flags & FIEMAP_EXTENT_LAST == 0 => {
+                start = last.logical + last.length;
+            }
+            _ => return Ok(extents),
+        }
+    }
+}
+
+/// Adds `key` as an fscrypt master key to the filesystem `dir` is on,
+/// returning the identifier policies refer to it by.
+pub fn fscrypt_add_key(
+    dir: &File,
+    key: &[u8; FSCRYPT_KEY_SIZE],
+) -> std::io::Result<FscryptKeyIdentifier> {
+    let mut arg = FscryptAddKeyArg {
+        header: FscryptAddKeyHeader {
+            key_spec: FscryptKeySpecifier::identifier([0; 16]),
+            raw_size: FSCRYPT_KEY_SIZE as u32,
+            key_id: 0,
+            reserved: [0; 8],
+        },
+        raw: *key,
+    };
+    unsafe { raw::add_encryption_key(dir.as_raw_fd(), &mut arg) }?;
+
+    let mut identifier = [0; 16];
+    identifier.copy_from_slice(&arg.header.key_spec.u[..16]);
+    Ok(identifier)
+}
+
+/// Encrypts everything created in the empty directory `dir` from now on
+/// with the key `identifier` names, using a v2 policy with AES-256-XTS
+/// contents and AES-256-CTS names, the defaults of the `fscrypt` tool.
+pub fn fscrypt_set_policy(dir: &File, identifier: FscryptKeyIdentifier) -> std::io::Result<()> {
+    let policy = FscryptPolicyV2 {
+        version: FSCRYPT_POLICY_V2,
+        contents_encryption_mode: FSCRYPT_MODE_AES_256_XTS,
+        filenames_encryption_mode: FSCRYPT_MODE_AES_256_CTS,
+        flags: FSCRYPT_POLICY_FLAGS_PAD_32,
+        log2_data_unit_size: 0,
+        reserved: [0; 3],
+        master_key_identifier: identifier,
+    };
+    unsafe { raw::set_encryption_policy(dir.as_raw_fd(), &policy) }?;
+    Ok(())
+}
+
+/// Removes the fscrypt master key `identifier` from the filesystem `dir`
+/// is on, locking files encrypted with it once they're closed.
+pub fn fscrypt_remove_key(dir: &File, identifier: FscryptKeyIdentifier) -> std::io::Result<()> {
+    let mut arg = FscryptRemoveKeyArg {
+        key_spec: FscryptKeySpecifier::identifier(identifier),
+        removal_status_flags: 0,
+        reserved: [0; 5],
+    };
+    unsafe { raw::remove_encryption_key(dir.as_raw_fd(), &mut arg) }?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(
+        target_env = "gnu",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn request_codes_match_linux_headers() {
+        assert_eq!(FICLONE, 0x40049409);
+        assert_eq!(FICLONERANGE, 0x4020940d);
+        assert_eq!(FIDEDUPERANGE, 0xc0189436);
+        assert_eq!(FS_IOC_FIEMAP, 0xc020660b);
+        assert_eq!(FITRIM, 0xc0185879);
+        assert_eq!(size_of::<FiemapExtent>(), 56);
+        assert_eq!(FS_IOC_SET_ENCRYPTION_POLICY, 0x800c6613);
+        assert_eq!(FS_IOC_ADD_ENCRYPTION_KEY, 0xc0506617);
+        assert_eq!(FS_IOC_REMOVE_ENCRYPTION_KEY, 0xc0406618);
+        assert_eq!(size_of::<FscryptPolicyV2>(), 24);
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fmt::Display;
+
+/// `IOPRIO_WHO_PROCESS` from linux/ioprio.h; with a thread id, sets just
+/// that thread
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+/// Bits the class is shifted by in an I/O priority value
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+
+/// I/O scheduling class, as with `ionice -c`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum IoClass {
+    /// Served first; needs CAP_SYS_ADMIN
+    Realtime,
+    /// The default class, ordered by level
+    BestEffort,
+    /// Only served when the disk is otherwise idle
+    Idle,
+}
+
+impl Display for IoClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoClass::Realtime => write!(f, "realtime"),
+            IoClass::BestEffort => write!(f, "best-effort"),
+            IoClass::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+/// An I/O priority: class plus level (0 highest to 7 lowest), which the
+/// idle class ignores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoPriority {
+    pub class: IoClass,
+    pub level: u8,
+}
+
+impl IoPriority {
+    /// The `ioprio` value passed to `ioprio_set`.
+    fn value(self) -> libc::c_int {
+        let class = match self.class {
+            IoClass::Realtime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        };
+        let level = match self.class {
+            IoClass::Idle => 0,
+            _ => self.level as libc::c_int,
+        };
+        (class << IOPRIO_CLASS_SHIFT) | level
+    }
+
+    /// Applies the priority to every thread of this process. Threads
+    /// started afterwards inherit it from the thread that starts them.
+    pub fn apply(self) -> Result<()> {
+        let tasks = std::fs::read_dir("/proc/self/task").context("Failed to list threads")?;
+        for task in tasks {
+            let name = task?.file_name();
+            let Some(tid) = name
+                .to_str()
+                .and_then(|tid| tid.parse::<libc::c_int>().ok())
+            else {
+                continue;
+            };
+            let result = unsafe {
+                libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, self.value())
+            };
+            if result != 0 {
+                let errno = std::io::Error::last_os_error();
+                anyhow::bail!("Failed to set I/O priority {}: {}", self, errno);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for IoPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.class {
+            IoClass::Idle => write!(f, "{}", self.class),
+            _ => write!(f, "{} level {}", self.class, self.level),
+        }
+    }
+}
+
+/// Block layer I/O scheduler for the benchmarked device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum IoScheduler {
+    /// No reordering or merging beyond the block layer's own
+    None,
+    MqDeadline,
+    Bfq,
+    Kyber,
+}
+
+impl Display for IoScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoScheduler::None => write!(f, "none"),
+            IoScheduler::MqDeadline => write!(f, "mq-deadline"),
+            IoScheduler::Bfq => write!(f, "bfq"),
+            IoScheduler::Kyber => write!(f, "kyber"),
+        }
+    }
+}
+
+impl IoScheduler {
+    /// Switches block device `device` (e.g. `loop0`) to this scheduler.
+    pub fn apply(self, device: &str) -> Result<()> {
+        let path = format!("/sys/block/{}/queue/scheduler", device);
+        let available = std::fs::read_to_string(&path)
+            .with_context(|| format!("{} has no I/O scheduler to set", device))?;
+        let name = self.to_string();
+        if !available
+            .split_whitespace()
+            .any(|scheduler| scheduler.trim_matches(['[', ']']) == name)
+        {
+            anyhow::bail!(
+                "I/O scheduler {} is not available for {} (available: {}); try modprobe {}",
+                name,
+                device,
+                available.trim(),
+                name.replace('-', "_")
+            );
+        }
+        std::fs::write(&path, &name)
+            .with_context(|| format!("Failed to set {}'s I/O scheduler to {}", device, name))
+    }
+}
+
+/// The I/O scheduler block device `device` is using, if it has one.
+pub fn current_scheduler(device: &str) -> Option<String> {
+    let path = format!("/sys/block/{}/queue/scheduler", device);
+    active_scheduler(&std::fs::read_to_string(path).ok()?)
+}
+
+/// The bracketed entry of a `queue/scheduler` file, e.g. `mq-deadline` from
+/// `none [mq-deadline] bfq`.
+fn active_scheduler(schedulers: &str) -> Option<String> {
+    schedulers
+        .split_whitespace()
+        .find_map(|scheduler| Some(scheduler.strip_prefix('[')?.strip_suffix(']')?.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priorities_and_schedulers_use_kernel_encodings() {
+        let priority = IoPriority {
+            class: IoClass::BestEffort,
+            level: 4,
+        };
+        assert_eq!(priority.value(), (2 << 13) | 4);
+        let idle = IoPriority {
+            class: IoClass::Idle,
+            level: 4,
+        };
+        assert_eq!(idle.value(), 3 << 13);
+        assert_eq!(idle.to_string(), "idle");
+
+        assert_eq!(
+            active_scheduler("none [mq-deadline] bfq\n").as_deref(),
+            Some("mq-deadline")
+        );
+        assert_eq!(active_scheduler("none"), None);
+    }
+}
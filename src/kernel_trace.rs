@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// Kernel entry points for FICLONE/FICLONERANGE on each filesystem. Names
+/// have moved between kernel versions, so only those present in
+/// /proc/kallsyms are traced.
+const REMAP_FUNCTIONS: &[&str] = &[
+    "xfs_reflink_remap_range",
+    "xfs_file_remap_range",
+    "xfs_reflink_remap_blocks",
+    "btrfs_remap_file_range",
+    "btrfs_clone_files",
+];
+
+/// How long bpftrace gets to compile and attach its probes.
+const STARTUP_DELAY: Duration = Duration::from_secs(2);
+
+/// Time spent in one kernel function over a benchmark run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KernelFunctionLatency {
+    pub function: String,
+    pub calls: u64,
+    pub mean: Duration,
+    /// bpftrace's log2 histogram of call latency in microseconds
+    pub histogram: String,
+}
+
+/// A bpftrace process timing the kernel's reflink remap functions.
+pub struct KernelTracer {
+    child: Child,
+}
+
+impl KernelTracer {
+    pub async fn start() -> Result<Self> {
+        let functions = traceable_functions()?;
+        if functions.is_empty() {
+            anyhow::bail!("None of the reflink remap functions are present in /proc/kallsyms");
+        }
+
+        let mut child = Command::new("bpftrace")
+            .arg("-e")
+            .arg(bpftrace_script(&functions))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start bpftrace; is it installed?")?;
+
+        tokio::time::sleep(STARTUP_DELAY).await;
+        if child.try_wait()?.is_some() {
+            let output = child.wait_with_output()?;
+            anyhow::bail!(
+                "bpftrace exited early: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(Self { child })
+    }
+
+    pub fn stop(self) -> Result<Vec<KernelFunctionLatency>> {
+        // bpftrace prints its maps when interrupted
+        let result = unsafe { libc::kill(self.child.id() as libc::pid_t, libc::SIGINT) };
+        if result != 0 {
+            let errno = std::io::Error::last_os_error();
+            let mut child = self.child;
+            let _ = child.kill();
+            anyhow::bail!("Failed to signal bpftrace: {}", errno);
+        }
+
+        let output = self.child.wait_with_output()?;
+        Ok(parse_maps(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+fn traceable_functions() -> Result<Vec<&'static str>> {
+    let kallsyms =
+        std::fs::read_to_string("/proc/kallsyms").context("Failed to read /proc/kallsyms")?;
+    let symbols: std::collections::HashSet<&str> = kallsyms
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(2))
+        .collect();
+
+    Ok(REMAP_FUNCTIONS
+        .iter()
+        .copied()
+        .filter(|function| symbols.contains(function))
+        .collect())
+}
+
+fn bpftrace_script(functions: &[&str]) -> String {
+    let mut script = String::new();
+    for function in functions {
+        script.push_str(&format!(
+            "kprobe:{f} {{ @start[\"{f}\", tid] = nsecs; }}\n\
+             kretprobe:{f} /@start[\"{f}\", tid]/ {{\n\
+             \x20 $ns = nsecs - @start[\"{f}\", tid];\n\
+             \x20 @stats[\"{f}\"] = stats($ns);\n\
+             \x20 @us[\"{f}\"] = hist($ns / 1000);\n\
+             \x20 delete(@start[\"{f}\", tid]);\n\
+             }}\n",
+            f = function
+        ));
+    }
+    script.push_str("END { clear(@start); }\n");
+    script
+}
+
+/// Parses the `@stats` and `@us` maps bpftrace prints on exit:
+///
+/// ```text
+/// @stats[xfs_file_remap_range]: count 1000, average 23456, total 23456000
+/// @us[xfs_file_remap_range]:
+/// [16, 32)    912 |@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@|
+/// ```
+fn parse_maps(output: &str) -> Vec<KernelFunctionLatency> {
+    let mut results: Vec<KernelFunctionLatency> = Vec::new();
+    let mut histogram_for: Option<String> = None;
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("@stats[") {
+            histogram_for = None;
+            let Some((function, values)) = rest.split_once("]: ") else {
+                continue;
+            };
+            let value = |name: &str| -> u64 {
+                values
+                    .split(", ")
+                    .find_map(|field| field.strip_prefix(name))
+                    .and_then(|v| v.trim().parse().ok())
+                    .unwrap_or(0)
+            };
+            results.push(KernelFunctionLatency {
+                function: function.to_string(),
+                calls: value("count "),
+                mean: Duration::from_nanos(value("average ")),
+                histogram: String::new(),
+            });
+        } else if let Some(rest) = line.strip_prefix("@us[") {
+            histogram_for = rest.strip_suffix("]:").map(str::to_string);
+        } else if line.trim().is_empty() {
+            histogram_for = None;
+        } else if let Some(function) = &histogram_for {
+            if let Some(result) = results.iter_mut().find(|r| &r.function == function) {
+                result.histogram.push_str(line);
+                result.histogram.push('\n');
+            }
+        }
+    }
+
+    results
+}
@@ -0,0 +1,86 @@
+//! Benchmarks reflink (`FICLONE`) + copy-on-write performance across
+//! filesystems.
+//!
+//! The building blocks live here; the `reflink-bench` binary adds flag
+//! parsing and the orchestration of a full run on top (expanding scenarios,
+//! setting up each filesystem, checkpointing, and choosing outputs), so
+//! other tools driving the benchmarks directly do that part themselves. At
+//! its core a run sets up a loopback filesystem with [`FilesystemManager`],
+//! benchmarks it with [`BenchmarkRunner`], and reports the
+//! [`BenchmarkResult`]s with [`ResultsReporter`]:
+//!
+//! ```no_run
+//! use reflink_bench::{BenchmarkConfig, BenchmarkRunner, FilesystemManager, FilesystemType};
+//! use std::path::Path;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! // Needs root for losetup and mount(2)
+//! let mut fs = FilesystemManager::new(FilesystemType::Xfs, 2, Path::new("/var/tmp"))?;
+//! fs.setup().await?;
+//!
+//! let config = BenchmarkConfig {
+//!     file_size_mb: 10,
+//!     reflink_count: 100,
+//!     thread_counts: vec![1, 4],
+//!     ..BenchmarkConfig::default()
+//! };
+//! let result = BenchmarkRunner::new(fs.mount_point(), config)
+//!     .run_benchmark()
+//!     .await?;
+//! println!("{:.0} reflink + write ops/sec", result.operations_per_sec);
+//!
+//! fs.cleanup().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod background;
+pub mod baseline;
+pub mod benchmark;
+pub mod blktrace;
+pub mod cgroup;
+pub mod charts;
+pub mod checkpoint;
+pub mod compare;
+pub mod copy;
+pub mod cpu;
+pub mod diskstats;
+pub mod doctor;
+pub mod environment;
+pub mod external;
+pub mod filesystem;
+/// The binary's privileged `mount-helper` subcommand; not library API.
+#[doc(hidden)]
+pub mod helper;
+pub mod history;
+pub mod interleave;
+pub mod ioctl;
+pub mod iosched;
+pub mod kernel_trace;
+pub mod list;
+pub mod lock;
+pub mod perf;
+pub mod placement;
+pub mod plan;
+pub mod progress;
+pub mod prometheus;
+pub mod provider;
+pub mod quiet;
+pub mod results;
+pub mod retry;
+pub mod run_dir;
+pub mod serve;
+pub mod shutdown;
+pub mod space;
+pub mod stats;
+pub mod stream;
+pub mod suite;
+pub mod timeseries;
+pub mod tui;
+pub mod verify;
+pub mod workloads;
+
+pub use benchmark::{BenchmarkConfig, BenchmarkResult, BenchmarkRunner};
+pub use filesystem::{FilesystemManager, FilesystemType};
+pub use results::{ComparisonResults, ResultsReporter};
+pub use workloads::{OperationMix, Workload, WorkloadKind, WorkloadResult};
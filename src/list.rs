@@ -0,0 +1,68 @@
+use clap::ValueEnum;
+use tabled::{Table, Tabled};
+
+use crate::environment;
+use crate::filesystem::FilesystemType;
+use crate::workloads::WorkloadKind;
+
+#[derive(Tabled)]
+struct WorkloadRow {
+    #[tabled(rename = "Workload")]
+    name: String,
+    #[tabled(rename = "Description")]
+    description: String,
+    #[tabled(rename = "Uses")]
+    operations: &'static str,
+    #[tabled(rename = "Limitations")]
+    limitations: &'static str,
+}
+
+#[derive(Tabled)]
+struct FilesystemRow {
+    #[tabled(rename = "Filesystem")]
+    name: String,
+    #[tabled(rename = "mkfs")]
+    mkfs: &'static str,
+    #[tabled(rename = "Installed")]
+    installed: &'static str,
+    #[tabled(rename = "Reflink Since Kernel")]
+    reflink_since: &'static str,
+}
+
+/// Prints the workloads and filesystems `run` can use.
+pub fn print_capabilities() {
+    let workload_rows: Vec<WorkloadRow> = WorkloadKind::value_variants()
+        .iter()
+        .map(|&workload| WorkloadRow {
+            name: workload.to_string(),
+            description: workload
+                .to_possible_value()
+                .and_then(|value| value.get_help().map(|help| help.to_string()))
+                .unwrap_or_default(),
+            operations: workload.operations(),
+            limitations: workload.limitations(),
+        })
+        .collect();
+
+    println!("🧪 WORKLOADS (--workloads)");
+    println!("==========================");
+    println!("{}", Table::new(workload_rows));
+
+    let filesystem_rows: Vec<FilesystemRow> = FilesystemType::value_variants()
+        .iter()
+        .map(|fs_type| FilesystemRow {
+            name: fs_type
+                .to_possible_value()
+                .map(|value| value.get_name().to_string())
+                .unwrap_or_default(),
+            mkfs: fs_type.mkfs_command(),
+            installed: if fs_type.is_available() { "✅" } else { "❌" },
+            reflink_since: fs_type.reflink_since(),
+        })
+        .collect();
+
+    println!("\n💾 FILESYSTEMS (--filesystems)");
+    println!("==============================");
+    println!("{}", Table::new(filesystem_rows));
+    println!("Running kernel: {}", environment::kernel_version());
+}
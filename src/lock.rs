@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Lock file created in the locked directory.
+pub const LOCK_FILE_NAME: &str = "reflink-bench.lock";
+
+/// An exclusive `flock` on a run directory, held for as long as the value
+/// lives. A run holds its own directory's lock and `cleanup` takes it
+/// before tearing anything down, so `cleanup` never removes the images and
+/// mounts of a run that is still going. The kernel drops the lock when the
+/// process exits, however it exits, so a crashed run never leaves its
+/// directory locked.
+pub struct DirLock {
+    _file: File,
+}
+
+impl DirLock {
+    /// Locks `dir`, failing straight away if another process holds it.
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        let path = dir.join(LOCK_FILE_NAME);
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+        let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+        if result != 0 {
+            let errno = std::io::Error::last_os_error();
+            if errno.raw_os_error() == Some(libc::EWOULDBLOCK) {
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                anyhow::bail!(
+                    "Another reflink-bench (pid {}) is using {}",
+                    pid.trim(),
+                    dir.display()
+                );
+            }
+            anyhow::bail!("Failed to lock {}: {}", path.display(), errno);
+        }
+
+        // Record who holds it for the message above
+        file.set_len(0)
+            .and_then(|()| file.rewind())
+            .and_then(|()| write!(file, "{}", std::process::id()))
+            .with_context(|| format!("Failed to write lock file {}", path.display()))?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_lock_fails_until_first_is_dropped() {
+        let workdir =
+            std::env::temp_dir().join(format!("reflink-bench-lock-test-{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+
+        let lock = DirLock::acquire(&workdir).unwrap();
+        let error = DirLock::acquire(&workdir).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains(&format!("pid {}", std::process::id())));
+
+        drop(lock);
+        assert!(DirLock::acquire(&workdir).is_ok());
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+}
@@ -1,14 +1,36 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-mod benchmark;
-mod filesystem;
-mod results;
-
-use benchmark::{BenchmarkConfig, BenchmarkRunner};
-use filesystem::{FilesystemManager, FilesystemType};
-use results::ResultsReporter;
+use reflink_bench::background::BackgroundLoad;
+use reflink_bench::benchmark::{
+    self, BenchmarkConfig, BenchmarkResult, BenchmarkRunner, CacheMode, Rate, RepetitionSummary,
+    Runner, ThreadCounts,
+};
+use reflink_bench::cgroup::{Cgroup, IoMax};
+use reflink_bench::checkpoint::{self, Checkpoint};
+use reflink_bench::environment::EnvironmentInfo;
+use reflink_bench::external::ExternalTool;
+use reflink_bench::filesystem::{self, FilesystemManager, FilesystemType};
+use reflink_bench::interleave::Turns;
+use reflink_bench::iosched::{IoClass, IoPriority, IoScheduler};
+use reflink_bench::placement::{self, CpuList, Placement};
+use reflink_bench::progress::ProgressBar;
+use reflink_bench::provider::LoopOptions;
+use reflink_bench::results::{self, OutputFormat, PresetCell, ResultsReporter, SweepCell};
+use reflink_bench::retry::RetryPolicy;
+use reflink_bench::run_dir::RunDir;
+use reflink_bench::stream::EventStream;
+use reflink_bench::suite::{self, PresetFamily, Scenario};
+use reflink_bench::tui::Dashboard;
+use reflink_bench::workloads::{OperationMix, WorkloadKind};
+use reflink_bench::{
+    baseline, compare, doctor, helper, history, list, plan, quiet, serve, shutdown,
+};
 
 #[derive(Parser)]
 #[command(name = "reflink-bench")]
@@ -19,9 +41,16 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+// Parsed once at startup, so the size of `Run` doesn't matter
+#[allow(clippy::large_enum_variant)]
 enum Commands {
-    /// Run benchmarks comparing XFS and btrfs reflink + write performance
+    /// Run benchmarks comparing reflink + write performance across filesystems
     Run {
+        /// Run every [[scenario]] in this TOML suite file; flags given on the
+        /// command line become defaults for each scenario
+        #[arg(long = "config", value_name = "PATH")]
+        suite_file: Option<PathBuf>,
+
         /// Size of test files in MB
         #[arg(long, default_value = "100")]
         file_size_mb: u64,
@@ -30,71 +59,1556 @@ enum Commands {
         #[arg(long, default_value = "1000")]
         reflink_count: u32,
 
+        /// Filesystems to benchmark, in order (e.g. btrfs or xfs,btrfs,bcachefs)
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "xfs,btrfs")]
+        filesystems: Vec<FilesystemType>,
+
+        /// Sweep source file sizes in MB (e.g. 10,100,1000); each size runs
+        /// on its own fresh filesystems
+        #[arg(long, value_delimiter = ',', value_parser = clap::value_parser!(u64).range(1..))]
+        file_sizes: Vec<u64>,
+
+        /// Sweep reflink+write counts (e.g. 100,1000,10000)
+        #[arg(long, value_delimiter = ',', value_parser = clap::value_parser!(u32).range(1..))]
+        reflink_counts: Vec<u32>,
+
+        /// Enable quota groups on btrfs before benchmarking it
+        #[arg(long)]
+        btrfs_qgroups: bool,
+
+        /// Mount XFS with prjquota and run under a project quota on the
+        /// benchmark directory
+        #[arg(long)]
+        xfs_project_quota: bool,
+
+        /// Also benchmark btrfs with autodefrag, with short and long commit
+        /// intervals, and with quota groups, and compare them side by side
+        #[arg(long, conflicts_with_all = ["file_sizes", "reflink_counts", "rootless"])]
+        btrfs_presets: bool,
+
+        /// Also benchmark every filesystem with each mount option variant
+        /// of these families, and compare them side by side
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            value_name = "FAMILY",
+            conflicts_with_all = ["btrfs_presets", "file_sizes", "reflink_counts", "rootless"]
+        )]
+        mount_presets: Vec<PresetFamily>,
+
         /// Size of filesystem images in GB
         #[arg(long, default_value = "2")]
         fs_size_gb: u64,
 
+        /// Directory to create filesystem images and mount points in
+        #[arg(long, default_value = filesystem::DEFAULT_WORKDIR)]
+        workdir: PathBuf,
+
+        /// Benchmark in DIR, on the already mounted XFS, btrfs, or bcachefs
+        /// filesystem holding it, instead of on loopback filesystems, so no
+        /// root is needed. The detected filesystem replaces --filesystems
+        #[arg(long, value_name = "DIR")]
+        rootless: Option<PathBuf>,
+
+        /// Run losetup, mkfs, and mount through this privileged command
+        /// instead of in-process, so the benchmark itself runs unprivileged,
+        /// e.g. "sudo -n /usr/local/bin/reflink-bench" or "pkexec
+        /// /usr/local/bin/reflink-bench". See `mount-helper`
+        #[arg(long, value_name = "COMMAND", conflicts_with = "rootless")]
+        helper: Option<String>,
+
+        /// Put each filesystem on a dm-crypt mapping over its loop device,
+        /// to measure the encryption tax on CoW writes and clones
+        #[arg(long, conflicts_with = "rootless")]
+        encrypt_underlay: bool,
+
+        /// Attach loop devices with direct I/O, so writes aren't cached a
+        /// second time for the image file and write numbers aren't inflated
+        #[arg(long, conflicts_with = "rootless")]
+        loop_direct_io: bool,
+
+        /// Logical sector size of the loop devices in bytes, e.g. 4096 to
+        /// match a 4Kn disk (512, 1024, 2048, or 4096)
+        #[arg(long, value_name = "BYTES", value_parser = parse_sector_size, conflicts_with = "rootless")]
+        loop_sector_size: Option<u32>,
+
+        /// Put the XFS image in DIR instead of --workdir, e.g. on its own
+        /// disk, so its writeback doesn't contend with the other filesystems'
+        #[arg(long, value_name = "DIR", conflicts_with = "rootless")]
+        backing_path_xfs: Option<PathBuf>,
+
+        /// Put the btrfs image in DIR instead of --workdir
+        #[arg(long, value_name = "DIR", conflicts_with = "rootless")]
+        backing_path_btrfs: Option<PathBuf>,
+
+        /// Put the bcachefs image in DIR instead of --workdir
+        #[arg(long, value_name = "DIR", conflicts_with = "rootless")]
+        backing_path_bcachefs: Option<PathBuf>,
+
+        /// Tries for loop device attachment, mount, and umount before
+        /// giving up, since they can fail transiently (EBUSY) right after I/O
+        #[arg(long, default_value = "5", value_parser = clap::value_parser!(u32).range(1..))]
+        setup_attempts: u32,
+
+        /// Wait before retrying a failed setup or cleanup step, doubling
+        /// after each failure (e.g. 100ms, 1s)
+        #[arg(long, default_value = "100ms", value_parser = humantime::parse_duration)]
+        setup_retry_delay: Duration,
+
+        /// Print the commands, mounts, and ioctls the run would perform, with
+        /// disk and time estimates, without doing anything
+        #[arg(long, conflicts_with_all = ["quiet", "tui"])]
+        dry_run: bool,
+
+        /// Leave each filesystem's last run mounted, with its files, for
+        /// inspection; remove them later with `cleanup --workdir`
+        #[arg(long)]
+        keep: bool,
+
+        /// Continue an interrupted run from its checkpoint, skipping the
+        /// filesystems it already finished
+        #[arg(long, conflicts_with = "dry_run")]
+        resume: bool,
+
+        /// Where progress is saved after each filesystem finishes
+        /// [default: <workdir>/reflink-bench-checkpoint.json]
+        #[arg(long)]
+        checkpoint: Option<PathBuf>,
+
         /// Output results to JSON file
         #[arg(long)]
         output: Option<PathBuf>,
+
+        /// Stream one JSON object per operation and phase to a file (or `-`
+        /// for stdout) while the benchmark runs
+        #[arg(long, value_name = "PATH|-")]
+        stream: Option<PathBuf>,
+
+        /// Push results to this Prometheus pushgateway (e.g. http://localhost:9091)
+        #[arg(long, value_name = "URL")]
+        pushgateway: Option<String>,
+
+        /// Job name to push metrics under
+        #[arg(long, default_value = "reflink-bench")]
+        push_job: String,
+
+        /// Show a live dashboard while the benchmark runs
+        #[arg(long)]
+        tui: bool,
+
+        /// Don't draw progress bars on stderr (they're only drawn when it's
+        /// a terminal)
+        #[arg(long)]
+        no_progress: bool,
+
+        /// Append results to this SQLite history database
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+
+        /// Save these results as a named baseline
+        #[arg(long, value_name = "NAME")]
+        save_baseline: Option<String>,
+
+        /// Show changes against a previously saved named baseline
+        #[arg(long, value_name = "NAME")]
+        baseline: Option<String>,
+
+        /// How to render the final comparison
+        #[arg(long, value_enum, default_value_t = OutputFormat::Terminal)]
+        format: OutputFormat,
+
+        /// Suppress all progress output and print only the results JSON
+        /// (same as --format json-stdout)
+        #[arg(short, long, conflicts_with_all = ["tui", "format"])]
+        quiet: bool,
+
+        /// Additional workloads to run after the reflink + write benchmark
+        #[arg(long, value_enum, value_delimiter = ',')]
+        workloads: Vec<WorkloadKind>,
+
+        /// Sweep CoW write sizes after each reflink (e.g. 4k,64k,1m,16m)
+        #[arg(long, value_delimiter = ',', value_parser = benchmark::parse_size)]
+        write_sizes: Vec<u64>,
+
+        /// Sweep clone latency over sources written in extents of these
+        /// sizes (e.g. 64k,1m,16m)
+        #[arg(long, value_delimiter = ',', value_parser = benchmark::parse_size)]
+        source_extent_sizes: Vec<u64>,
+
+        /// Number of source files created by the small-files workload
+        #[arg(long, default_value = "20000")]
+        small_file_count: u32,
+
+        /// Number of distinct source files to reflink from
+        #[arg(long, default_value = "1")]
+        source_count: u32,
+
+        /// Write each source file as N separately allocated extents, to
+        /// measure how clone cost grows with the source's extent count
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        fragment_source: Option<u32>,
+
+        /// Reflink/write/read percentages for the mixed workload
+        #[arg(long, default_value = "70/20/10")]
+        mix: OperationMix,
+
+        /// Worker counts for the concurrent scaling benchmark (e.g.
+        /// 1,4,16,64), or `auto` for powers of two up to twice the CPU count
+        #[arg(long, default_value = "1,2,4,8,16,32,64,128")]
+        threads: ThreadCounts,
+
+        /// Seed for source data and randomized offsets; runs with the same
+        /// seed write identical bytes. Random (and recorded) by default
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Also run reflink + write for this long (e.g. 10m) and report
+        /// sustained ops/sec and latency drift over time
+        #[arg(long, value_parser = humantime::parse_duration)]
+        duration: Option<Duration>,
+
+        /// Also issue reflink + write on a fixed schedule at this rate (e.g.
+        /// 500ops/s), whether or not earlier operations have finished, and
+        /// report latency from each operation's scheduled start
+        #[arg(long)]
+        rate: Option<Rate>,
+
+        /// Also run reflink + write in an fscrypt-encrypted directory, on
+        /// filesystems that support fscrypt
+        #[arg(long)]
+        fscrypt: bool,
+
+        /// Also run reflink + write of a sparse source this big (e.g. 4t),
+        /// with 64 KB of data every GB; it can be far larger than the
+        /// filesystem
+        #[arg(long, value_name = "SIZE", value_parser = benchmark::parse_size)]
+        sparse_source: Option<u64>,
+
+        /// Also try to clone the source into DIR, which must be on another
+        /// mount, and time the EXDEV failure and the copies that fall back
+        /// from it
+        #[arg(long, value_name = "DIR")]
+        cross_fs: Option<PathBuf>,
+
+        /// Also time copying the source within each filesystem with sendfile
+        /// and a read/write loop, to compare the clones against
+        #[arg(long)]
+        copy_baselines: bool,
+
+        /// Also run reflink + write with each clone done by these tools
+        /// (cp, xfs-io), to compare exec and tool overhead with the
+        /// in-process ioctl
+        #[arg(long, value_enum, value_delimiter = ',')]
+        external_tools: Vec<ExternalTool>,
+
+        /// Number of concurrent workers in the mixed workload
+        #[arg(long, default_value = "8")]
+        mixed_workers: u32,
+
+        /// Percentage of blocks the backup workload rewrites per generation
+        #[arg(long, default_value = "5", value_parser = clap::value_parser!(u32).range(1..=100))]
+        backup_change_percent: u32,
+
+        /// Measure free-space usage across separate clone and write phases
+        #[arg(long)]
+        space_report: bool,
+
+        /// Report shared, exclusive, and on-disk bytes of the sequential
+        /// phase's and each workload's files from their FIEMAP extent maps
+        #[arg(long)]
+        extent_usage: bool,
+
+        /// Untimed reflink+write operations to perform before measuring
+        #[arg(long, default_value = "0")]
+        warmup: u32,
+
+        /// Number of times to repeat each benchmark on a fresh filesystem
+        #[arg(long, default_value = "1")]
+        repeat: u32,
+
+        /// Mount a scenario's filesystems together and alternate their
+        /// phases (A, B, A, B...) instead of running all of one first, so
+        /// drift affects every filesystem equally
+        #[arg(long)]
+        interleave: bool,
+
+        /// Pin the benchmark, every worker included, to these CPUs (e.g.
+        /// 0-7,16)
+        #[arg(long, value_name = "LIST")]
+        cpus: Option<CpuList>,
+
+        /// Run on this NUMA node's CPUs and allocate memory only from it
+        #[arg(long, value_name = "NODE")]
+        numa_node: Option<u32>,
+
+        /// Throttle the benchmark's I/O to each filesystem's device with
+        /// cgroup v2 io.max limits (e.g. wbps=100m,riops=1000)
+        #[arg(long, value_name = "LIMITS")]
+        cgroup_io_max: Option<IoMax>,
+
+        /// Cap the benchmark's memory, page cache included, with cgroup v2
+        /// memory.max (e.g. 512m)
+        #[arg(long, value_name = "SIZE", value_parser = benchmark::parse_size)]
+        cgroup_mem_max: Option<u64>,
+
+        /// Run the benchmark's I/O in this scheduling class, as with ionice
+        #[arg(long, value_enum, value_name = "CLASS")]
+        ionice: Option<IoClass>,
+
+        /// Priority within the --ionice class, from 0 (highest) to 7
+        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(0..=7))]
+        ionice_level: u8,
+
+        /// Threads in tokio's blocking pool, which runs every operation with
+        /// the default runner; concurrent phases above this size queue for
+        /// a thread instead of running in parallel
+        #[arg(long, default_value_t = DEFAULT_IO_THREADS, value_parser = parse_io_threads)]
+        io_threads: usize,
+
+        /// Switch each filesystem's block device to this I/O scheduler
+        #[arg(long, value_enum, value_name = "SCHEDULER")]
+        io_scheduler: Option<IoScheduler>,
+
+        /// Keep this I/O running on each filesystem while it is measured,
+        /// to see how reflinks degrade when the journal or writeback is busy
+        #[arg(long, value_enum, value_name = "LOAD")]
+        background_load: Option<BackgroundLoad>,
+
+        /// Run the sequential and concurrent operations on tokio's blocking
+        /// pool or on plain OS threads, to tell the runtime's scheduling
+        /// apart from the filesystem's
+        #[arg(long, value_enum, default_value = "tokio")]
+        runner: Runner,
+
+        /// Also report latency statistics with 3×IQR outliers removed
+        #[arg(long)]
+        trim_outliers: bool,
+
+        /// Sync and drop page caches between benchmark phases
+        #[arg(long)]
+        drop_caches: bool,
+
+        /// Discard each filesystem's free space (FITRIM, as fstrim does)
+        /// between benchmark phases
+        #[arg(long)]
+        fstrim: bool,
+
+        /// Also measure reflink + write with warm and/or cold caches
+        #[arg(long, value_enum)]
+        cache_mode: Option<CacheMode>,
+
+        /// Record a blktrace of each phase's loop device into this directory
+        #[arg(long)]
+        blktrace: Option<PathBuf>,
+
+        /// Record CPU hardware counters for each phase with `perf stat`
+        #[arg(long)]
+        perf_stat: bool,
+
+        /// Histogram time spent in the kernel's reflink remap functions
+        /// with bpftrace
+        #[arg(long)]
+        trace_kernel: bool,
+
+        /// After the timed phases, read back every clone and check it
+        /// matches its source outside the written region, and holds the
+        /// written pattern inside it
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Show per-metric changes between two saved result files
+    Compare {
+        /// Earlier results JSON
+        old: PathBuf,
+        /// Later results JSON
+        new: PathBuf,
+    },
+    /// Exit non-zero when results regress beyond a threshold of a baseline
+    Check {
+        /// Results JSON to check
+        results: PathBuf,
+
+        /// Baseline results JSON to check against
+        #[arg(long)]
+        baseline: PathBuf,
+
+        /// Largest tolerated regression of any key metric, e.g. 10%
+        #[arg(long, default_value = "10%", value_parser = compare::parse_percent)]
+        max_regression: f64,
+    },
+    /// Show metric trends across runs stored with --db
+    History {
+        /// SQLite history database
+        #[arg(long)]
+        db: PathBuf,
+
+        /// Only show this filesystem (e.g. xfs)
+        #[arg(long)]
+        filesystem: Option<String>,
+
+        /// Only show this workload (e.g. sequential, "concurrent x8", punch-hole)
+        #[arg(long)]
+        workload: Option<String>,
+
+        /// Only show this metric (e.g. ops_per_sec, reflink_p99_us)
+        #[arg(long)]
+        metric: Option<String>,
+
+        /// Most recent runs to include in each trend
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+    /// Serve stored runs as web pages with trend charts and comparisons
+    Serve {
+        /// SQLite history database written with --db
+        #[arg(long)]
+        db: Option<PathBuf>,
+
+        /// Directory of results JSON files written with --output
+        #[arg(long, value_name = "DIR")]
+        results_dir: Option<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+    },
+    /// List available workloads and filesystems
+    List,
+    /// Check privileges, tools, kernel support, and free space before a run
+    Doctor {
+        /// Filesystems the run will benchmark
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "xfs,btrfs")]
+        filesystems: Vec<FilesystemType>,
+
+        /// Size of filesystem images in GB
+        #[arg(long, default_value = "2")]
+        fs_size_gb: u64,
+
+        /// Working directory the run will use (see `run --workdir`)
+        #[arg(long, default_value = filesystem::DEFAULT_WORKDIR)]
+        workdir: PathBuf,
+    },
+    /// Clean up the filesystem images and mounts of every finished run
+    Cleanup {
+        /// Working directory the runs used (see `run --workdir`)
+        #[arg(long, default_value = filesystem::DEFAULT_WORKDIR)]
+        workdir: PathBuf,
+    },
+    /// Privileged half of `run --helper`: sets up or tears down one loopback
+    /// filesystem in a run directory. Invoke through sudo or pkexec
+    #[command(hide = true)]
+    MountHelper {
+        #[command(subcommand)]
+        action: HelperAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum HelperAction {
+    /// Create, format, and mount a filesystem, printing its loop device
+    Setup {
+        #[arg(long, value_enum)]
+        filesystem: FilesystemType,
+
+        #[arg(long)]
+        dir: PathBuf,
+
+        #[arg(long)]
+        size_gb: u64,
+
+        #[arg(long)]
+        image_dir: Option<PathBuf>,
+
+        #[arg(long)]
+        mount_options: Option<String>,
+
+        #[arg(long)]
+        encrypt_underlay: bool,
+
+        #[arg(long)]
+        loop_direct_io: bool,
+
+        #[arg(long, value_parser = parse_sector_size)]
+        loop_sector_size: Option<u32>,
+
+        #[command(flatten)]
+        retry: HelperRetry,
     },
-    /// Clean up any leftover filesystem images and mounts
-    Cleanup,
+    /// Unmount and remove a filesystem
+    Cleanup {
+        #[arg(long, value_enum)]
+        filesystem: FilesystemType,
+
+        #[arg(long)]
+        dir: PathBuf,
+
+        #[arg(long)]
+        image_dir: Option<PathBuf>,
+
+        #[command(flatten)]
+        retry: HelperRetry,
+    },
+}
+
+/// `run --setup-attempts` and `--setup-retry-delay`, passed on to the helper
+#[derive(clap::Args)]
+struct HelperRetry {
+    #[arg(long, default_value = "5", value_parser = clap::value_parser!(u32).range(1..))]
+    attempts: u32,
+
+    #[arg(long, default_value = "100ms", value_parser = humantime::parse_duration)]
+    retry_delay: Duration,
+}
+
+impl From<HelperRetry> for RetryPolicy {
+    fn from(retry: HelperRetry) -> Self {
+        Self {
+            attempts: retry.attempts,
+            delay: retry.retry_delay,
+        }
+    }
+}
+
+/// Tokio's own default for `max_blocking_threads`.
+const DEFAULT_IO_THREADS: usize = 512;
+
+fn parse_io_threads(s: &str) -> Result<usize> {
+    match s.parse().context("Invalid thread count")? {
+        0 => anyhow::bail!("--io-threads must be at least 1"),
+        threads => Ok(threads),
+    }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn parse_sector_size(s: &str) -> Result<u32> {
+    match s.parse().context("Invalid sector size")? {
+        size @ (512 | 1024 | 2048 | 4096) => Ok(size),
+        _ => anyhow::bail!("sector size must be 512, 1024, 2048, or 4096"),
+    }
+}
+
+fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // Only mount-helper checks the paths it's given; any other subcommand
+    // in a setuid or setgid copy would write wherever its caller says as root
+    let elevated =
+        unsafe { libc::geteuid() != libc::getuid() || libc::getegid() != libc::getgid() };
+    if elevated && !matches!(cli.command, Commands::MountHelper { .. }) {
+        anyhow::bail!(
+            "reflink-bench must not be installed setuid or setgid; run mount-helper through sudo or pkexec instead"
+        );
+    }
+
+    // The pool is sized when the runtime is built, so before any command runs
+    let io_threads = match &cli.command {
+        Commands::Run { io_threads, .. } => *io_threads,
+        _ => DEFAULT_IO_THREADS,
+    };
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .max_blocking_threads(io_threads)
+        .build()
+        .context("Failed to start the async runtime")?
+        .block_on(run_command(cli))
+}
+
+async fn run_command(cli: Cli) -> Result<()> {
     match cli.command {
         Commands::Run {
+            suite_file,
             file_size_mb,
             reflink_count,
+            filesystems,
+            file_sizes,
+            btrfs_presets,
+            mount_presets,
+            btrfs_qgroups,
+            xfs_project_quota,
+            reflink_counts,
             fs_size_gb,
+            workdir,
+            rootless,
+            helper,
+            encrypt_underlay,
+            loop_direct_io,
+            loop_sector_size,
+            backing_path_xfs,
+            backing_path_btrfs,
+            backing_path_bcachefs,
+            setup_attempts,
+            setup_retry_delay,
+            dry_run,
+            keep,
+            resume,
+            checkpoint,
             output,
+            format,
+            quiet,
+            stream,
+            tui,
+            no_progress,
+            db,
+            save_baseline,
+            baseline,
+            pushgateway,
+            push_job,
+            workloads,
+            write_sizes,
+            source_extent_sizes,
+            small_file_count,
+            source_count,
+            fragment_source,
+            mix,
+            threads,
+            seed,
+            mixed_workers,
+            backup_change_percent,
+            space_report,
+            extent_usage,
+            warmup,
+            repeat,
+            interleave,
+            cpus,
+            numa_node,
+            cgroup_io_max,
+            cgroup_mem_max,
+            ionice,
+            ionice_level,
+            io_threads,
+            io_scheduler,
+            background_load,
+            runner,
+            trim_outliers,
+            drop_caches,
+            fstrim,
+            cache_mode,
+            duration,
+            rate,
+            fscrypt,
+            sparse_source,
+            cross_fs,
+            copy_baselines,
+            external_tools,
+            blktrace,
+            perf_stat,
+            trace_kernel,
+            verify,
         } => {
-            println!("🚀 Starting reflink + write benchmark suite");
-            println!(
-                "File size: {}MB, Reflink+write count: {}, FS size: {}GB",
-                file_size_mb, reflink_count, fs_size_gb
-            );
+            let placement = Placement { cpus, numa_node };
+            if !dry_run {
+                // Re-executes pinned; returns in the pinned process
+                placement.apply()?;
+            }
+            let io_priority = ionice.map(|class| IoPriority {
+                class,
+                level: ionice_level,
+            });
+            if let (Some(priority), false) = (io_priority, dry_run) {
+                priority.apply()?;
+            }
+
+            let format = if quiet {
+                OutputFormat::JsonStdout
+            } else {
+                format
+            };
+            let silence = if format == OutputFormat::JsonStdout {
+                if tui {
+                    anyhow::bail!("--tui cannot be combined with JSON-only output");
+                }
+                if stream
+                    .as_deref()
+                    .is_some_and(|path| path.as_os_str() == "-")
+                {
+                    anyhow::bail!("--stream - would mix events into the JSON on stdout; stream to a file instead");
+                }
+                Some(quiet::Silence::start()?)
+            } else {
+                None
+            };
+
+            let checkpoint_path =
+                checkpoint.unwrap_or_else(|| workdir.join(checkpoint::DEFAULT_FILE_NAME));
+            let resumed = if resume {
+                Some(Checkpoint::load(&checkpoint_path)?)
+            } else {
+                None
+            };
 
             let config = BenchmarkConfig {
                 file_size_mb,
                 reflink_count,
+                workloads,
+                write_sizes,
+                source_extent_sizes,
+                small_file_count,
+                source_count,
+                fragment_source,
+                mix,
+                mixed_workers,
+                backup_change_percent,
+                thread_counts: threads.0,
+                // A resumed run must regenerate the same data as the original
+                seed: seed
+                    .or(resumed.as_ref().map(|c| c.seed()))
+                    .unwrap_or_else(rand::random),
+                space_report,
+                extent_usage,
+                warmup,
+                trim_outliers,
+                drop_caches,
+                fstrim,
+                cache_mode,
+                duration,
+                rate,
+                fscrypt,
+                sparse_source_bytes: sparse_source,
+                cross_fs_dir: cross_fs,
+                copy_baselines,
+                external_tools,
+                btrfs_qgroups,
+                xfs_project_quota,
+                io_scheduler,
+                background_load,
+                runner,
+                blktrace_dir: None,
+                perf_stat,
+                trace_kernel,
+                verify,
+                stream: None,
+                monitor: None,
+                progress: None,
+                interleave,
+                turns: None,
+                cgroup: None,
+            };
+            let run_seed = config.seed;
+            let cli_scenario = Scenario {
+                name: None,
+                filesystems,
+                fs_size_gb,
+                repeat,
+                mount_options: Default::default(),
+                backing_paths: [
+                    (FilesystemType::Xfs, backing_path_xfs),
+                    (FilesystemType::Btrfs, backing_path_btrfs),
+                    (FilesystemType::Bcachefs, backing_path_bcachefs),
+                ]
+                .into_iter()
+                .filter_map(|(fs_type, path)| Some((suite::fs_key(fs_type), path?)))
+                .collect(),
+                sweep: None,
+                preset: None,
+                config,
+            };
+            let scenarios = match &suite_file {
+                Some(path) => suite::load(path, &cli_scenario)?,
+                None => vec![cli_scenario],
+            };
+            let mut scenarios = suite::expand_sweep(scenarios, &file_sizes, &reflink_counts);
+            if btrfs_presets {
+                scenarios = suite::expand_btrfs_presets(scenarios);
+            }
+            scenarios = suite::expand_mount_presets(scenarios, &mount_presets);
+            let rootless = rootless
+                .map(|dir| -> Result<_> {
+                    let dir = dir
+                        .canonicalize()
+                        .with_context(|| format!("Failed to resolve {}", dir.display()))?;
+                    let fs_type = FilesystemType::detect(&dir)?;
+                    Ok((dir, fs_type))
+                })
+                .transpose()?;
+            for scenario in &mut scenarios {
+                if let Some((_, fs_type)) = &rootless {
+                    scenario.filesystems = vec![*fs_type];
+                    scenario.backing_paths.clear();
+                }
+                for path in scenario.backing_paths.values_mut() {
+                    *path = path.canonicalize().with_context(|| {
+                        format!("Failed to resolve backing path {}", path.display())
+                    })?;
+                }
+            }
+            if let Some(checkpoint) = &resumed {
+                checkpoint.check_matches(&scenarios)?;
+            }
+
+            let loop_options = LoopOptions {
+                direct_io: loop_direct_io,
+                sector_size: loop_sector_size,
+            };
+
+            if dry_run {
+                drop(silence);
+                return plan::print_plan(
+                    &scenarios,
+                    &plan::PlanOptions {
+                        workdir: &workdir,
+                        rootless: rootless.as_ref().map(|(dir, _)| dir.as_path()),
+                        helper: helper.as_deref(),
+                        encrypt_underlay,
+                        loop_options,
+                        keep,
+                        blktrace: blktrace.as_deref(),
+                        output: output.as_deref(),
+                        db: db.as_deref(),
+                        save_baseline: save_baseline.as_deref(),
+                        pushgateway: pushgateway.as_deref(),
+                    },
+                );
+            }
+
+            println!("🚀 Starting reflink + write benchmark suite");
+            let stream = stream.as_deref().map(EventStream::open).transpose()?;
+
+            // Load baselines up front so a typo doesn't cost a full run
+            let baselines = scenarios
+                .iter()
+                .map(|scenario| {
+                    baseline
+                        .as_ref()
+                        .map(|name| {
+                            let name = scenario.qualify(name);
+                            baseline::load(&name).map(|results| (name, results))
+                        })
+                        .transpose()
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut all_filesystems: Vec<FilesystemType> = Vec::new();
+            for fs in scenarios.iter().flat_map(|s| &s.filesystems) {
+                if !all_filesystems.contains(fs) {
+                    all_filesystems.push(*fs);
+                }
+            }
+            // Fail before any setup rather than after the first filesystem's run
+            let missing: Vec<String> = all_filesystems
+                .iter()
+                // A helper finds mkfs on its own PATH
+                .filter(|fs| rootless.is_none() && helper.is_none() && !fs.is_available())
+                .map(|fs| format!("{} ({})", fs, fs.mkfs_command()))
+                .collect();
+            if !missing.is_empty() {
+                anyhow::bail!(
+                    "mkfs tools not found for {}; install them or pick other filesystems with --filesystems",
+                    missing.join(", ")
+                );
+            }
+            let missing_tools: Vec<&str> = scenarios
+                .iter()
+                .flat_map(|s| &s.config.external_tools)
+                .map(|tool| tool.program())
+                .filter(|program| !filesystem::command_exists(program))
+                .collect();
+            if let Some(program) = missing_tools.first() {
+                anyhow::bail!("--external-tools needs {} installed", program);
+            }
+            if encrypt_underlay && helper.is_none() && !filesystem::command_exists("cryptsetup") {
+                anyhow::bail!("--encrypt-underlay needs cryptsetup installed");
+            }
+
+            std::fs::create_dir_all(&workdir).with_context(|| {
+                format!("Failed to create working directory {}", workdir.display())
+            })?;
+            // Absolute, so mount points match /proc/self/mounts
+            let workdir = workdir.canonicalize().with_context(|| {
+                format!("Failed to resolve working directory {}", workdir.display())
+            })?;
+            // Locked until the run exits, so `cleanup` leaves it alone
+            let run_dir = RunDir::create(rootless.as_ref().map_or(&workdir, |(dir, _)| dir))?;
+            println!("📂 Run {} in {}", run_dir.id(), run_dir.path().display());
+            // Images elsewhere get a run directory there too, so `cleanup
+            // --workdir <path>` finds them after a crash
+            let mut image_dirs = BTreeMap::new();
+            for path in scenarios.iter().flat_map(|s| s.backing_paths.values()) {
+                if !image_dirs.contains_key(path) {
+                    let dir = RunDir::create(path)?;
+                    println!("💾 Images also in {}", dir.path().display());
+                    image_dirs.insert(path.clone(), dir);
+                }
+            }
+            match &rootless {
+                Some((dir, fs_type)) => println!(
+                    "🔓 Rootless: benchmarking the {} filesystem holding {}, without images or mounts",
+                    fs_type,
+                    dir.display()
+                ),
+                None => {
+                    // A scenario's filesystems are set up side by side
+                    let mut needed_gb = BTreeMap::new();
+                    for scenario in &scenarios {
+                        let mut scenario_gb = BTreeMap::new();
+                        for &fs_type in &scenario.filesystems {
+                            let dir = scenario.backing_path_for(fs_type).unwrap_or(&workdir);
+                            *scenario_gb.entry(dir).or_insert(0) += scenario.fs_size_gb;
+                        }
+                        for (dir, gb) in scenario_gb {
+                            let needed = needed_gb.entry(dir).or_insert(0);
+                            *needed = gb.max(*needed);
+                        }
+                    }
+                    for (dir, gb) in needed_gb {
+                        filesystem::check_image_space(dir, gb)?;
+                        if filesystem::is_tmpfs(dir) {
+                            println!(
+                                "⚠️  {} is tmpfs: images will be held in memory; consider --workdir on a disk",
+                                dir.display()
+                            );
+                        }
+                    }
+                    if encrypt_underlay {
+                        println!("🔏 Filesystems go on dm-crypt (aes-xts-plain64) over their loop devices");
+                    }
+                    if loop_options != LoopOptions::default() {
+                        println!(
+                            "💽 Loop devices attached with losetup {}",
+                            loop_options.losetup_args().join(" ")
+                        );
+                    }
+                }
+            }
+
+            let mut checkpoint = match resumed {
+                Some(checkpoint) => {
+                    println!(
+                        "⏩ Resuming from {}: {} filesystem run(s) already finished",
+                        checkpoint.path().display(),
+                        checkpoint.completed_count()
+                    );
+                    checkpoint
+                }
+                None => {
+                    if checkpoint_path.exists() {
+                        println!(
+                            "⚠️  Replacing the checkpoint of an unfinished run at {} (pass --resume to continue it instead)",
+                            checkpoint_path.display()
+                        );
+                    }
+                    Checkpoint::create(&checkpoint_path, &scenarios, run_seed)?
+                }
+            };
+
+            let cgroup = if cgroup_io_max.is_some() || cgroup_mem_max.is_some() {
+                let cgroup = Arc::new(Cgroup::enter(cgroup_io_max, cgroup_mem_max)?);
+                for scenario in &mut scenarios {
+                    scenario.config.cgroup = Some(Arc::clone(&cgroup));
+                }
+                Some(cgroup)
+            } else {
+                None
+            };
+
+            let mut environment = EnvironmentInfo::collect(&all_filesystems);
+            if !placement.is_empty() {
+                let cpus = placement::current_affinity().map(|cpus| cpus.to_string());
+                println!(
+                    "🧷 Pinned to CPUs {}{}",
+                    cpus.as_deref().unwrap_or("?"),
+                    numa_node.map_or(String::new(), |node| format!(
+                        ", memory on NUMA node {}",
+                        node
+                    ))
+                );
+                environment.cpu_affinity = cpus;
+                environment.numa_node = numa_node;
+            }
+            if let Some(cgroup) = &cgroup {
+                println!(
+                    "🚧 Running in a cgroup limited to{}{}",
+                    cgroup
+                        .io_max()
+                        .map_or(String::new(), |io_max| format!(" io.max {}", io_max)),
+                    cgroup_mem_max.map_or(String::new(), |bytes| format!(
+                        " memory.max {}",
+                        results::format_bytes(bytes)
+                    ))
+                );
+                environment.cgroup_io_max = cgroup.io_max().map(|io_max| io_max.to_string());
+                environment.cgroup_memory_max = cgroup_mem_max;
+            }
+            if let Some(priority) = io_priority {
+                println!("🪫 I/O priority {}", priority);
+                environment.io_priority = Some(priority.to_string());
+            }
+            environment.io_threads = Some(io_threads);
+            let most_threads = scenarios
+                .iter()
+                .filter(|scenario| scenario.config.runner == Runner::Tokio)
+                .flat_map(|scenario| scenario.config.thread_counts.iter().copied())
+                .max()
+                .unwrap_or(0);
+            if most_threads as usize > io_threads {
+                println!(
+                    "⚠️  --threads goes up to {} but the blocking pool has {} threads (--io-threads); the rest will queue",
+                    most_threads, io_threads
+                );
+            }
+            let dashboard = if tui {
+                let names: Vec<String> = scenarios
+                    .iter()
+                    .flat_map(|scenario| scenario.filesystems.iter().map(|fs| scenario.label(*fs)))
+                    .collect();
+                Some(Dashboard::start(&names)?)
+            } else {
+                None
+            };
+            // The dashboard shows progress itself
+            if !no_progress
+                && !tui
+                && format != OutputFormat::JsonStdout
+                && std::io::stderr().is_terminal()
+            {
+                let progress = ProgressBar::start();
+                for scenario in &mut scenarios {
+                    scenario.config.progress = Some(progress.clone());
+                }
+            }
+
+            let retry = RetryPolicy {
+                attempts: setup_attempts,
+                delay: setup_retry_delay,
+            };
+            let image_dir = |scenario: &Scenario, fs_type| {
+                scenario
+                    .backing_path_for(fs_type)
+                    .map(|path| image_dirs[path].path())
             };
+            let new_manager = |scenario: &Scenario, fs_type| match (&rootless, &helper) {
+                (Some(_), _) => Ok(FilesystemManager::in_directory(fs_type, run_dir.path())),
+                (None, Some(helper)) => Ok(FilesystemManager::with_helper(
+                    helper,
+                    fs_type,
+                    scenario.fs_size_gb,
+                    run_dir.path(),
+                )
+                .with_image_dir(image_dir(scenario, fs_type))
+                .with_mount_options(scenario.mount_options_for(fs_type))
+                .with_retry_policy(retry)
+                .with_encrypt_underlay(encrypt_underlay)
+                .with_loop_options(loop_options)),
+                (None, None) => {
+                    Ok(
+                        FilesystemManager::new(fs_type, scenario.fs_size_gb, run_dir.path())?
+                            .with_image_dir(image_dir(scenario, fs_type))
+                            .with_mount_options(scenario.mount_options_for(fs_type))
+                            .with_retry_policy(retry)
+                            .with_encrypt_underlay(encrypt_underlay)
+                            .with_loop_options(loop_options),
+                    )
+                }
+            };
+
+            let mut scenario_results = Vec::with_capacity(scenarios.len());
+            let run_all = async {
+                for scenario in &scenarios {
+                    if let Some(name) = &scenario.name {
+                        println!("\n🧪 Scenario '{}'", name);
+                    }
+                    println!(
+                        "File size: {}MB, Reflink+write count: {}, FS size: {}GB",
+                        scenario.config.file_size_mb,
+                        scenario.config.reflink_count,
+                        scenario.fs_size_gb
+                    );
+
+                    let results = run_scenario(
+                        scenario,
+                        stream.as_ref(),
+                        dashboard.as_ref(),
+                        blktrace.as_deref(),
+                        &new_manager,
+                        keep,
+                        &mut checkpoint,
+                    )
+                    .await?;
+                    scenario_results.push(results);
+                }
+                Ok::<_, anyhow::Error>(())
+            };
+            // Losing the race drops the run, and with it every mounted
+            // filesystem's manager, which cleans up
+            tokio::select! {
+                result = run_all => result?,
+                signal = shutdown::wait_for_signal() => {
+                    drop(dashboard);
+                    anyhow::bail!(
+                        "Interrupted by {}; filesystems cleaned up. Rerun with --resume to continue",
+                        signal?
+                    );
+                }
+            }
+
+            if let Some(dashboard) = dashboard {
+                dashboard.stop();
+            }
+
+            let sweep_cells: Vec<SweepCell> = scenarios
+                .iter()
+                .zip(&scenario_results)
+                .filter_map(|(scenario, results)| Some((scenario.sweep.as_ref()?, results)))
+                .flat_map(|(point, results)| {
+                    results.iter().map(|(fs_type, result)| SweepCell {
+                        base: point.base.clone(),
+                        filesystem: *fs_type,
+                        file_size_mb: point.file_size_mb,
+                        reflink_count: point.reflink_count,
+                        operations_per_sec: result.operations_per_sec,
+                        throughput_mb_per_sec: result.throughput_mb_per_sec,
+                    })
+                })
+                .collect();
+
+            let preset_cells: Vec<PresetCell> = if btrfs_presets || !mount_presets.is_empty() {
+                scenarios
+                    .iter()
+                    .zip(&scenario_results)
+                    .flat_map(|(scenario, results)| {
+                        results.iter().map(|(fs_type, result)| PresetCell {
+                            base: match &scenario.preset {
+                                Some(preset) => preset.base.clone(),
+                                None => scenario.name.clone(),
+                            },
+                            filesystem: *fs_type,
+                            preset: scenario.preset.as_ref().map(|p| p.name.clone()),
+                            mount_options: scenario.mount_options_for(*fs_type).unwrap_or_default(),
+                            operations_per_sec: result.operations_per_sec,
+                            throughput_mb_per_sec: result.throughput_mb_per_sec,
+                            reflink_p99: result.reflink_latency.p99,
+                            write_p99: result.write_latency.p99,
+                        })
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            let mut reporters = Vec::with_capacity(scenarios.len());
+            for ((scenario, results), baseline) in
+                scenarios.iter().zip(scenario_results).zip(baselines)
+            {
+                let mut reporter = ResultsReporter::new(results)
+                    .with_environment(environment.clone())
+                    .with_scenario(scenario.name.clone())
+                    .with_seed(scenario.config.seed);
+                if let Some((name, results)) = baseline {
+                    reporter = reporter.with_baseline(&name, results);
+                }
 
-            let mut results = Vec::new();
+                if let Some(name) = &scenario.name {
+                    println!("\n🧪 Results for scenario '{}'", name);
+                }
+                match format {
+                    OutputFormat::Terminal => reporter.print_comparison(),
+                    OutputFormat::Markdown => reporter.print_markdown(),
+                    OutputFormat::Bencher => reporter.print_bencher(),
+                    // Printed last, once stdout is restored
+                    OutputFormat::JsonStdout => {}
+                }
 
-            for fs_type in [FilesystemType::Xfs, FilesystemType::Btrfs] {
-                println!("\n📊 Testing {} filesystem...", fs_type);
+                if let Some(output_path) = &output {
+                    let output_path = scenario.qualify_path(output_path);
+                    reporter.save_to_file(&output_path)?;
+                    println!("\n💾 Results saved to {}", output_path.display());
+                }
 
-                let mut fs_manager = FilesystemManager::new(fs_type, fs_size_gb)?;
-                fs_manager.setup().await?;
+                if let Some(db) = &db {
+                    history::append(db, &reporter.comparison_results())?;
+                    println!("🗄️  Run appended to {}", db.display());
+                }
 
-                let runner = BenchmarkRunner::new(fs_manager.mount_point(), config.clone());
-                let mut result = runner.run_benchmark().await?;
-                result.filesystem = format!("{}", fs_type);
+                if let Some(name) = &save_baseline {
+                    let name = scenario.qualify(name);
+                    let path = baseline::baseline_path(&name)?;
+                    reporter.save_to_file(&path)?;
+                    println!("📏 Baseline '{}' saved to {}", name, path.display());
+                }
 
-                results.push((fs_type, result));
+                if let Some(gateway) = &pushgateway {
+                    // Each scenario gets its own pushgateway grouping key
+                    let job = match &scenario.name {
+                        Some(name) => format!("{}/scenario/{}", push_job, name),
+                        None => push_job.clone(),
+                    };
+                    reporter.push_to_gateway(gateway, &job)?;
+                    println!("📡 Metrics pushed to {}", gateway);
+                }
 
-                fs_manager.cleanup().await?;
+                reporters.push(reporter);
             }
+            checkpoint.finish()?;
 
-            let reporter = ResultsReporter::new(results);
-            reporter.print_comparison();
+            match format {
+                OutputFormat::Terminal => {
+                    results::print_sweep(&sweep_cells, false);
+                    results::print_presets(&preset_cells, false);
+                }
+                OutputFormat::Markdown => {
+                    results::print_sweep(&sweep_cells, true);
+                    results::print_presets(&preset_cells, true);
+                }
+                OutputFormat::Bencher | OutputFormat::JsonStdout => {}
+            }
+
+            if let Some(dir) = blktrace {
+                println!("🔬 Block traces saved to {}", dir.display());
+            }
 
-            if let Some(output_path) = output {
-                reporter.save_to_file(&output_path)?;
-                println!("\n💾 Results saved to {}", output_path.display());
+            if keep {
+                println!(
+                    "📌 Filesystems kept under {}; remove them with `reflink-bench cleanup --workdir {}`",
+                    run_dir.path().display(),
+                    workdir.display()
+                );
+            }
+
+            if let Some(silence) = silence {
+                drop(silence);
+                match reporters.as_slice() {
+                    [reporter] => reporter.print_json()?,
+                    reporters => {
+                        let all: Vec<_> =
+                            reporters.iter().map(|r| r.comparison_results()).collect();
+                        println!(
+                            "{}",
+                            serde_json::to_string_pretty(&all)
+                                .context("Failed to serialize results")?
+                        );
+                    }
+                }
             }
         }
-        Commands::Cleanup => {
-            println!("🧹 Cleaning up filesystem artifacts...");
-            FilesystemManager::cleanup_all().await?;
+        Commands::Compare { old, new } => {
+            let old_results = compare::load_results(&old)?;
+            let new_results = compare::load_results(&new)?;
+
+            println!(
+                "🔍 Comparing {} ({}) against {} ({})",
+                new.display(),
+                new_results.timestamp,
+                old.display(),
+                old_results.timestamp
+            );
+
+            let deltas = compare::metric_deltas(&old_results, &new_results);
+            if deltas.is_empty() {
+                anyhow::bail!("No filesystems in common between the two result files");
+            }
+            compare::print_deltas(&deltas);
+        }
+        Commands::Check {
+            results,
+            baseline,
+            max_regression,
+        } => {
+            let deltas = compare::metric_deltas(
+                &compare::load_results(&baseline)?,
+                &compare::load_results(&results)?,
+            );
+            if deltas.is_empty() {
+                anyhow::bail!("No filesystems in common with the baseline");
+            }
+
+            let regressions: Vec<_> = deltas
+                .into_iter()
+                .filter(|d| d.regression_percent() > max_regression)
+                .collect();
+
+            if regressions.is_empty() {
+                println!(
+                    "✅ No metric regressed more than {}% against {}",
+                    max_regression,
+                    baseline.display()
+                );
+            } else {
+                println!(
+                    "❌ {} metric(s) regressed more than {}% against {}",
+                    regressions.len(),
+                    max_regression,
+                    baseline.display()
+                );
+                compare::print_deltas(&regressions);
+                std::process::exit(1);
+            }
+        }
+        Commands::History {
+            db,
+            filesystem,
+            workload,
+            metric,
+            limit,
+        } => {
+            history::print_history(
+                &db,
+                &history::HistoryQuery {
+                    filesystem,
+                    workload,
+                    metric,
+                    limit: limit.max(1),
+                },
+            )?;
+        }
+        Commands::Serve {
+            db,
+            results_dir,
+            listen,
+        } => {
+            if db.is_none() && results_dir.is_none() {
+                anyhow::bail!("Nothing to serve: pass --db and/or --results-dir");
+            }
+            serve::serve(serve::RunStore { db, results_dir }, &listen).await?;
+        }
+        Commands::List => list::print_capabilities(),
+        Commands::Doctor {
+            filesystems,
+            fs_size_gb,
+            workdir,
+        } => doctor::run_checks(&filesystems, &workdir, fs_size_gb)?,
+        Commands::Cleanup { workdir } => {
+            println!(
+                "🧹 Cleaning up filesystem artifacts in {}...",
+                workdir.display()
+            );
+            FilesystemManager::cleanup_all(&workdir).await?;
             println!("✅ Cleanup completed");
         }
+        Commands::MountHelper { action } => match action {
+            HelperAction::Setup {
+                filesystem,
+                dir,
+                size_gb,
+                image_dir,
+                mount_options,
+                encrypt_underlay,
+                loop_direct_io,
+                loop_sector_size,
+                retry,
+            } => println!(
+                "{}",
+                helper::setup(
+                    filesystem,
+                    size_gb,
+                    &dir,
+                    &helper::SetupOptions {
+                        mount_options: mount_options.as_deref(),
+                        encrypt_underlay,
+                        loop_options: LoopOptions {
+                            direct_io: loop_direct_io,
+                            sector_size: loop_sector_size,
+                        },
+                        image_dir: image_dir.as_deref(),
+                    },
+                    retry.into()
+                )?
+            ),
+            HelperAction::Cleanup {
+                filesystem,
+                dir,
+                image_dir,
+                retry,
+            } => helper::cleanup(filesystem, &dir, image_dir.as_deref(), retry.into())?,
+        },
     }
 
     Ok(())
 }
+
+/// Runs every filesystem of a scenario, repeating each as configured. Each
+/// round sets up all of the scenario's filesystems in parallel, then
+/// measures them one at a time so they don't compete for the disk.
+async fn run_scenario(
+    scenario: &Scenario,
+    stream: Option<&EventStream>,
+    dashboard: Option<&Dashboard>,
+    blktrace: Option<&Path>,
+    new_manager: &dyn Fn(&Scenario, FilesystemType) -> Result<FilesystemManager>,
+    keep: bool,
+    checkpoint: &mut Checkpoint,
+) -> Result<Vec<(FilesystemType, BenchmarkResult)>> {
+    let mut pending = Vec::new();
+    for &fs_type in &scenario.filesystems {
+        if checkpoint.completed(scenario, fs_type).is_some() {
+            let label = scenario.label(fs_type);
+            println!(
+                "\n⏭️  Skipping {} (finished before the interruption)",
+                label
+            );
+            if let Some(dashboard) = dashboard {
+                dashboard
+                    .monitor()
+                    .for_filesystem(&label)
+                    .finish_filesystem();
+            }
+        } else {
+            pending.push(fs_type);
+        }
+    }
+
+    let repeat = scenario.repeat.max(1);
+    let mut runs: Vec<Vec<BenchmarkResult>> =
+        vec![Vec::with_capacity(repeat as usize); pending.len()];
+    for run in 1..=repeat {
+        if pending.is_empty() {
+            break;
+        }
+        if repeat > 1 {
+            println!("\n🔁 Run {}/{}", run, repeat);
+        }
+
+        let managers = pending
+            .iter()
+            .map(|&fs_type| new_manager(scenario, fs_type))
+            .collect::<Result<Vec<_>>>()?;
+        println!(
+            "\n🛠️  Setting up {}...",
+            pending
+                .iter()
+                .map(|fs| fs.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let managers = FilesystemManager::setup_all(managers).await?;
+        let fs_types: Vec<FilesystemType> = managers.iter().map(|m| m.fs_type()).collect();
+        let configs = fs_types.iter().map(|&fs_type| {
+            let label = scenario.label(fs_type);
+            let mut run_config = scenario.config.clone();
+            if let Some(dir) = blktrace {
+                // Keep each filesystem's (and each run's) traces apart
+                let mut name = scenario.qualify(&format!("{:?}", fs_type).to_lowercase());
+                if repeat > 1 {
+                    name = format!("{}-run{}", name, run);
+                }
+                run_config.blktrace_dir = Some(dir.join(name));
+            }
+            run_config.stream = stream.map(|s| s.for_filesystem(&label));
+            run_config.monitor = dashboard.map(|d| d.monitor().for_filesystem(&label));
+            run_config.progress = run_config
+                .progress
+                .map(|progress| progress.for_filesystem(&label));
+            run_config
+        });
+        let keep_run = keep && run == repeat;
+
+        let mut record = |i: usize, result: Result<BenchmarkResult>| -> Result<()> {
+            let label = scenario.label(fs_types[i]);
+            if let Some(stream) = stream.map(|s| s.for_filesystem(&label)) {
+                match &result {
+                    Ok(result) => stream.result(run, result)?,
+                    Err(e) => stream.error(run, e)?,
+                }
+            }
+            runs[i].push(result?);
+
+            if run == repeat {
+                let mut result = runs[i][0].clone();
+                if repeat > 1 {
+                    result.repetitions = Some(RepetitionSummary::from_results(&runs[i]));
+                }
+                checkpoint.record(scenario, fs_types[i], &result)?;
+                if let Some(dashboard) = dashboard {
+                    dashboard
+                        .monitor()
+                        .for_filesystem(&label)
+                        .finish_filesystem();
+                }
+            }
+            Ok(())
+        };
+
+        if scenario.config.interleave && managers.len() > 1 {
+            let labels: Vec<String> = fs_types.iter().map(|&fs| scenario.label(fs)).collect();
+            println!(
+                "\n📊 Testing {} phase by phase, taking turns...",
+                labels.join(", ")
+            );
+            let tasks: Vec<_> = managers
+                .into_iter()
+                .zip(configs)
+                .zip(Turns::table(&labels))
+                .map(|((manager, mut run_config), turns)| {
+                    run_config.turns = Some(turns.clone());
+                    tokio::spawn(async move {
+                        let _leave = turns.leave_on_drop();
+                        run_filesystem(manager, keep_run, &run_config).await
+                    })
+                })
+                .collect();
+
+            let mut tasks = tasks.into_iter().enumerate();
+            while let Some((i, task)) = tasks.next() {
+                let result = task
+                    .await
+                    .context("Task panicked")
+                    .and_then(|result| result);
+                if let Err(e) = record(i, result) {
+                    // Dropping a run tears its filesystem down
+                    for (_, task) in tasks {
+                        task.abort();
+                    }
+                    return Err(e);
+                }
+            }
+        } else {
+            let mut rounds = managers.into_iter().zip(configs).enumerate();
+            while let Some((i, (manager, run_config))) = rounds.next() {
+                println!("\n📊 Testing {} filesystem...", scenario.label(fs_types[i]));
+                let result = run_filesystem(manager, keep_run, &run_config).await;
+                if let Err(e) = record(i, result) {
+                    // Don't leave the rest of the round mounted
+                    for (_, (manager, _)) in rounds {
+                        let _ = manager.cleanup().await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    // Everything is in the checkpoint now, whether from this run or an
+    // interrupted one
+    let results = scenario
+        .filesystems
+        .iter()
+        .filter_map(|&fs_type| Some((fs_type, checkpoint.completed(scenario, fs_type)?.clone())))
+        .collect();
+    Ok(results)
+}
+
+/// Benchmarks an already set-up filesystem, then tears it down unless it
+/// should be kept for inspection.
+async fn run_filesystem(
+    fs_manager: FilesystemManager,
+    keep: bool,
+    config: &BenchmarkConfig,
+) -> Result<BenchmarkResult> {
+    let fs_type = fs_manager.fs_type();
+    let runner = BenchmarkRunner::new(fs_manager.mount_point(), config.clone());
+    let result = runner.run_benchmark().await;
+    // Unmounting is I/O too, so it waits for a turn like any phase
+    if let Some(turns) = &config.turns {
+        turns.next().await;
+    }
+    let mut result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            let _ = fs_manager.cleanup().await;
+            return Err(e);
+        }
+    };
+    result.filesystem = format!("{}", fs_type);
+
+    if keep {
+        println!(
+            "📌 Keeping {} mounted at {} ({})",
+            fs_type,
+            fs_manager.mount_point().display(),
+            fs_manager.device().unwrap_or("no device")
+        );
+        fs_manager.keep();
+    } else {
+        fs_manager.cleanup().await?;
+    }
+
+    Ok(result)
+}
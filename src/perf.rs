@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// Events recorded for every phase, in the order `perf stat` reports them.
+const EVENTS: &str = "cycles,instructions,cache-misses,context-switches";
+
+/// How long perf gets to attach to the process before the phase starts.
+const STARTUP_DELAY: Duration = Duration::from_millis(200);
+
+/// Hardware and software counters for one benchmark phase. Counters the
+/// machine doesn't expose (common in VMs) are `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerfCounters {
+    pub phase: String,
+    pub cycles: Option<u64>,
+    pub instructions: Option<u64>,
+    pub cache_misses: Option<u64>,
+    pub context_switches: Option<u64>,
+}
+
+impl PerfCounters {
+    /// Instructions per cycle.
+    pub fn ipc(&self) -> Option<f64> {
+        match (self.instructions, self.cycles) {
+            (Some(instructions), Some(cycles)) if cycles > 0 => {
+                Some(instructions as f64 / cycles as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A `perf stat` process attached to this benchmark for one phase.
+pub struct PerfStat {
+    phase: String,
+    child: Child,
+    output: PathBuf,
+}
+
+impl PerfStat {
+    pub async fn start(phase: &str) -> Result<Self> {
+        let output = std::env::temp_dir().join(format!(
+            "reflink-bench-perf-{}-{}.csv",
+            std::process::id(),
+            phase.replace(' ', "-")
+        ));
+
+        let mut child = Command::new("perf")
+            .args(["stat", "-x", ",", "-e", EVENTS, "-p"])
+            .arg(std::process::id().to_string())
+            .arg("-o")
+            .arg(&output)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start perf; is it installed?")?;
+
+        tokio::time::sleep(STARTUP_DELAY).await;
+        if child.try_wait()?.is_some() {
+            let stderr = child.wait_with_output()?.stderr;
+            anyhow::bail!(
+                "perf stat exited early: {}",
+                String::from_utf8_lossy(&stderr).trim()
+            );
+        }
+
+        Ok(Self {
+            phase: phase.to_string(),
+            child,
+            output,
+        })
+    }
+
+    pub fn stop(mut self) -> Result<PerfCounters> {
+        // perf stat prints its counts on SIGINT
+        let result = unsafe { libc::kill(self.child.id() as libc::pid_t, libc::SIGINT) };
+        if result != 0 {
+            let errno = std::io::Error::last_os_error();
+            let _ = self.child.kill();
+            anyhow::bail!("Failed to signal perf: {}", errno);
+        }
+        self.child.wait()?;
+
+        let contents = std::fs::read_to_string(&self.output)
+            .with_context(|| format!("Failed to read perf output {}", self.output.display()))?;
+        let _ = std::fs::remove_file(&self.output);
+
+        let mut counters = PerfCounters {
+            phase: self.phase,
+            cycles: None,
+            instructions: None,
+            cache_misses: None,
+            context_switches: None,
+        };
+
+        // CSV lines look like `12345,,cycles,1000,100.00,,`; unsupported
+        // counters report `<not supported>` as the value
+        for line in contents.lines().filter(|line| !line.starts_with('#')) {
+            let fields: Vec<&str> = line.split(',').collect();
+            if fields.len() < 3 {
+                continue;
+            }
+            let value = fields[0].parse().ok();
+            // Events may carry a modifier suffix such as `cycles:u`
+            match fields[2].split(':').next().unwrap_or_default() {
+                "cycles" => counters.cycles = value,
+                "instructions" => counters.instructions = value,
+                "cache-misses" => counters.cache_misses = value,
+                "context-switches" => counters.context_switches = value,
+                _ => {}
+            }
+        }
+
+        Ok(counters)
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use std::fmt::Display;
+use std::os::unix::process::CommandExt;
+use std::str::FromStr;
+
+/// Set in the re-executed process so it doesn't pin and re-execute again.
+const PINNED_ENV: &str = "REFLINK_BENCH_PINNED";
+
+/// `MPOL_BIND` from linux/mempolicy.h
+const MPOL_BIND: libc::c_int = 2;
+
+/// CPUs in the kernel's cpulist format, e.g. `0-7,16,18-19`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuList(pub Vec<usize>);
+
+impl FromStr for CpuList {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || format!("Invalid CPU list (expected e.g. 0-7,16): {}", s);
+        let mut cpus = Vec::new();
+        for part in s.trim().split(',').filter(|part| !part.is_empty()) {
+            match part.split_once('-') {
+                Some((first, last)) => {
+                    let first: usize = first.trim().parse().with_context(invalid)?;
+                    let last: usize = last.trim().parse().with_context(invalid)?;
+                    if first > last {
+                        anyhow::bail!(invalid());
+                    }
+                    cpus.extend(first..=last);
+                }
+                None => cpus.push(part.trim().parse().with_context(invalid)?),
+            }
+        }
+        if cpus.is_empty() {
+            anyhow::bail!(invalid());
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(Self(cpus))
+    }
+}
+
+impl Display for CpuList {
+    /// Back in cpulist format, with runs collapsed into ranges.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ranges = Vec::new();
+        let mut cpus = self.0.iter().copied().peekable();
+        while let Some(first) = cpus.next() {
+            let mut last = first;
+            while cpus.peek() == Some(&(last + 1)) {
+                last = cpus.next().unwrap_or(last);
+            }
+            ranges.push(if first == last {
+                first.to_string()
+            } else {
+                format!("{}-{}", first, last)
+            });
+        }
+        write!(f, "{}", ranges.join(","))
+    }
+}
+
+/// Where to run the benchmark, for `--cpus` and `--numa-node`. On big
+/// machines, workers wandering across NUMA nodes and touching remote memory
+/// add more noise than the XFS/btrfs difference being measured.
+#[derive(Debug, Clone, Default)]
+pub struct Placement {
+    pub cpus: Option<CpuList>,
+    pub numa_node: Option<u32>,
+}
+
+impl Placement {
+    pub fn is_empty(&self) -> bool {
+        self.cpus.is_none() && self.numa_node.is_none()
+    }
+
+    /// The CPUs to pin to: `--cpus`, limited to the node's CPUs when a node
+    /// is given too.
+    pub fn cpu_list(&self) -> Result<Option<CpuList>> {
+        let node_cpus = self.numa_node.map(node_cpus).transpose()?;
+        let cpus = match (&self.cpus, node_cpus) {
+            (Some(cpus), Some(node)) => {
+                let both: Vec<usize> = cpus
+                    .0
+                    .iter()
+                    .copied()
+                    .filter(|cpu| node.0.contains(cpu))
+                    .collect();
+                if both.is_empty() {
+                    anyhow::bail!(
+                        "None of CPUs {} are on NUMA node {} (CPUs {})",
+                        cpus,
+                        self.numa_node.unwrap_or_default(),
+                        node
+                    );
+                }
+                Some(CpuList(both))
+            }
+            (Some(cpus), None) => Some(cpus.clone()),
+            (None, node) => node,
+        };
+        Ok(cpus)
+    }
+
+    /// Pins this process to the placement's CPUs, binds its memory to the
+    /// NUMA node, and re-executes the binary, so every thread (including
+    /// the async runtime's, which already exist) inherits both. Returns
+    /// straight away in the re-executed process, or when there's nothing
+    /// to place.
+    pub fn apply(&self) -> Result<()> {
+        if self.is_empty() || std::env::var_os(PINNED_ENV).is_some() {
+            return Ok(());
+        }
+
+        if let Some(cpus) = self.cpu_list()? {
+            let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+            for &cpu in &cpus.0 {
+                unsafe { libc::CPU_SET(cpu, &mut set) };
+            }
+            let result =
+                unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+            if result != 0 {
+                let errno = std::io::Error::last_os_error();
+                anyhow::bail!("Failed to pin to CPUs {}: {}", cpus, errno);
+            }
+        }
+
+        if let Some(node) = self.numa_node {
+            if node >= 64 {
+                anyhow::bail!("NUMA node {} is out of range", node);
+            }
+            let mask: libc::c_ulong = 1 << node;
+            let result = unsafe {
+                libc::syscall(
+                    libc::SYS_set_mempolicy,
+                    MPOL_BIND,
+                    &mask as *const libc::c_ulong,
+                    libc::c_ulong::BITS as libc::c_ulong + 1,
+                )
+            };
+            if result != 0 {
+                let errno = std::io::Error::last_os_error();
+                anyhow::bail!("Failed to bind memory to NUMA node {}: {}", node, errno);
+            }
+        }
+
+        let exe = std::env::current_exe().context("Failed to find own executable")?;
+        let error = std::process::Command::new(exe)
+            .args(std::env::args_os().skip(1))
+            .env(PINNED_ENV, "1")
+            .exec();
+        Err(error).context("Failed to re-execute pinned")
+    }
+}
+
+/// The CPUs of NUMA node `node`.
+pub fn node_cpus(node: u32) -> Result<CpuList> {
+    let path = format!("/sys/devices/system/node/node{}/cpulist", node);
+    std::fs::read_to_string(&path)
+        .with_context(|| format!("NUMA node {} not found ({})", node, path))?
+        .parse()
+}
+
+/// Each NUMA node's CPUs in cpulist format, by node number, for results.
+/// Empty where the kernel doesn't expose NUMA topology.
+pub fn numa_topology() -> Vec<String> {
+    let Ok(online) = std::fs::read_to_string("/sys/devices/system/node/online") else {
+        return Vec::new();
+    };
+    let Ok(nodes) = online.parse::<CpuList>() else {
+        return Vec::new();
+    };
+    nodes
+        .0
+        .iter()
+        .map(|&node| {
+            node_cpus(node as u32)
+                .map(|cpus| cpus.to_string())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// The CPUs this process may run on, if it could be read.
+pub fn current_affinity() -> Option<CpuList> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let result =
+        unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) };
+    if result != 0 {
+        return None;
+    }
+    let cpus = (0..libc::CPU_SETSIZE as usize)
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect();
+    Some(CpuList(cpus))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_lists_round_trip() {
+        let cpus: CpuList = "0-3,8, 10-11,2".parse().unwrap();
+        assert_eq!(cpus.0, vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(cpus.to_string(), "0-3,8,10-11");
+        assert!("3-1".parse::<CpuList>().is_err());
+        assert!("".parse::<CpuList>().is_err());
+        assert!("a-b".parse::<CpuList>().is_err());
+    }
+}
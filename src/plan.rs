@@ -0,0 +1,450 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::benchmark::{
+    CacheMode, Runner, DEFAULT_WRITE_SIZE, SPARSE_DATA_BYTES, SPARSE_DATA_STRIDE,
+};
+use crate::copy::{CopyStrategy, COPIES_PER_STRATEGY};
+use crate::filesystem::{self, FilesystemManager, FilesystemType};
+use crate::provider::LoopOptions;
+use crate::run_dir;
+use crate::suite::Scenario;
+
+/// Rough rates behind the time estimate; real runs vary by an order of
+/// magnitude with hardware, so the estimate is only for planning.
+const ASSUMED_IMAGE_MB_PER_SEC: f64 = 1000.0;
+const ASSUMED_SOURCE_MB_PER_SEC: f64 = 500.0;
+const ASSUMED_OPS_PER_SEC: f64 = 2000.0;
+
+/// Run-wide settings that affect what a run would do, beyond its scenarios.
+pub struct PlanOptions<'a> {
+    pub workdir: &'a Path,
+    /// Existing directory to benchmark in instead of loopback filesystems
+    pub rootless: Option<&'a Path>,
+    /// Privileged command that sets up and tears down loopback filesystems
+    pub helper: Option<&'a str>,
+    /// Whether loopback filesystems go on a dm-crypt mapping
+    pub encrypt_underlay: bool,
+    /// How loop devices are attached
+    pub loop_options: LoopOptions,
+    pub keep: bool,
+    pub blktrace: Option<&'a Path>,
+    pub output: Option<&'a Path>,
+    pub db: Option<&'a Path>,
+    pub save_baseline: Option<&'a str>,
+    pub pushgateway: Option<&'a str>,
+}
+
+/// Prints every command, mount, and ioctl a run would perform, with disk
+/// and time estimates, without doing any of it.
+pub fn print_plan(scenarios: &[Scenario], options: &PlanOptions) -> Result<()> {
+    println!("🧾 DRY RUN: nothing will be created, mounted, or written");
+    println!("=======================================================");
+
+    let mut needs_root = if options.rootless.is_some() || options.helper.is_some() {
+        Vec::new()
+    } else if options.encrypt_underlay {
+        vec!["losetup", "cryptsetup", "mount(2)/umount(2)"]
+    } else {
+        vec!["losetup", "mount(2)/umount(2)"]
+    };
+    let mut total_ops = 0u64;
+    let mut total_seconds = 0.0;
+    let mut largest_image_gb = 0;
+    let mut largest_scenario_gb = 0;
+    let mut filesystems = Vec::new();
+    let run_dir = run_dir::placeholder(options.rootless.unwrap_or(options.workdir));
+    println!("  mkdir -p {}", run_dir.display());
+    let mut image_dirs: Vec<&Path> = scenarios
+        .iter()
+        .flat_map(|s| s.backing_paths.values().map(PathBuf::as_path))
+        .collect();
+    image_dirs.sort();
+    image_dirs.dedup();
+    for dir in image_dirs {
+        println!("  mkdir -p {}", run_dir::placeholder(dir).display());
+    }
+
+    for scenario in scenarios {
+        let config = &scenario.config;
+        if let Some(name) = &scenario.name {
+            println!("\n🧪 Scenario '{}'", name);
+        }
+
+        let repeat = scenario.repeat.max(1);
+        let source_mb = config.file_size_mb * config.source_count.max(1) as u64;
+        let reflink_count = config.reflink_count as u64;
+        let cache_conditions = config.cache_mode.map_or(0, |mode| match mode {
+            CacheMode::Both => 2,
+            CacheMode::Warm | CacheMode::Cold => 1,
+        });
+        // Sequential, the single-threaded concurrent baseline, each thread
+        // count, each cache condition, and each write size
+        let phases = 2
+            + config.thread_counts.len() as u64
+            + cache_conditions
+            + config.fscrypt as u64
+            + config.sparse_source_bytes.is_some() as u64
+            + config.external_tools.len() as u64
+            + config.write_sizes.len() as u64;
+        let ops_per_run = config.warmup as u64
+            + reflink_count * (phases + config.source_extent_sizes.len() as u64);
+        let cow_bytes = reflink_count
+            * (DEFAULT_WRITE_SIZE as u64 * (phases - config.write_sizes.len() as u64)
+                + config.write_sizes.iter().sum::<u64>());
+        let data_mb = source_mb + cow_bytes / (1024 * 1024);
+
+        for &fs_type in &scenario.filesystems {
+            let image_dir = scenario.backing_path_for(fs_type).map(run_dir::placeholder);
+            let manager = match (options.rootless, options.helper) {
+                (Some(_), _) => FilesystemManager::in_directory(fs_type, &run_dir),
+                (None, Some(helper)) => {
+                    FilesystemManager::with_helper(helper, fs_type, scenario.fs_size_gb, &run_dir)
+                        .with_image_dir(image_dir.as_deref())
+                        .with_mount_options(scenario.mount_options_for(fs_type))
+                        .with_encrypt_underlay(options.encrypt_underlay)
+                        .with_loop_options(options.loop_options)
+                }
+                (None, None) => FilesystemManager::new(fs_type, scenario.fs_size_gb, &run_dir)?
+                    .with_image_dir(image_dir.as_deref())
+                    .with_mount_options(scenario.mount_options_for(fs_type))
+                    .with_encrypt_underlay(options.encrypt_underlay)
+                    .with_loop_options(options.loop_options),
+            };
+            let mount_point = manager.mount_point();
+
+            println!(
+                "\n📊 {}{}",
+                scenario.label(fs_type),
+                if repeat > 1 {
+                    format!(" (× {} runs)", repeat)
+                } else {
+                    String::new()
+                }
+            );
+            if !manager.is_available() {
+                println!("  ⚠️  {} is not installed", fs_type.mkfs_command());
+            }
+            for step in manager.setup_plan() {
+                println!("  {}", step);
+            }
+            if config.btrfs_qgroups && fs_type == FilesystemType::Btrfs {
+                println!("  $ btrfs quota enable {}", mount_point.display());
+                println!("  $ btrfs quota rescan -W {}", mount_point.display());
+            }
+            if config.xfs_project_quota && fs_type == FilesystemType::Xfs {
+                println!(
+                    "  $ xfs_quota -x -c 'project -s -p {} {}' {}",
+                    mount_point.display(),
+                    filesystem::XFS_PROJECT_ID,
+                    mount_point.display()
+                );
+                println!(
+                    "  $ xfs_quota -x -c 'limit -p bhard=<2× free space> {}' {}",
+                    filesystem::XFS_PROJECT_ID,
+                    mount_point.display()
+                );
+            }
+            println!(
+                "  write {} source file(s) of {}MB",
+                config.source_count.max(1),
+                config.file_size_mb
+            );
+            if let Some(extents) = config.fragment_source {
+                println!(
+                    "    each as {} chunks in shuffled order, fdatasync'd apart with a spacer file in between",
+                    extents
+                );
+            }
+            if config.warmup > 0 {
+                println!("  {} untimed warmup FICLONE + pwrite", config.warmup);
+            }
+            if config.runner == Runner::Threads {
+                println!("  sequential and concurrent operations run on plain OS threads");
+            }
+            if let Some(load) = config.background_load {
+                println!(
+                    "  start {} background load in {} until every phase finishes",
+                    load,
+                    mount_point.join("background").display()
+                );
+            }
+            println!(
+                "  {} × ioctl(FICLONE) + pwrite({} bytes) sequentially",
+                reflink_count, DEFAULT_WRITE_SIZE
+            );
+            println!(
+                "  {} × ioctl(FICLONE) + pwrite at each of 1 (baseline), {} threads",
+                reflink_count,
+                config
+                    .thread_counts
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            if let Some(mode) = config.cache_mode {
+                println!(
+                    "  {} × ioctl(FICLONE) + pwrite per {:?} cache condition",
+                    reflink_count, mode
+                );
+            }
+            if let Some(duration) = config.duration {
+                println!(
+                    "  ioctl(FICLONE) + pwrite({} bytes) for {} over {} recycled targets",
+                    DEFAULT_WRITE_SIZE,
+                    humantime::format_duration(duration),
+                    reflink_count.max(1)
+                );
+            }
+            if let Some(rate) = config.rate {
+                println!(
+                    "  {} × ioctl(FICLONE) + pwrite({} bytes) started at {} without waiting",
+                    reflink_count, DEFAULT_WRITE_SIZE, rate
+                );
+            }
+            if config.fscrypt {
+                let dir = mount_point.join("fscrypt");
+                println!(
+                    "  mkdir {}; ioctl(FS_IOC_ADD_ENCRYPTION_KEY) + ioctl(FS_IOC_SET_ENCRYPTION_POLICY) (skipped if unsupported)",
+                    dir.display()
+                );
+                println!(
+                    "  copy a source file into {}, then {} × ioctl(FICLONE) + pwrite({} bytes) there",
+                    dir.display(),
+                    reflink_count,
+                    DEFAULT_WRITE_SIZE
+                );
+                println!("  ioctl(FS_IOC_REMOVE_ENCRYPTION_KEY)");
+            }
+            if let Some(bytes) = config.sparse_source_bytes {
+                let source = mount_point.join("sparse").join("source.dat");
+                println!(
+                    "  ftruncate {} to {} bytes, pwrite({} bytes) every {} bytes",
+                    source.display(),
+                    bytes,
+                    SPARSE_DATA_BYTES,
+                    SPARSE_DATA_STRIDE
+                );
+                println!(
+                    "  {} × ioctl(FICLONE) + pwrite({} bytes) of it",
+                    reflink_count, DEFAULT_WRITE_SIZE
+                );
+            }
+            if let Some(dir) = &config.cross_fs_dir {
+                println!(
+                    "  {} × ioctl(FICLONE) of the source into {} (expecting EXDEV)",
+                    COPIES_PER_STRATEGY,
+                    dir.display()
+                );
+                for strategy in CopyStrategy::ALL {
+                    println!(
+                        "  {} × {} of the source into {} + fdatasync",
+                        COPIES_PER_STRATEGY,
+                        strategy,
+                        dir.display()
+                    );
+                }
+            }
+            if config.copy_baselines {
+                for strategy in CopyStrategy::BASELINES {
+                    println!(
+                        "  {} × {} of the source into {} + fdatasync",
+                        COPIES_PER_STRATEGY,
+                        strategy,
+                        mount_point.join("copies").display()
+                    );
+                }
+            }
+            for tool in &config.external_tools {
+                println!(
+                    "  {} × $ {} + pwrite({} bytes)",
+                    reflink_count,
+                    tool.command_line(
+                        &mount_point.join("source_file.dat"),
+                        &mount_point.join(format!("external_{}_N.dat", tool))
+                    ),
+                    DEFAULT_WRITE_SIZE
+                );
+            }
+            for size in &config.source_extent_sizes {
+                println!(
+                    "  write a {}MB source in {}-byte extents, then {} × ioctl(FICLONE) of it",
+                    config.file_size_mb, size, reflink_count
+                );
+            }
+            for size in &config.write_sizes {
+                println!(
+                    "  {} × ioctl(FICLONE) + pwrite({} bytes)",
+                    reflink_count, size
+                );
+            }
+            if config.space_report && fs_type == FilesystemType::Btrfs {
+                println!(
+                    "  $ btrfs filesystem du -s --raw {}/...",
+                    mount_point.display()
+                );
+            }
+            if config.verify {
+                println!("  read back every clone and compare it with its source");
+            }
+            for workload in &config.workloads {
+                println!("  {} workload", workload);
+            }
+            if config.extent_usage {
+                println!("  ioctl(FS_IOC_FIEMAP) of every sequential and workload file");
+            }
+            println!("  syncfs() after each phase");
+            if config.fstrim {
+                println!(
+                    "  ioctl(FITRIM) of {} before each phase",
+                    mount_point.display()
+                );
+            }
+            if drops_caches(scenario) {
+                println!("  write 3 > /proc/sys/vm/drop_caches");
+            }
+            if config.perf_stat {
+                println!(
+                    "  $ perf stat -x , -e ... -p {} per phase",
+                    std::process::id()
+                );
+            }
+            if config.trace_kernel {
+                println!("  $ bpftrace -e <remap function histograms>");
+            }
+            if let Some(dir) = options.blktrace {
+                println!("  $ blktrace -d /dev/loopN -D {} per phase", dir.display());
+            }
+            if options.keep {
+                println!("  keep mounted for inspection");
+            } else {
+                for step in manager.cleanup_plan() {
+                    println!("  {}", step);
+                }
+            }
+
+            if options.rootless.is_none() && data_mb > scenario.fs_size_gb * 1024 {
+                println!(
+                    "  ⚠️  ~{}MB of data won't fit in a {}GB image; raise --fs-size-gb",
+                    data_mb, scenario.fs_size_gb
+                );
+            }
+
+            let seconds = source_mb as f64 / ASSUMED_SOURCE_MB_PER_SEC
+                + ops_per_run as f64 / ASSUMED_OPS_PER_SEC
+                + config.duration.map_or(0.0, |d| d.as_secs_f64())
+                + config
+                    .rate
+                    .map_or(0.0, |rate| reflink_count as f64 / rate.0);
+            total_seconds += seconds * repeat as f64;
+            total_ops += ops_per_run * repeat as u64;
+            largest_image_gb = largest_image_gb.max(scenario.fs_size_gb);
+        }
+        // Images are written in parallel, so only one counts toward time
+        total_seconds +=
+            (scenario.fs_size_gb * 1024) as f64 / ASSUMED_IMAGE_MB_PER_SEC * repeat as f64;
+        largest_scenario_gb =
+            largest_scenario_gb.max(scenario.fs_size_gb * scenario.filesystems.len() as u64);
+        for fs_type in &scenario.filesystems {
+            if !filesystems.contains(fs_type) {
+                filesystems.push(*fs_type);
+            }
+        }
+
+        if drops_caches(scenario) {
+            needs_root.push("/proc/sys/vm/drop_caches");
+        }
+        if config.perf_stat {
+            needs_root.push("perf (or kernel.perf_event_paranoid <= 1)");
+        }
+        if config.trace_kernel {
+            needs_root.push("bpftrace");
+        }
+    }
+
+    if options.blktrace.is_some() {
+        needs_root.push("blktrace");
+    }
+    let mut seen = Vec::new();
+    needs_root.retain(|what| {
+        let first = !seen.contains(what);
+        seen.push(*what);
+        first
+    });
+
+    println!("\n📦 OUTPUTS");
+    println!("==========");
+    for scenario in scenarios {
+        if let Some(path) = options.output {
+            println!("  results JSON: {}", scenario.qualify_path(path).display());
+        }
+        if let Some(name) = options.save_baseline {
+            println!("  baseline: {}", scenario.qualify(name));
+        }
+    }
+    if let Some(db) = options.db {
+        println!("  history database: {}", db.display());
+    }
+    if let Some(gateway) = options.pushgateway {
+        println!(
+            "  $ curl -X PUT {}/metrics/job/...",
+            gateway.trim_end_matches('/')
+        );
+    }
+
+    println!("\n📐 ESTIMATES");
+    println!("============");
+    // A scenario's images exist side by side, and --keep leaves one per
+    // filesystem
+    let disk_gb = if options.keep {
+        largest_scenario_gb.max(largest_image_gb * filesystems.len() as u64)
+    } else {
+        largest_scenario_gb
+    };
+    match options.rootless {
+        Some(dir) => println!(
+            "  Disk: no images; sources and clones are written under {}",
+            dir.display()
+        ),
+        None => println!(
+            "  Disk: {}GB free in {} (images are fully written)",
+            disk_gb,
+            options.workdir.display()
+        ),
+    }
+    println!(
+        "  Reflink + write operations (excluding workloads): {}",
+        total_ops
+    );
+    println!(
+        "  Time: roughly {:.0} min (assuming ~{:.0} ops/sec; actual rates vary widely)",
+        (total_seconds / 60.0).ceil(),
+        ASSUMED_OPS_PER_SEC
+    );
+
+    let is_root = unsafe { libc::geteuid() } == 0;
+    println!("\n🔐 PRIVILEGES");
+    println!("=============");
+    if needs_root.is_empty() {
+        println!("  Root needed for: nothing");
+    } else {
+        println!("  Root needed for: {}", needs_root.join(", "));
+    }
+    if let Some(helper) = options.helper {
+        println!("  losetup, mkfs, and mount run through: {}", helper);
+    }
+    if !is_root && !needs_root.is_empty() {
+        println!("  ⚠️  Not running as root; the real run will fail");
+    }
+
+    Ok(())
+}
+
+fn drops_caches(scenario: &Scenario) -> bool {
+    scenario.config.drop_caches
+        || matches!(
+            scenario.config.cache_mode,
+            Some(CacheMode::Cold | CacheMode::Both)
+        )
+}
@@ -0,0 +1,171 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use crate::charts;
+
+/// How often the bar is redrawn while a phase runs.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+const BAR_WIDTH: usize = 30;
+
+/// The phase being drawn, if it has a known length.
+struct Phase {
+    label: String,
+    unit: &'static str,
+    total: u64,
+    completed: u64,
+    started: Instant,
+}
+
+/// A progress bar on stderr for the running phase: items completed, rate,
+/// and ETA, redrawn in place. A phase's last item finishes its bar with a
+/// newline, so output printed after the phase starts on a line of its own.
+/// The redraw thread exits once every handle is dropped.
+#[derive(Clone)]
+pub struct ProgressBar {
+    phase: Arc<Mutex<Option<Phase>>>,
+    filesystem: String,
+}
+
+impl std::fmt::Debug for ProgressBar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressBar")
+            .field("filesystem", &self.filesystem)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProgressBar {
+    pub fn start() -> Self {
+        let phase = Arc::new(Mutex::new(None));
+        let weak = Arc::downgrade(&phase);
+        std::thread::spawn(move || redraw(weak));
+        Self {
+            phase,
+            filesystem: String::new(),
+        }
+    }
+
+    /// A handle to the same bar that labels phases with `filesystem`.
+    pub fn for_filesystem(&self, filesystem: &str) -> Self {
+        Self {
+            phase: Arc::clone(&self.phase),
+            filesystem: filesystem.to_string(),
+        }
+    }
+
+    /// Starts a bar for a phase of `total` items counted in `unit`. A total
+    /// of 0 (unknown) draws nothing.
+    pub fn begin_phase(&self, phase: &str, total: u64, unit: &'static str) {
+        let mut current = self.phase.lock().unwrap();
+        if current.take().is_some() {
+            // Left unfinished by an error or interruption
+            eprint!("\r\x1b[2K");
+        }
+        if total > 0 {
+            *current = Some(Phase {
+                label: format!("{} {}", self.filesystem, phase).trim().to_string(),
+                unit,
+                total,
+                completed: 0,
+                started: Instant::now(),
+            });
+        }
+    }
+
+    /// Counts `n` more items done, finishing the bar at the phase's total.
+    pub fn inc(&self, n: u64) {
+        let mut current = self.phase.lock().unwrap();
+        let Some(phase) = current.as_mut() else {
+            return;
+        };
+        phase.completed += n;
+        if phase.completed >= phase.total {
+            let line = render(phase, Instant::now());
+            let _ = writeln!(std::io::stderr(), "\r\x1b[2K{}", line);
+            *current = None;
+        }
+    }
+}
+
+fn redraw(phase: Weak<Mutex<Option<Phase>>>) {
+    loop {
+        std::thread::sleep(REDRAW_INTERVAL);
+        let Some(shared) = phase.upgrade() else {
+            return;
+        };
+        let current = shared.lock().unwrap();
+        if let Some(phase) = current.as_ref() {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K{}", render(phase, Instant::now()));
+            let _ = stderr.flush();
+        }
+    }
+}
+
+/// One line of progress: bar, counts, rate, and the time left, or the time
+/// taken once the phase is done.
+fn render(phase: &Phase, now: Instant) -> String {
+    let elapsed = now.duration_since(phase.started);
+    let completed = phase.completed.min(phase.total);
+    let rate = completed as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+
+    let time = if completed == phase.total {
+        format!("in {}", format_clock(elapsed))
+    } else if completed == 0 {
+        "ETA --:--".to_string()
+    } else {
+        let remaining = (phase.total - completed) as f64 / rate;
+        format!("ETA {}", format_clock(Duration::from_secs_f64(remaining)))
+    };
+
+    format!(
+        "  {} [{:<width$}] {}/{} {} ({:.0}%) {:.0} {}/s {}",
+        phase.label,
+        charts::bar(completed as f64, phase.total as f64, BAR_WIDTH),
+        completed,
+        phase.total,
+        phase.unit,
+        completed as f64 / phase.total as f64 * 100.0,
+        rate,
+        phase.unit,
+        time,
+        width = BAR_WIDTH
+    )
+}
+
+/// `m:ss`, or `h:mm:ss` past an hour.
+fn format_clock(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bar_shows_counts_rate_and_eta() {
+        let started = Instant::now();
+        let mut phase = Phase {
+            label: "XFS sequential".to_string(),
+            unit: "ops",
+            total: 10000,
+            completed: 2500,
+            started,
+        };
+
+        let line = render(&phase, started + Duration::from_secs(10));
+        assert!(line.starts_with("  XFS sequential [███████▌"));
+        assert!(line.ends_with("] 2500/10000 ops (25%) 250 ops/s ETA 0:30"));
+
+        phase.completed = 10000;
+        let line = render(&phase, started + Duration::from_secs(3725));
+        assert!(line.ends_with("10000/10000 ops (100%) 3 ops/s in 1:02:05"));
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+
+use crate::benchmark::BenchmarkResult;
+use crate::filesystem::FilesystemType;
+use crate::stats::LatencyStats;
+
+/// Renders results in the Prometheus text exposition format.
+pub fn render(results: &[(FilesystemType, BenchmarkResult)]) -> String {
+    let mut metrics = Metrics::default();
+
+    for (fs_type, result) in results {
+        let fs = fs_type.to_string();
+        let labels = [("filesystem", fs.as_str())];
+
+        metrics.gauge(
+            "reflink_bench_sequential_ops_per_second",
+            "Sequential reflink + write operations per second",
+            &labels,
+            result.operations_per_sec,
+        );
+        metrics.gauge(
+            "reflink_bench_sequential_throughput_mb_per_second",
+            "Sequential throughput in MB/s of reflinked data",
+            &labels,
+            result.throughput_mb_per_sec,
+        );
+        metrics.latency(
+            "reflink_bench_reflink_latency_seconds",
+            "Sequential reflink latency",
+            &fs,
+            &result.reflink_latency,
+        );
+        metrics.latency(
+            "reflink_bench_write_latency_seconds",
+            "Sequential CoW write latency",
+            &fs,
+            &result.write_latency,
+        );
+
+        let concurrent = &result.concurrent_results;
+        for (i, threads) in concurrent.thread_counts.iter().enumerate() {
+            let threads = threads.to_string();
+            let labels = [("filesystem", fs.as_str()), ("threads", threads.as_str())];
+            metrics.gauge(
+                "reflink_bench_concurrent_ops_per_second",
+                "Concurrent reflink + write operations per second",
+                &labels,
+                concurrent.operations_per_sec[i],
+            );
+            metrics.gauge(
+                "reflink_bench_contention_ratio",
+                "Single-thread ops/sec divided by ops/sec at this thread count",
+                &labels,
+                concurrent.contention_ratios[i],
+            );
+        }
+
+        for workload in &result.workload_results {
+            metrics.gauge(
+                "reflink_bench_workload_ops_per_second",
+                "Operations per second of an additional workload",
+                &[
+                    ("filesystem", fs.as_str()),
+                    ("workload", workload.workload.as_str()),
+                ],
+                workload.operations_per_sec,
+            );
+        }
+    }
+
+    metrics.gauge(
+        "reflink_bench_last_run_timestamp_seconds",
+        "Unix time the benchmark finished",
+        &[],
+        chrono::Utc::now().timestamp() as f64,
+    );
+
+    metrics.finish()
+}
+
+/// Pushes `body` to a Prometheus pushgateway, replacing the job's metrics.
+pub fn push(gateway: &str, job: &str, body: &str) -> Result<()> {
+    let url = format!("{}/metrics/job/{}", gateway.trim_end_matches('/'), job);
+
+    let mut child = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "-X", "PUT"])
+        .args(["--data-binary", "@-", &url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+
+    child
+        .stdin
+        .take()
+        .context("curl stdin unavailable")?
+        .write_all(body.as_bytes())
+        .context("Failed to send metrics to curl")?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to push metrics to {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Exposition text grouped by metric family, each with one HELP/TYPE header.
+#[derive(Default)]
+struct Metrics {
+    families: Vec<(String, String)>,
+}
+
+impl Metrics {
+    fn gauge(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        let family = self.family(name, help, "gauge");
+        let _ = writeln!(family, "{}{} {}", name, format_labels(labels), value);
+    }
+
+    fn latency(&mut self, name: &str, help: &str, filesystem: &str, stats: &LatencyStats) {
+        let family = self.family(name, help, "summary");
+        for (quantile, value) in [
+            ("0.5", stats.p50),
+            ("0.9", stats.p90),
+            ("0.99", stats.p99),
+            ("0.999", stats.p999),
+        ] {
+            let labels = format_labels(&[("filesystem", filesystem), ("quantile", quantile)]);
+            let _ = writeln!(family, "{}{} {}", name, labels, value.as_secs_f64());
+        }
+
+        let labels = format_labels(&[("filesystem", filesystem)]);
+        let sum = stats.mean.as_secs_f64() * stats.count as f64;
+        let _ = writeln!(family, "{}_sum{} {}", name, labels, sum);
+        let _ = writeln!(family, "{}_count{} {}", name, labels, stats.count);
+    }
+
+    fn family(&mut self, name: &str, help: &str, kind: &str) -> &mut String {
+        let index = match self.families.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                let header = format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind);
+                self.families.push((name.to_string(), header));
+                self.families.len() - 1
+            }
+        };
+        &mut self.families[index].1
+    }
+
+    fn finish(self) -> String {
+        self.families.into_iter().map(|(_, text)| text).collect()
+    }
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("{}=\"{}\"", name, escaped)
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
@@ -0,0 +1,1039 @@
+use anyhow::{Context, Result};
+use nix::errno::Errno;
+use nix::mount::{mount, umount, umount2, MntFlags};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::benchmark::BenchmarkRunner;
+use crate::filesystem::{self, split_mount_options, FilesystemType};
+use crate::retry::RetryPolicy;
+use crate::run_dir::HELPER_DIR_NAME;
+
+/// Creates a filesystem to benchmark, mounts it, and removes it again.
+///
+/// [`FilesystemManager`](crate::filesystem::FilesystemManager) drives a
+/// provider; adding a way of getting a filesystem (another filesystem, an
+/// existing mount, a raw device) means implementing this trait, not
+/// touching the benchmark loop. Methods block and shell out, so async
+/// callers should run them on a blocking thread.
+pub trait FilesystemProvider: Send + Sync {
+    fn fs_type(&self) -> FilesystemType;
+
+    /// Whether the tools this provider needs are installed.
+    fn is_available(&self) -> bool;
+
+    /// Replaces anything left from an earlier run, then creates and formats
+    /// the filesystem.
+    fn setup(&mut self) -> Result<()>;
+
+    /// Mounts the filesystem at [`mount_point`](Self::mount_point).
+    fn mount(&mut self) -> Result<()>;
+
+    /// Unmounts and removes everything [`setup`](Self::setup) created,
+    /// skipping whatever doesn't exist.
+    fn cleanup(&self) -> Result<()>;
+
+    fn mount_point(&self) -> &Path;
+
+    /// Block device the filesystem is on, once set up.
+    fn device(&self) -> Option<&str>;
+
+    /// Image file behind the device, for providers that create one.
+    fn image_path(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Mounts with these comma-separated options, e.g. `noatime,compress=zstd`.
+    fn set_mount_options(&mut self, options: Option<String>);
+
+    /// Retries transient failures of setup and cleanup steps with `policy`.
+    /// Providers with nothing that fails transiently ignore it.
+    fn set_retry_policy(&mut self, _policy: RetryPolicy) {}
+
+    /// Puts the filesystem on a dm-crypt mapping instead of directly on its
+    /// device. Providers that don't create a device ignore it.
+    fn set_encrypt_underlay(&mut self, _encrypt: bool) {}
+
+    /// Attaches the loop device with `options`. Providers without a loop
+    /// device ignore it.
+    fn set_loop_options(&mut self, _options: LoopOptions) {}
+
+    /// Creates the image file in `dir`, e.g. on another disk, instead of
+    /// next to the mount point. Providers without an image ignore it.
+    fn set_image_dir(&mut self, _dir: &Path) {}
+
+    /// The commands and syscalls setup and mount perform, for `--dry-run`.
+    fn setup_plan(&self) -> Vec<String>;
+
+    /// The commands and syscalls cleanup performs, for `--dry-run`.
+    fn cleanup_plan(&self) -> Vec<String>;
+}
+
+/// The loopback provider for `fs_type`: an image at `<dir>/<fs>.img`
+/// mounted on `<dir>/<fs>`.
+pub fn loopback(fs_type: FilesystemType, size_gb: u64, dir: &Path) -> Box<dyn FilesystemProvider> {
+    loopback_in(fs_type, size_gb, dir, dir)
+}
+
+/// A loopback provider for run directory `dir` whose image and mount point
+/// go in `files_dir` instead.
+pub fn loopback_in(
+    fs_type: FilesystemType,
+    size_gb: u64,
+    dir: &Path,
+    files_dir: &Path,
+) -> Box<dyn FilesystemProvider> {
+    let image = LoopbackImage::new(fs_type, size_gb, dir, files_dir);
+    match fs_type {
+        FilesystemType::Xfs => Box::new(XfsProvider { image }),
+        FilesystemType::Btrfs => Box::new(BtrfsProvider { image }),
+        FilesystemType::Bcachefs => Box::new(BcachefsProvider { image }),
+    }
+}
+
+/// The directory provider: `dir` on whatever filesystem already holds it.
+pub fn directory(fs_type: FilesystemType, dir: &Path) -> Box<dyn FilesystemProvider> {
+    Box::new(DirectoryProvider {
+        fs_type,
+        dir: dir.to_path_buf(),
+    })
+}
+
+/// The helper provider: a loopback filesystem like [`loopback`]'s, set up
+/// and torn down by running `command mount-helper ...`, where `command` is
+/// split on whitespace (e.g. `sudo -n /usr/local/bin/reflink-bench`).
+pub fn helper(
+    command: &str,
+    fs_type: FilesystemType,
+    size_gb: u64,
+    dir: &Path,
+) -> Box<dyn FilesystemProvider> {
+    Box::new(HelperProvider {
+        command: command.split_whitespace().map(str::to_string).collect(),
+        fs_type,
+        dir: dir.to_path_buf(),
+        image_dir: None,
+        image: LoopbackImage::new(fs_type, size_gb, dir, &dir.join(HELPER_DIR_NAME)),
+    })
+}
+
+/// A loopback filesystem whose losetup, mkfs, and mount run in a privileged
+/// helper, for `--helper`, so the process doing the measuring never needs
+/// root. The helper is this binary's hidden `mount-helper` subcommand,
+/// run through sudo or pkexec; it only works in
+/// run directories the caller owns and always mounts `nosuid,nodev`.
+pub struct HelperProvider {
+    command: Vec<String>,
+    fs_type: FilesystemType,
+    dir: PathBuf,
+    /// Run directory the image goes in, when not `dir`
+    image_dir: Option<PathBuf>,
+    /// Paths and state only; the helper does the work
+    image: LoopbackImage,
+}
+
+impl HelperProvider {
+    /// Runs the helper with `args` after `mount-helper`, returning its stdout.
+    fn run_helper(&self, args: &[String]) -> Result<String> {
+        let (program, prefix) = self.command.split_first().context("Empty helper command")?;
+        let output = Command::new(program)
+            .args(prefix)
+            .arg("mount-helper")
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run mount helper {}", program))?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Mount helper failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn helper_args(&self, action: &str) -> Vec<String> {
+        let mut args = vec![
+            action.to_string(),
+            "--filesystem".to_string(),
+            self.fs_type.mount_type().to_string(),
+            "--dir".to_string(),
+            self.dir.display().to_string(),
+        ];
+        let retry = self.image.retry;
+        if retry != RetryPolicy::default() {
+            args.extend([
+                "--attempts".to_string(),
+                retry.attempts.to_string(),
+                "--retry-delay".to_string(),
+                humantime::format_duration(retry.delay).to_string(),
+            ]);
+        }
+        if let Some(dir) = &self.image_dir {
+            args.extend(["--image-dir".to_string(), dir.display().to_string()]);
+        }
+        if action == "setup" {
+            args.extend(["--size-gb".to_string(), self.image.size_gb.to_string()]);
+            if let Some(options) = &self.image.mount_options {
+                args.extend(["--mount-options".to_string(), options.clone()]);
+            }
+            if self.image.encrypt {
+                args.push("--encrypt-underlay".to_string());
+            }
+            if self.image.loop_options.direct_io {
+                args.push("--loop-direct-io".to_string());
+            }
+            if let Some(size) = self.image.loop_options.sector_size {
+                args.extend(["--loop-sector-size".to_string(), size.to_string()]);
+            }
+        }
+        args
+    }
+
+    fn plan_line(&self, action: &str) -> String {
+        format!(
+            "$ {} mount-helper {}",
+            self.command.join(" "),
+            self.helper_args(action).join(" ")
+        )
+    }
+}
+
+impl FilesystemProvider for HelperProvider {
+    fn fs_type(&self) -> FilesystemType {
+        self.fs_type
+    }
+
+    fn is_available(&self) -> bool {
+        match self.command.first() {
+            Some(program) if program.contains('/') => Path::new(program).is_file(),
+            Some(program) => filesystem::command_exists(program),
+            None => false,
+        }
+    }
+
+    fn setup(&mut self) -> Result<()> {
+        let device = self.run_helper(&self.helper_args("setup"))?;
+        self.image.loop_device = Some(device);
+        Ok(())
+    }
+
+    /// The helper mounts as part of setup.
+    fn mount(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn setup_plan(&self) -> Vec<String> {
+        vec![self.plan_line("setup")]
+    }
+
+    fn cleanup_plan(&self) -> Vec<String> {
+        vec![self.plan_line("cleanup")]
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.run_helper(&self.helper_args("cleanup")).map(drop)
+    }
+
+    fn mount_point(&self) -> &Path {
+        &self.image.mount_point
+    }
+
+    fn device(&self) -> Option<&str> {
+        self.image.device()
+    }
+
+    fn image_path(&self) -> Option<&Path> {
+        Some(&self.image.image_path)
+    }
+
+    fn set_mount_options(&mut self, options: Option<String>) {
+        self.image.mount_options = options;
+    }
+
+    fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.image.retry = policy;
+    }
+
+    fn set_encrypt_underlay(&mut self, encrypt: bool) {
+        self.image.encrypt = encrypt;
+    }
+
+    fn set_loop_options(&mut self, options: LoopOptions) {
+        self.image.loop_options = options;
+    }
+
+    fn set_image_dir(&mut self, dir: &Path) {
+        self.image_dir = Some(dir.to_path_buf());
+        self.image.set_image_dir(&dir.join(HELPER_DIR_NAME));
+    }
+}
+
+/// A directory on an already mounted reflink-capable filesystem, for
+/// `--rootless`. Setup creates the directory and checks that files in it
+/// can be cloned, and cleanup deletes it; nothing is formatted or mounted,
+/// so no root is needed, but results reflect however that filesystem was
+/// made and mounted.
+pub struct DirectoryProvider {
+    fs_type: FilesystemType,
+    dir: PathBuf,
+}
+
+impl FilesystemProvider for DirectoryProvider {
+    fn fs_type(&self) -> FilesystemType {
+        self.fs_type
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn setup(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+
+        // Fail now rather than on the first timed operation
+        let probe = self.dir.join("reflink-probe");
+        std::fs::write(&probe, [0u8; 4096]).context("Failed to write reflink probe")?;
+        let cloned =
+            BenchmarkRunner::create_reflink_blocking(&probe, &self.dir.join("reflink-probe-clone"))
+                .with_context(|| format!("{} doesn't support reflinks", self.dir.display()));
+        let _ = std::fs::remove_file(&probe);
+        let _ = std::fs::remove_file(self.dir.join("reflink-probe-clone"));
+        cloned
+    }
+
+    fn mount(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn setup_plan(&self) -> Vec<String> {
+        vec![
+            format!("mkdir -p {}", self.dir.display()),
+            format!("ioctl(FICLONE) probe in {}", self.dir.display()),
+        ]
+    }
+
+    fn cleanup_plan(&self) -> Vec<String> {
+        vec![format!("rm -r {}", self.dir.display())]
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir)
+                .with_context(|| format!("Failed to remove {}", self.dir.display()))?;
+        }
+        Ok(())
+    }
+
+    fn mount_point(&self) -> &Path {
+        &self.dir
+    }
+
+    fn device(&self) -> Option<&str> {
+        None
+    }
+
+    /// The filesystem is already mounted, so there's nothing to apply these to.
+    fn set_mount_options(&mut self, _options: Option<String>) {}
+}
+
+/// XFS on a loopback image, formatted with reflink enabled explicitly since
+/// xfsprogs before 5.1 leaves it off.
+pub struct XfsProvider {
+    image: LoopbackImage,
+}
+
+const XFS_MKFS_ARGS: &[&str] = &["-f", "-m", "reflink=1"];
+
+impl FilesystemProvider for XfsProvider {
+    fn fs_type(&self) -> FilesystemType {
+        FilesystemType::Xfs
+    }
+
+    fn is_available(&self) -> bool {
+        filesystem::command_exists("mkfs.xfs") && filesystem::command_exists("losetup")
+    }
+
+    fn setup(&mut self) -> Result<()> {
+        let device = self.image.create()?;
+        run_mkfs("mkfs.xfs", XFS_MKFS_ARGS, &device)
+    }
+
+    fn mount(&mut self) -> Result<()> {
+        self.image.mount("xfs")
+    }
+
+    fn setup_plan(&self) -> Vec<String> {
+        self.image.setup_plan("mkfs.xfs", XFS_MKFS_ARGS, "xfs")
+    }
+
+    fn cleanup_plan(&self) -> Vec<String> {
+        self.image.cleanup_plan()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.image.cleanup()
+    }
+
+    fn mount_point(&self) -> &Path {
+        &self.image.mount_point
+    }
+
+    fn device(&self) -> Option<&str> {
+        self.image.device()
+    }
+
+    fn image_path(&self) -> Option<&Path> {
+        Some(&self.image.image_path)
+    }
+
+    fn set_mount_options(&mut self, options: Option<String>) {
+        self.image.mount_options = options;
+    }
+
+    fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.image.retry = policy;
+    }
+
+    fn set_encrypt_underlay(&mut self, encrypt: bool) {
+        self.image.encrypt = encrypt;
+    }
+
+    fn set_loop_options(&mut self, options: LoopOptions) {
+        self.image.loop_options = options;
+    }
+
+    fn set_image_dir(&mut self, dir: &Path) {
+        self.image.set_image_dir(dir);
+    }
+}
+
+/// btrfs on a loopback image. Reflinks are always available.
+pub struct BtrfsProvider {
+    image: LoopbackImage,
+}
+
+const BTRFS_MKFS_ARGS: &[&str] = &["-f"];
+
+impl FilesystemProvider for BtrfsProvider {
+    fn fs_type(&self) -> FilesystemType {
+        FilesystemType::Btrfs
+    }
+
+    fn is_available(&self) -> bool {
+        filesystem::command_exists("mkfs.btrfs") && filesystem::command_exists("losetup")
+    }
+
+    fn setup(&mut self) -> Result<()> {
+        let device = self.image.create()?;
+        run_mkfs("mkfs.btrfs", BTRFS_MKFS_ARGS, &device)
+    }
+
+    fn mount(&mut self) -> Result<()> {
+        self.image.mount("btrfs")
+    }
+
+    fn setup_plan(&self) -> Vec<String> {
+        self.image
+            .setup_plan("mkfs.btrfs", BTRFS_MKFS_ARGS, "btrfs")
+    }
+
+    fn cleanup_plan(&self) -> Vec<String> {
+        self.image.cleanup_plan()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.image.cleanup()
+    }
+
+    fn mount_point(&self) -> &Path {
+        &self.image.mount_point
+    }
+
+    fn device(&self) -> Option<&str> {
+        self.image.device()
+    }
+
+    fn image_path(&self) -> Option<&Path> {
+        Some(&self.image.image_path)
+    }
+
+    fn set_mount_options(&mut self, options: Option<String>) {
+        self.image.mount_options = options;
+    }
+
+    fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.image.retry = policy;
+    }
+
+    fn set_encrypt_underlay(&mut self, encrypt: bool) {
+        self.image.encrypt = encrypt;
+    }
+
+    fn set_loop_options(&mut self, options: LoopOptions) {
+        self.image.loop_options = options;
+    }
+
+    fn set_image_dir(&mut self, dir: &Path) {
+        self.image.set_image_dir(dir);
+    }
+}
+
+/// bcachefs on a loopback image.
+pub struct BcachefsProvider {
+    image: LoopbackImage,
+}
+
+const BCACHEFS_MKFS_ARGS: &[&str] = &["-f"];
+
+impl FilesystemProvider for BcachefsProvider {
+    fn fs_type(&self) -> FilesystemType {
+        FilesystemType::Bcachefs
+    }
+
+    fn is_available(&self) -> bool {
+        filesystem::command_exists("mkfs.bcachefs") && filesystem::command_exists("losetup")
+    }
+
+    fn setup(&mut self) -> Result<()> {
+        let device = self.image.create()?;
+        run_mkfs("mkfs.bcachefs", BCACHEFS_MKFS_ARGS, &device)
+    }
+
+    fn mount(&mut self) -> Result<()> {
+        self.image.mount("bcachefs")
+    }
+
+    fn setup_plan(&self) -> Vec<String> {
+        self.image
+            .setup_plan("mkfs.bcachefs", BCACHEFS_MKFS_ARGS, "bcachefs")
+    }
+
+    fn cleanup_plan(&self) -> Vec<String> {
+        self.image.cleanup_plan()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.image.cleanup()
+    }
+
+    fn mount_point(&self) -> &Path {
+        &self.image.mount_point
+    }
+
+    fn device(&self) -> Option<&str> {
+        self.image.device()
+    }
+
+    fn image_path(&self) -> Option<&Path> {
+        Some(&self.image.image_path)
+    }
+
+    fn set_mount_options(&mut self, options: Option<String>) {
+        self.image.mount_options = options;
+    }
+
+    fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.image.retry = policy;
+    }
+
+    fn set_encrypt_underlay(&mut self, encrypt: bool) {
+        self.image.encrypt = encrypt;
+    }
+
+    fn set_loop_options(&mut self, options: LoopOptions) {
+        self.image.loop_options = options;
+    }
+
+    fn set_image_dir(&mut self, dir: &Path) {
+        self.image.set_image_dir(dir);
+    }
+}
+
+fn run_mkfs(command: &str, args: &[&str], device: &str) -> Result<()> {
+    let output = Command::new(command)
+        .args(args)
+        .arg(device)
+        .output()
+        .with_context(|| format!("Failed to run {}", command))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} failed: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// `cryptsetup open` arguments for `--encrypt-underlay`: plain dm-crypt
+/// with a throwaway random key, which has the same data path as a LUKS2
+/// volume with its default cipher but no header or key derivation to set up.
+const CRYPT_OPEN_ARGS: &[&str] = &[
+    "open",
+    "--type",
+    "plain",
+    "--cipher",
+    "aes-xts-plain64",
+    "--key-size",
+    "512",
+    "--key-file",
+    "/dev/urandom",
+];
+
+/// How the loop device is attached. By default reads and writes go through
+/// the page cache twice, once for the filesystem on the device and once for
+/// the image file behind it, which inflates write throughput.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoopOptions {
+    /// `losetup --direct-io=on`: the loop device bypasses the page cache of
+    /// the filesystem holding the image
+    pub direct_io: bool,
+    /// `losetup --sector-size`: the device's logical sector size in bytes,
+    /// instead of 512
+    pub sector_size: Option<u32>,
+}
+
+impl LoopOptions {
+    /// `losetup` arguments for these options, before the image path.
+    pub fn losetup_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.direct_io {
+            args.push("--direct-io=on".to_string());
+        }
+        if let Some(size) = self.sector_size {
+            args.extend(["--sector-size".to_string(), size.to_string()]);
+        }
+        args
+    }
+}
+
+/// A zero-filled image file attached to a loop device, shared by the
+/// loopback providers, optionally with a dm-crypt mapping on top.
+pub struct LoopbackImage {
+    size_gb: u64,
+    image_path: PathBuf,
+    mount_point: PathBuf,
+    loop_device: Option<String>,
+    mount_options: Option<String>,
+    retry: RetryPolicy,
+    encrypt: bool,
+    loop_options: LoopOptions,
+    /// Device-mapper name of the crypt mapping, unique to the run directory
+    /// and filesystem so cleanup can find it without knowing it was used
+    crypt_name: String,
+    crypt_device: Option<String>,
+}
+
+impl LoopbackImage {
+    /// An image for run directory `dir`, with the image file and mount
+    /// point in `files_dir`.
+    pub fn new(fs_type: FilesystemType, size_gb: u64, dir: &Path, files_dir: &Path) -> Self {
+        let name = fs_type.mount_type();
+        let run = dir.file_name().unwrap_or_default().to_string_lossy();
+        Self {
+            size_gb,
+            image_path: files_dir.join(format!("{}.img", name)),
+            mount_point: files_dir.join(name),
+            loop_device: None,
+            mount_options: None,
+            retry: RetryPolicy::default(),
+            encrypt: false,
+            loop_options: LoopOptions::default(),
+            crypt_name: format!("reflink-bench-{}-{}", run, name),
+            crypt_device: None,
+        }
+    }
+
+    /// Puts the image file in `dir` instead of the run directory.
+    fn set_image_dir(&mut self, dir: &Path) {
+        self.image_path = dir.join(self.image_path.file_name().unwrap_or_default());
+    }
+
+    /// Replaces any earlier image, then creates a new one and attaches it,
+    /// returning the device to format: the loop device, or the crypt
+    /// mapping over it when encrypting.
+    pub fn create(&mut self) -> Result<String> {
+        self.cleanup_existing();
+        self.create_image()?;
+        let loop_device = self.setup_loop_device()?;
+        if self.encrypt {
+            self.open_crypt(&loop_device)
+        } else {
+            Ok(loop_device)
+        }
+    }
+
+    /// The device the filesystem is on, once set up.
+    fn device(&self) -> Option<&str> {
+        self.crypt_device.as_deref().or(self.loop_device.as_deref())
+    }
+
+    /// Mounts the attached device as `mount_type`.
+    pub fn mount(&self, mount_type: &str) -> Result<()> {
+        let device = self.device().context("Loop device not set up")?;
+
+        if !self.mount_point.exists() {
+            std::fs::create_dir_all(&self.mount_point).context("Failed to create mount point")?;
+        }
+
+        let (flags, data) = split_mount_options(self.mount_options.as_deref().unwrap_or(""));
+
+        self.retry
+            .run(
+                "mount",
+                || {
+                    mount(
+                        Some(device),
+                        &self.mount_point,
+                        Some(mount_type),
+                        flags,
+                        (!data.is_empty()).then_some(data.as_str()),
+                    )
+                },
+                |e| *e == Errno::EBUSY,
+            )
+            .with_context(|| match &self.mount_options {
+                Some(options) => format!("Failed to mount filesystem with options {}", options),
+                None => "Failed to mount filesystem".to_string(),
+            })?;
+
+        // Set permissions for non-root access
+        Command::new("chmod")
+            .args(["777", &self.mount_point.to_string_lossy()])
+            .output()
+            .context("Failed to set mount point permissions")?;
+
+        Ok(())
+    }
+
+    fn setup_plan(&self, mkfs: &str, mkfs_args: &[&str], mount_type: &str) -> Vec<String> {
+        let mut plan = vec![
+            format!(
+                "$ dd if=/dev/zero of={} bs=1M count={}",
+                self.image_path.display(),
+                self.size_gb * 1024
+            ),
+            format!(
+                "$ losetup -f --show {}{}",
+                self.loop_options
+                    .losetup_args()
+                    .iter()
+                    .map(|arg| format!("{} ", arg))
+                    .collect::<String>(),
+                self.image_path.display()
+            ),
+        ];
+        let device = if self.encrypt {
+            plan.push(format!(
+                "$ cryptsetup {} /dev/loopN {}",
+                CRYPT_OPEN_ARGS.join(" "),
+                self.crypt_name
+            ));
+            format!("/dev/mapper/{}", self.crypt_name)
+        } else {
+            "/dev/loopN".to_string()
+        };
+        plan.extend([
+            format!("$ {} {} {}", mkfs, mkfs_args.join(" "), device),
+            format!(
+                "mount({}, {}, {}, {})",
+                device,
+                self.mount_point.display(),
+                mount_type,
+                self.mount_options.as_deref().unwrap_or("defaults")
+            ),
+            format!("$ chmod 777 {}", self.mount_point.display()),
+        ]);
+        plan
+    }
+
+    fn cleanup_plan(&self) -> Vec<String> {
+        let mut plan = vec![format!("umount({})", self.mount_point.display())];
+        if self.encrypt {
+            plan.push(format!("$ cryptsetup close {}", self.crypt_name));
+        }
+        plan.extend([
+            "$ losetup -d /dev/loopN".to_string(),
+            format!("rm {}", self.image_path.display()),
+        ]);
+        plan
+    }
+
+    pub fn cleanup(&self) -> Result<()> {
+        if self.mount_point.exists() {
+            // Writeback right after a run can keep the mount busy briefly,
+            // and an interrupted benchmark thread may still have a file
+            // open; detach lazily so the mount goes away once it's closed
+            let unmounted = self.retry.run(
+                "umount",
+                || umount(&self.mount_point),
+                |e| *e == Errno::EBUSY,
+            );
+            if unmounted == Err(Errno::EBUSY) {
+                let _ = umount2(&self.mount_point, MntFlags::MNT_DETACH);
+            }
+        }
+
+        // The mapping holds the loop device open, so it has to go first
+        self.close_crypt()?;
+
+        if let Some(loop_dev) = &self.loop_device {
+            let _ = self
+                .retry
+                .run("losetup -d", || detach_loop_device(loop_dev), |_| true);
+        }
+        self.detach_stale_loop_devices();
+
+        if self.image_path.exists() {
+            std::fs::remove_file(&self.image_path).context("Failed to remove filesystem image")?;
+        }
+
+        if self.mount_point.exists() {
+            std::fs::remove_dir(&self.mount_point).context("Failed to remove mount point")?;
+        }
+
+        Ok(())
+    }
+
+    fn cleanup_existing(&self) {
+        if self.mount_point.exists() {
+            let _ = umount(&self.mount_point);
+        }
+        let _ = self.close_crypt();
+        // A filesystem kept with --keep still has its loop device attached
+        self.detach_stale_loop_devices();
+
+        if self.image_path.exists() {
+            std::fs::remove_file(&self.image_path).ok();
+        }
+    }
+
+    fn create_image(&self) -> Result<()> {
+        let workdir = self.image_path.parent().unwrap_or(Path::new("/"));
+        filesystem::check_image_space(workdir, self.size_gb)?;
+
+        let size_mb = self.size_gb * 1024;
+
+        let output = Command::new("dd")
+            .args([
+                "if=/dev/zero",
+                &format!("of={}", self.image_path.display()),
+                "bs=1M",
+                &format!("count={}", size_mb),
+            ])
+            .output()
+            .context("Failed to create filesystem image")?;
+
+        if !output.status.success() {
+            anyhow::bail!("dd failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        Ok(())
+    }
+
+    fn setup_loop_device(&mut self) -> Result<String> {
+        // Another process can take the free device between losetup finding
+        // and attaching it
+        let output = self.retry.run(
+            "losetup",
+            || {
+                let output = Command::new("losetup")
+                    .args(["-f", "--show"])
+                    .args(self.loop_options.losetup_args())
+                    .arg(&self.image_path)
+                    .output()
+                    .context("Failed to setup loop device")?;
+
+                if !output.status.success() {
+                    anyhow::bail!(
+                        "losetup failed: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+                Ok(output)
+            },
+            |_| true,
+        )?;
+
+        let loop_device = String::from_utf8(output.stdout)
+            .context("Invalid UTF-8 in losetup output")?
+            .trim()
+            .to_string();
+
+        self.loop_device = Some(loop_device.clone());
+        Ok(loop_device)
+    }
+
+    /// Opens a dm-crypt mapping over `loop_device`, returning its device.
+    fn open_crypt(&mut self, loop_device: &str) -> Result<String> {
+        let mut args = CRYPT_OPEN_ARGS.to_vec();
+        args.extend([loop_device, self.crypt_name.as_str()]);
+        run_cryptsetup(&args)?;
+
+        let device = format!("/dev/mapper/{}", self.crypt_name);
+        self.crypt_device = Some(device.clone());
+        Ok(device)
+    }
+
+    /// Closes the dm-crypt mapping, if this image has one open, whether or
+    /// not this run opened it.
+    fn close_crypt(&self) -> Result<()> {
+        if !Path::new("/dev/mapper").join(&self.crypt_name).exists() {
+            return Ok(());
+        }
+        // Like umount, closing can hit a device still busy from writeback
+        self.retry.run(
+            "cryptsetup close",
+            || run_cryptsetup(&["close", &self.crypt_name]),
+            |_| true,
+        )
+    }
+
+    /// Detaches loop devices left attached to our image by an earlier run.
+    fn detach_stale_loop_devices(&self) {
+        if !self.image_path.exists() {
+            return;
+        }
+
+        let Ok(output) = Command::new("losetup")
+            .args(["--noheadings", "--output", "NAME", "--associated"])
+            .arg(&self.image_path)
+            .output()
+        else {
+            return;
+        };
+
+        for device in String::from_utf8_lossy(&output.stdout).split_whitespace() {
+            if Some(device) != self.loop_device.as_deref() {
+                let _ = detach_loop_device(device);
+            }
+        }
+    }
+}
+
+fn run_cryptsetup(args: &[&str]) -> Result<()> {
+    let output = Command::new("cryptsetup")
+        .args(args)
+        .output()
+        .context("Failed to run cryptsetup")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "cryptsetup {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+fn detach_loop_device(loop_device: &str) -> Result<()> {
+    let output = Command::new("losetup")
+        .args(["-d", loop_device])
+        .output()
+        .context("Failed to detach loop device")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "losetup -d failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xfs_setup_enables_reflink() {
+        let mut provider = loopback(FilesystemType::Xfs, 2, Path::new("/var/tmp"));
+        provider.set_mount_options(Some("noatime".to_string()));
+        let plan = provider.setup_plan();
+        assert_eq!(
+            plan[0],
+            "$ dd if=/dev/zero of=/var/tmp/xfs.img bs=1M count=2048"
+        );
+        assert!(plan.contains(&"$ mkfs.xfs -f -m reflink=1 /dev/loopN".to_string()));
+        assert!(plan.contains(&"mount(/dev/loopN, /var/tmp/xfs, xfs, noatime)".to_string()));
+    }
+
+    #[test]
+    fn encrypted_underlay_is_mapped_and_closed() {
+        let mut provider = loopback(FilesystemType::Btrfs, 2, Path::new("/var/tmp/run-1"));
+        provider.set_encrypt_underlay(true);
+        let plan = provider.setup_plan();
+        assert!(plan[2].starts_with("$ cryptsetup open --type plain"));
+        assert!(plan[2].ends_with("/dev/loopN reflink-bench-run-1-btrfs"));
+        assert!(plan.contains(&"$ mkfs.btrfs -f /dev/mapper/reflink-bench-run-1-btrfs".to_string()));
+        assert_eq!(
+            provider.cleanup_plan()[1],
+            "$ cryptsetup close reflink-bench-run-1-btrfs"
+        );
+    }
+
+    #[test]
+    fn helper_passes_setup_through_the_command() {
+        let mut provider = helper(
+            "sudo -n /usr/local/bin/reflink-bench",
+            FilesystemType::Btrfs,
+            2,
+            Path::new("/var/tmp/run"),
+        );
+        provider.set_mount_options(Some("compress=zstd".to_string()));
+        assert_eq!(
+            provider.setup_plan(),
+            vec![
+                "$ sudo -n /usr/local/bin/reflink-bench mount-helper setup --filesystem btrfs \
+                  --dir /var/tmp/run --size-gb 2 --mount-options compress=zstd"
+                    .to_string()
+            ]
+        );
+        assert_eq!(
+            provider.mount_point(),
+            Path::new("/var/tmp/run/helper/btrfs")
+        );
+    }
+
+    #[test]
+    fn image_dir_moves_only_the_image() {
+        let mut provider = loopback(FilesystemType::Btrfs, 2, Path::new("/var/tmp/run"));
+        provider.set_image_dir(Path::new("/mnt/nvme1/run"));
+        assert_eq!(
+            provider.image_path(),
+            Some(Path::new("/mnt/nvme1/run/btrfs.img"))
+        );
+        assert_eq!(provider.mount_point(), Path::new("/var/tmp/run/btrfs"));
+    }
+
+    #[test]
+    fn loop_options_are_passed_to_losetup() {
+        let options = LoopOptions {
+            direct_io: true,
+            sector_size: Some(4096),
+        };
+        let mut provider = loopback(FilesystemType::Xfs, 2, Path::new("/var/tmp"));
+        provider.set_loop_options(options);
+        assert_eq!(
+            provider.setup_plan()[1],
+            "$ losetup -f --show --direct-io=on --sector-size 4096 /var/tmp/xfs.img"
+        );
+
+        let mut provider = helper(
+            "reflink-bench",
+            FilesystemType::Xfs,
+            2,
+            Path::new("/var/tmp"),
+        );
+        provider.set_loop_options(options);
+        assert!(provider.setup_plan()[0].ends_with("--loop-direct-io --loop-sector-size 4096"));
+    }
+}
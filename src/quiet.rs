@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::{AsRawFd, RawFd};
+
+/// Points stdout at /dev/null until dropped, silencing our own progress
+/// output as well as anything printed by mkfs, mount and friends.
+pub struct Silence {
+    saved_stdout: RawFd,
+}
+
+impl Silence {
+    pub fn start() -> Result<Self> {
+        let null = File::options()
+            .write(true)
+            .open("/dev/null")
+            .context("Failed to open /dev/null")?;
+
+        let saved_stdout = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if saved_stdout < 0 {
+            let errno = std::io::Error::last_os_error();
+            anyhow::bail!("dup failed: {}", errno);
+        }
+
+        let _ = std::io::stdout().flush();
+        if unsafe { libc::dup2(null.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            let errno = std::io::Error::last_os_error();
+            unsafe { libc::close(saved_stdout) };
+            anyhow::bail!("dup2 failed: {}", errno);
+        }
+
+        Ok(Self { saved_stdout })
+    }
+}
+
+impl Drop for Silence {
+    fn drop(&mut self) {
+        let _ = std::io::stdout().flush();
+        unsafe {
+            libc::dup2(self.saved_stdout, libc::STDOUT_FILENO);
+            libc::close(self.saved_stdout);
+        }
+    }
+}
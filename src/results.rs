@@ -1,20 +1,154 @@
 use anyhow::{Context, Result};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::time::Duration;
+use tabled::builder::Builder;
+use tabled::settings::Style;
 use tabled::{Table, Tabled};
 
-use crate::benchmark::BenchmarkResult;
+use crate::benchmark::{BenchmarkResult, Runner};
+use crate::charts;
+use crate::compare;
+use crate::copy::CopyResult;
+use crate::environment::EnvironmentInfo;
 use crate::filesystem::FilesystemType;
+use crate::prometheus;
+use crate::stats::{self, LatencyStats, Summary};
 
+/// p-value below which a difference between filesystems is reported as real.
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// Fewest repetitions per filesystem at which the two-sided Mann-Whitney
+/// test can reach [`SIGNIFICANCE_LEVEL`]; with 3 each, even completely
+/// separated samples only get p=0.1.
+const MIN_SIGNIFICANT_REPEATS: usize = 4;
+
+/// How the final comparison is rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Emoji-annotated tables for reading in a terminal
+    #[default]
+    Terminal,
+    /// GitHub-flavored Markdown for issues and pull requests
+    Markdown,
+    /// libtest `bench:` lines, as read by github-action-benchmark and bencher
+    Bencher,
+    /// Only the final results JSON on stdout, with all progress output
+    /// suppressed (same as --quiet)
+    JsonStdout,
+}
+
+/// Version of the saved results format. Bump it when `BenchmarkResult`
+/// changes shape, and teach `ComparisonResults::from_json` to upgrade the
+/// previous version.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// The saved form of a run: the JSON written by `--output` and stored as
+/// baselines and in the history database.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ComparisonResults {
+    pub schema_version: u32,
+    /// Suite scenario these results belong to, when run with --config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+    /// `--seed` the run used; absent in results from before seeding
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub environment: EnvironmentInfo,
     pub results: Vec<(String, BenchmarkResult)>,
     pub timestamp: String,
 }
 
+impl ComparisonResults {
+    /// Parses saved results, upgrading files written by older versions.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_str(json).context("Invalid JSON")?;
+
+        // Files from before versioning have no schema_version
+        let version = value
+            .get("schema_version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1) as u32;
+        if version > SCHEMA_VERSION {
+            anyhow::bail!(
+                "Results use schema version {}, newer than the supported {}; upgrade reflink-bench",
+                version,
+                SCHEMA_VERSION
+            );
+        }
+
+        if version < 2 {
+            upgrade_v1(&mut value)?;
+        }
+        if version < 3 {
+            upgrade_v2(&mut value)?;
+        }
+
+        serde_json::from_value(value).context("Results don't match the expected schema")
+    }
+}
+
+/// Version 1 results only had sequential timings and concurrent ops/sec;
+/// every field added since takes its default (empty or zero) value.
+fn upgrade_v1(value: &mut serde_json::Value) -> Result<()> {
+    let defaults = serde_json::to_value(BenchmarkResult::default())?;
+    let results = value
+        .get_mut("results")
+        .and_then(|results| results.as_array_mut())
+        .context("Missing results array")?;
+
+    for entry in results {
+        // Each entry is a [filesystem, result] pair
+        if let Some(result) = entry.get_mut(1) {
+            fill_missing(result, &defaults);
+        }
+    }
+
+    value["schema_version"] = 2.into();
+    Ok(())
+}
+
+/// Version 2 results didn't record the environment.
+fn upgrade_v2(value: &mut serde_json::Value) -> Result<()> {
+    let defaults = serde_json::to_value(BenchmarkResult::default())?;
+    if let Some(results) = value.get_mut("results").and_then(|r| r.as_array_mut()) {
+        for entry in results {
+            if let Some(result) = entry.get_mut(1) {
+                fill_missing(result, &defaults);
+            }
+        }
+    }
+
+    value["environment"] = serde_json::to_value(EnvironmentInfo::default())?;
+    value["schema_version"] = 3.into();
+    Ok(())
+}
+
+/// Recursively copies keys present in `defaults` but missing from `value`.
+fn fill_missing(value: &mut serde_json::Value, defaults: &serde_json::Value) {
+    let (Some(value), Some(defaults)) = (value.as_object_mut(), defaults.as_object()) else {
+        return;
+    };
+
+    for (key, default) in defaults {
+        match value.get_mut(key) {
+            Some(existing) => fill_missing(existing, default),
+            None => {
+                value.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
+
+/// Prints, saves, and exports the results of one run (or scenario).
 pub struct ResultsReporter {
     results: Vec<(FilesystemType, BenchmarkResult)>,
+    /// Named baseline to show deltas against
+    baseline: Option<(String, ComparisonResults)>,
+    environment: EnvironmentInfo,
+    scenario: Option<String>,
+    seed: Option<u64>,
 }
 
 #[derive(Tabled)]
@@ -51,85 +185,1739 @@ struct ConcurrencyRow {
     winner: String,
 }
 
+#[derive(Tabled)]
+struct LatencyRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Operation")]
+    operation: String,
+    #[tabled(rename = "p50")]
+    p50: String,
+    #[tabled(rename = "p90")]
+    p90: String,
+    #[tabled(rename = "p99")]
+    p99: String,
+    #[tabled(rename = "p99.9")]
+    p999: String,
+    #[tabled(rename = "Max")]
+    max: String,
+    #[tabled(rename = "Outliers")]
+    outliers: String,
+}
+
+impl LatencyRow {
+    fn new(filesystem: &FilesystemType, operation: String, stats: &LatencyStats) -> Self {
+        Self {
+            filesystem: format!("{}", filesystem),
+            operation,
+            p50: format_latency(stats.p50),
+            p90: format_latency(stats.p90),
+            p99: format_latency(stats.p99),
+            p999: format_latency(stats.p999),
+            max: format_latency(stats.max),
+            outliers: format!(
+                "{}{} ({:.0}% of time)",
+                if stats.outliers_dominate() {
+                    "⚠️ "
+                } else {
+                    ""
+                },
+                stats.outliers,
+                stats.outlier_time_share * 100.0
+            ),
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct CpuRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Phase")]
+    phase: String,
+    #[tabled(rename = "Process CPU")]
+    process_cpu: String,
+    #[tabled(rename = "System Share")]
+    system_share: String,
+    #[tabled(rename = "Machine CPU")]
+    machine_cpu: String,
+    #[tabled(rename = "Voluntary CS")]
+    voluntary: i64,
+    #[tabled(rename = "Involuntary CS")]
+    involuntary: i64,
+}
+
+#[derive(Tabled)]
+struct PerfRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Phase")]
+    phase: String,
+    #[tabled(rename = "Cycles")]
+    cycles: String,
+    #[tabled(rename = "Instructions")]
+    instructions: String,
+    #[tabled(rename = "IPC")]
+    ipc: String,
+    #[tabled(rename = "Cache Misses")]
+    cache_misses: String,
+    #[tabled(rename = "Context Switches")]
+    context_switches: String,
+}
+
+#[derive(Tabled)]
+struct KernelLatencyRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Kernel Function")]
+    function: String,
+    #[tabled(rename = "Calls")]
+    calls: u64,
+    #[tabled(rename = "Mean")]
+    mean: String,
+    #[tabled(rename = "Total")]
+    total: String,
+}
+
+#[derive(Tabled)]
+struct WorkerBalanceRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Threads")]
+    threads: u32,
+    #[tabled(rename = "Ops/Worker")]
+    operations: String,
+    #[tabled(rename = "Median Finish")]
+    median_finish: String,
+    #[tabled(rename = "Last Finish")]
+    last_finish: String,
+    #[tabled(rename = "Straggler")]
+    straggler: String,
+    #[tabled(rename = "Idle")]
+    idle: String,
+}
+
+#[derive(Tabled)]
+struct PresetRow {
+    #[tabled(rename = "Configuration")]
+    configuration: String,
+    #[tabled(rename = "Mount options")]
+    mount_options: String,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "MB/s")]
+    throughput: String,
+    #[tabled(rename = "Reflink p99")]
+    reflink_p99: String,
+    #[tabled(rename = "Write p99")]
+    write_p99: String,
+    #[tabled(rename = "vs Default")]
+    vs_default: String,
+}
+
+#[derive(Tabled)]
+struct OpenLoopRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Target ops/sec")]
+    target: String,
+    #[tabled(rename = "Achieved ops/sec")]
+    achieved: String,
+    #[tabled(rename = "Response p50")]
+    response_p50: String,
+    #[tabled(rename = "Response p99")]
+    response_p99: String,
+    #[tabled(rename = "Response p99.9")]
+    response_p999: String,
+    #[tabled(rename = "Service p99")]
+    service_p99: String,
+    #[tabled(rename = "Max in flight")]
+    max_in_flight: u64,
+}
+
+#[derive(Tabled)]
+struct SoakRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Duration")]
+    duration: String,
+    #[tabled(rename = "Ops")]
+    operations: u64,
+    #[tabled(rename = "Sustained ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "Slowest window")]
+    slowest: String,
+    #[tabled(rename = "Ops/sec drift")]
+    drift: String,
+    #[tabled(rename = "Reflink p99 drift")]
+    reflink_p99_drift: String,
+    #[tabled(rename = "Write p99 drift")]
+    write_p99_drift: String,
+    #[tabled(rename = "Trend")]
+    trend: String,
+}
+
+#[derive(Tabled)]
+struct CacheRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Cache")]
+    cache_mode: String,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "Reflink p50")]
+    reflink_p50: String,
+    #[tabled(rename = "Reflink p99")]
+    reflink_p99: String,
+    #[tabled(rename = "Write p50")]
+    write_p50: String,
+    #[tabled(rename = "Write p99")]
+    write_p99: String,
+}
+
+#[derive(Tabled)]
+struct FscryptRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "vs unencrypted")]
+    vs_unencrypted: String,
+    #[tabled(rename = "Reflink p50")]
+    reflink_p50: String,
+    #[tabled(rename = "Reflink p99")]
+    reflink_p99: String,
+    #[tabled(rename = "Write p50")]
+    write_p50: String,
+    #[tabled(rename = "Write p99")]
+    write_p99: String,
+}
+
+#[derive(Tabled)]
+struct CrossFsRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Method")]
+    method: String,
+    #[tabled(rename = "Outcome")]
+    outcome: String,
+    #[tabled(rename = "p50")]
+    p50: String,
+    #[tabled(rename = "p99")]
+    p99: String,
+    #[tabled(rename = "MB/s")]
+    throughput: String,
+    #[tabled(rename = "vs Reflink")]
+    vs_reflink: String,
+}
+
+#[derive(Tabled)]
+struct TrimRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Trims")]
+    trims: usize,
+    #[tabled(rename = "Trimmed")]
+    trimmed: String,
+    #[tabled(rename = "Trim p50")]
+    p50: String,
+    #[tabled(rename = "Trim max")]
+    max: String,
+}
+
+#[derive(Tabled)]
+struct CopyBaselineRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Method")]
+    method: String,
+    #[tabled(rename = "p50")]
+    p50: String,
+    #[tabled(rename = "p99")]
+    p99: String,
+    #[tabled(rename = "MB/s")]
+    throughput: String,
+    #[tabled(rename = "Reflink Speedup")]
+    speedup: String,
+}
+
+#[derive(Tabled)]
+struct SparseSourceRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Apparent")]
+    apparent: String,
+    #[tabled(rename = "Allocated")]
+    allocated: String,
+    #[tabled(rename = "Extents")]
+    extents: String,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "Reflink p50")]
+    reflink_p50: String,
+    #[tabled(rename = "Reflink p99")]
+    reflink_p99: String,
+    #[tabled(rename = "Write p50")]
+    write_p50: String,
+}
+
+#[derive(Tabled)]
+struct BackgroundLoadRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Load")]
+    load: String,
+    #[tabled(rename = "Operations")]
+    operations: u64,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+}
+
+#[derive(Tabled)]
+struct ExternalToolRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Clone via")]
+    tool: String,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "Clone p50")]
+    reflink_p50: String,
+    #[tabled(rename = "Clone p99")]
+    reflink_p99: String,
+    #[tabled(rename = "Write p50")]
+    write_p50: String,
+    #[tabled(rename = "Overhead p50")]
+    overhead_p50: String,
+}
+
+#[derive(Tabled)]
+struct ThroughputSeriesRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Phase")]
+    phase: String,
+    #[tabled(rename = "Samples")]
+    samples: usize,
+    #[tabled(rename = "Peak Ops/sec")]
+    peak: String,
+    #[tabled(rename = "Lowest Ops/sec")]
+    trough: String,
+    #[tabled(rename = "Lowest / Peak")]
+    ratio: String,
+    #[tabled(rename = "Trend")]
+    trend: String,
+}
+
+#[derive(Tabled)]
+struct RepetitionRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Metric")]
+    metric: String,
+    #[tabled(rename = "Runs")]
+    runs: usize,
+    #[tabled(rename = "Mean ± Stddev")]
+    mean: String,
+    #[tabled(rename = "95% CI")]
+    ci95: String,
+}
+
+impl RepetitionRow {
+    fn new(filesystem: &FilesystemType, metric: String, summary: &Summary) -> Self {
+        Self {
+            filesystem: format!("{}", filesystem),
+            metric,
+            runs: summary.samples.len(),
+            mean: format!("{:.2} ± {:.2}", summary.mean, summary.stddev),
+            ci95: format!("[{:.2}, {:.2}]", summary.ci95_low, summary.ci95_high),
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct WriteSizeRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Write Size")]
+    write_size: String,
+    #[tabled(rename = "Avg per Op")]
+    avg_time: String,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "Write MB/s")]
+    write_throughput: String,
+}
+
+#[derive(Tabled)]
+struct SourceExtentRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Extent Size")]
+    extent_size: String,
+    #[tabled(rename = "Extents")]
+    extents: String,
+    #[tabled(rename = "Clone p50")]
+    clone_p50: String,
+    #[tabled(rename = "Clone p99")]
+    clone_p99: String,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "p50")]
+    chart: String,
+}
+
+#[derive(Tabled)]
+struct WriteAmplificationRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Phase")]
+    phase: String,
+    #[tabled(rename = "Logical Written")]
+    logical: String,
+    #[tabled(rename = "Physical Written")]
+    physical: String,
+    #[tabled(rename = "Amplification")]
+    amplification: String,
+}
+
+#[derive(Tabled)]
+struct DeviceStatsRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Phase")]
+    phase: String,
+    #[tabled(rename = "Read IOPS")]
+    read_iops: String,
+    #[tabled(rename = "Write IOPS")]
+    write_iops: String,
+    #[tabled(rename = "Queue Depth")]
+    queue_depth: String,
+    #[tabled(rename = "Await")]
+    await_ms: String,
+    #[tabled(rename = "Util")]
+    utilization: String,
+}
+
+#[derive(Tabled)]
+struct SpaceRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Clones")]
+    clones: u32,
+    #[tabled(rename = "Apparent")]
+    apparent: String,
+    #[tabled(rename = "Used by Clones")]
+    clone_used: String,
+    #[tabled(rename = "Used by Writes")]
+    write_used: String,
+    #[tabled(rename = "Efficiency")]
+    efficiency: String,
+    #[tabled(rename = "Shared")]
+    shared: String,
+    #[tabled(rename = "Exclusive")]
+    exclusive: String,
+}
+
+#[derive(Tabled)]
+struct ExtentUsageRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Phase")]
+    phase: String,
+    #[tabled(rename = "Files")]
+    files: u32,
+    #[tabled(rename = "Referenced")]
+    referenced: String,
+    #[tabled(rename = "On Disk")]
+    disk: String,
+    #[tabled(rename = "Shared")]
+    shared: String,
+    #[tabled(rename = "Exclusive")]
+    exclusive: String,
+    #[tabled(rename = "Savings")]
+    savings: String,
+}
+
+#[derive(Tabled)]
+struct VerifyRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Clones Verified")]
+    files: u64,
+    #[tabled(rename = "Data Read")]
+    bytes_read: String,
+    #[tabled(rename = "Duration")]
+    duration: String,
+}
+
+#[derive(Tabled)]
+struct WorkloadRow {
+    #[tabled(rename = "Filesystem")]
+    filesystem: String,
+    #[tabled(rename = "Workload")]
+    workload: String,
+    #[tabled(rename = "Ops")]
+    operations: u32,
+    #[tabled(rename = "Total Time")]
+    total_time: String,
+    #[tabled(rename = "Avg per Op")]
+    avg_time: String,
+    #[tabled(rename = "Ops/sec")]
+    ops_per_sec: String,
+    #[tabled(rename = "Metrics")]
+    metrics: String,
+}
+
 impl ResultsReporter {
     pub fn new(results: Vec<(FilesystemType, BenchmarkResult)>) -> Self {
-        Self { results }
+        Self {
+            results,
+            baseline: None,
+            environment: EnvironmentInfo::default(),
+            scenario: None,
+            seed: None,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_scenario(mut self, scenario: Option<String>) -> Self {
+        self.scenario = scenario;
+        self
+    }
+
+    pub fn with_environment(mut self, environment: EnvironmentInfo) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    pub fn with_baseline(mut self, name: &str, baseline: ComparisonResults) -> Self {
+        self.baseline = Some((name.to_string(), baseline));
+        self
     }
 
     pub fn print_comparison(&self) {
+        self.print_environment();
+
         println!("\n📊 FILESYSTEM COMPARISON RESULTS");
         println!("================================");
 
-        // Main comparison table
-        let comparison_rows: Vec<ComparisonRow> = self
+        // Main comparison table
+        let table = Table::new(self.comparison_rows());
+        println!("{}", table);
+
+        // Repetition statistics
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| result.repetitions.is_some())
+        {
+            self.print_repetition_statistics();
+        }
+
+        // Latency percentiles
+        self.print_latency_percentiles();
+
+        // Cache conditions
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.cache_results.is_empty())
+        {
+            self.print_cache_results();
+        }
+
+        // Soak drift
+        if self.results.iter().any(|(_, result)| result.soak.is_some()) {
+            self.print_soak_results();
+        }
+
+        // Open-loop latency
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| result.open_loop.is_some())
+        {
+            self.print_open_loop_results();
+        }
+
+        // External tools against the in-process ioctl
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.external_tools.is_empty())
+        {
+            self.print_external_tools();
+        }
+
+        // fscrypt overhead
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| result.fscrypt.is_some())
+        {
+            self.print_fscrypt_results();
+        }
+
+        // Huge sparse source
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| result.sparse_source.is_some())
+        {
+            self.print_sparse_source();
+        }
+
+        // Cross-filesystem fallback
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| result.cross_fs.is_some())
+        {
+            self.print_cross_fs();
+        }
+
+        // Discards between phases
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.fstrim.is_empty())
+        {
+            self.print_fstrim();
+        }
+
+        // Copying without reflink
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.copy_baselines.is_empty())
+        {
+            self.print_copy_baselines();
+        }
+
+        // Background load
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| result.background_load.is_some())
+        {
+            self.print_background_load();
+        }
+
+        // Throughput over time
+        self.print_throughput_series();
+
+        // CPU cost
+        self.print_cpu_usage();
+
+        // Hardware counters
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.perf_counters.is_empty())
+        {
+            self.print_perf_counters();
+        }
+
+        // Kernel function latency
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.kernel_latency.is_empty())
+        {
+            self.print_kernel_latency();
+        }
+
+        // Concurrency analysis
+        if self.results.len() == 2 {
+            self.print_concurrency_analysis();
+        }
+
+        // Scaling charts
+        self.print_scaling_charts();
+
+        // Worker load balance
+        self.print_worker_balance();
+
+        // Write-size sweep
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.write_size_results.is_empty())
+        {
+            self.print_write_size_sweep();
+        }
+
+        // Source extent-size sweep
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.source_extent_results.is_empty())
+        {
+            self.print_source_extent_sweep();
+        }
+
+        // Write amplification
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.write_amplification.is_empty())
+        {
+            self.print_write_amplification();
+        }
+
+        // Device statistics
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.device_stats.is_empty())
+        {
+            self.print_device_stats();
+        }
+
+        // Space accounting
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| result.space_report.is_some())
+        {
+            self.print_space_report();
+        }
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.extent_usage.is_empty())
+        {
+            self.print_extent_usage();
+        }
+
+        // Data integrity
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| result.verification.is_some())
+        {
+            self.print_verification();
+        }
+
+        // Additional workloads
+        if self
+            .results
+            .iter()
+            .any(|(_, result)| !result.workload_results.is_empty())
+        {
+            self.print_workload_results();
+        }
+
+        // Changes against a saved baseline
+        if self.baseline.is_some() {
+            self.print_baseline_deltas();
+        }
+
+        // Performance summary
+        self.print_performance_summary();
+    }
+
+    fn print_baseline_deltas(&self) {
+        let Some((name, baseline)) = &self.baseline else {
+            return;
+        };
+
+        println!("\n📏 CHANGES VS BASELINE '{}'", name);
+        println!("=========================");
+        println!("Baseline recorded {}", baseline.timestamp);
+
+        let deltas = compare::metric_deltas(baseline, &self.comparison_results());
+        if deltas.is_empty() {
+            println!("No filesystems in common with the baseline");
+        } else {
+            compare::print_deltas(&deltas);
+        }
+    }
+
+    fn print_environment(&self) {
+        let env = &self.environment;
+        println!("\n🖥️  ENVIRONMENT");
+        println!("==============");
+        println!(
+            "reflink-bench {}, kernel {}",
+            env.tool_version, env.kernel_version
+        );
+        println!(
+            "CPU: {} ({} threads), Memory: {}",
+            env.cpu_model,
+            env.cpu_count,
+            format_bytes(env.memory_bytes)
+        );
+        if env.numa_nodes.len() > 1 {
+            println!("NUMA nodes: {}", env.numa_nodes.join(" | "));
+        }
+        if let Some(cpus) = &env.cpu_affinity {
+            match env.numa_node {
+                Some(node) => println!("Pinned to CPUs {}, memory on NUMA node {}", cpus, node),
+                None => println!("Pinned to CPUs {}", cpus),
+            }
+        }
+        if let Some(io_max) = &env.cgroup_io_max {
+            println!("cgroup io.max: {}", io_max);
+        }
+        if let Some(bytes) = env.cgroup_memory_max {
+            println!("cgroup memory.max: {}", format_bytes(bytes));
+        }
+        if let Some(priority) = &env.io_priority {
+            println!("I/O priority: {}", priority);
+        }
+        if let Some(threads) = env.io_threads {
+            println!("Blocking thread pool: {} threads", threads);
+        }
+        if let Some(seed) = self.seed {
+            println!(
+                "Seed: {} (rerun with --seed {} for identical data)",
+                seed, seed
+            );
+        }
+        for (fs_type, result) in &self.results {
+            let device = &result.environment;
+            let mkfs = env
+                .mkfs_versions
+                .get(&fs_type.to_string())
+                .map(String::as_str)
+                .unwrap_or("");
+            let crypt = if device.encrypted { "dm-crypt on " } else { "" };
+            let mut loop_options = String::new();
+            if device.loop_direct_io {
+                loop_options.push_str(", direct I/O");
+            }
+            if let Some(size) = device.sector_size.filter(|&size| size != 512) {
+                loop_options.push_str(&format!(", {}-byte sectors", size));
+            }
+            let backing = match &device.backing_file {
+                Some(path) => format!(" ({}loop → {}{})", crypt, path.display(), loop_options),
+                None if device.loop_device => format!(" ({}loop{})", crypt, loop_options),
+                None if device.encrypted => " (dm-crypt)".to_string(),
+                None => String::new(),
+            };
+            let scheduler = match &device.io_scheduler {
+                Some(scheduler) => format!(", I/O scheduler {}", scheduler),
+                None => String::new(),
+            };
+            let extents = match result.source_extents {
+                Some(extents) => format!(", source in {} extents", extents),
+                None => String::new(),
+            };
+            let runner = match result.runner {
+                Runner::Tokio => "",
+                Runner::Threads => ", on OS threads",
+            };
+            let quota = if device.qgroups {
+                ", qgroups enabled"
+            } else if device.project_quota {
+                ", project quota enabled"
+            } else {
+                ""
+            };
+            println!(
+                "{}: {} on {}{}, mounted {}{}{}{}{}",
+                fs_type,
+                mkfs,
+                device.device,
+                backing,
+                device.mount_options,
+                scheduler,
+                quota,
+                extents,
+                runner
+            );
+        }
+    }
+
+    fn print_write_size_sweep(&self) {
+        println!("\n✍️  WRITE SIZE SWEEP");
+        println!("==================");
+
+        let write_size_rows: Vec<WriteSizeRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result.write_size_results.iter().map(move |w| WriteSizeRow {
+                    filesystem: format!("{}", fs_type),
+                    write_size: format_bytes(w.write_size_bytes),
+                    avg_time: format_latency(w.avg_op_time),
+                    ops_per_sec: format!("{:.2}", w.operations_per_sec),
+                    write_throughput: format!(
+                        "{:.2}",
+                        w.operations_per_sec * w.write_size_bytes as f64 / (1024.0 * 1024.0)
+                    ),
+                })
+            })
+            .collect();
+
+        let table = Table::new(write_size_rows);
+        println!("{}", table);
+    }
+
+    fn print_source_extent_sweep(&self) {
+        println!("\n📐 SOURCE EXTENT SWEEP");
+        println!("=====================");
+        const BAR_WIDTH: usize = 20;
+
+        let max_p50 = self
+            .results
+            .iter()
+            .flat_map(|(_, result)| &result.source_extent_results)
+            .map(|e| e.clone_latency.p50.as_secs_f64())
+            .fold(0.0, f64::max);
+        let rows: Vec<SourceExtentRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result
+                    .source_extent_results
+                    .iter()
+                    .map(move |e| SourceExtentRow {
+                        filesystem: format!("{}", fs_type),
+                        extent_size: format_bytes(e.extent_size_bytes),
+                        extents: e
+                            .extents
+                            .map_or_else(|| "?".to_string(), |extents| extents.to_string()),
+                        clone_p50: format_latency(e.clone_latency.p50),
+                        clone_p99: format_latency(e.clone_latency.p99),
+                        ops_per_sec: format!("{:.2}", e.operations_per_sec),
+                        chart: charts::bar(e.clone_latency.p50.as_secs_f64(), max_p50, BAR_WIDTH),
+                    })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!(
+            "FICLONE alone, without the CoW write; fewer, larger extents should clone faster."
+        );
+    }
+
+    fn print_write_amplification(&self) {
+        println!("\n📝 WRITE AMPLIFICATION");
+        println!("=====================");
+
+        let rows: Vec<WriteAmplificationRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result
+                    .write_amplification
+                    .iter()
+                    .map(move |w| WriteAmplificationRow {
+                        filesystem: format!("{}", fs_type),
+                        phase: w.phase.clone(),
+                        logical: format_bytes(w.logical_bytes),
+                        physical: format_bytes(w.physical_bytes),
+                        amplification: format!("{:.2}x", w.amplification),
+                    })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+    }
+
+    fn print_device_stats(&self) {
+        println!("\n💽 DEVICE STATISTICS");
+        println!("===================");
+
+        let rows: Vec<DeviceStatsRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result.device_stats.iter().map(move |d| DeviceStatsRow {
+                    filesystem: format!("{}", fs_type),
+                    phase: d.phase.clone(),
+                    read_iops: format!("{:.0}", d.read_iops),
+                    write_iops: format!("{:.0}", d.write_iops),
+                    queue_depth: format!("{:.2}", d.avg_queue_depth),
+                    await_ms: format!("{:.2}ms", d.await_ms),
+                    utilization: format!("{:.0}%", d.utilization * 100.0),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+
+        println!("💡 Near-100% utilization points to a device-bound run; low utilization");
+        println!("   alongside slow operations points to contention inside the filesystem.");
+    }
+
+    fn print_space_report(&self) {
+        println!("\n💾 SPACE ACCOUNTING");
+        println!("==================");
+
+        let space_rows: Vec<SpaceRow> = self
+            .results
+            .iter()
+            .filter_map(|(fs_type, result)| {
+                let report = result.space_report.as_ref()?;
+                Some(SpaceRow {
+                    filesystem: format!("{}", fs_type),
+                    clones: report.clones,
+                    apparent: format_bytes(report.apparent_bytes),
+                    clone_used: format_bytes(report.clone_used_bytes),
+                    write_used: format_bytes(report.write_used_bytes),
+                    efficiency: format!("{:.1}x", report.space_efficiency()),
+                    shared: report
+                        .shared_bytes
+                        .map(format_bytes)
+                        .unwrap_or_else(|| "n/a".to_string()),
+                    exclusive: report
+                        .exclusive_bytes
+                        .map(format_bytes)
+                        .unwrap_or_else(|| "n/a".to_string()),
+                })
+            })
+            .collect();
+
+        let table = Table::new(space_rows);
+        println!("{}", table);
+    }
+
+    fn print_extent_usage(&self) {
+        println!("\n🧮 EXTENT USAGE");
+        println!("===============");
+
+        let rows: Vec<ExtentUsageRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result.extent_usage.iter().map(move |usage| ExtentUsageRow {
+                    filesystem: format!("{}", fs_type),
+                    phase: usage.phase.clone(),
+                    files: usage.files,
+                    referenced: format_bytes(usage.referenced_bytes),
+                    disk: format_bytes(usage.disk_bytes),
+                    shared: format_bytes(usage.shared_bytes),
+                    exclusive: format_bytes(usage.exclusive_bytes),
+                    savings: format!("{:.1}%", usage.savings() * 100.0),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+
+        println!("💡 Referenced counts every file's mapped bytes; On Disk counts each");
+        println!("   physical extent once, so the gap is what sharing saved.");
+    }
+
+    fn print_verification(&self) {
+        println!("\n🔍 DATA INTEGRITY");
+        println!("=================");
+
+        let rows: Vec<VerifyRow> = self
+            .results
+            .iter()
+            .filter_map(|(fs_type, result)| {
+                let report = result.verification.as_ref()?;
+                Some(VerifyRow {
+                    filesystem: format!("{}", fs_type),
+                    files: report.files,
+                    bytes_read: format_bytes(report.bytes_read),
+                    duration: format!("{:?}", report.duration),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!("All clones matched their sources outside the written regions.");
+    }
+
+    fn print_workload_results(&self) {
+        println!("\n🧪 WORKLOAD RESULTS");
+        println!("==================");
+
+        let table = Table::new(self.workload_rows("\n"));
+        println!("{}", table);
+    }
+
+    fn comparison_rows(&self) -> Vec<ComparisonRow> {
+        self.results
+            .iter()
+            .map(|(fs_type, result)| ComparisonRow {
+                filesystem: format!("{}", fs_type),
+                file_size: result.file_size_mb,
+                reflink_count: result.reflink_count,
+                total_time: format_duration(result.total_duration),
+                avg_time: format_duration(result.avg_reflink_time),
+                throughput: format!("{:.2}", result.throughput_mb_per_sec),
+                ops_per_sec: format!("{:.2}", result.operations_per_sec),
+            })
+            .collect()
+    }
+
+    /// Workload rows with each row's metrics joined by `metric_separator`.
+    fn workload_rows(&self, metric_separator: &str) -> Vec<WorkloadRow> {
+        self.results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result.workload_results.iter().map(move |w| WorkloadRow {
+                    filesystem: format!("{}", fs_type),
+                    workload: w.workload.clone(),
+                    operations: w.operations,
+                    total_time: format_duration(w.total_duration),
+                    avg_time: format_latency(w.avg_op_time),
+                    ops_per_sec: format!("{:.2}", w.operations_per_sec),
+                    metrics: w
+                        .metrics
+                        .iter()
+                        .map(|(name, value)| format!("{}={:.2}", name, value))
+                        .collect::<Vec<_>>()
+                        .join(metric_separator),
+                })
+            })
+            .collect()
+    }
+
+    fn print_repetition_statistics(&self) {
+        println!("\n🔁 REPETITION STATISTICS");
+        println!("=======================");
+
+        let mut rows = Vec::new();
+        for (fs_type, result) in &self.results {
+            let Some(repetitions) = &result.repetitions else {
+                continue;
+            };
+
+            rows.push(RepetitionRow::new(
+                fs_type,
+                "Ops/sec".to_string(),
+                &repetitions.operations_per_sec,
+            ));
+            rows.push(RepetitionRow::new(
+                fs_type,
+                "Throughput (MB/s)".to_string(),
+                &repetitions.throughput_mb_per_sec,
+            ));
+            rows.push(RepetitionRow::new(
+                fs_type,
+                "Reflink p99 (µs)".to_string(),
+                &repetitions.reflink_p99_us,
+            ));
+            rows.push(RepetitionRow::new(
+                fs_type,
+                "CoW write p99 (µs)".to_string(),
+                &repetitions.write_p99_us,
+            ));
+            for (threads, summary) in result
+                .concurrent_results
+                .thread_counts
+                .iter()
+                .zip(&repetitions.concurrent_operations_per_sec)
+            {
+                rows.push(RepetitionRow::new(
+                    fs_type,
+                    format!("Ops/sec @ {} threads", threads),
+                    summary,
+                ));
+            }
+        }
+
+        let table = Table::new(rows);
+        println!("{}", table);
+    }
+
+    fn print_latency_percentiles(&self) {
+        println!("\n⏱️  LATENCY PERCENTILES");
+        println!("=====================");
+
+        let table = Table::new(self.latency_rows());
+        println!("{}", table);
+    }
+
+    fn latency_rows(&self) -> Vec<LatencyRow> {
+        let mut latency_rows = Vec::new();
+        for (fs_type, result) in &self.results {
+            latency_rows.push(LatencyRow::new(
+                fs_type,
+                "reflink".to_string(),
+                &result.reflink_latency,
+            ));
+            latency_rows.push(LatencyRow::new(
+                fs_type,
+                "CoW write".to_string(),
+                &result.write_latency,
+            ));
+            if let Some(trimmed) = &result.reflink_latency_trimmed {
+                latency_rows.push(LatencyRow::new(
+                    fs_type,
+                    "reflink (trimmed)".to_string(),
+                    trimmed,
+                ));
+            }
+            if let Some(trimmed) = &result.write_latency_trimmed {
+                latency_rows.push(LatencyRow::new(
+                    fs_type,
+                    "CoW write (trimmed)".to_string(),
+                    trimmed,
+                ));
+            }
+
+            let concurrent = &result.concurrent_results;
+            for (threads, stats) in concurrent.thread_counts.iter().zip(&concurrent.latencies) {
+                latency_rows.push(LatencyRow::new(
+                    fs_type,
+                    format!("reflink + write @ {} threads", threads),
+                    stats,
+                ));
+            }
+        }
+
+        latency_rows
+    }
+
+    fn print_cache_results(&self) {
+        println!("\n🧊 CACHE CONDITIONS");
+        println!("==================");
+
+        let rows: Vec<CacheRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result.cache_results.iter().map(move |c| CacheRow {
+                    filesystem: format!("{}", fs_type),
+                    cache_mode: c.cache_mode.clone(),
+                    ops_per_sec: format!("{:.2}", c.operations_per_sec),
+                    reflink_p50: format_latency(c.reflink_latency.p50),
+                    reflink_p99: format_latency(c.reflink_latency.p99),
+                    write_p50: format_latency(c.write_latency.p50),
+                    write_p99: format_latency(c.write_latency.p99),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+    }
+
+    fn print_open_loop_results(&self) {
+        println!("\n🎯 OPEN LOOP");
+        println!("============");
+
+        let rows: Vec<OpenLoopRow> = self
+            .results
+            .iter()
+            .filter_map(|(fs_type, result)| {
+                let open_loop = result.open_loop.as_ref()?;
+                Some(OpenLoopRow {
+                    filesystem: format!("{}", fs_type),
+                    target: format!("{:.2}", open_loop.target_rate),
+                    achieved: format!(
+                        "{:.2}{}",
+                        open_loop.achieved_rate,
+                        if open_loop.kept_up() { "" } else { " ⚠️" }
+                    ),
+                    response_p50: format_latency(open_loop.response_latency.p50),
+                    response_p99: format_latency(open_loop.response_latency.p99),
+                    response_p999: format_latency(open_loop.response_latency.p999),
+                    service_p99: format_latency(open_loop.service_latency.p99),
+                    max_in_flight: open_loop.max_in_flight,
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!("Response time counts from each operation's scheduled start, queueing included.");
+    }
+
+    fn print_external_tools(&self) {
+        println!("\n🛠️  EXTERNAL TOOLS");
+        println!("=================");
+
+        let rows: Vec<ExternalToolRow> = self
+            .results
+            .iter()
+            .filter(|(_, result)| !result.external_tools.is_empty())
+            .flat_map(|(fs_type, result)| {
+                let in_process = ExternalToolRow {
+                    filesystem: format!("{}", fs_type),
+                    tool: "FICLONE (in-process)".to_string(),
+                    ops_per_sec: format!("{:.2}", result.operations_per_sec),
+                    reflink_p50: format_latency(result.reflink_latency.p50),
+                    reflink_p99: format_latency(result.reflink_latency.p99),
+                    write_p50: format_latency(result.write_latency.p50),
+                    overhead_p50: "-".to_string(),
+                };
+                let baseline = result.reflink_latency.p50;
+                std::iter::once(in_process).chain(result.external_tools.iter().map(move |t| {
+                    ExternalToolRow {
+                        filesystem: format!("{}", fs_type),
+                        tool: t.tool.clone(),
+                        ops_per_sec: format!("{:.2}", t.operations_per_sec),
+                        reflink_p50: format_latency(t.reflink_latency.p50),
+                        reflink_p99: format_latency(t.reflink_latency.p99),
+                        write_p50: format_latency(t.write_latency.p50),
+                        overhead_p50: format!(
+                            "+{}",
+                            format_latency(t.reflink_latency.p50.saturating_sub(baseline))
+                        ),
+                    }
+                }))
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!("Tool clone times include fork, exec, and the tool's own startup.");
+    }
+
+    fn print_fscrypt_results(&self) {
+        println!("\n🔐 FSCRYPT");
+        println!("==========");
+
+        let rows: Vec<FscryptRow> = self
+            .results
+            .iter()
+            .filter_map(|(fs_type, result)| {
+                let fscrypt = result.fscrypt.as_ref()?;
+                Some(FscryptRow {
+                    filesystem: format!("{}", fs_type),
+                    ops_per_sec: format!("{:.2}", fscrypt.operations_per_sec),
+                    vs_unencrypted: format!(
+                        "{:+.1}%",
+                        (fscrypt.operations_per_sec / result.operations_per_sec - 1.0) * 100.0
+                    ),
+                    reflink_p50: format_latency(fscrypt.reflink_latency.p50),
+                    reflink_p99: format_latency(fscrypt.reflink_latency.p99),
+                    write_p50: format_latency(fscrypt.write_latency.p50),
+                    write_p99: format_latency(fscrypt.write_latency.p99),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!("Compared with the sequential phase; filesystems without fscrypt are left out.");
+    }
+
+    fn print_sparse_source(&self) {
+        println!("\n🕳️  SPARSE SOURCE");
+        println!("=================");
+
+        let rows: Vec<SparseSourceRow> = self
+            .results
+            .iter()
+            .filter_map(|(fs_type, result)| {
+                let sparse = result.sparse_source.as_ref()?;
+                Some(SparseSourceRow {
+                    filesystem: format!("{}", fs_type),
+                    apparent: format_bytes(sparse.apparent_bytes),
+                    allocated: format_bytes(sparse.allocated_bytes),
+                    extents: sparse
+                        .extents
+                        .map_or_else(|| "?".to_string(), |extents| extents.to_string()),
+                    ops_per_sec: format!("{:.2}", sparse.operations_per_sec),
+                    reflink_p50: format_latency(sparse.reflink_latency.p50),
+                    reflink_p99: format_latency(sparse.reflink_latency.p99),
+                    write_p50: format_latency(sparse.write_latency.p50),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+    }
+
+    fn print_cross_fs(&self) {
+        println!("\n🌉 CROSS-FILESYSTEM FALLBACK");
+        println!("============================");
+
+        let mut rows = Vec::new();
+        for (fs_type, result) in &self.results {
+            let Some(cross_fs) = &result.cross_fs else {
+                continue;
+            };
+            // How many same-filesystem reflinks one fallback costs
+            let vs_reflink = |latency: Duration| {
+                let reflink = result.reflink_latency.p50.as_secs_f64();
+                if reflink > 0.0 {
+                    format!("{:.0}×", latency.as_secs_f64() / reflink)
+                } else {
+                    "n/a".to_string()
+                }
+            };
+            rows.push(CrossFsRow {
+                filesystem: format!("{}", fs_type),
+                method: "FICLONE".to_string(),
+                outcome: cross_fs
+                    .ficlone_error
+                    .clone()
+                    .unwrap_or_else(|| "cloned".to_string()),
+                p50: format_latency(cross_fs.ficlone_latency.p50),
+                p99: format_latency(cross_fs.ficlone_latency.p99),
+                throughput: "-".to_string(),
+                vs_reflink: vs_reflink(cross_fs.ficlone_latency.p50),
+            });
+            for fallback in &cross_fs.fallbacks {
+                let copied = fallback.error.is_none();
+                rows.push(CrossFsRow {
+                    filesystem: format!("{}", fs_type),
+                    method: fallback.strategy.clone(),
+                    outcome: fallback
+                        .error
+                        .clone()
+                        .unwrap_or_else(|| "copied".to_string()),
+                    p50: format_latency(fallback.latency.p50),
+                    p99: format_latency(fallback.latency.p99),
+                    throughput: if copied {
+                        format!("{:.1}", fallback.throughput_mb_per_sec)
+                    } else {
+                        "-".to_string()
+                    },
+                    vs_reflink: if copied {
+                        vs_reflink(fallback.latency.p50)
+                    } else {
+                        "-".to_string()
+                    },
+                });
+            }
+        }
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!(
+            "Copies are timed through fdatasync; vs Reflink divides by the sequential reflink p50."
+        );
+    }
+
+    fn print_fstrim(&self) {
+        println!("\n✂️  FSTRIM BETWEEN PHASES");
+        println!("=========================");
+
+        let rows: Vec<TrimRow> = self
+            .results
+            .iter()
+            .filter(|(_, result)| !result.fstrim.is_empty())
+            .map(|(fs_type, result)| {
+                let durations: Vec<Duration> =
+                    result.fstrim.iter().map(|trim| trim.duration).collect();
+                let latency = LatencyStats::from_samples(&durations);
+                TrimRow {
+                    filesystem: format!("{}", fs_type),
+                    trims: result.fstrim.len(),
+                    trimmed: format_bytes(result.fstrim.iter().map(|t| t.trimmed_bytes).sum()),
+                    p50: format_latency(latency.p50),
+                    max: format_latency(latency.max),
+                }
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!("Trims are untimed by the phases; what they freed up shows in the next phase.");
+    }
+
+    fn print_copy_baselines(&self) {
+        println!("\n📋 COPY BASELINES");
+        println!("=================");
+
+        let rows: Vec<CopyBaselineRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result
+                    .copy_baselines
+                    .iter()
+                    .map(move |copy| CopyBaselineRow {
+                        filesystem: format!("{}", fs_type),
+                        method: copy.strategy.clone(),
+                        p50: copy
+                            .error
+                            .clone()
+                            .unwrap_or_else(|| format_latency(copy.latency.p50)),
+                        p99: format_latency(copy.latency.p99),
+                        throughput: format!("{:.1}", copy.throughput_mb_per_sec),
+                        speedup: reflink_speedup(result, copy)
+                            .map_or_else(|| "n/a".to_string(), |x| format!("{:.0}x", x)),
+                    })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!(
+            "Copies are timed through fdatasync; speedup divides by the sequential reflink p50."
+        );
+    }
+
+    fn print_background_load(&self) {
+        println!("\n🌪️  BACKGROUND LOAD");
+        println!("===================");
+
+        let rows: Vec<BackgroundLoadRow> = self
+            .results
+            .iter()
+            .filter_map(|(fs_type, result)| {
+                let load = result.background_load.as_ref()?;
+                Some(BackgroundLoadRow {
+                    filesystem: format!("{}", fs_type),
+                    load: load.load.clone(),
+                    operations: load.operations,
+                    ops_per_sec: format!("{:.2}", load.operations_per_sec),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!("Every phase above ran alongside this load; compare with a run without it.");
+    }
+
+    fn print_soak_results(&self) {
+        println!("\n⏱️  SOAK");
+        println!("=======");
+
+        let rows: Vec<SoakRow> = self
+            .results
+            .iter()
+            .filter_map(|(fs_type, result)| {
+                let soak = result.soak.as_ref()?;
+                Some(SoakRow {
+                    filesystem: format!("{}", fs_type),
+                    duration: humantime::format_duration(Duration::from_secs(
+                        soak.duration.as_secs(),
+                    ))
+                    .to_string(),
+                    operations: soak.operations,
+                    ops_per_sec: format!("{:.2}", soak.operations_per_sec),
+                    slowest: format!("{:.2}", soak.slowest_window()),
+                    drift: format!("{:+.1}%", soak.throughput_drift() * 100.0),
+                    reflink_p99_drift: format!("{:+.1}%", soak.reflink_p99_drift() * 100.0),
+                    write_p99_drift: format!("{:+.1}%", soak.write_p99_drift() * 100.0),
+                    trend: charts::sparkline(&soak.series(|w| w.operations_per_sec)),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+        println!("Drift compares the last quarter of the run with the first.");
+    }
+
+    fn print_cpu_usage(&self) {
+        println!("\n🖥️  CPU USAGE");
+        println!("============");
+
+        let rows: Vec<CpuRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                std::iter::once(&result.sequential_cpu)
+                    .chain(&result.concurrent_results.cpu_usage)
+                    .map(move |cpu| CpuRow {
+                        filesystem: format!("{}", fs_type),
+                        phase: cpu.phase.clone(),
+                        process_cpu: format!("{:.0}%", cpu.process_cpu_percent),
+                        system_share: format!("{:.0}%", cpu.system_time_share * 100.0),
+                        machine_cpu: format!("{:.0}%", cpu.machine_cpu_percent),
+                        voluntary: cpu.voluntary_context_switches,
+                        involuntary: cpu.involuntary_context_switches,
+                    })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+    }
+
+    fn print_perf_counters(&self) {
+        println!("\n🔬 HARDWARE COUNTERS");
+        println!("===================");
+
+        let rows: Vec<PerfRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                result.perf_counters.iter().map(move |p| PerfRow {
+                    filesystem: format!("{}", fs_type),
+                    phase: p.phase.clone(),
+                    cycles: format_count(p.cycles),
+                    instructions: format_count(p.instructions),
+                    ipc: p
+                        .ipc()
+                        .map(|ipc| format!("{:.2}", ipc))
+                        .unwrap_or_else(|| "n/a".to_string()),
+                    cache_misses: format_count(p.cache_misses),
+                    context_switches: format_count(p.context_switches),
+                })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+    }
+
+    fn print_kernel_latency(&self) {
+        println!("\n🐝 KERNEL REMAP LATENCY");
+        println!("======================");
+
+        let rows: Vec<KernelLatencyRow> = self
             .results
             .iter()
-            .map(|(fs_type, result)| ComparisonRow {
-                filesystem: format!("{}", fs_type),
-                file_size: result.file_size_mb,
-                reflink_count: result.reflink_count,
-                total_time: format_duration(result.total_duration),
-                avg_time: format_duration(result.avg_reflink_time),
-                throughput: format!("{:.2}", result.throughput_mb_per_sec),
-                ops_per_sec: format!("{:.2}", result.operations_per_sec),
+            .flat_map(|(fs_type, result)| {
+                result.kernel_latency.iter().map(move |k| KernelLatencyRow {
+                    filesystem: format!("{}", fs_type),
+                    function: k.function.clone(),
+                    calls: k.calls,
+                    mean: format_latency(k.mean),
+                    total: format_duration(k.mean * k.calls as u32),
+                })
             })
             .collect();
 
-        let table = Table::new(comparison_rows);
+        let table = Table::new(rows);
         println!("{}", table);
 
-        // Concurrency analysis
-        if self.results.len() == 2 {
-            self.print_concurrency_analysis();
+        for (fs_type, result) in &self.results {
+            for k in &result.kernel_latency {
+                println!("\n{} {} (µs):", fs_type, k.function);
+                print!("{}", k.histogram);
+            }
         }
-
-        // Performance summary
-        self.print_performance_summary();
     }
 
-    fn print_concurrency_analysis(&self) {
-        println!("\n⚡ CONCURRENCY PERFORMANCE ANALYSIS");
-        println!("==================================");
+    fn print_throughput_series(&self) {
+        println!("\n📈 THROUGHPUT OVER TIME");
+        println!("======================");
 
-        let xfs_result = self
+        let rows: Vec<ThroughputSeriesRow> = self
             .results
             .iter()
-            .find(|(fs, _)| matches!(fs, FilesystemType::Xfs));
-        let btrfs_result = self
+            .flat_map(|(fs_type, result)| {
+                std::iter::once(&result.sequential_throughput)
+                    .chain(&result.concurrent_results.throughput_series)
+                    .filter(|series| !series.ops_per_sec.is_empty())
+                    .map(move |series| ThroughputSeriesRow {
+                        filesystem: format!("{}", fs_type),
+                        phase: series.phase.clone(),
+                        samples: series.ops_per_sec.len(),
+                        peak: format!("{:.1}", series.peak()),
+                        trough: format!("{:.1}", series.trough()),
+                        ratio: format!("{:.2}", series.trough() / series.peak().max(f64::EPSILON)),
+                        trend: charts::sparkline(&series.ops_per_sec),
+                    })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+    }
+
+    fn print_scaling_charts(&self) {
+        const BAR_WIDTH: usize = 40;
+
+        println!("\n📊 SCALING");
+        println!("==========");
+
+        let max_ops = self
             .results
             .iter()
-            .find(|(fs, _)| matches!(fs, FilesystemType::Btrfs));
+            .flat_map(|(_, result)| &result.concurrent_results.operations_per_sec)
+            .fold(0.0f64, |a, &b| a.max(b));
+        let thread_counts = self
+            .results
+            .first()
+            .map(|(_, result)| result.concurrent_results.thread_counts.clone())
+            .unwrap_or_default();
+
+        println!("Ops/sec by thread count:");
+        for (i, threads) in thread_counts.iter().enumerate() {
+            for (j, (fs_type, result)) in self.results.iter().enumerate() {
+                let Some(&ops) = result.concurrent_results.operations_per_sec.get(i) else {
+                    continue;
+                };
+                let label = if j == 0 {
+                    format!("{:>4}", threads)
+                } else {
+                    String::new()
+                };
+                println!(
+                    "  {:>4} {:<6} {:<width$} {:.1}",
+                    label,
+                    fs_type.to_string(),
+                    charts::bar(ops, max_ops, BAR_WIDTH),
+                    ops,
+                    width = BAR_WIDTH
+                );
+            }
+        }
 
-        if let (Some((_, xfs)), Some((_, btrfs))) = (xfs_result, btrfs_result) {
-            let thread_counts = &xfs.concurrent_results.thread_counts;
-            let concurrency_rows: Vec<ConcurrencyRow> = thread_counts
+        println!(
+            "\np99 latency trend from 1 to {} threads:",
+            thread_counts.last().unwrap_or(&1)
+        );
+        for (fs_type, result) in &self.results {
+            let p99s: Vec<f64> = result
+                .concurrent_results
+                .latencies
                 .iter()
-                .enumerate()
-                .map(|(i, &threads)| {
-                    let xfs_ops = xfs.concurrent_results.operations_per_sec[i];
-                    let btrfs_ops = btrfs.concurrent_results.operations_per_sec[i];
-                    let xfs_contention = xfs.concurrent_results.contention_ratios[i];
-                    let btrfs_contention = btrfs.concurrent_results.contention_ratios[i];
-
-                    let winner = if xfs_ops > btrfs_ops { "XFS" } else { "btrfs" };
-                    let advantage = if xfs_ops > btrfs_ops {
-                        xfs_ops / btrfs_ops
-                    } else {
-                        btrfs_ops / xfs_ops
-                    };
+                .map(|l| l.p99.as_secs_f64())
+                .collect();
+            let (first, last) = match (p99s.first(), p99s.last()) {
+                (Some(&first), Some(&last)) => (first, last),
+                _ => continue,
+            };
+            println!(
+                "  {:<6} {}  {} → {}",
+                fs_type.to_string(),
+                charts::sparkline(&p99s),
+                format_latency(Duration::from_secs_f64(first)),
+                format_latency(Duration::from_secs_f64(last))
+            );
+        }
+    }
 
-                    ConcurrencyRow {
+    fn print_worker_balance(&self) {
+        println!("\n⚖️  WORKER BALANCE");
+        println!("=================");
+
+        let rows: Vec<WorkerBalanceRow> = self
+            .results
+            .iter()
+            .flat_map(|(fs_type, result)| {
+                let concurrent = &result.concurrent_results;
+                concurrent
+                    .thread_counts
+                    .iter()
+                    .zip(&concurrent.worker_balance)
+                    .map(move |(&threads, balance)| WorkerBalanceRow {
+                        filesystem: format!("{}", fs_type),
                         threads,
-                        xfs_ops: format!("{:.1}", xfs_ops),
-                        btrfs_ops: format!("{:.1}", btrfs_ops),
-                        xfs_contention: format!("{:.2}x", xfs_contention),
-                        btrfs_contention: format!("{:.2}x", btrfs_contention),
-                        winner: format!("{} ({:.1}x faster)", winner, advantage),
-                    }
-                })
-                .collect();
+                        operations: format!(
+                            "{}-{}",
+                            balance.min_operations(),
+                            balance.max_operations()
+                        ),
+                        median_finish: format_duration(balance.median_finish),
+                        last_finish: format_duration(balance.last_finish),
+                        straggler: format!("{:.2}x", balance.straggler_ratio),
+                        idle: format!("{:.0}%", balance.idle_share * 100.0),
+                    })
+            })
+            .collect();
+
+        let table = Table::new(rows);
+        println!("{}", table);
+    }
+
+    fn print_concurrency_analysis(&self) {
+        println!("\n⚡ CONCURRENCY PERFORMANCE ANALYSIS");
+        println!("==================================");
 
-            let table = Table::new(concurrency_rows);
+        if let Some((xfs, btrfs)) = self.xfs_and_btrfs() {
+            let table = Table::new(concurrency_rows(xfs, btrfs));
             println!("{}", table);
 
             // Contention analysis
@@ -180,31 +1968,48 @@ impl ResultsReporter {
                 .find(|(fs, _)| matches!(fs, FilesystemType::Btrfs));
 
             if let (Some((_, xfs)), Some((_, btrfs))) = (xfs_result, btrfs_result) {
-                let xfs_faster_sequential = xfs.operations_per_sec > btrfs.operations_per_sec;
+                let xfs_sequential = sequential_ops_per_sec(xfs);
+                let btrfs_sequential = sequential_ops_per_sec(btrfs);
+                let xfs_faster_sequential = xfs_sequential > btrfs_sequential;
                 let sequential_ratio = if xfs_faster_sequential {
-                    xfs.operations_per_sec / btrfs.operations_per_sec
+                    xfs_sequential / btrfs_sequential
                 } else {
-                    btrfs.operations_per_sec / xfs.operations_per_sec
+                    btrfs_sequential / xfs_sequential
                 };
 
+                let sequential_p = repetition_p_value(
+                    xfs.repetitions
+                        .as_ref()
+                        .map(|r| r.operations_per_sec.samples.as_slice()),
+                    btrfs
+                        .repetitions
+                        .as_ref()
+                        .map(|r| r.operations_per_sec.samples.as_slice()),
+                );
+
                 println!("Sequential Performance:");
-                if xfs_faster_sequential {
-                    println!("  🏆 XFS is {:.1}x faster than btrfs", sequential_ratio);
+                if let Some(p) = sequential_p.insignificant() {
+                    println!(
+                        "  🤷 No significant difference between XFS and btrfs (p={:.3})",
+                        p
+                    );
+                } else if xfs_faster_sequential {
+                    println!(
+                        "  🏆 XFS is {:.1}x faster than btrfs{}",
+                        sequential_ratio,
+                        format_p_value(sequential_p)
+                    );
                 } else {
-                    println!("  🏆 btrfs is {:.1}x faster than XFS", sequential_ratio);
+                    println!(
+                        "  🏆 btrfs is {:.1}x faster than XFS{}",
+                        sequential_ratio,
+                        format_p_value(sequential_p)
+                    );
                 }
 
                 // Find best concurrent performance for each
-                let xfs_best_concurrent = xfs
-                    .concurrent_results
-                    .operations_per_sec
-                    .iter()
-                    .fold(0.0f64, |a, &b| a.max(b));
-                let btrfs_best_concurrent = btrfs
-                    .concurrent_results
-                    .operations_per_sec
-                    .iter()
-                    .fold(0.0f64, |a, &b| a.max(b));
+                let xfs_best_concurrent = best_concurrent_ops_per_sec(xfs);
+                let btrfs_best_concurrent = best_concurrent_ops_per_sec(btrfs);
 
                 let concurrent_ratio = if xfs_best_concurrent > btrfs_best_concurrent {
                     xfs_best_concurrent / btrfs_best_concurrent
@@ -212,21 +2017,60 @@ impl ResultsReporter {
                     btrfs_best_concurrent / xfs_best_concurrent
                 };
 
+                let concurrent_p = repetition_p_value(
+                    best_concurrent_samples(xfs),
+                    best_concurrent_samples(btrfs),
+                );
+
                 println!("Best Concurrent Performance:");
-                if xfs_best_concurrent > btrfs_best_concurrent {
+                if let Some(p) = concurrent_p.insignificant() {
                     println!(
-                        "  🏆 XFS is {:.1}x faster than btrfs at optimal concurrency",
-                        concurrent_ratio
+                        "  🤷 No significant difference at optimal concurrency (p={:.3})",
+                        p
+                    );
+                } else if xfs_best_concurrent > btrfs_best_concurrent {
+                    println!(
+                        "  🏆 XFS is {:.1}x faster than btrfs at optimal concurrency{}",
+                        concurrent_ratio,
+                        format_p_value(concurrent_p)
                     );
                 } else {
                     println!(
-                        "  🏆 btrfs is {:.1}x faster than XFS at optimal concurrency",
-                        concurrent_ratio
+                        "  🏆 btrfs is {:.1}x faster than XFS at optimal concurrency{}",
+                        concurrent_ratio,
+                        format_p_value(concurrent_p)
                     );
                 }
             }
         }
 
+        let copy_speedups: Vec<(FilesystemType, &str, f64)> = self
+            .results
+            .iter()
+            .filter_map(|(fs_type, result)| {
+                // Against the fastest copy, so the claim is a conservative one
+                let fastest = result
+                    .copy_baselines
+                    .iter()
+                    .filter(|copy| copy.error.is_none())
+                    .min_by_key(|copy| copy.latency.p50)?;
+                Some((
+                    *fs_type,
+                    fastest.strategy.as_str(),
+                    reflink_speedup(result, fastest)?,
+                ))
+            })
+            .collect();
+        if !copy_speedups.is_empty() {
+            println!("Reflink vs Copying:");
+            for (fs_type, strategy, speedup) in copy_speedups {
+                println!(
+                    "  ⚡ On {}, reflink is {:.0}x faster than copying with {}",
+                    fs_type, speedup, strategy
+                );
+            }
+        }
+
         println!("\nRecommendations:");
         println!(
             "  📈 Use these results to choose the optimal filesystem for your reflink workload"
@@ -235,17 +2079,210 @@ impl ResultsReporter {
         println!("  🔧 Test with your specific file sizes and access patterns");
     }
 
-    pub fn save_to_file(&self, path: &Path) -> Result<()> {
-        let comparison_results = ComparisonResults {
+    /// GitHub-flavored Markdown tables and a summary paragraph, for pasting
+    /// into issues and pull requests.
+    pub fn print_markdown(&self) {
+        println!("## Reflink + Write Benchmark Results\n");
+
+        if let Some((_, first)) = self.results.first() {
+            println!(
+                "{} reflink + write operations on {} MB files ({} source file(s)).\n",
+                first.reflink_count, first.file_size_mb, first.source_count
+            );
+        }
+
+        if let Some(summary) = self.markdown_summary() {
+            println!("{}\n", summary);
+        }
+
+        println!("### Sequential\n");
+        println!(
+            "{}\n",
+            Table::new(self.comparison_rows()).with(Style::markdown())
+        );
+
+        println!("### Latency Percentiles\n");
+        println!(
+            "{}\n",
+            Table::new(self.latency_rows()).with(Style::markdown())
+        );
+
+        if let Some((xfs, btrfs)) = self.xfs_and_btrfs() {
+            println!("### Concurrency\n");
+            println!(
+                "{}\n",
+                Table::new(concurrency_rows(xfs, btrfs)).with(Style::markdown())
+            );
+        }
+
+        let workload_rows = self.workload_rows("<br>");
+        if !workload_rows.is_empty() {
+            println!("### Workloads\n");
+            println!("{}\n", Table::new(workload_rows).with(Style::markdown()));
+        }
+    }
+
+    /// One paragraph naming the faster filesystem sequentially and at its
+    /// best concurrency, hedged when repetitions show no significant
+    /// difference.
+    fn markdown_summary(&self) -> Option<String> {
+        let (xfs, btrfs) = self.xfs_and_btrfs()?;
+
+        let describe = |setting: &str, xfs_ops: f64, btrfs_ops: f64, p: Significance| {
+            if let Some(p) = p.insignificant() {
+                return format!(
+                    "{} there is no significant difference ({:.1} vs {:.1} ops/sec, p={:.3}).",
+                    setting, xfs_ops, btrfs_ops, p
+                );
+            }
+            let (winner, loser, ratio) = if xfs_ops > btrfs_ops {
+                ("XFS", "btrfs", xfs_ops / btrfs_ops)
+            } else {
+                ("btrfs", "XFS", btrfs_ops / xfs_ops)
+            };
+            format!(
+                "{} **{}** is {:.1}x faster than {} ({:.1} vs {:.1} ops/sec{}).",
+                setting,
+                winner,
+                ratio,
+                loser,
+                xfs_ops.max(btrfs_ops),
+                xfs_ops.min(btrfs_ops),
+                p.note()
+                    .map(|note| format!(", {}", note))
+                    .unwrap_or_default()
+            )
+        };
+
+        let sequential = describe(
+            "Sequentially,",
+            sequential_ops_per_sec(xfs),
+            sequential_ops_per_sec(btrfs),
+            repetition_p_value(
+                xfs.repetitions
+                    .as_ref()
+                    .map(|r| r.operations_per_sec.samples.as_slice()),
+                btrfs
+                    .repetitions
+                    .as_ref()
+                    .map(|r| r.operations_per_sec.samples.as_slice()),
+            ),
+        );
+        let concurrent = describe(
+            "At each filesystem's best concurrency,",
+            best_concurrent_ops_per_sec(xfs),
+            best_concurrent_ops_per_sec(btrfs),
+            repetition_p_value(best_concurrent_samples(xfs), best_concurrent_samples(btrfs)),
+        );
+
+        Some(format!("{} {}", sequential, concurrent))
+    }
+
+    /// Prints one libtest-style `bench:` line per measured operation, so
+    /// existing benchmark-regression tooling can track reflink numbers. The
+    /// `+/-` spread is p90 minus p50 where percentiles are available.
+    pub fn print_bencher(&self) {
+        for (fs_type, result) in &self.results {
+            let fs = format!("{:?}", fs_type).to_lowercase();
+            let per_op = |duration: Duration, count: u32| duration / count.max(1);
+
+            print_bench_line(
+                &format!("{}/sequential/reflink_write", fs),
+                per_op(result.total_duration, result.reflink_count),
+                Duration::ZERO,
+            );
+            for (operation, stats) in [
+                ("reflink", &result.reflink_latency),
+                ("cow_write", &result.write_latency),
+            ] {
+                print_bench_line(
+                    &format!("{}/sequential/{}", fs, operation),
+                    stats.mean,
+                    stats.p90.saturating_sub(stats.p50),
+                );
+            }
+
+            let concurrent = &result.concurrent_results;
+            for (i, threads) in concurrent.thread_counts.iter().enumerate() {
+                let latency = &concurrent.latencies[i];
+                print_bench_line(
+                    &format!("{}/concurrent/{}_threads", fs, threads),
+                    per_op(concurrent.durations[i], result.reflink_count),
+                    latency.p90.saturating_sub(latency.p50),
+                );
+            }
+
+            for w in &result.write_size_results {
+                print_bench_line(
+                    &format!("{}/write_size/{}", fs, w.write_size_bytes),
+                    w.avg_op_time,
+                    Duration::ZERO,
+                );
+            }
+
+            for e in &result.source_extent_results {
+                print_bench_line(
+                    &format!("{}/source_extents/{}", fs, e.extent_size_bytes),
+                    e.clone_latency.p50,
+                    e.clone_latency.p90.saturating_sub(e.clone_latency.p50),
+                );
+            }
+
+            for w in &result.workload_results {
+                print_bench_line(
+                    &format!("{}/workload/{}", fs, w.workload),
+                    w.avg_op_time,
+                    Duration::ZERO,
+                );
+            }
+        }
+    }
+
+    fn xfs_and_btrfs(&self) -> Option<(&BenchmarkResult, &BenchmarkResult)> {
+        let xfs = self
+            .results
+            .iter()
+            .find(|(fs, _)| matches!(fs, FilesystemType::Xfs))?;
+        let btrfs = self
+            .results
+            .iter()
+            .find(|(fs, _)| matches!(fs, FilesystemType::Btrfs))?;
+        Some((&xfs.1, &btrfs.1))
+    }
+
+    /// Pushes the headline metrics to a Prometheus pushgateway.
+    pub fn push_to_gateway(&self, gateway: &str, job: &str) -> Result<()> {
+        prometheus::push(gateway, job, &prometheus::render(&self.results))
+    }
+
+    pub fn comparison_results(&self) -> ComparisonResults {
+        ComparisonResults {
+            schema_version: SCHEMA_VERSION,
+            scenario: self.scenario.clone(),
+            seed: self.seed,
+            environment: self.environment.clone(),
             results: self
                 .results
                 .iter()
                 .map(|(fs_type, result)| (format!("{}", fs_type), (*result).clone()))
                 .collect::<Vec<_>>(),
             timestamp: chrono::Utc::now().to_rfc3339(),
-        };
+        }
+    }
+
+    pub fn print_json(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.comparison_results())
+            .context("Failed to serialize results")?;
+        println!("{}", json);
+        Ok(())
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).context("Failed to create results directory")?;
+        }
 
-        let json = serde_json::to_string_pretty(&comparison_results)
+        let json = serde_json::to_string_pretty(&self.comparison_results())
             .context("Failed to serialize results")?;
 
         std::fs::write(path, json).context("Failed to write results file")?;
@@ -254,6 +2291,333 @@ impl ResultsReporter {
     }
 }
 
+/// Sequential results of one filesystem at one point of a parameter sweep.
+pub struct SweepCell {
+    /// Scenario the sweep was expanded from
+    pub base: Option<String>,
+    pub filesystem: FilesystemType,
+    pub file_size_mb: u64,
+    pub reflink_count: u32,
+    pub operations_per_sec: f64,
+    pub throughput_mb_per_sec: f64,
+}
+
+/// Sequential results of one filesystem configuration in a
+/// `--btrfs-presets` comparison.
+#[derive(Debug, Clone)]
+pub struct PresetCell {
+    /// Scenario the presets were expanded from
+    pub base: Option<String>,
+    pub filesystem: FilesystemType,
+    /// `None` for the scenario's own configuration
+    pub preset: Option<String>,
+    pub mount_options: String,
+    pub operations_per_sec: f64,
+    pub throughput_mb_per_sec: f64,
+    pub reflink_p99: Duration,
+    pub write_p99: Duration,
+}
+
+/// Prints each scenario's filesystems next to its presets, with ops/sec
+/// relative to the same filesystem as configured by the scenario.
+pub fn print_presets(cells: &[PresetCell], markdown: bool) {
+    if cells.is_empty() {
+        return;
+    }
+
+    if markdown {
+        println!("\n## Mount Presets\n");
+    } else {
+        println!("\n🎛️  MOUNT PRESETS");
+        println!("================");
+    }
+
+    let mut bases: Vec<Option<&str>> = Vec::new();
+    for cell in cells {
+        if !bases.contains(&cell.base.as_deref()) {
+            bases.push(cell.base.as_deref());
+        }
+    }
+
+    for base in bases {
+        let group: Vec<&PresetCell> = cells.iter().filter(|c| c.base.as_deref() == base).collect();
+        let default_ops = |fs: FilesystemType| {
+            group
+                .iter()
+                .find(|c| c.preset.is_none() && c.filesystem == fs)
+                .map(|c| c.operations_per_sec)
+        };
+
+        if let Some(base) = base {
+            if markdown {
+                println!("### {}\n", base);
+            } else {
+                println!("\n{}", base);
+            }
+        }
+
+        let rows: Vec<PresetRow> = group
+            .iter()
+            .map(|c| PresetRow {
+                configuration: match &c.preset {
+                    Some(preset) => format!("{} {}", c.filesystem, preset),
+                    None => c.filesystem.to_string(),
+                },
+                mount_options: if c.mount_options.is_empty() {
+                    "-".to_string()
+                } else {
+                    c.mount_options.clone()
+                },
+                ops_per_sec: format!("{:.2}", c.operations_per_sec),
+                throughput: format!("{:.2}", c.throughput_mb_per_sec),
+                reflink_p99: format_latency(c.reflink_p99),
+                write_p99: format_latency(c.write_p99),
+                vs_default: match default_ops(c.filesystem) {
+                    Some(ops) if ops > 0.0 && c.preset.is_some() => {
+                        format!("{:+.1}%", (c.operations_per_sec / ops - 1.0) * 100.0)
+                    }
+                    _ => "-".to_string(),
+                },
+            })
+            .collect();
+
+        let mut table = Table::new(rows);
+        if markdown {
+            table.with(Style::markdown());
+            println!("{}\n", table);
+        } else {
+            println!("{}", table);
+        }
+    }
+}
+
+/// Prints a file-size × reflink-count grid of sequential throughput for
+/// each filesystem of each swept scenario.
+pub fn print_sweep(cells: &[SweepCell], markdown: bool) {
+    if cells.is_empty() {
+        return;
+    }
+
+    if markdown {
+        println!("\n## Parameter Sweep\n");
+    } else {
+        println!("\n🧮 PARAMETER SWEEP");
+        println!("==================");
+    }
+
+    let mut groups: Vec<(Option<&str>, FilesystemType)> = Vec::new();
+    for cell in cells {
+        let key = (cell.base.as_deref(), cell.filesystem);
+        if !groups.contains(&key) {
+            groups.push(key);
+        }
+    }
+
+    for (base, filesystem) in groups {
+        let group: Vec<&SweepCell> = cells
+            .iter()
+            .filter(|c| c.base.as_deref() == base && c.filesystem == filesystem)
+            .collect();
+        let mut sizes: Vec<u64> = group.iter().map(|c| c.file_size_mb).collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+        let mut counts: Vec<u32> = group.iter().map(|c| c.reflink_count).collect();
+        counts.sort_unstable();
+        counts.dedup();
+
+        let title = match base {
+            Some(base) => format!("{} ({})", filesystem, base),
+            None => filesystem.to_string(),
+        };
+        if markdown {
+            println!("### {}\n", title);
+        } else {
+            println!(
+                "\n{} — ops/sec (MB/s) by file size and reflink count",
+                title
+            );
+        }
+
+        let mut builder = Builder::default();
+        let mut header = vec!["File size".to_string()];
+        header.extend(counts.iter().map(|count| format!("×{}", count)));
+        builder.push_record(header);
+
+        for size in &sizes {
+            let mut row = vec![format!("{} MB", size)];
+            for count in &counts {
+                let cell = group
+                    .iter()
+                    .find(|c| c.file_size_mb == *size && c.reflink_count == *count);
+                row.push(match cell {
+                    Some(c) => format!(
+                        "{:.0} ({:.1})",
+                        c.operations_per_sec, c.throughput_mb_per_sec
+                    ),
+                    None => "-".to_string(),
+                });
+            }
+            builder.push_record(row);
+        }
+
+        let mut table = builder.build();
+        if markdown {
+            table.with(Style::markdown());
+            println!("{}\n", table);
+        } else {
+            println!("{}", table);
+        }
+    }
+}
+
+fn print_bench_line(name: &str, per_iteration: Duration, spread: Duration) {
+    println!(
+        "test {} ... bench: {:>15} ns/iter (+/- {})",
+        name,
+        format_thousands(per_iteration.as_nanos()),
+        format_thousands(spread.as_nanos())
+    );
+}
+
+/// Formats an integer with comma thousands separators, as libtest does.
+fn format_thousands(value: u128) -> String {
+    let digits = value.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+fn concurrency_rows(xfs: &BenchmarkResult, btrfs: &BenchmarkResult) -> Vec<ConcurrencyRow> {
+    xfs.concurrent_results
+        .thread_counts
+        .iter()
+        .enumerate()
+        .map(|(i, &threads)| {
+            let xfs_ops = xfs.concurrent_results.operations_per_sec[i];
+            let btrfs_ops = btrfs.concurrent_results.operations_per_sec[i];
+            let xfs_contention = xfs.concurrent_results.contention_ratios[i];
+            let btrfs_contention = btrfs.concurrent_results.contention_ratios[i];
+
+            let winner = if xfs_ops > btrfs_ops { "XFS" } else { "btrfs" };
+            let advantage = if xfs_ops > btrfs_ops {
+                xfs_ops / btrfs_ops
+            } else {
+                btrfs_ops / xfs_ops
+            };
+
+            ConcurrencyRow {
+                threads,
+                xfs_ops: format!("{:.1}", xfs_ops),
+                btrfs_ops: format!("{:.1}", btrfs_ops),
+                xfs_contention: format!("{:.2}x", xfs_contention),
+                btrfs_contention: format!("{:.2}x", btrfs_contention),
+                winner: format!("{} ({:.1}x faster)", winner, advantage),
+            }
+        })
+        .collect()
+}
+
+/// How many times longer `copy` took than a sequential reflink, by p50.
+fn reflink_speedup(result: &BenchmarkResult, copy: &CopyResult) -> Option<f64> {
+    let reflink = result.reflink_latency.p50.as_secs_f64();
+    (copy.error.is_none() && reflink > 0.0).then(|| copy.latency.p50.as_secs_f64() / reflink)
+}
+
+/// Sequential ops/sec, averaged over repetitions when there were any.
+fn sequential_ops_per_sec(result: &BenchmarkResult) -> f64 {
+    result
+        .repetitions
+        .as_ref()
+        .map(|r| r.operations_per_sec.mean)
+        .unwrap_or(result.operations_per_sec)
+}
+
+/// Best ops/sec across thread counts, averaged over repetitions when there
+/// were any.
+fn best_concurrent_ops_per_sec(result: &BenchmarkResult) -> f64 {
+    match &result.repetitions {
+        Some(repetitions) => repetitions
+            .concurrent_operations_per_sec
+            .iter()
+            .fold(0.0f64, |a, b| a.max(b.mean)),
+        None => result
+            .concurrent_results
+            .operations_per_sec
+            .iter()
+            .fold(0.0f64, |a, &b| a.max(b)),
+    }
+}
+
+/// Samples at the thread count with the best mean ops/sec.
+fn best_concurrent_samples(result: &BenchmarkResult) -> Option<&[f64]> {
+    result
+        .repetitions
+        .as_ref()?
+        .concurrent_operations_per_sec
+        .iter()
+        .max_by(|a, b| a.mean.total_cmp(&b.mean))
+        .map(|summary| summary.samples.as_slice())
+}
+
+/// What repeated runs say about the difference between two filesystems.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Significance {
+    /// Either side wasn't repeated
+    Untested,
+    /// Repeated, but too few times for any difference to be significant
+    TooFewRepeats,
+    /// Mann-Whitney U p-value
+    Tested(f64),
+}
+
+impl Significance {
+    /// The p-value, when it says the filesystems can't be told apart.
+    fn insignificant(self) -> Option<f64> {
+        match self {
+            Significance::Tested(p) if p >= SIGNIFICANCE_LEVEL => Some(p),
+            _ => None,
+        }
+    }
+
+    /// What to say after a winner, e.g. `p=0.012`.
+    fn note(self) -> Option<String> {
+        match self {
+            Significance::Untested => None,
+            Significance::TooFewRepeats => Some(format!(
+                "significance needs --repeat {} or more",
+                MIN_SIGNIFICANT_REPEATS
+            )),
+            Significance::Tested(p) => Some(format!("p={:.3}", p)),
+        }
+    }
+}
+
+/// Mann-Whitney U test between two filesystems' repeated-run samples.
+fn repetition_p_value(a: Option<&[f64]>, b: Option<&[f64]>) -> Significance {
+    let (Some(a), Some(b)) = (a, b) else {
+        return Significance::Untested;
+    };
+    if a.len() < 2 || b.len() < 2 {
+        return Significance::Untested;
+    }
+    if a.len() < MIN_SIGNIFICANT_REPEATS || b.len() < MIN_SIGNIFICANT_REPEATS {
+        return Significance::TooFewRepeats;
+    }
+    stats::mann_whitney_p_value(a, b).map_or(Significance::Untested, Significance::Tested)
+}
+
+fn format_p_value(significance: Significance) -> String {
+    significance
+        .note()
+        .map(|note| format!(" ({})", note))
+        .unwrap_or_default()
+}
+
 fn format_duration(duration: Duration) -> String {
     let total_ms = duration.as_millis();
     if total_ms < 1000 {
@@ -266,3 +2630,49 @@ fn format_duration(duration: Duration) -> String {
         format!("{}m{:.1}s", minutes, seconds)
     }
 }
+
+fn format_latency(duration: Duration) -> String {
+    let total_us = duration.as_micros();
+    if total_us < 1000 {
+        format!("{}µs", total_us)
+    } else {
+        format_duration(duration)
+    }
+}
+
+/// Formats a large event count with a decimal suffix, e.g. `1.23G`.
+fn format_count(count: Option<u64>) -> String {
+    let Some(count) = count else {
+        return "n/a".to_string();
+    };
+
+    const UNITS: [&str; 4] = ["", "K", "M", "G"];
+    let mut value = count as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{}", count)
+    } else {
+        format!("{:.2}{}", value, UNITS[unit])
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if value.fract() == 0.0 {
+        format!("{}{}", value, UNITS[unit])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
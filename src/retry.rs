@@ -0,0 +1,103 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+/// How often to retry a setup or cleanup step that can fail transiently,
+/// such as attaching a loop device while another process grabs the same
+/// free one, or unmounting while the kernel is still finishing I/O
+/// (`EBUSY`). The delay doubles after each failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Tries in total, including the first
+    pub attempts: u32,
+    /// Wait before the first retry
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            delay: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Runs `op` until it succeeds, fails with an error `transient` rejects,
+    /// or runs out of attempts, returning its last result.
+    pub fn run<T, E: Display>(
+        &self,
+        what: &str,
+        mut op: impl FnMut() -> Result<T, E>,
+        transient: impl Fn(&E) -> bool,
+    ) -> Result<T, E> {
+        let mut delay = self.delay;
+        let mut attempt = 1;
+        loop {
+            match op() {
+                Err(e) if attempt < self.attempts && transient(&e) => {
+                    println!(
+                        "⏳ {} failed ({}); retrying in {}",
+                        what,
+                        e,
+                        humantime::format_duration(delay)
+                    );
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_transient_errors_until_attempts_run_out() {
+        let policy = RetryPolicy {
+            attempts: 3,
+            delay: Duration::ZERO,
+        };
+
+        let mut calls = 0;
+        let result: Result<(), &str> = policy.run(
+            "op",
+            || {
+                calls += 1;
+                Err("busy")
+            },
+            |e| *e == "busy",
+        );
+        assert_eq!((result, calls), (Err("busy"), 3));
+
+        let mut calls = 0;
+        let result: Result<(), &str> = policy.run(
+            "op",
+            || {
+                calls += 1;
+                Err("invalid")
+            },
+            |e| *e == "busy",
+        );
+        assert_eq!((result, calls), (Err("invalid"), 1));
+
+        let mut calls = 0;
+        let result = policy.run(
+            "op",
+            || {
+                calls += 1;
+                if calls < 2 {
+                    Err("busy")
+                } else {
+                    Ok(calls)
+                }
+            },
+            |e| *e == "busy",
+        );
+        assert_eq!(result, Ok(2));
+    }
+}
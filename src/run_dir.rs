@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::lock::{DirLock, LOCK_FILE_NAME};
+
+/// Directory under the working directory that holds one directory per run.
+pub const RUNS_DIR_NAME: &str = "reflink-bench";
+
+/// Root-owned directory in a run directory that `mount-helper` creates its
+/// images and mount points in, so the run's owner can't replace them.
+pub const HELPER_DIR_NAME: &str = "helper";
+
+/// A run's own directory, `<workdir>/reflink-bench/<run-id>`, holding its
+/// images and mount points so concurrent runs never share paths. It stays
+/// locked while the value lives, which tells `cleanup` the run is still
+/// going, and is removed on drop unless something (a `--keep` filesystem)
+/// is left in it.
+pub struct RunDir {
+    id: String,
+    path: PathBuf,
+    lock: Option<DirLock>,
+}
+
+impl RunDir {
+    /// Creates and locks a directory for a new run under `workdir`.
+    pub fn create(workdir: &Path) -> Result<Self> {
+        // Timestamp for people, pid to keep same-second runs apart
+        let id = format!(
+            "{}-{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            std::process::id()
+        );
+        let path = workdir.join(RUNS_DIR_NAME).join(&id);
+        std::fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create run directory {}", path.display()))?;
+        let lock = DirLock::acquire(&path)?;
+
+        Ok(Self {
+            id,
+            path,
+            lock: Some(lock),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for RunDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(self.path.join(LOCK_FILE_NAME));
+        self.lock.take();
+        // Fails, leaving it for `cleanup`, unless the run left nothing behind
+        let _ = std::fs::remove_dir(&self.path);
+    }
+}
+
+/// Every run directory under `workdir`, oldest first.
+pub fn list(workdir: &Path) -> Result<Vec<PathBuf>> {
+    let runs_dir = workdir.join(RUNS_DIR_NAME);
+    if !runs_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut runs = Vec::new();
+    for entry in std::fs::read_dir(&runs_dir)
+        .with_context(|| format!("Failed to read {}", runs_dir.display()))?
+    {
+        let entry = entry.context("Failed to read directory entry")?;
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            runs.push(entry.path());
+        }
+    }
+    runs.sort();
+    Ok(runs)
+}
+
+/// Where a run under `workdir` would put its files, for `--dry-run`.
+pub fn placeholder(workdir: &Path) -> PathBuf {
+    workdir.join(RUNS_DIR_NAME).join("<run-id>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_dirs_are_listed_and_removed_when_empty() {
+        let workdir =
+            std::env::temp_dir().join(format!("reflink-bench-run-dir-test-{}", std::process::id()));
+
+        let run = RunDir::create(&workdir).unwrap();
+        assert!(run.path().starts_with(workdir.join(RUNS_DIR_NAME)));
+        assert!(run.id().ends_with(&std::process::id().to_string()));
+        assert_eq!(list(&workdir).unwrap(), vec![run.path().to_path_buf()]);
+
+        drop(run);
+        assert!(list(&workdir).unwrap().is_empty());
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+}
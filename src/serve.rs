@@ -0,0 +1,415 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::compare;
+use crate::history;
+use crate::results::ComparisonResults;
+
+/// Largest request head we'll read; everything served is a plain GET.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+const CHART_WIDTH: f64 = 720.0;
+const CHART_HEIGHT: f64 = 200.0;
+
+/// Series colours, assigned to filesystems in order.
+const COLORS: [&str; 4] = ["#1f77b4", "#d62728", "#2ca02c", "#9467bd"];
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;max-width:60em}\
+table{border-collapse:collapse;margin:1em 0}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:right}\
+th:first-child,td:first-child{text-align:left}\
+.better{color:#2ca02c}.worse{color:#d62728}";
+
+/// Where stored runs are read from. Both are re-read on every request so
+/// runs added by a nightly job show up without restarting the server.
+pub struct RunStore {
+    pub db: Option<PathBuf>,
+    pub results_dir: Option<PathBuf>,
+}
+
+struct StoredRun {
+    id: String,
+    results: ComparisonResults,
+}
+
+impl RunStore {
+    fn load(&self) -> Result<Vec<StoredRun>> {
+        let mut runs = Vec::new();
+
+        if let Some(db) = &self.db {
+            for (id, results) in history::load_runs(db)? {
+                runs.push(StoredRun {
+                    id: format!("db-{}", id),
+                    results,
+                });
+            }
+        }
+
+        if let Some(dir) = &self.results_dir {
+            let entries = std::fs::read_dir(dir)
+                .with_context(|| format!("Failed to read results directory {}", dir.display()))?;
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                // Skip stray JSON that isn't a results file rather than failing the page
+                let Ok(results) = compare::load_results(&path) else {
+                    continue;
+                };
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                runs.push(StoredRun {
+                    id: name.into_owned(),
+                    results,
+                });
+            }
+        }
+
+        runs.sort_by(|a, b| (&a.results.timestamp, &a.id).cmp(&(&b.results.timestamp, &b.id)));
+        Ok(runs)
+    }
+}
+
+pub async fn serve(store: RunStore, listen: &str) -> Result<()> {
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    println!("🌐 Serving results on http://{}", listener.local_addr()?);
+
+    let store = Arc::new(store);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let store = store.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &store).await {
+                eprintln!("⚠️  Request failed: {:#}", e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, store: &RunStore) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() + n > MAX_REQUEST_BYTES {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let (method, target) = (parts.next(), parts.next().unwrap_or("/"));
+
+    let (status, body) = if method != Some("GET") {
+        (
+            "405 Method Not Allowed",
+            page("Error", "<p>Only GET is supported.</p>"),
+        )
+    } else {
+        match route(store, target) {
+            Ok(Some(body)) => ("200 OK", body),
+            Ok(None) => (
+                "404 Not Found",
+                page("Not found", "<p>No such page or run.</p>"),
+            ),
+            Err(e) => (
+                "500 Internal Server Error",
+                page(
+                    "Error",
+                    &format!("<pre>{}</pre>", escape(&format!("{:#}", e))),
+                ),
+            ),
+        }
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+fn route(store: &RunStore, target: &str) -> Result<Option<String>> {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params: BTreeMap<String, String> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (percent_decode(k), percent_decode(v)))
+        .collect();
+
+    let runs = store.load()?;
+    let find = |key: &str| {
+        params
+            .get(key)
+            .and_then(|id| runs.iter().find(|run| run.id == *id))
+    };
+
+    Ok(match path {
+        "/" => Some(index_page(&runs)),
+        "/run" => find("id").map(run_page),
+        "/compare" => match (find("old"), find("new")) {
+            (Some(old), Some(new)) => Some(compare_page(old, new)),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn index_page(runs: &[StoredRun]) -> String {
+    let mut html = String::new();
+
+    if runs.is_empty() {
+        html.push_str("<p>No stored runs yet.</p>");
+        return page("reflink-bench results", &html);
+    }
+
+    html.push_str("<h2>Sequential ops/sec</h2>");
+    html.push_str(&trend_chart(runs));
+
+    if runs.len() >= 2 {
+        html.push_str("<h2>Compare</h2><form action=\"/compare\">");
+        for (name, default) in [("old", runs.len() - 2), ("new", runs.len() - 1)] {
+            let _ = write!(html, "<select name=\"{}\">", name);
+            for (i, run) in runs.iter().enumerate() {
+                let _ = write!(
+                    html,
+                    "<option value=\"{}\"{}>{}</option>",
+                    escape(&run.id),
+                    if i == default { " selected" } else { "" },
+                    escape(&run_label(run)),
+                );
+            }
+            html.push_str("</select> ");
+        }
+        html.push_str("<button>Compare</button></form>");
+    }
+
+    let filesystems = filesystem_names(runs);
+    html.push_str("<h2>Runs</h2><table><tr><th>Run</th><th>Kernel</th><th>Version</th>");
+    for fs in &filesystems {
+        let _ = write!(html, "<th>{} ops/sec</th>", escape(fs));
+    }
+    html.push_str("</tr>");
+    for run in runs.iter().rev() {
+        let env = &run.results.environment;
+        let _ = write!(
+            html,
+            "<tr><td><a href=\"/run?id={}\">{}</a></td><td>{}</td><td>{}</td>",
+            percent_encode(&run.id),
+            escape(&run.results.timestamp),
+            escape(&env.kernel_version),
+            escape(&env.tool_version),
+        );
+        for fs in &filesystems {
+            let ops = sequential_ops(run, fs)
+                .map(|ops| format!("{:.0}", ops))
+                .unwrap_or_default();
+            let _ = write!(html, "<td>{}</td>", ops);
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+
+    page("reflink-bench results", &html)
+}
+
+fn run_page(run: &StoredRun) -> String {
+    let env = &run.results.environment;
+    let mut html = format!(
+        "<p><a href=\"/\">All runs</a></p><table>\
+         <tr><td>Tool version</td><td>{}</td></tr>\
+         <tr><td>Kernel</td><td>{}</td></tr>\
+         <tr><td>CPU</td><td>{} ({} cores)</td></tr>\
+         <tr><td>Memory</td><td>{:.1} GiB</td></tr></table>",
+        escape(&env.tool_version),
+        escape(&env.kernel_version),
+        escape(&env.cpu_model),
+        env.cpu_count,
+        env.memory_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+    );
+
+    for (filesystem, result) in &run.results.results {
+        let _ = write!(
+            html,
+            "<h2>{}</h2><table><tr><th>Metric</th><th>Value</th></tr>",
+            escape(filesystem)
+        );
+        for (metric, value, _) in compare::key_metrics(result) {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td>{:.2}</td></tr>",
+                escape(&metric),
+                value
+            );
+        }
+        html.push_str("</table>");
+    }
+
+    page(&run_label(run), &html)
+}
+
+fn compare_page(old: &StoredRun, new: &StoredRun) -> String {
+    let mut html = format!(
+        "<p><a href=\"/\">All runs</a></p><p>{} → {}</p>\
+         <table><tr><th>Filesystem</th><th>Metric</th><th>Old</th><th>New</th><th>Change</th></tr>",
+        escape(&run_label(old)),
+        escape(&run_label(new)),
+    );
+
+    for delta in compare::metric_deltas(&old.results, &new.results) {
+        let class = if delta.regression_percent() > 0.0 {
+            "worse"
+        } else {
+            "better"
+        };
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{:.2}</td><td class=\"{}\">{:+.1}%</td></tr>",
+            escape(&delta.filesystem),
+            escape(&delta.metric),
+            delta.old,
+            delta.new,
+            class,
+            delta.change_percent(),
+        );
+    }
+    html.push_str("</table>");
+
+    page("Comparison", &html)
+}
+
+/// Inline SVG line chart of sequential ops/sec per filesystem across runs.
+fn trend_chart(runs: &[StoredRun]) -> String {
+    let filesystems = filesystem_names(runs);
+    let max = runs
+        .iter()
+        .flat_map(|run| filesystems.iter().filter_map(|fs| sequential_ops(run, fs)))
+        .fold(0.0, f64::max)
+        .max(f64::EPSILON);
+    let step = CHART_WIDTH / (runs.len().max(2) - 1) as f64;
+
+    let mut svg = format!(
+        "<svg width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"none\" stroke=\"#ccc\"/>",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT
+    );
+
+    for (fs, color) in filesystems.iter().zip(COLORS.iter().cycle()) {
+        let points: Vec<String> = runs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, run)| {
+                let ops = sequential_ops(run, fs)?;
+                let x = i as f64 * step;
+                let y = CHART_HEIGHT - ops / max * (CHART_HEIGHT - 10.0);
+                Some(format!("{:.1},{:.1}", x, y))
+            })
+            .collect();
+        let _ = write!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>",
+            color,
+            points.join(" ")
+        );
+    }
+    svg.push_str("</svg><p>");
+
+    for (fs, color) in filesystems.iter().zip(COLORS.iter().cycle()) {
+        let _ = write!(
+            svg,
+            "<span style=\"color:{}\">■ {}</span> ",
+            color,
+            escape(fs)
+        );
+    }
+    let _ = write!(svg, "(max {:.0} ops/sec)</p>", max);
+    svg
+}
+
+fn sequential_ops(run: &StoredRun, filesystem: &str) -> Option<f64> {
+    run.results
+        .results
+        .iter()
+        .find(|(fs, _)| fs == filesystem)
+        .map(|(_, result)| result.operations_per_sec)
+}
+
+fn filesystem_names(runs: &[StoredRun]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (fs, _) in runs.iter().flat_map(|run| &run.results.results) {
+        if !names.contains(fs) {
+            names.push(fs.clone());
+        }
+    }
+    names
+}
+
+fn run_label(run: &StoredRun) -> String {
+    format!("{} ({})", run.results.timestamp, run.id)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title>\
+         <style>{STYLE}</style></head><body><h1>{title}</h1>{body}</body></html>",
+        title = escape(title),
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        decoded.push(b);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,34 @@
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::signal::unix::{signal, SignalKind};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Waits for SIGINT or SIGTERM and returns its name.
+///
+/// Benchmark workers check [`requested`] and stop early, and dropping a run
+/// drops its [`FilesystemManager`](crate::filesystem::FilesystemManager)s,
+/// which tear their filesystems down. The signals' default action is
+/// restored once one arrives, so a second Ctrl-C kills a cleanup that hangs.
+pub async fn wait_for_signal() -> Result<&'static str> {
+    let mut interrupt =
+        signal(SignalKind::interrupt()).context("Failed to install SIGINT handler")?;
+    let mut terminate =
+        signal(SignalKind::terminate()).context("Failed to install SIGTERM handler")?;
+
+    let name = tokio::select! {
+        _ = interrupt.recv() => "SIGINT",
+        _ = terminate.recv() => "SIGTERM",
+    };
+    REQUESTED.store(true, Ordering::Relaxed);
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+        libc::signal(libc::SIGTERM, libc::SIG_DFL);
+    }
+    Ok(name)
+}
+
+/// Whether the run has been interrupted and should stop starting new work.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
@@ -0,0 +1,250 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::benchmark::{BenchmarkRunner, DEFAULT_WRITE_SIZE, WRITE_PATTERN};
+use crate::filesystem;
+use crate::ioctl;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpaceReport {
+    pub clones: u32,
+    /// Sum of the clones' file sizes, i.e. what a plain copy would use
+    pub apparent_bytes: u64,
+    /// Free-space consumed by creating the clones
+    pub clone_used_bytes: u64,
+    /// Free-space consumed by the CoW writes into the clones
+    pub write_used_bytes: u64,
+    /// Bytes shared with other files, where the filesystem can report it
+    pub shared_bytes: Option<u64>,
+    /// Bytes exclusive to the clones, where the filesystem can report it
+    pub exclusive_bytes: Option<u64>,
+}
+
+impl SpaceReport {
+    pub fn actual_used_bytes(&self) -> u64 {
+        self.clone_used_bytes + self.write_used_bytes
+    }
+
+    /// How many times smaller the clones are on disk than full copies.
+    pub fn space_efficiency(&self) -> f64 {
+        self.apparent_bytes as f64 / self.actual_used_bytes().max(1) as f64
+    }
+}
+
+/// Where a phase's files' bytes actually live, from their FIEMAP extent
+/// maps, like `compsize` reports for btrfs but on any filesystem with
+/// FIEMAP. Shows what reflinks and dedup really saved rather than assuming
+/// every clone is free.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtentUsage {
+    pub phase: String,
+    pub files: u32,
+    /// Sum of the files' mapped extents, i.e. what they'd take unshared
+    pub referenced_bytes: u64,
+    /// Referenced bytes in extents the filesystem flags as shared
+    pub shared_bytes: u64,
+    /// Referenced bytes in extents only one file maps
+    pub exclusive_bytes: u64,
+    /// Distinct physical bytes behind all the files' extents
+    pub disk_bytes: u64,
+}
+
+impl ExtentUsage {
+    /// Fraction of the referenced bytes that sharing saved.
+    pub fn savings(&self) -> f64 {
+        1.0 - self.disk_bytes as f64 / self.referenced_bytes.max(1) as f64
+    }
+}
+
+/// Adds up the extent maps of `files`.
+///
+/// This is blocking and should be called from `spawn_blocking`.
+pub fn extent_usage(phase: &str, files: &[PathBuf]) -> Result<ExtentUsage> {
+    let mut usage = ExtentUsage {
+        phase: phase.to_string(),
+        files: files.len() as u32,
+        ..Default::default()
+    };
+    let mut physical = Vec::new();
+    for path in files {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let extents =
+            ioctl::fiemap(&file).with_context(|| format!("FIEMAP failed on {}", path.display()))?;
+        for extent in extents {
+            usage.referenced_bytes += extent.length;
+            if extent.shared {
+                usage.shared_bytes += extent.length;
+            } else {
+                usage.exclusive_bytes += extent.length;
+            }
+            physical.push((extent.physical, extent.physical + extent.length));
+        }
+    }
+    usage.disk_bytes = merged_length(physical);
+    Ok(usage)
+}
+
+/// Total length of the union of `[start, end)` ranges.
+fn merged_length(mut ranges: Vec<(u64, u64)>) -> u64 {
+    ranges.sort_unstable();
+    let mut total = 0;
+    let mut covered_to = 0;
+    for (start, end) in ranges {
+        let start = start.max(covered_to);
+        if end > start {
+            total += end - start;
+            covered_to = end;
+        }
+    }
+    total
+}
+
+/// Every regular file under `dir`, recursively.
+pub fn files_under(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in
+            std::fs::read_dir(&dir).with_context(|| format!("Failed to list {}", dir.display()))?
+        {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Clones `source_file` `count` times into `dir`, then CoW-writes each clone,
+/// recording the free-space delta across each step.
+///
+/// This is blocking and should be called from `spawn_blocking`.
+pub fn measure_space(dir: &Path, source_file: &Path, count: u32) -> Result<SpaceReport> {
+    use std::os::unix::fs::FileExt;
+
+    std::fs::create_dir_all(dir).context("Failed to create space accounting directory")?;
+    let file_len = std::fs::metadata(source_file)
+        .context("Failed to stat source file")?
+        .len();
+
+    filesystem::sync_filesystem(dir)?;
+    let free_before = filesystem::free_space_bytes(dir)?;
+
+    let mut targets = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        let target = dir.join(format!("space_{}.dat", i));
+        BenchmarkRunner::create_reflink_blocking(source_file, &target)
+            .context(format!("Failed to create space accounting clone {}", i))?;
+        targets.push(target);
+    }
+
+    filesystem::sync_filesystem(dir)?;
+    let free_after_clone = filesystem::free_space_bytes(dir)?;
+
+    let write_data = vec![WRITE_PATTERN; DEFAULT_WRITE_SIZE];
+    for target in &targets {
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(target)
+            .context("Failed to open space accounting clone")?;
+        file.write_all_at(&write_data, 0)
+            .context("Failed to write to space accounting clone")?;
+    }
+
+    filesystem::sync_filesystem(dir)?;
+    let free_after_write = filesystem::free_space_bytes(dir)?;
+
+    let (shared_bytes, exclusive_bytes) = if filesystem::is_btrfs(dir) {
+        match btrfs_shared_usage(dir) {
+            Ok((shared, exclusive)) => (Some(shared), Some(exclusive)),
+            Err(e) => {
+                eprintln!("Failed to query btrfs shared extents: {}", e);
+                (None, None)
+            }
+        }
+    } else {
+        (None, None)
+    };
+
+    Ok(SpaceReport {
+        clones: count,
+        apparent_bytes: file_len * count as u64,
+        clone_used_bytes: free_before.saturating_sub(free_after_clone),
+        write_used_bytes: free_after_clone.saturating_sub(free_after_write),
+        shared_bytes,
+        exclusive_bytes,
+    })
+}
+
+/// Returns `(shared, exclusive)` bytes for `dir` from `btrfs filesystem du`.
+fn btrfs_shared_usage(dir: &Path) -> Result<(u64, u64)> {
+    let output = Command::new("btrfs")
+        .args(["filesystem", "du", "-s", "--raw", &dir.to_string_lossy()])
+        .output()
+        .context("Failed to run btrfs filesystem du")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "btrfs filesystem du failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Output is a header followed by "Total Exclusive Set-shared Filename"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout
+        .lines()
+        .nth(1)
+        .context("Unexpected btrfs filesystem du output")?
+        .split_whitespace()
+        .collect();
+
+    let parse = |i: usize| -> Result<u64> {
+        fields
+            .get(i)
+            .and_then(|field| field.parse().ok())
+            .context("Unexpected btrfs filesystem du output")
+    };
+
+    Ok((parse(2)?, parse(1)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extent_usage_counts_overlapping_extents_once() {
+        assert_eq!(
+            merged_length(vec![(0, 10), (5, 15), (20, 30), (22, 25)]),
+            25
+        );
+        assert_eq!(merged_length(Vec::new()), 0);
+
+        let dir = std::env::temp_dir().join(format!(
+            "reflink-bench-extent-usage-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("nested").join("a.dat"), vec![1u8; 64 * 1024]).unwrap();
+        let files = files_under(&dir).unwrap();
+        let usage = extent_usage("test", &files);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files.len(), 1);
+        // Some filesystems (tmpfs) don't support FIEMAP
+        if let Ok(usage) = usage {
+            assert_eq!(usage.referenced_bytes, 64 * 1024);
+            assert_eq!(usage.exclusive_bytes, 64 * 1024);
+            assert_eq!(usage.disk_bytes, 64 * 1024);
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Distribution of individual operation latencies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub count: usize,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+    pub max: Duration,
+    /// Samples outside the 3×IQR fences
+    pub outliers: usize,
+    /// Fraction of the total latency spent in outlier samples
+    pub outlier_time_share: f64,
+    /// Sample counts in power-of-two microsecond buckets; absent from
+    /// results saved before it was recorded
+    #[serde(default)]
+    pub histogram: Vec<HistogramBucket>,
+}
+
+/// Samples with a latency in `(upper_us / 2, upper_us]` microseconds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub upper_us: u64,
+    pub count: u64,
+}
+
+/// Outliers taking more than this share of total time usually mean
+/// background work (fstrim, journal flushes) interfered with the run.
+const OUTLIER_DOMINANCE_SHARE: f64 = 0.25;
+
+impl LatencyStats {
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let total: Duration = sorted.iter().sum();
+
+        let (lower, upper) = outlier_fences(&sorted);
+        let outlier_time: Duration = sorted.iter().filter(|&&d| d < lower || d > upper).sum();
+        let outliers = sorted.iter().filter(|&&d| d < lower || d > upper).count();
+
+        Self {
+            count: sorted.len(),
+            min: sorted[0],
+            mean: total / sorted.len() as u32,
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+            p999: percentile(&sorted, 99.9),
+            max: sorted[sorted.len() - 1],
+            outliers,
+            outlier_time_share: outlier_time.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON),
+            histogram: log2_histogram(&sorted),
+        }
+    }
+
+    /// Statistics over the samples that fall inside the 3×IQR fences.
+    pub fn trimmed(samples: &[Duration]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        if sorted.is_empty() {
+            return Self::default();
+        }
+
+        let (lower, upper) = outlier_fences(&sorted);
+        let kept: Vec<Duration> = sorted
+            .into_iter()
+            .filter(|&d| d >= lower && d <= upper)
+            .collect();
+        Self::from_samples(&kept)
+    }
+
+    /// Whether outliers account for enough of the run to distort it.
+    pub fn outliers_dominate(&self) -> bool {
+        self.outlier_time_share > OUTLIER_DOMINANCE_SHARE
+    }
+}
+
+fn log2_histogram(sorted: &[Duration]) -> Vec<HistogramBucket> {
+    let mut buckets: Vec<HistogramBucket> = Vec::new();
+    for sample in sorted {
+        let upper_us = (sample.as_micros() as u64).max(1).next_power_of_two();
+        match buckets.last_mut() {
+            Some(bucket) if bucket.upper_us == upper_us => bucket.count += 1,
+            _ => buckets.push(HistogramBucket { upper_us, count: 1 }),
+        }
+    }
+    buckets
+}
+
+/// Lower and upper fences at 3× the interquartile range beyond Q1 and Q3.
+fn outlier_fences(sorted: &[Duration]) -> (Duration, Duration) {
+    let q1 = percentile(sorted, 25.0);
+    let q3 = percentile(sorted, 75.0);
+    let iqr = q3 - q1;
+    (q1.saturating_sub(iqr * 3), q3 + iqr * 3)
+}
+
+/// Nearest-rank percentile of an already sorted, non-empty slice.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Mean, spread, and 95% confidence interval of a metric sampled across
+/// repeated runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub samples: Vec<f64>,
+    pub mean: f64,
+    pub stddev: f64,
+    pub ci95_low: f64,
+    pub ci95_high: f64,
+}
+
+impl Summary {
+    pub fn from_samples(samples: Vec<f64>) -> Self {
+        let n = samples.len();
+        let mean = if n > 0 {
+            samples.iter().sum::<f64>() / n as f64
+        } else {
+            0.0
+        };
+        let stddev = sample_stddev(&samples, mean);
+
+        // Student's t interval; a single sample has no spread to speak of
+        let half_width = if n > 1 {
+            t_critical_95(n - 1) * stddev / (n as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        Self {
+            samples,
+            mean,
+            stddev,
+            ci95_low: mean - half_width,
+            ci95_high: mean + half_width,
+        }
+    }
+}
+
+fn sample_stddev(samples: &[f64], mean: f64) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+
+    let variance =
+        samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64;
+    variance.sqrt()
+}
+
+/// Two-sided 95% critical value of Student's t distribution.
+fn t_critical_95(degrees_of_freedom: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+
+    match degrees_of_freedom {
+        0 => f64::NAN,
+        df if df <= TABLE.len() => TABLE[df - 1],
+        _ => 1.96,
+    }
+}
+
+/// Two-sided p-value of the Mann-Whitney U test for whether `a` and `b` come
+/// from the same distribution. Small samples without ties use the exact
+/// distribution of U; otherwise the tie-corrected normal approximation.
+///
+/// Returns `None` when either side has no samples.
+pub fn mann_whitney_p_value(a: &[f64], b: &[f64]) -> Option<f64> {
+    let (n1, n2) = (a.len(), b.len());
+    if n1 == 0 || n2 == 0 {
+        return None;
+    }
+
+    // Rank the pooled samples, giving ties their average rank
+    let mut pooled: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&x| (x, true))
+        .chain(b.iter().map(|&x| (x, false)))
+        .collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut rank_sum_a = 0.0;
+    let mut tie_correction = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let mut j = i;
+        while j + 1 < pooled.len() && pooled[j + 1].0 == pooled[i].0 {
+            j += 1;
+        }
+        let average_rank = (i + j) as f64 / 2.0 + 1.0;
+        rank_sum_a += pooled[i..=j].iter().filter(|x| x.1).count() as f64 * average_rank;
+        let ties = (j - i + 1) as f64;
+        tie_correction += ties.powi(3) - ties;
+        i = j + 1;
+    }
+
+    let u = rank_sum_a - (n1 * (n1 + 1)) as f64 / 2.0;
+    let u = u.min((n1 * n2) as f64 - u);
+
+    if tie_correction == 0.0 && n1 + n2 <= 20 {
+        return Some(exact_mann_whitney_p_value(n1, n2, u as usize));
+    }
+
+    let n = (n1 + n2) as f64;
+    let mean = (n1 * n2) as f64 / 2.0;
+    let variance = (n1 * n2) as f64 / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return Some(1.0);
+    }
+
+    // Continuity-corrected z score for the smaller U
+    let z = ((mean - u) - 0.5).max(0.0) / variance.sqrt();
+    Some((2.0 * (1.0 - normal_cdf(z))).min(1.0))
+}
+
+/// P(U <= u) * 2 under the null hypothesis, by counting the orderings of
+/// `n1` + `n2` distinct values that produce each U.
+fn exact_mann_whitney_p_value(n1: usize, n2: usize, u: usize) -> f64 {
+    let max_u = n1 * n2;
+    // counts[i][j][k]: orderings of i and j samples with U = k
+    let mut counts = vec![vec![vec![0f64; max_u + 1]; n2 + 1]; n1 + 1];
+    for i in 0..=n1 {
+        for j in 0..=n2 {
+            if i == 0 || j == 0 {
+                counts[i][j][0] = 1.0;
+                continue;
+            }
+            for k in 0..=(i * j) {
+                let with_a_last = if k >= j { counts[i - 1][j][k - j] } else { 0.0 };
+                counts[i][j][k] = with_a_last + counts[i][j - 1][k];
+            }
+        }
+    }
+
+    let total: f64 = counts[n1][n2].iter().sum();
+    let tail: f64 = counts[n1][n2][..=u.min(max_u)].iter().sum();
+    (2.0 * tail / total).min(1.0)
+}
+
+/// Standard normal CDF via the Abramowitz-Stegun erf approximation.
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn micros(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&us| Duration::from_micros(us)).collect()
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let samples = micros(&(1..=100).collect::<Vec<_>>());
+        let stats = LatencyStats::from_samples(&samples);
+
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.min, Duration::from_micros(1));
+        assert_eq!(stats.p50, Duration::from_micros(50));
+        assert_eq!(stats.p90, Duration::from_micros(90));
+        assert_eq!(stats.p99, Duration::from_micros(99));
+        assert_eq!(stats.max, Duration::from_micros(100));
+        assert_eq!(stats.outliers, 0);
+    }
+
+    #[test]
+    fn empty_samples_give_default_stats() {
+        let stats = LatencyStats::from_samples(&[]);
+        assert_eq!(stats.count, 0);
+        assert!(stats.histogram.is_empty());
+    }
+
+    #[test]
+    fn outliers_beyond_three_iqr_are_flagged_and_trimmed() {
+        let mut values = vec![10; 50];
+        values.extend(vec![12; 49]);
+        values.push(10_000);
+        let samples = micros(&values);
+
+        let stats = LatencyStats::from_samples(&samples);
+        assert_eq!(stats.outliers, 1);
+        assert!(stats.outliers_dominate());
+
+        let trimmed = LatencyStats::trimmed(&samples);
+        assert_eq!(trimmed.count, 99);
+        assert_eq!(trimmed.max, Duration::from_micros(12));
+    }
+
+    #[test]
+    fn histogram_buckets_by_power_of_two() {
+        let stats = LatencyStats::from_samples(&micros(&[1, 3, 4, 5, 900]));
+        let buckets: Vec<(u64, u64)> = stats
+            .histogram
+            .iter()
+            .map(|bucket| (bucket.upper_us, bucket.count))
+            .collect();
+        assert_eq!(buckets, vec![(1, 1), (4, 2), (8, 1), (1024, 1)]);
+    }
+
+    #[test]
+    fn summary_confidence_interval_uses_t_distribution() {
+        let summary = Summary::from_samples(vec![10.0, 12.0, 14.0]);
+        assert_eq!(summary.mean, 12.0);
+        assert_eq!(summary.stddev, 2.0);
+        let half_width = 4.303 * 2.0 / 3f64.sqrt();
+        assert!((summary.ci95_high - (12.0 + half_width)).abs() < 1e-9);
+
+        let single = Summary::from_samples(vec![5.0]);
+        assert_eq!((single.ci95_low, single.ci95_high), (5.0, 5.0));
+    }
+
+    #[test]
+    fn mann_whitney_separates_distinct_samples() {
+        let fast = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let slow = [10.0, 11.0, 12.0, 13.0, 14.0];
+        assert!(mann_whitney_p_value(&fast, &slow).unwrap() < 0.05);
+        assert!(mann_whitney_p_value(&fast, &fast).unwrap() > 0.5);
+        assert_eq!(mann_whitney_p_value(&fast, &[]), None);
+
+        // Three runs each can't reach p < 0.05 however far apart they are
+        assert!(mann_whitney_p_value(&fast[..3], &slow[..3]).unwrap() >= 0.05);
+        assert!(mann_whitney_p_value(&fast[..4], &slow[..4]).unwrap() < 0.05);
+    }
+}
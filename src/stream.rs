@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::benchmark::{BenchmarkResult, OpTiming};
+
+/// One line of `--stream` output. Durations are in microseconds.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Operation {
+        filesystem: &'a str,
+        phase: &'a str,
+        index: u32,
+        reflink_us: u128,
+        write_us: u128,
+    },
+    Phase {
+        filesystem: &'a str,
+        phase: &'a str,
+        operations: u64,
+        duration_us: u128,
+        operations_per_sec: f64,
+    },
+    Result {
+        filesystem: &'a str,
+        run: u32,
+        result: &'a BenchmarkResult,
+    },
+    Error {
+        filesystem: &'a str,
+        run: u32,
+        message: String,
+    },
+}
+
+/// Writes JSON Lines events as the benchmark progresses, flushing each line
+/// so the stream can be tailed and survives a failed run.
+#[derive(Clone)]
+pub struct EventStream {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    filesystem: String,
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("filesystem", &self.filesystem)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EventStream {
+    /// Opens `path` for streaming, or stdout when `path` is `-`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = if path.as_os_str() == "-" {
+            Box::new(std::io::stdout())
+        } else {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create stream file {}", path.display()))?;
+            Box::new(LineWriter::new(file))
+        };
+
+        Ok(Self {
+            writer: Arc::new(Mutex::new(writer)),
+            filesystem: String::new(),
+        })
+    }
+
+    /// A handle to the same stream that labels events with `filesystem`.
+    pub fn for_filesystem(&self, filesystem: &str) -> Self {
+        Self {
+            writer: Arc::clone(&self.writer),
+            filesystem: filesystem.to_string(),
+        }
+    }
+
+    pub fn operation(&self, phase: &str, index: u32, timing: &OpTiming) -> Result<()> {
+        self.emit(&Event::Operation {
+            filesystem: &self.filesystem,
+            phase,
+            index,
+            reflink_us: timing.reflink.as_micros(),
+            write_us: timing.write.as_micros(),
+        })
+    }
+
+    pub fn phase(&self, phase: &str, operations: u64, duration: Duration) -> Result<()> {
+        self.emit(&Event::Phase {
+            filesystem: &self.filesystem,
+            phase,
+            operations,
+            duration_us: duration.as_micros(),
+            operations_per_sec: operations as f64 / duration.as_secs_f64().max(f64::EPSILON),
+        })
+    }
+
+    pub fn result(&self, run: u32, result: &BenchmarkResult) -> Result<()> {
+        self.emit(&Event::Result {
+            filesystem: &self.filesystem,
+            run,
+            result,
+        })
+    }
+
+    pub fn error(&self, run: u32, error: &anyhow::Error) -> Result<()> {
+        self.emit(&Event::Error {
+            filesystem: &self.filesystem,
+            run,
+            message: format!("{:#}", error),
+        })
+    }
+
+    fn emit(&self, event: &Event) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        serde_json::to_writer(&mut *writer, event).context("Failed to serialize stream event")?;
+        writer
+            .write_all(b"\n")
+            .and_then(|_| writer.flush())
+            .context("Failed to write stream event")
+    }
+}
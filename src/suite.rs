@@ -0,0 +1,891 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::background::BackgroundLoad;
+use crate::benchmark::{parse_size, BenchmarkConfig, CacheMode, Runner, ThreadCounts};
+use crate::external::ExternalTool;
+use crate::filesystem::FilesystemType;
+use crate::iosched::IoScheduler;
+use crate::workloads::WorkloadKind;
+
+/// One set of benchmark parameters run against each of its filesystems.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    /// `None` for the single unnamed scenario described by CLI flags
+    pub name: Option<String>,
+    pub filesystems: Vec<FilesystemType>,
+    pub fs_size_gb: u64,
+    pub repeat: u32,
+    /// Extra mount options per filesystem
+    pub mount_options: BTreeMap<String, String>,
+    /// Directory each filesystem's image goes in, when not the run directory
+    pub backing_paths: BTreeMap<String, PathBuf>,
+    /// Set on scenarios generated by a --file-sizes/--reflink-counts sweep
+    pub sweep: Option<SweepPoint>,
+    /// Set on scenarios generated by --btrfs-presets
+    pub preset: Option<PresetPoint>,
+    pub config: BenchmarkConfig,
+}
+
+/// Where a generated scenario sits in a parameter sweep.
+#[derive(Debug, Clone)]
+pub struct SweepPoint {
+    /// Name of the scenario the sweep was expanded from
+    pub base: Option<String>,
+    pub file_size_mb: u64,
+    pub reflink_count: u32,
+}
+
+/// A ready-made btrfs configuration for `--btrfs-presets`.
+pub struct BtrfsPreset {
+    pub name: &'static str,
+    /// Appended to the scenario's btrfs mount options
+    pub mount_options: &'static str,
+    /// Enable quota groups before benchmarking
+    pub qgroups: bool,
+}
+
+/// The `--btrfs-presets` configurations. Each changes how CoW writes reach
+/// the disk: `autodefrag` queues small random writes for background
+/// rewriting, `commit=` sets how many seconds pass between transaction
+/// commits (30 by default), and qgroups account every shared extent to
+/// each subvolume referencing it.
+pub const BTRFS_PRESETS: &[BtrfsPreset] = &[
+    BtrfsPreset {
+        name: "autodefrag",
+        mount_options: "autodefrag",
+        qgroups: false,
+    },
+    BtrfsPreset {
+        name: "commit-5",
+        mount_options: "commit=5",
+        qgroups: false,
+    },
+    BtrfsPreset {
+        name: "commit-120",
+        mount_options: "commit=120",
+        qgroups: false,
+    },
+    BtrfsPreset {
+        name: "autodefrag-commit-120",
+        mount_options: "autodefrag,commit=120",
+        qgroups: false,
+    },
+    BtrfsPreset {
+        name: "qgroups",
+        mount_options: "",
+        qgroups: true,
+    },
+];
+
+/// A ready-made mount option variant for `--mount-presets`.
+pub struct MountPreset {
+    pub name: &'static str,
+    /// Appended to each filesystem's own mount options
+    pub mount_options: &'static str,
+    /// Filesystems the option applies to; empty for all of them
+    pub filesystems: &'static [FilesystemType],
+}
+
+/// A group of mount option variants that `--mount-presets` compares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PresetFamily {
+    /// When reads update the access time: `noatime`, `relatime` (the
+    /// kernel default), `strictatime`, and `strictatime` with `lazytime`
+    Atime,
+    /// btrfs free-space tracking: the v1 space cache files or the v2 free
+    /// space tree
+    SpaceCache,
+    /// Online discard of freed extents: none, synchronous, or (on btrfs)
+    /// asynchronous
+    Discard,
+}
+
+impl PresetFamily {
+    pub fn presets(self) -> &'static [MountPreset] {
+        match self {
+            PresetFamily::Atime => ATIME_PRESETS,
+            PresetFamily::SpaceCache => SPACE_CACHE_PRESETS,
+            PresetFamily::Discard => DISCARD_PRESETS,
+        }
+    }
+}
+
+/// Access time updates on the source inode are metadata writes that every
+/// clone of it can trigger, journalled by XFS and CoW'd by btrfs.
+/// `lazytime` keeps the updated times in memory until something else
+/// writes the inode.
+pub const ATIME_PRESETS: &[MountPreset] = &[
+    MountPreset {
+        name: "noatime",
+        mount_options: "noatime",
+        filesystems: &[],
+    },
+    MountPreset {
+        name: "relatime",
+        mount_options: "relatime",
+        filesystems: &[],
+    },
+    MountPreset {
+        name: "strictatime",
+        mount_options: "strictatime",
+        filesystems: &[],
+    },
+    MountPreset {
+        name: "lazytime",
+        mount_options: "strictatime,lazytime",
+        filesystems: &[],
+    },
+];
+
+/// Which `--btrfs-presets` or `--mount-presets` configuration a generated
+/// scenario runs.
+#[derive(Debug, Clone)]
+pub struct PresetPoint {
+    /// Name of the scenario the presets were expanded from
+    pub base: Option<String>,
+    pub name: String,
+    pub mount_options: String,
+}
+
+impl Scenario {
+    /// Name used for this filesystem in progress output, streams, and the
+    /// dashboard, qualified by the scenario name when there is one.
+    pub fn label(&self, fs_type: FilesystemType) -> String {
+        match &self.name {
+            Some(name) => format!("{}/{}", name, fs_type),
+            None => fs_type.to_string(),
+        }
+    }
+
+    /// The scenario's mount options for `fs_type`, plus `prjquota` on XFS
+    /// when project quotas are wanted.
+    pub fn mount_options_for(&self, fs_type: FilesystemType) -> Option<String> {
+        let own = self.mount_options.get(&fs_key(fs_type)).cloned();
+        if fs_type != FilesystemType::Xfs || !self.config.xfs_project_quota {
+            return own;
+        }
+        Some(match own {
+            Some(own) => format!("{},prjquota", own),
+            None => "prjquota".to_string(),
+        })
+    }
+
+    /// Where the scenario puts `fs_type`'s image, if not in the run
+    /// directory.
+    pub fn backing_path_for(&self, fs_type: FilesystemType) -> Option<&Path> {
+        self.backing_paths
+            .get(&fs_key(fs_type))
+            .map(PathBuf::as_path)
+    }
+
+    /// `base` with `-<scenario>` appended, for keeping per-scenario output
+    /// files and baselines apart.
+    pub fn qualify(&self, base: &str) -> String {
+        match &self.name {
+            Some(name) => format!("{}-{}", base, name),
+            None => base.to_string(),
+        }
+    }
+
+    /// `path` with `-<scenario>` inserted before its extension.
+    pub fn qualify_path(&self, path: &Path) -> PathBuf {
+        let Some(name) = &self.name else {
+            return path.to_path_buf();
+        };
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let file_name = match path.extension() {
+            Some(ext) => format!("{}-{}.{}", stem, name, ext.to_string_lossy()),
+            None => format!("{}-{}", stem, name),
+        };
+        path.with_file_name(file_name)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SuiteFile {
+    #[serde(rename = "scenario")]
+    scenarios: Vec<ScenarioSpec>,
+}
+
+/// A `[[scenario]]` table. Anything left out falls back to the value given
+/// on the command line (or its default).
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ScenarioSpec {
+    name: String,
+    filesystems: Option<Vec<String>>,
+    file_size_mb: Option<u64>,
+    reflink_count: Option<u32>,
+    fs_size_gb: Option<u64>,
+    repeat: Option<u32>,
+    threads: Option<ThreadsSpec>,
+    write_sizes: Option<Vec<String>>,
+    source_extent_sizes: Option<Vec<String>>,
+    workloads: Option<Vec<String>>,
+    mix: Option<String>,
+    source_count: Option<u32>,
+    fragment_source: Option<u32>,
+    small_file_count: Option<u32>,
+    mixed_workers: Option<u32>,
+    backup_change_percent: Option<u32>,
+    warmup: Option<u32>,
+    drop_caches: Option<bool>,
+    fstrim: Option<bool>,
+    cache_mode: Option<String>,
+    duration: Option<String>,
+    rate: Option<String>,
+    fscrypt: Option<bool>,
+    sparse_source: Option<String>,
+    cross_fs: Option<PathBuf>,
+    copy_baselines: Option<bool>,
+    external_tools: Option<Vec<String>>,
+    io_scheduler: Option<String>,
+    background_load: Option<String>,
+    runner: Option<String>,
+    seed: Option<u64>,
+    verify: Option<bool>,
+    extent_usage: Option<bool>,
+    interleave: Option<bool>,
+    btrfs_qgroups: Option<bool>,
+    xfs_project_quota: Option<bool>,
+    #[serde(default)]
+    mount_options: BTreeMap<String, String>,
+    #[serde(default)]
+    backing_paths: BTreeMap<String, PathBuf>,
+}
+
+/// `threads = [1, 4, 16]` or `threads = "auto"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ThreadsSpec {
+    Counts(Vec<u32>),
+    Named(String),
+}
+
+/// Reads the scenarios in a suite file, each layered over `defaults`.
+pub fn load(path: &Path, defaults: &Scenario) -> Result<Vec<Scenario>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read suite file {}", path.display()))?;
+    let suite: SuiteFile = toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse suite file {}", path.display()))?;
+
+    if suite.scenarios.is_empty() {
+        anyhow::bail!("{} defines no [[scenario]] tables", path.display());
+    }
+
+    let mut names = HashSet::new();
+    let mut scenarios = Vec::with_capacity(suite.scenarios.len());
+    for spec in suite.scenarios {
+        // Names end up in file names and baseline names
+        if spec.name.is_empty()
+            || !spec
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!(
+                "Invalid scenario name {:?}: use letters, digits, '-' and '_'",
+                spec.name
+            );
+        }
+        if !names.insert(spec.name.clone()) {
+            anyhow::bail!("Scenario {:?} is defined more than once", spec.name);
+        }
+
+        let name = spec.name.clone();
+        scenarios.push(
+            apply(spec, defaults.clone()).with_context(|| format!("In scenario {:?}", name))?,
+        );
+    }
+
+    Ok(scenarios)
+}
+
+fn apply(spec: ScenarioSpec, mut scenario: Scenario) -> Result<Scenario> {
+    scenario.name = Some(spec.name);
+
+    if let Some(filesystems) = spec.filesystems {
+        scenario.filesystems = filesystems
+            .iter()
+            .map(|name| parse_value::<FilesystemType>(name, "filesystem"))
+            .collect::<Result<_>>()?;
+    }
+    if let Some(size) = spec.fs_size_gb {
+        scenario.fs_size_gb = size;
+    }
+    if let Some(repeat) = spec.repeat {
+        scenario.repeat = repeat;
+    }
+
+    let known: Vec<String> = scenario.filesystems.iter().map(|fs| fs_key(*fs)).collect();
+    for (key, fs) in spec
+        .mount_options
+        .keys()
+        .map(|fs| ("mount-options", fs))
+        .chain(spec.backing_paths.keys().map(|fs| ("backing-paths", fs)))
+    {
+        if !known.contains(fs) {
+            anyhow::bail!("{} given for {}, which the scenario doesn't run", key, fs);
+        }
+    }
+    scenario.mount_options.extend(spec.mount_options);
+    scenario.backing_paths.extend(spec.backing_paths);
+
+    let config = &mut scenario.config;
+    if let Some(size) = spec.file_size_mb {
+        config.file_size_mb = size;
+    }
+    if let Some(count) = spec.reflink_count {
+        config.reflink_count = count;
+    }
+    if let Some(threads) = spec.threads {
+        config.thread_counts = match threads {
+            ThreadsSpec::Counts(counts) => {
+                if counts.is_empty() || counts.contains(&0) {
+                    anyhow::bail!("threads must be a non-empty list of positive counts");
+                }
+                counts
+            }
+            ThreadsSpec::Named(name) => name.parse::<ThreadCounts>()?.0,
+        };
+    }
+    if let Some(sizes) = spec.write_sizes {
+        config.write_sizes = sizes.iter().map(|s| parse_size(s)).collect::<Result<_>>()?;
+    }
+    if let Some(sizes) = spec.source_extent_sizes {
+        config.source_extent_sizes = sizes.iter().map(|s| parse_size(s)).collect::<Result<_>>()?;
+    }
+    if let Some(workloads) = spec.workloads {
+        config.workloads = workloads
+            .iter()
+            .map(|name| parse_value::<WorkloadKind>(name, "workload"))
+            .collect::<Result<_>>()?;
+    }
+    if let Some(mix) = spec.mix {
+        config.mix = mix.parse()?;
+    }
+    if let Some(count) = spec.source_count {
+        config.source_count = count;
+    }
+    if let Some(extents) = spec.fragment_source {
+        if extents == 0 {
+            anyhow::bail!("fragment-source must be at least 1");
+        }
+        config.fragment_source = Some(extents);
+    }
+    if let Some(count) = spec.small_file_count {
+        config.small_file_count = count;
+    }
+    if let Some(workers) = spec.mixed_workers {
+        config.mixed_workers = workers;
+    }
+    if let Some(percent) = spec.backup_change_percent {
+        if !(1..=100).contains(&percent) {
+            anyhow::bail!("backup-change-percent must be 1-100, got {}", percent);
+        }
+        config.backup_change_percent = percent;
+    }
+    if let Some(warmup) = spec.warmup {
+        config.warmup = warmup;
+    }
+    if let Some(drop_caches) = spec.drop_caches {
+        config.drop_caches = drop_caches;
+    }
+    if let Some(fstrim) = spec.fstrim {
+        config.fstrim = fstrim;
+    }
+    if let Some(seed) = spec.seed {
+        config.seed = seed;
+    }
+    if let Some(verify) = spec.verify {
+        config.verify = verify;
+    }
+    if let Some(extent_usage) = spec.extent_usage {
+        config.extent_usage = extent_usage;
+    }
+    if let Some(interleave) = spec.interleave {
+        config.interleave = interleave;
+    }
+    if let Some(qgroups) = spec.btrfs_qgroups {
+        config.btrfs_qgroups = qgroups;
+    }
+    if let Some(project_quota) = spec.xfs_project_quota {
+        config.xfs_project_quota = project_quota;
+    }
+    if let Some(mode) = spec.cache_mode {
+        config.cache_mode = Some(parse_value::<CacheMode>(&mode, "cache mode")?);
+    }
+    if let Some(duration) = spec.duration {
+        config.duration = Some(
+            humantime::parse_duration(&duration)
+                .with_context(|| format!("Invalid duration: {}", duration))?,
+        );
+    }
+    if let Some(rate) = spec.rate {
+        config.rate = Some(rate.parse()?);
+    }
+    if let Some(fscrypt) = spec.fscrypt {
+        config.fscrypt = fscrypt;
+    }
+    if let Some(size) = spec.sparse_source {
+        config.sparse_source_bytes = Some(parse_size(&size)?);
+    }
+    if let Some(dir) = spec.cross_fs {
+        config.cross_fs_dir = Some(dir);
+    }
+    if let Some(copy_baselines) = spec.copy_baselines {
+        config.copy_baselines = copy_baselines;
+    }
+    if let Some(tools) = spec.external_tools {
+        config.external_tools = tools
+            .iter()
+            .map(|name| parse_value::<ExternalTool>(name, "external tool"))
+            .collect::<Result<_>>()?;
+    }
+    if let Some(scheduler) = spec.io_scheduler {
+        config.io_scheduler = Some(parse_value::<IoScheduler>(&scheduler, "I/O scheduler")?);
+    }
+    if let Some(load) = spec.background_load {
+        config.background_load = Some(parse_value::<BackgroundLoad>(&load, "background load")?);
+    }
+    if let Some(runner) = spec.runner {
+        config.runner = parse_value::<Runner>(&runner, "runner")?;
+    }
+
+    Ok(scenario)
+}
+
+/// Replaces each scenario with one per combination of `file_sizes` and
+/// `reflink_counts`; an empty list keeps the scenario's own value.
+pub fn expand_sweep(
+    scenarios: Vec<Scenario>,
+    file_sizes: &[u64],
+    reflink_counts: &[u32],
+) -> Vec<Scenario> {
+    if file_sizes.is_empty() && reflink_counts.is_empty() {
+        return scenarios;
+    }
+
+    let mut expanded = Vec::new();
+    for scenario in scenarios {
+        let sizes = match file_sizes {
+            [] => vec![scenario.config.file_size_mb],
+            sizes => sizes.to_vec(),
+        };
+        let counts = match reflink_counts {
+            [] => vec![scenario.config.reflink_count],
+            counts => counts.to_vec(),
+        };
+
+        for &file_size_mb in &sizes {
+            for &reflink_count in &counts {
+                let point = format!("{}mb-x{}", file_size_mb, reflink_count);
+                let mut generated = scenario.clone();
+                generated.name = Some(match &scenario.name {
+                    Some(name) => format!("{}-{}", name, point),
+                    None => point,
+                });
+                generated.config.file_size_mb = file_size_mb;
+                generated.config.reflink_count = reflink_count;
+                generated.sweep = Some(SweepPoint {
+                    base: scenario.name.clone(),
+                    file_size_mb,
+                    reflink_count,
+                });
+                expanded.push(generated);
+            }
+        }
+    }
+    expanded
+}
+
+/// Adds a btrfs-only scenario per [`BTRFS_PRESETS`] entry after each
+/// scenario that benchmarks btrfs, with the preset's options appended to
+/// the scenario's own btrfs mount options.
+pub fn expand_btrfs_presets(scenarios: Vec<Scenario>) -> Vec<Scenario> {
+    let key = fs_key(FilesystemType::Btrfs);
+    let mut expanded = Vec::new();
+    for scenario in scenarios {
+        let presets: Vec<Scenario> = if scenario.filesystems.contains(&FilesystemType::Btrfs) {
+            BTRFS_PRESETS
+                .iter()
+                .map(|preset| {
+                    let mut generated = scenario.clone();
+                    generated.name = Some(match &scenario.name {
+                        Some(base) => format!("{}-btrfs-{}", base, preset.name),
+                        None => format!("btrfs-{}", preset.name),
+                    });
+                    generated.filesystems = vec![FilesystemType::Btrfs];
+                    let own = scenario.mount_options.get(&key);
+                    let mount_options = match (own, preset.mount_options) {
+                        (Some(own), "") => own.clone(),
+                        (Some(own), options) => format!("{},{}", own, options),
+                        (None, options) => options.to_string(),
+                    };
+                    if !mount_options.is_empty() {
+                        generated
+                            .mount_options
+                            .insert(key.clone(), mount_options.clone());
+                    }
+                    generated.config.btrfs_qgroups |= preset.qgroups;
+                    generated.preset = Some(PresetPoint {
+                        base: scenario.name.clone(),
+                        name: preset.name.to_string(),
+                        mount_options,
+                    });
+                    generated
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        expanded.push(scenario);
+        expanded.extend(presets);
+    }
+    expanded
+}
+
+/// Every CoW write allocates, and btrfs finds free space through either
+/// the v1 space cache, inodes rewritten at each transaction commit, or the
+/// v2 free space tree, a btree updated as extents come and go. mkfs.btrfs
+/// creates the tree by default since btrfs-progs 5.15, so v1 has to clear
+/// it at mount.
+pub const SPACE_CACHE_PRESETS: &[MountPreset] = &[
+    MountPreset {
+        name: "space-cache-v1",
+        mount_options: "clear_cache,space_cache=v1",
+        filesystems: &[FilesystemType::Btrfs],
+    },
+    MountPreset {
+        name: "space-cache-v2",
+        mount_options: "space_cache=v2",
+        filesystems: &[FilesystemType::Btrfs],
+    },
+];
+
+/// CoW writes and deleted clones free extents all the time. With
+/// `discard`, the filesystem issues a discard for each as it's freed, in
+/// the commit path; btrfs's `discard=async` queues them and trickles them
+/// out in the background instead.
+pub const DISCARD_PRESETS: &[MountPreset] = &[
+    MountPreset {
+        name: "nodiscard",
+        mount_options: "nodiscard",
+        filesystems: &[],
+    },
+    MountPreset {
+        name: "discard",
+        mount_options: "discard",
+        filesystems: &[],
+    },
+    MountPreset {
+        name: "discard-async",
+        mount_options: "discard=async",
+        filesystems: &[FilesystemType::Btrfs],
+    },
+];
+
+/// Adds a scenario per preset of each of `families` after each scenario,
+/// with the preset's options appended to the mount options of every
+/// filesystem it applies to. Presets that apply to none of a scenario's
+/// filesystems are skipped.
+pub fn expand_mount_presets(scenarios: Vec<Scenario>, families: &[PresetFamily]) -> Vec<Scenario> {
+    let mut expanded = Vec::new();
+    for scenario in scenarios {
+        let mut presets = Vec::new();
+        for preset in families.iter().flat_map(|family| family.presets()) {
+            let filesystems: Vec<FilesystemType> = scenario
+                .filesystems
+                .iter()
+                .copied()
+                .filter(|fs| preset.filesystems.is_empty() || preset.filesystems.contains(fs))
+                .collect();
+            if filesystems.is_empty() {
+                continue;
+            }
+            let mut generated = scenario.clone();
+            generated.name = Some(match &scenario.name {
+                Some(base) => format!("{}-{}", base, preset.name),
+                None => preset.name.to_string(),
+            });
+            for &fs in &filesystems {
+                let key = fs_key(fs);
+                let mount_options = match scenario.mount_options.get(&key) {
+                    Some(own) => format!("{},{}", own, preset.mount_options),
+                    None => preset.mount_options.to_string(),
+                };
+                generated.mount_options.insert(key, mount_options);
+            }
+            generated.filesystems = filesystems;
+            generated.preset = Some(PresetPoint {
+                base: scenario.name.clone(),
+                name: preset.name.to_string(),
+                mount_options: preset.mount_options.to_string(),
+            });
+            presets.push(generated);
+        }
+        expanded.push(scenario);
+        expanded.extend(presets);
+    }
+    expanded
+}
+
+/// Parses `value` the same way the matching CLI flag would.
+fn parse_value<T: ValueEnum>(value: &str, what: &str) -> Result<T> {
+    T::from_str(value, true).map_err(|_| {
+        let valid: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|v| v.to_possible_value())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        anyhow::anyhow!(
+            "Unknown {} {:?} (expected one of: {})",
+            what,
+            value,
+            valid.join(", ")
+        )
+    })
+}
+
+/// Key used for a filesystem in `mount-options` and `backing-paths` tables.
+pub fn fs_key(fs_type: FilesystemType) -> String {
+    fs_type
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Scenario {
+        Scenario {
+            name: None,
+            filesystems: vec![FilesystemType::Xfs, FilesystemType::Btrfs],
+            fs_size_gb: 2,
+            repeat: 1,
+            mount_options: BTreeMap::new(),
+            backing_paths: BTreeMap::new(),
+            sweep: None,
+            preset: None,
+            config: BenchmarkConfig::default(),
+        }
+    }
+
+    fn load_str(contents: &str) -> Result<Vec<Scenario>> {
+        let path = std::env::temp_dir().join(format!(
+            "reflink-bench-suite-test-{}-{}.toml",
+            std::process::id(),
+            crate::benchmark::stable_hash(contents)
+        ));
+        std::fs::write(&path, contents).unwrap();
+        let result = load(&path, &defaults());
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn scenarios_layer_over_defaults() {
+        let scenarios = load_str(
+            r#"
+            [[scenario]]
+            name = "small"
+            filesystems = ["btrfs"]
+            file-size-mb = 10
+            threads = [1, 4]
+            write-sizes = ["4k", "1m"]
+            mount-options = { btrfs = "compress=zstd" }
+            backing-paths = { btrfs = "/mnt/nvme1" }
+
+            [[scenario]]
+            name = "defaults"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(scenarios.len(), 2);
+        let small = &scenarios[0];
+        assert_eq!(small.name.as_deref(), Some("small"));
+        assert_eq!(small.filesystems, vec![FilesystemType::Btrfs]);
+        assert_eq!(small.config.file_size_mb, 10);
+        assert_eq!(small.config.reflink_count, 1000);
+        assert_eq!(small.config.thread_counts, vec![1, 4]);
+        assert_eq!(small.config.write_sizes, vec![4096, 1 << 20]);
+        assert_eq!(
+            small.mount_options_for(FilesystemType::Btrfs).as_deref(),
+            Some("compress=zstd")
+        );
+        assert_eq!(
+            small.backing_path_for(FilesystemType::Btrfs),
+            Some(Path::new("/mnt/nvme1"))
+        );
+
+        let defaults = &scenarios[1];
+        assert_eq!(defaults.filesystems.len(), 2);
+        assert_eq!(defaults.config.file_size_mb, 100);
+        assert_eq!(defaults.mount_options_for(FilesystemType::Xfs), None);
+        assert_eq!(defaults.backing_path_for(FilesystemType::Btrfs), None);
+    }
+
+    #[test]
+    fn project_quotas_add_prjquota_to_xfs_only() {
+        let mut scenario = defaults();
+        scenario.config.xfs_project_quota = true;
+        scenario
+            .mount_options
+            .insert("xfs".to_string(), "noatime".to_string());
+        assert_eq!(
+            scenario.mount_options_for(FilesystemType::Xfs).as_deref(),
+            Some("noatime,prjquota")
+        );
+        assert_eq!(scenario.mount_options_for(FilesystemType::Btrfs), None);
+    }
+
+    #[test]
+    fn invalid_suites_are_rejected() {
+        for contents in [
+            "",
+            "[[scenario]]\nname = \"bad name\"",
+            "[[scenario]]\nname = \"a\"\n[[scenario]]\nname = \"a\"",
+            "[[scenario]]\nname = \"a\"\nunknown-key = 1",
+            "[[scenario]]\nname = \"a\"\nfilesystems = [\"ext4\"]",
+            "[[scenario]]\nname = \"a\"\nfilesystems = [\"xfs\"]\nmount-options = { btrfs = \"ssd\" }",
+            "[[scenario]]\nname = \"a\"\nfilesystems = [\"xfs\"]\nbacking-paths = { btrfs = \"/mnt\" }",
+            "[[scenario]]\nname = \"a\"\nthreads = [0]",
+        ] {
+            assert!(load_str(contents).is_err(), "accepted: {}", contents);
+        }
+    }
+
+    #[test]
+    fn sweep_expands_every_combination() {
+        let mut named = defaults();
+        named.name = Some("base".to_string());
+
+        let expanded = expand_sweep(vec![defaults(), named], &[1, 10], &[100]);
+        let names: Vec<_> = expanded.iter().filter_map(|s| s.name.clone()).collect();
+        assert_eq!(
+            names,
+            vec!["1mb-x100", "10mb-x100", "base-1mb-x100", "base-10mb-x100"]
+        );
+        assert_eq!(expanded[1].config.file_size_mb, 10);
+        assert_eq!(
+            expanded[3].sweep.as_ref().unwrap().base.as_deref(),
+            Some("base")
+        );
+
+        assert_eq!(expand_sweep(vec![defaults()], &[], &[]).len(), 1);
+    }
+
+    #[test]
+    fn btrfs_presets_follow_each_btrfs_scenario() {
+        let mut xfs_only = defaults();
+        xfs_only.name = Some("xfs".to_string());
+        xfs_only.filesystems = vec![FilesystemType::Xfs];
+        let mut tuned = defaults();
+        tuned
+            .mount_options
+            .insert("btrfs".to_string(), "noatime".to_string());
+
+        let expanded = expand_btrfs_presets(vec![xfs_only, tuned]);
+        assert_eq!(expanded.len(), 2 + BTRFS_PRESETS.len());
+        assert_eq!(expanded[0].name.as_deref(), Some("xfs"));
+        assert!(expanded[1].preset.is_none());
+
+        let autodefrag = &expanded[2];
+        assert_eq!(autodefrag.name.as_deref(), Some("btrfs-autodefrag"));
+        assert_eq!(autodefrag.filesystems, vec![FilesystemType::Btrfs]);
+        assert_eq!(
+            autodefrag
+                .mount_options_for(FilesystemType::Btrfs)
+                .as_deref(),
+            Some("noatime,autodefrag")
+        );
+        assert_eq!(autodefrag.preset.as_ref().unwrap().base, None);
+
+        let qgroups = expanded.last().unwrap();
+        assert_eq!(qgroups.name.as_deref(), Some("btrfs-qgroups"));
+        assert!(qgroups.config.btrfs_qgroups);
+        assert_eq!(
+            qgroups.mount_options_for(FilesystemType::Btrfs).as_deref(),
+            Some("noatime")
+        );
+    }
+
+    #[test]
+    fn mount_presets_apply_to_every_filesystem() {
+        let mut tuned = defaults();
+        tuned.filesystems = vec![FilesystemType::Xfs, FilesystemType::Btrfs];
+        tuned
+            .mount_options
+            .insert("xfs".to_string(), "logbsize=256k".to_string());
+
+        let expanded = expand_mount_presets(vec![tuned], &[PresetFamily::Atime]);
+        assert_eq!(expanded.len(), 1 + ATIME_PRESETS.len());
+        assert!(expanded[0].preset.is_none());
+
+        let noatime = &expanded[1];
+        assert_eq!(noatime.name.as_deref(), Some("noatime"));
+        assert_eq!(
+            noatime.filesystems,
+            vec![FilesystemType::Xfs, FilesystemType::Btrfs]
+        );
+        assert_eq!(
+            noatime.mount_options_for(FilesystemType::Xfs).as_deref(),
+            Some("logbsize=256k,noatime")
+        );
+        assert_eq!(
+            noatime.mount_options_for(FilesystemType::Btrfs).as_deref(),
+            Some("noatime")
+        );
+        assert_eq!(
+            expanded
+                .last()
+                .unwrap()
+                .mount_options_for(FilesystemType::Btrfs)
+                .as_deref(),
+            Some("strictatime,lazytime")
+        );
+    }
+
+    #[test]
+    fn btrfs_only_presets_skip_other_filesystems() {
+        let mut xfs_only = defaults();
+        xfs_only.filesystems = vec![FilesystemType::Xfs];
+        let expanded =
+            expand_mount_presets(vec![xfs_only, defaults()], &[PresetFamily::SpaceCache]);
+        assert_eq!(expanded.len(), 2 + SPACE_CACHE_PRESETS.len());
+        assert!(expanded[1].preset.is_none());
+
+        let v1 = &expanded[2];
+        assert_eq!(v1.name.as_deref(), Some("space-cache-v1"));
+        assert_eq!(v1.filesystems, vec![FilesystemType::Btrfs]);
+        assert_eq!(
+            v1.mount_options_for(FilesystemType::Btrfs).as_deref(),
+            Some("clear_cache,space_cache=v1")
+        );
+    }
+
+    #[test]
+    fn qualify_appends_scenario_name() {
+        let mut scenario = defaults();
+        assert_eq!(
+            scenario.qualify_path(Path::new("out/results.json")),
+            Path::new("out/results.json")
+        );
+
+        scenario.name = Some("small".to_string());
+        assert_eq!(scenario.qualify("nightly"), "nightly-small");
+        assert_eq!(
+            scenario.qualify_path(Path::new("out/results.json")),
+            Path::new("out/results-small.json")
+        );
+        assert_eq!(scenario.label(FilesystemType::Xfs), "small/XFS");
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// How often completed operations are sampled.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Ops/sec measured over consecutive intervals of a single phase.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThroughputSeries {
+    pub phase: String,
+    pub interval: Duration,
+    pub ops_per_sec: Vec<f64>,
+}
+
+impl ThroughputSeries {
+    pub fn peak(&self) -> f64 {
+        self.ops_per_sec.iter().fold(0.0f64, |a, &b| a.max(b))
+    }
+
+    pub fn trough(&self) -> f64 {
+        self.ops_per_sec
+            .iter()
+            .copied()
+            .reduce(f64::min)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Samples a shared completed-operations counter on a fixed interval while
+/// a phase runs.
+pub struct ThroughputSampler {
+    phase: String,
+    counter: Arc<AtomicU64>,
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<Vec<f64>>,
+}
+
+impl ThroughputSampler {
+    pub fn start(phase: &str) -> Self {
+        let counter = Arc::new(AtomicU64::new(0));
+        let (stop, mut stopped) = oneshot::channel();
+        let sampled = Arc::clone(&counter);
+
+        let handle = tokio::spawn(async move {
+            let mut samples = Vec::new();
+            let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+            interval.tick().await; // The first tick completes immediately
+            let mut last_count = 0;
+            let mut last_tick = Instant::now();
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        let count = sampled.load(Ordering::Relaxed);
+                        samples.push((count - last_count) as f64 / last_tick.elapsed().as_secs_f64());
+                        last_count = count;
+                        last_tick = Instant::now();
+                    }
+                    _ = &mut stopped => {
+                        // Include the trailing partial interval if it did any work
+                        let count = sampled.load(Ordering::Relaxed);
+                        if count > last_count {
+                            samples.push((count - last_count) as f64 / last_tick.elapsed().as_secs_f64());
+                        }
+                        break;
+                    }
+                }
+            }
+
+            samples
+        });
+
+        Self {
+            phase: phase.to_string(),
+            counter,
+            stop,
+            handle,
+        }
+    }
+
+    /// Counter that workers increment once per completed operation.
+    pub fn counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.counter)
+    }
+
+    pub async fn finish(self) -> ThroughputSeries {
+        let _ = self.stop.send(());
+        let ops_per_sec = self.handle.await.unwrap_or_default();
+
+        ThroughputSeries {
+            phase: self.phase,
+            interval: SAMPLE_INTERVAL,
+            ops_per_sec,
+        }
+    }
+}
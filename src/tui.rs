@@ -0,0 +1,364 @@
+use anyhow::{Context, Result};
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::cursor::{Hide, Show};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+use ratatui::{Frame, Terminal};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::benchmark::OpTiming;
+
+/// How often the dashboard redraws and samples ops/sec.
+const TICK: Duration = Duration::from_millis(250);
+/// Operations kept for the rolling latency percentiles.
+const LATENCY_WINDOW: usize = 1000;
+/// Ops/sec samples kept for the sparkline.
+const HISTORY_LEN: usize = 240;
+/// Progress lines kept for the log panel.
+const LOG_LINES: usize = 200;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pending,
+    Running,
+    Done,
+}
+
+struct LiveState {
+    filesystems: Vec<(String, Status)>,
+    filesystem: String,
+    phase: String,
+    total: u64,
+    completed: u64,
+    phase_started: Instant,
+    latencies: VecDeque<Duration>,
+    ops_history: VecDeque<u64>,
+    log: VecDeque<String>,
+}
+
+/// Handle the benchmark runner reports live progress through.
+#[derive(Clone)]
+pub struct LiveMonitor {
+    state: Arc<Mutex<LiveState>>,
+    filesystem: String,
+}
+
+impl std::fmt::Debug for LiveMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LiveMonitor")
+            .field("filesystem", &self.filesystem)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LiveMonitor {
+    /// A handle to the same dashboard that reports as `filesystem`.
+    pub fn for_filesystem(&self, filesystem: &str) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+            filesystem: filesystem.to_string(),
+        }
+    }
+
+    /// Starts a new phase of `total` operations (0 when unknown).
+    pub fn begin_phase(&self, phase: &str, total: u64) {
+        let mut state = self.state.lock().unwrap();
+        for (name, status) in &mut state.filesystems {
+            if *name == self.filesystem {
+                *status = Status::Running;
+            }
+        }
+        state.filesystem = self.filesystem.clone();
+        state.phase = phase.to_string();
+        state.total = total;
+        state.completed = 0;
+        state.phase_started = Instant::now();
+        state.latencies.clear();
+    }
+
+    pub fn record(&self, timing: &OpTiming) {
+        let mut state = self.state.lock().unwrap();
+        state.completed += 1;
+        if state.latencies.len() == LATENCY_WINDOW {
+            state.latencies.pop_front();
+        }
+        state.latencies.push_back(timing.total());
+    }
+
+    pub fn finish_filesystem(&self) {
+        let mut state = self.state.lock().unwrap();
+        for (name, status) in &mut state.filesystems {
+            if *name == self.filesystem {
+                *status = Status::Done;
+            }
+        }
+    }
+}
+
+/// Full-screen live view of a running benchmark. Progress printed to
+/// stdout is captured into the dashboard's log panel while it runs.
+pub struct Dashboard {
+    monitor: LiveMonitor,
+    stop: Arc<AtomicBool>,
+    renderer: Option<JoinHandle<()>>,
+    capture: Option<StdoutCapture>,
+}
+
+impl Dashboard {
+    pub fn start(filesystems: &[String]) -> Result<Self> {
+        let state = Arc::new(Mutex::new(LiveState {
+            filesystems: filesystems
+                .iter()
+                .map(|name| (name.clone(), Status::Pending))
+                .collect(),
+            filesystem: String::new(),
+            phase: "starting".to_string(),
+            total: 0,
+            completed: 0,
+            phase_started: Instant::now(),
+            latencies: VecDeque::with_capacity(LATENCY_WINDOW),
+            ops_history: VecDeque::with_capacity(HISTORY_LEN),
+            log: VecDeque::with_capacity(LOG_LINES),
+        }));
+
+        // The terminal keeps its own copy of the real stdout
+        let tty = dup_fd(libc::STDOUT_FILENO)?;
+        let mut tty = unsafe { File::from_raw_fd(tty) };
+        execute!(tty, EnterAlternateScreen, Hide).context("Failed to enter alternate screen")?;
+        let mut terminal =
+            Terminal::new(CrosstermBackend::new(tty)).context("Failed to start terminal UI")?;
+
+        let capture = StdoutCapture::start(Arc::clone(&state))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let renderer = {
+            let state = Arc::clone(&state);
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || {
+                let mut last_completed = 0;
+                let mut last_phase = String::new();
+                while !stop.load(Ordering::Relaxed) {
+                    {
+                        let mut state = state.lock().unwrap();
+                        if state.phase != last_phase {
+                            last_phase = state.phase.clone();
+                            last_completed = 0;
+                        }
+                        let ops = (state.completed.saturating_sub(last_completed) as f64
+                            / TICK.as_secs_f64()) as u64;
+                        last_completed = state.completed;
+                        if state.ops_history.len() == HISTORY_LEN {
+                            state.ops_history.pop_front();
+                        }
+                        state.ops_history.push_back(ops);
+
+                        let _ = terminal.draw(|frame| draw(frame, &state));
+                    }
+                    std::thread::sleep(TICK);
+                }
+
+                let _ = execute!(terminal.backend_mut(), LeaveAlternateScreen, Show);
+            })
+        };
+
+        Ok(Self {
+            monitor: LiveMonitor {
+                state,
+                filesystem: String::new(),
+            },
+            stop,
+            renderer: Some(renderer),
+            capture: Some(capture),
+        })
+    }
+
+    pub fn monitor(&self) -> &LiveMonitor {
+        &self.monitor
+    }
+
+    /// Closes the dashboard and restores normal stdout.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(renderer) = self.renderer.take() {
+            let _ = renderer.join();
+        }
+        if let Some(capture) = self.capture.take() {
+            capture.restore();
+        }
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn draw(frame: &mut Frame, state: &LiveState) {
+    let [header, progress, throughput, latency, log] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Length(6),
+        Constraint::Length(3),
+        Constraint::Min(3),
+    ])
+    .areas(frame.area());
+
+    let statuses: Vec<String> = state
+        .filesystems
+        .iter()
+        .map(|(name, status)| match status {
+            Status::Pending => format!("{} ⏳ pending", name),
+            Status::Running => format!("{} ▶ {}", name, state.phase),
+            Status::Done => format!("{} ✅ done", name),
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(statuses.join("    ")).block(Block::bordered().title(" reflink-bench ")),
+        header,
+    );
+
+    let (ratio, label) = if state.total > 0 {
+        (
+            (state.completed as f64 / state.total as f64).min(1.0),
+            format!("{} / {}", state.completed, state.total),
+        )
+    } else {
+        (0.0, format!("{} ops", state.completed))
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(format!(" {}: {} ", state.filesystem, state.phase)))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(ratio)
+            .label(label),
+        progress,
+    );
+
+    let current = state.ops_history.back().copied().unwrap_or(0);
+    let elapsed = state.phase_started.elapsed().as_secs_f64();
+    let history: Vec<u64> = state.ops_history.iter().copied().collect();
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(format!(
+                " Ops/sec: {} now, {:.1} phase average ",
+                current,
+                state.completed as f64 / elapsed.max(f64::EPSILON)
+            )))
+            .style(Style::default().fg(Color::Green))
+            .data(&history),
+        throughput,
+    );
+
+    let mut sorted: Vec<Duration> = state.latencies.iter().copied().collect();
+    sorted.sort_unstable();
+    let percentile = |p: f64| -> String {
+        if sorted.is_empty() {
+            return "-".to_string();
+        }
+        let rank = ((p / 100.0 * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+        format!("{:?}", sorted[rank - 1])
+    };
+    frame.render_widget(
+        Paragraph::new(format!(
+            "p50 {}   p90 {}   p99 {}",
+            percentile(50.0),
+            percentile(90.0),
+            percentile(99.0)
+        ))
+        .block(Block::bordered().title(format!(
+            " Reflink + write latency (last {} ops) ",
+            sorted.len()
+        ))),
+        latency,
+    );
+
+    let visible = log.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = state
+        .log
+        .iter()
+        .skip(state.log.len().saturating_sub(visible))
+        .map(|line| Line::raw(line.as_str()))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" Log ")),
+        log,
+    );
+}
+
+/// Redirects stdout into a pipe whose lines feed the dashboard's log panel.
+struct StdoutCapture {
+    saved_stdout: RawFd,
+    reader: JoinHandle<()>,
+}
+
+impl StdoutCapture {
+    fn start(state: Arc<Mutex<LiveState>>) -> Result<Self> {
+        let saved_stdout = dup_fd(libc::STDOUT_FILENO)?;
+
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            let errno = std::io::Error::last_os_error();
+            anyhow::bail!("pipe failed: {}", errno);
+        }
+        let [read_end, write_end] = fds;
+
+        let _ = std::io::stdout().flush();
+        let result = unsafe { libc::dup2(write_end, libc::STDOUT_FILENO) };
+        unsafe { libc::close(write_end) };
+        if result < 0 {
+            let errno = std::io::Error::last_os_error();
+            anyhow::bail!("dup2 failed: {}", errno);
+        }
+
+        let reader = std::thread::spawn(move || {
+            let pipe = unsafe { File::from_raw_fd(read_end) };
+            for line in BufReader::new(pipe).lines().map_while(|line| line.ok()) {
+                let mut state = state.lock().unwrap();
+                if state.log.len() == LOG_LINES {
+                    state.log.pop_front();
+                }
+                state.log.push_back(line);
+            }
+        });
+
+        Ok(Self {
+            saved_stdout,
+            reader,
+        })
+    }
+
+    fn restore(self) {
+        let _ = std::io::stdout().flush();
+        unsafe {
+            libc::dup2(self.saved_stdout, libc::STDOUT_FILENO);
+            libc::close(self.saved_stdout);
+        }
+        // The pipe's last writer is gone, so the reader sees EOF
+        let _ = self.reader.join();
+    }
+}
+
+fn dup_fd(fd: RawFd) -> Result<RawFd> {
+    let duplicate = unsafe { libc::dup(fd) };
+    if duplicate < 0 {
+        let errno = std::io::Error::last_os_error();
+        anyhow::bail!("dup failed: {}", errno);
+    }
+    Ok(duplicate)
+}
@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::benchmark::WRITE_PATTERN;
+
+/// Outcome of reading back every clone a run made.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub files: u64,
+    pub bytes_read: u64,
+    pub duration: Duration,
+}
+
+impl VerifyReport {
+    /// Counts another check's files, bytes, and time into this one.
+    pub fn add(&mut self, other: &VerifyReport) {
+        self.files += other.files;
+        self.bytes_read += other.bytes_read;
+        self.duration += other.duration;
+    }
+}
+
+/// The clones one phase left behind: `{prefix}_{i}.dat` for each operation,
+/// cloned from `sources[i % sources]` and overwritten from offset 0 with
+/// `write_size` bytes of the write pattern.
+pub struct PhaseTargets {
+    pub prefix: String,
+    pub count: u32,
+    pub write_size: u64,
+    /// How many of the run's source files the phase cycled through
+    pub sources: usize,
+}
+
+/// Checksums of the source files taken right after they were written, to
+/// check clones (and the sources themselves) against later.
+pub struct Verifier {
+    sources: Vec<PathBuf>,
+    checksums: Vec<u64>,
+}
+
+impl Verifier {
+    /// This is blocking and should be called from `spawn_blocking`.
+    pub fn new(sources: &[PathBuf]) -> Result<Self> {
+        let checksums = sources
+            .iter()
+            .map(|source| checksum_from(source, 0).map(|(sum, _)| sum))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            sources: sources.to_vec(),
+            checksums,
+        })
+    }
+
+    /// Checks that no source changed when its clones were written, and that
+    /// every clone holds the write pattern where it was written and its
+    /// source's bytes everywhere else.
+    ///
+    /// This is blocking and should be called from `spawn_blocking`.
+    pub fn verify(&self, dir: &Path, phases: &[PhaseTargets]) -> Result<VerifyReport> {
+        let start = Instant::now();
+        let mut bytes_read = 0;
+
+        for (source, &expected) in self.sources.iter().zip(&self.checksums) {
+            let (sum, len) = checksum_from(source, 0)?;
+            bytes_read += len;
+            if sum != expected {
+                anyhow::bail!(
+                    "Data integrity check failed: source {} changed after its clones were written",
+                    source.display()
+                );
+            }
+        }
+
+        let mut report = self.verify_clones(dir, phases)?;
+        report.bytes_read += bytes_read;
+        report.duration = start.elapsed();
+        Ok(report)
+    }
+
+    /// Checks every clone in `dir` against its source, without checking
+    /// the sources themselves, for phases whose clones are removed before
+    /// the run ends.
+    ///
+    /// This is blocking and should be called from `spawn_blocking`.
+    pub fn verify_clones(&self, dir: &Path, phases: &[PhaseTargets]) -> Result<VerifyReport> {
+        let start = Instant::now();
+        let mut files = 0;
+        let mut bytes_read = 0;
+
+        // Checksum of each source past each write size, computed once
+        let mut unshared: HashMap<(usize, u64), u64> = HashMap::new();
+        for phase in phases {
+            for i in 0..phase.count {
+                let index = i as usize % phase.sources.clamp(1, self.sources.len());
+                let source = &self.sources[index];
+                let expected = match unshared.get(&(index, phase.write_size)) {
+                    Some(&sum) => sum,
+                    None => {
+                        let (sum, len) = checksum_from(source, phase.write_size)?;
+                        bytes_read += len;
+                        unshared.insert((index, phase.write_size), sum);
+                        sum
+                    }
+                };
+
+                let target = dir.join(format!("{}_{}.dat", phase.prefix, i));
+                bytes_read += verify_clone(&target, source, phase.write_size, expected)?;
+                files += 1;
+            }
+        }
+
+        Ok(VerifyReport {
+            files,
+            bytes_read,
+            duration: start.elapsed(),
+        })
+    }
+}
+
+/// Returns the number of bytes read.
+fn verify_clone(target: &Path, source: &Path, write_size: u64, expected: u64) -> Result<u64> {
+    let target_len = std::fs::metadata(target)
+        .with_context(|| format!("Failed to stat clone {}", target.display()))?
+        .len();
+    let source_len = std::fs::metadata(source)
+        .with_context(|| format!("Failed to stat source {}", source.display()))?
+        .len();
+    if target_len != source_len {
+        anyhow::bail!(
+            "Data integrity check failed: {} is {} bytes but its source {} is {}",
+            target.display(),
+            target_len,
+            source.display(),
+            source_len
+        );
+    }
+
+    let mut file = std::fs::File::open(target)
+        .with_context(|| format!("Failed to open clone {}", target.display()))?;
+    let mut written = vec![0u8; write_size as usize];
+    file.read_exact(&mut written)
+        .with_context(|| format!("Failed to read clone {}", target.display()))?;
+    if let Some(offset) = written.iter().position(|&byte| byte != WRITE_PATTERN) {
+        anyhow::bail!(
+            "Data integrity check failed: {} has {:#04x} at offset {} where {:#04x} was written",
+            target.display(),
+            written[offset],
+            offset,
+            WRITE_PATTERN
+        );
+    }
+
+    let (sum, len) = checksum_from(target, write_size)?;
+    if sum != expected {
+        anyhow::bail!(
+            "Data integrity check failed: {} differs from its source {} outside the {} bytes written",
+            target.display(),
+            source.display(),
+            write_size
+        );
+    }
+
+    Ok(write_size + len)
+}
+
+/// Checksum of a file from `offset` to the end, and the bytes read.
+fn checksum_from(path: &Path, offset: u64) -> Result<(u64, u64)> {
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open {} for verification", path.display()))?;
+    file.seek(SeekFrom::Start(offset))
+        .with_context(|| format!("Failed to seek in {}", path.display()))?;
+
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut total = 0;
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buffer[..read]);
+        total += read as u64;
+    }
+
+    Ok((hasher.finish(), total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch directory holding a source and `count` copies of it with
+    /// the write pattern over the first `write_size` bytes, standing in for
+    /// a phase's clones.
+    fn phase_dir(name: &str, count: u32, write_size: usize) -> (PathBuf, Verifier) {
+        let dir = std::env::temp_dir().join(format!(
+            "reflink-bench-verify-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let source: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        let source_path = dir.join("source_file.dat");
+        std::fs::write(&source_path, &source).unwrap();
+        let verifier = Verifier::new(&[source_path]).unwrap();
+
+        let mut clone = source;
+        clone[..write_size].fill(WRITE_PATTERN);
+        for i in 0..count {
+            std::fs::write(dir.join(format!("sequential_{}.dat", i)), &clone).unwrap();
+        }
+        (dir, verifier)
+    }
+
+    fn phases(count: u32) -> Vec<PhaseTargets> {
+        vec![PhaseTargets {
+            prefix: "sequential".to_string(),
+            count,
+            write_size: 4096,
+            sources: 1,
+        }]
+    }
+
+    #[test]
+    fn intact_clones_pass() {
+        let (dir, verifier) = phase_dir("intact", 3, 4096);
+        let report = verifier.verify(&dir, &phases(3)).unwrap();
+        assert_eq!(report.files, 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupted_shared_region_fails() {
+        let (dir, verifier) = phase_dir("shared", 2, 4096);
+        let clone = dir.join("sequential_1.dat");
+        let mut data = std::fs::read(&clone).unwrap();
+        data[50_000] ^= 0xff;
+        std::fs::write(&clone, &data).unwrap();
+
+        let error = verifier.verify(&dir, &phases(2)).unwrap_err();
+        assert!(error.to_string().contains("outside the 4096 bytes written"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_write_pattern_fails() {
+        let (dir, verifier) = phase_dir("pattern", 1, 4000);
+        let error = verifier.verify(&dir, &phases(1)).unwrap_err();
+        assert!(error.to_string().contains("at offset 4000"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn modified_source_fails() {
+        let (dir, verifier) = phase_dir("source", 1, 4096);
+        std::fs::write(dir.join("source_file.dat"), b"changed").unwrap();
+        let error = verifier.verify(&dir, &phases(1)).unwrap_err();
+        assert!(error.to_string().contains("changed after its clones"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reports_add_up() {
+        let (dir, verifier) = phase_dir("add", 2, 4096);
+        let mut report = verifier.verify(&dir, &phases(2)).unwrap();
+        let clones = verifier.verify_clones(&dir, &phases(1)).unwrap();
+        assert_eq!(clones.files, 1);
+        report.add(&clones);
+        assert_eq!(report.files, 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,2639 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::benchmark::{self, BenchmarkConfig, BenchmarkRunner, OpTiming, WRITE_PATTERN};
+use crate::filesystem;
+use crate::ioctl::{self, Dedupe};
+use crate::stats::LatencyStats;
+
+/// A benchmark made of many independent, individually timed operations,
+/// such as cloning a file and dirtying its first block.
+///
+/// The runner calls [`prepare`](Self::prepare) untimed, times
+/// [`run`](Self::run) for every operation (from several threads at once in
+/// concurrent phases), then calls [`teardown`](Self::teardown). Each sample
+/// is reported in the same latency and throughput tables, so a new kind of
+/// operation only needs an implementation of this trait. Methods block and
+/// should be called from `spawn_blocking`.
+pub trait Workload: Send + Sync {
+    /// Names the workload in output, stream events, and file names.
+    fn name(&self) -> &str;
+
+    /// What each sample's `reflink` and `write` times measure. A workload
+    /// with a single step leaves `write` zero, as does an operation that
+    /// skips the second step; those are left out of its percentiles.
+    fn steps(&self) -> &'static [&'static str] {
+        &["reflink", "write"]
+    }
+
+    /// How many operations [`run`](Self::run) is called for.
+    fn operations(&self) -> u32;
+
+    /// Creates whatever the operations need, outside the timed section.
+    fn prepare(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Performs operation `index` and times its steps.
+    fn run(&self, index: u32) -> Result<OpTiming>;
+
+    /// Removes anything the workload doesn't leave for the filesystem's
+    /// cleanup.
+    fn teardown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Metrics besides each step's p50 and p99, from the samples (in
+    /// operation order) and anything the workload kept while running.
+    fn metrics(&self, _timings: &[OpTiming]) -> Vec<(String, f64)> {
+        Vec::new()
+    }
+}
+
+/// The core benchmark operation: clone `{dir}/{name}_{i}.dat` from the
+/// sources in turn, then overwrite its first `write_size` bytes to force
+/// copy-on-write.
+pub struct ReflinkWrite {
+    name: String,
+    dir: PathBuf,
+    sources: Vec<PathBuf>,
+    count: u32,
+    write_size: usize,
+}
+
+impl ReflinkWrite {
+    pub fn new(name: &str, dir: &Path, sources: &[PathBuf], count: u32, write_size: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            dir: dir.to_path_buf(),
+            sources: sources.to_vec(),
+            count,
+            write_size,
+        }
+    }
+}
+
+impl Workload for ReflinkWrite {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        let target = self.dir.join(format!("{}_{}.dat", self.name, index));
+        // Spread operations across sources so workers don't all lock one inode
+        let source = &self.sources[index as usize % self.sources.len()];
+        BenchmarkRunner::create_reflink_and_write_sized_blocking(source, &target, self.write_size)
+    }
+}
+
+/// Optional workloads run after the core reflink + write phases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WorkloadKind {
+    /// Punch holes into reflinked files and measure space reclamation
+    PunchHole,
+    /// Compare lazy CoW against fallocate-based explicit unsharing
+    Fallocate,
+    /// Reflink tens of thousands of small (4-64 KB) source files once each
+    SmallFiles,
+    /// Concurrent workers interleaving reflinks, CoW writes, and reads per `--mix`
+    Mixed,
+    /// Clone over pre-filled targets with FICLONE and FICLONERANGE
+    Overwrite,
+    /// Clone ranges to other offsets within a single file with FICLONERANGE
+    SameFile,
+    /// Time stat, open, and readdir over a directory of reflinked files
+    Metadata,
+    /// Reflink without writing, isolating the cost of the clone itself
+    CloneOnly,
+    /// Deduplicate identical, separately written copies with FIDEDUPERANGE
+    Dedup,
+    /// Reflink-copy a directory of files as a unit, like `cp -r --reflink`
+    Snapshot,
+    /// Read back clones that share all but their first block with the source
+    Read,
+    /// Random 8 KB writes with fdatasync to one large file, reflinked whole
+    /// at each checkpoint, like a database taking reflink backups
+    Database,
+    /// Clone a many-small-file layer tree per container, then write to
+    /// scattered files in it, like a reflink container snapshotter
+    Container,
+    /// Clone a sparse multi-GB disk image per VM, then make random 64 KB
+    /// O_DIRECT writes into it
+    VmImage,
+    /// Rewrite a percentage of a dataset's blocks, then reflink it to a new
+    /// backup generation, many times over
+    Backup,
+    /// Clone a cache of thousands of build outputs into a build directory,
+    /// then partially rewrite some of them, like ccache or a cloned
+    /// cargo target dir
+    BuildCache,
+    /// Clone a file from several threads while another keeps appending to
+    /// and overwriting it, then check every clone for torn writes
+    CloneWhileWriting,
+    /// Read reflinked files at rest, then again while writers unshare the
+    /// same files
+    Contention,
+    /// Free heavily shared files with `truncate(0)` and with `unlink`, and
+    /// time how long until statfs shows the space back
+    Reclaim,
+    /// Clone a file to a temp file, modify and fsync it, and rename it over
+    /// the original: the atomic update pattern
+    AtomicReplace,
+    /// Clone, then make sub-block and unaligned writes that need the
+    /// shared block read before it can be rewritten
+    Unaligned,
+}
+
+impl Display for WorkloadKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkloadKind::PunchHole => write!(f, "punch-hole"),
+            WorkloadKind::Fallocate => write!(f, "fallocate"),
+            WorkloadKind::SmallFiles => write!(f, "small-files"),
+            WorkloadKind::Mixed => write!(f, "mixed"),
+            WorkloadKind::Overwrite => write!(f, "overwrite"),
+            WorkloadKind::SameFile => write!(f, "same-file"),
+            WorkloadKind::Metadata => write!(f, "metadata"),
+            WorkloadKind::CloneOnly => write!(f, "clone-only"),
+            WorkloadKind::Dedup => write!(f, "dedup"),
+            WorkloadKind::Snapshot => write!(f, "snapshot"),
+            WorkloadKind::Read => write!(f, "read"),
+            WorkloadKind::Database => write!(f, "database"),
+            WorkloadKind::Container => write!(f, "container"),
+            WorkloadKind::VmImage => write!(f, "vm-image"),
+            WorkloadKind::Backup => write!(f, "backup"),
+            WorkloadKind::BuildCache => write!(f, "build-cache"),
+            WorkloadKind::CloneWhileWriting => write!(f, "clone-while-writing"),
+            WorkloadKind::Contention => write!(f, "contention"),
+            WorkloadKind::Reclaim => write!(f, "reclaim"),
+            WorkloadKind::AtomicReplace => write!(f, "atomic-replace"),
+            WorkloadKind::Unaligned => write!(f, "unaligned"),
+        }
+    }
+}
+
+impl WorkloadKind {
+    /// Syscalls and ioctls the workload exercises.
+    pub fn operations(self) -> &'static str {
+        match self {
+            WorkloadKind::PunchHole => "FICLONE, pwrite, fallocate(PUNCH_HOLE)",
+            WorkloadKind::Fallocate => "FICLONE, pwrite, fallocate(UNSHARE_RANGE), fallocate",
+            WorkloadKind::SmallFiles => "FICLONE",
+            WorkloadKind::Mixed => "FICLONE, pwrite, pread",
+            WorkloadKind::Overwrite => "FICLONE, FICLONERANGE",
+            WorkloadKind::SameFile => "FICLONERANGE",
+            WorkloadKind::Metadata => "FICLONE, stat, open, readdir",
+            WorkloadKind::CloneOnly => "FICLONE",
+            WorkloadKind::Dedup => "FIDEDUPERANGE",
+            WorkloadKind::Snapshot => "FICLONE, mkdir",
+            WorkloadKind::Read => "FICLONE, pwrite, pread",
+            WorkloadKind::Database => "pwrite, fdatasync, FICLONE",
+            WorkloadKind::Container => "mkdir, FICLONE, pwrite",
+            WorkloadKind::VmImage => "FICLONE, O_DIRECT pwrite",
+            WorkloadKind::Backup => "pwrite, FICLONE",
+            WorkloadKind::BuildCache => "mkdir, FICLONE, pwrite",
+            WorkloadKind::CloneWhileWriting => "FICLONE, pwrite (concurrent)",
+            WorkloadKind::Contention => "pread, pwrite, fdatasync (concurrent)",
+            WorkloadKind::Reclaim => "ftruncate, unlink, statfs",
+            WorkloadKind::AtomicReplace => "FICLONE, pwrite, fsync, rename",
+            WorkloadKind::Unaligned => "FICLONE, pwrite (sub-block, unaligned), fdatasync",
+        }
+    }
+
+    /// The workload as a [`Workload`], for the kinds built on the trait.
+    /// The others time themselves in [`run_workload`].
+    pub fn build(
+        self,
+        dir: &Path,
+        sources: &[PathBuf],
+        config: &BenchmarkConfig,
+    ) -> Option<Box<dyn Workload>> {
+        let dir = dir.to_path_buf();
+        let sources = sources.to_vec();
+        let count = config.reflink_count;
+        match self {
+            WorkloadKind::CloneOnly => Some(Box::new(CloneOnly {
+                dir,
+                sources,
+                count,
+            })),
+            WorkloadKind::Dedup => Some(Box::new(Dedup {
+                dir,
+                source: sources[0].clone(),
+                count,
+            })),
+            WorkloadKind::Snapshot => Some(Box::new(Snapshot {
+                dir,
+                sources,
+                count: (count / SNAPSHOT_FILES).max(1),
+                files: Vec::new(),
+            })),
+            WorkloadKind::Read => Some(Box::new(ReadBack {
+                dir,
+                sources,
+                count,
+                read_len: 0,
+            })),
+            WorkloadKind::AtomicReplace => Some(Box::new(AtomicReplace {
+                dir,
+                source: sources[0].clone(),
+                count,
+                seed: config.seed,
+                blocks: 0,
+                commits: Mutex::default(),
+            })),
+            WorkloadKind::Unaligned => Some(Box::new(Unaligned {
+                dir,
+                source: sources[0].clone(),
+                per_pattern: (count / UNALIGNED_PATTERNS.len() as u32).max(1),
+            })),
+            WorkloadKind::Database => Some(Box::new(Database {
+                dir,
+                source: sources[0].clone(),
+                count,
+                seed: config.seed,
+                db: None,
+                pages: 0,
+                checkpoints: Mutex::default(),
+                first_writes: Mutex::default(),
+            })),
+            WorkloadKind::Container => Some(Box::new(Container {
+                dir,
+                count: (count / CONTAINER_LAYER_FILES).max(1),
+                seed: config.seed,
+                files: Vec::new(),
+                first_writes: Mutex::default(),
+                writes: Mutex::default(),
+            })),
+            WorkloadKind::VmImage => Some(Box::new(VmImage {
+                dir,
+                count: (count / 100).max(1),
+                seed: config.seed,
+                base: None,
+                buf: Vec::new(),
+                writes: Mutex::default(),
+                steady: Mutex::default(),
+            })),
+            WorkloadKind::Backup => Some(Box::new(Backup {
+                dir,
+                source: sources[0].clone(),
+                count: (count / 50).max(1),
+                seed: config.seed,
+                change_percent: config.backup_change_percent,
+                dataset: None,
+                blocks: 0,
+                changed: 0,
+                free: Mutex::default(),
+                growth: Mutex::default(),
+            })),
+            WorkloadKind::BuildCache => Some(Box::new(BuildCache {
+                dir,
+                count: (count / 200).max(1),
+                seed: config.seed,
+                files: Vec::new(),
+                cache_bytes: 0,
+                data: Vec::new(),
+            })),
+            WorkloadKind::Reclaim => Some(Box::new(Reclaim {
+                dir,
+                source: sources[0].clone(),
+                file_len: 0,
+                metrics: Mutex::default(),
+            })),
+            _ => None,
+        }
+    }
+
+    /// Where the workload only partly runs.
+    pub fn limitations(self) -> &'static str {
+        match self {
+            WorkloadKind::Fallocate => {
+                "Unshare variant skipped where FALLOC_FL_UNSHARE_RANGE is unsupported (btrfs)"
+            }
+            _ => "",
+        }
+    }
+}
+
+/// Percentages of reflink, CoW write, and read operations in the mixed
+/// workload, written as `reflink/write/read` (e.g. `70/20/10`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationMix {
+    pub reflink: u32,
+    pub write: u32,
+    pub read: u32,
+}
+
+impl Default for OperationMix {
+    /// The `run --mix` default, `70/20/10`.
+    fn default() -> Self {
+        Self {
+            reflink: 70,
+            write: 20,
+            read: 10,
+        }
+    }
+}
+
+impl FromStr for OperationMix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts = s
+            .split('/')
+            .map(|part| part.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .context(format!("Invalid operation mix: {}", s))?;
+
+        let [reflink, write, read] = parts[..] else {
+            anyhow::bail!("Operation mix must be reflink/write/read, got: {}", s);
+        };
+        if reflink + write + read != 100 {
+            anyhow::bail!("Operation mix must add up to 100, got: {}", s);
+        }
+
+        Ok(Self {
+            reflink,
+            write,
+            read,
+        })
+    }
+}
+
+impl Display for OperationMix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.reflink, self.write, self.read)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadResult {
+    pub workload: String,
+    pub operations: u32,
+    pub total_duration: Duration,
+    pub avg_op_time: Duration,
+    pub operations_per_sec: f64,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+impl WorkloadResult {
+    fn new(workload: impl Display, operations: u32, total_duration: Duration) -> Self {
+        let avg_op_time = if operations > 0 {
+            total_duration / operations
+        } else {
+            Duration::ZERO
+        };
+
+        Self {
+            workload: workload.to_string(),
+            operations,
+            total_duration,
+            avg_op_time,
+            operations_per_sec: operations as f64 / total_duration.as_secs_f64(),
+            metrics: BTreeMap::new(),
+        }
+    }
+
+    /// Summarizes a [`Workload`]'s samples, with p50 and p99 latency for
+    /// each of its steps.
+    pub fn from_samples(workload: &dyn Workload, timings: &[OpTiming], duration: Duration) -> Self {
+        let mut result = Self::new(workload.name(), timings.len() as u32, duration);
+        for (i, step) in workload.steps().iter().enumerate() {
+            let samples: Vec<Duration> = timings
+                .iter()
+                .map(|t| if i == 0 { t.reflink } else { t.write })
+                .filter(|d| i == 0 || !d.is_zero())
+                .collect();
+            if i > 0 && samples.is_empty() {
+                continue;
+            }
+            let latency = LatencyStats::from_samples(&samples);
+            result = result
+                .with_metric(&format!("{}_p50_us", step), latency.p50.as_secs_f64() * 1e6)
+                .with_metric(&format!("{}_p99_us", step), latency.p99.as_secs_f64() * 1e6);
+        }
+        workload
+            .metrics(timings)
+            .into_iter()
+            .fold(result, |result, (name, value)| {
+                result.with_metric(&name, value)
+            })
+    }
+
+    fn with_metric(mut self, name: &str, value: f64) -> Self {
+        self.metrics.insert(name.to_string(), value);
+        self
+    }
+}
+
+/// Runs a single workload in its own directory under the mount point.
+///
+/// This is blocking and should be called from `spawn_blocking`.
+pub fn run_workload(
+    workload: WorkloadKind,
+    mount_point: &Path,
+    source_file: &Path,
+    config: &BenchmarkConfig,
+) -> Result<WorkloadResult> {
+    let dir = mount_point.join(workload.to_string());
+    std::fs::create_dir_all(&dir)
+        .context(format!("Failed to create {} workload directory", workload))?;
+
+    match workload {
+        WorkloadKind::PunchHole => run_punch_hole(&dir, source_file, config),
+        WorkloadKind::Fallocate => run_fallocate(&dir, source_file, config),
+        WorkloadKind::SmallFiles => run_small_files(&dir, config),
+        WorkloadKind::Mixed => run_mixed(&dir, source_file, config),
+        WorkloadKind::Overwrite => run_overwrite(&dir, source_file, config),
+        WorkloadKind::SameFile => run_same_file(&dir, source_file, config),
+        WorkloadKind::Metadata => run_metadata(&dir, source_file, config),
+        WorkloadKind::CloneWhileWriting => run_clone_while_writing(&dir, config),
+        WorkloadKind::Contention => run_contention(&dir, source_file, config),
+        WorkloadKind::CloneOnly
+        | WorkloadKind::Dedup
+        | WorkloadKind::Snapshot
+        | WorkloadKind::Read
+        | WorkloadKind::Database
+        | WorkloadKind::Container
+        | WorkloadKind::VmImage
+        | WorkloadKind::Backup
+        | WorkloadKind::BuildCache
+        | WorkloadKind::Reclaim
+        | WorkloadKind::AtomicReplace
+        | WorkloadKind::Unaligned => {
+            anyhow::bail!("{} workload runs through the Workload trait", workload)
+        }
+    }
+}
+
+/// Clones without the follow-up write, so the sample is the FICLONE alone.
+struct CloneOnly {
+    dir: PathBuf,
+    sources: Vec<PathBuf>,
+    count: u32,
+}
+
+impl Workload for CloneOnly {
+    fn name(&self) -> &str {
+        "clone-only"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["reflink"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create clone-only directory")
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        let source = &self.sources[index as usize % self.sources.len()];
+        let target = self.dir.join(format!("clone_{}.dat", index));
+        let start = Instant::now();
+        BenchmarkRunner::create_reflink_blocking(source, &target)?;
+        Ok(OpTiming {
+            reflink: start.elapsed(),
+            write: Duration::ZERO,
+        })
+    }
+}
+
+/// Writes separate copies of the start of the source, then deduplicates
+/// each against it, so every operation compares the data and then shares
+/// its extents.
+struct Dedup {
+    dir: PathBuf,
+    source: PathBuf,
+    count: u32,
+}
+
+impl Dedup {
+    fn target(&self, index: u32) -> PathBuf {
+        self.dir.join(format!("dedup_{}.dat", index))
+    }
+}
+
+impl Workload for Dedup {
+    fn name(&self) -> &str {
+        "dedup"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["dedupe"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        use std::io::Read;
+
+        std::fs::create_dir_all(&self.dir).context("Failed to create dedup directory")?;
+        // Copied by hand, since copy_file_range may reflink and leave
+        // nothing to deduplicate
+        let mut data = Vec::new();
+        std::fs::File::open(&self.source)
+            .context("Failed to open source file")?
+            .take(FALLOCATE_REGION_BYTES)
+            .read_to_end(&mut data)
+            .context("Failed to read source file")?;
+        for i in 0..self.count {
+            create_prefilled(&self.target(i), &data)
+                .context(format!("Failed to prepare dedup target {}", i))?;
+        }
+        filesystem::sync_filesystem(&self.dir)
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        let start = Instant::now();
+        let source = std::fs::File::open(&self.source).context("Failed to open source file")?;
+        let target = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(self.target(index))
+            .context("Failed to open dedup target")?;
+        let len = target
+            .metadata()
+            .context("Failed to stat dedup target")?
+            .len();
+        dedupe_range(&source, &target, len)
+            .context(format!("Failed to deduplicate target {}", index))?;
+        Ok(OpTiming {
+            reflink: start.elapsed(),
+            write: Duration::ZERO,
+        })
+    }
+}
+
+/// Files in the directory tree each snapshot operation copies.
+const SNAPSHOT_FILES: u32 = 16;
+
+/// Clones a directory of [`SNAPSHOT_FILES`] files into a new directory per
+/// operation, a filesystem-agnostic stand-in for snapshotting a small tree.
+/// Runs `reflink_count / SNAPSHOT_FILES` operations so the total number of
+/// clones matches the other phases.
+struct Snapshot {
+    dir: PathBuf,
+    sources: Vec<PathBuf>,
+    count: u32,
+    files: Vec<PathBuf>,
+}
+
+impl Workload for Snapshot {
+    fn name(&self) -> &str {
+        "snapshot"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["snapshot"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        let tree = self.dir.join("tree");
+        std::fs::create_dir_all(&tree).context("Failed to create snapshot tree")?;
+        self.files = (0..SNAPSHOT_FILES)
+            .map(|i| {
+                let file = tree.join(format!("file_{}.dat", i));
+                let source = &self.sources[i as usize % self.sources.len()];
+                BenchmarkRunner::create_reflink_and_write_blocking(source, &file)
+                    .context(format!("Failed to prepare snapshot file {}", i))?;
+                Ok(file)
+            })
+            .collect::<Result<_>>()?;
+        filesystem::sync_filesystem(&self.dir)
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        let snapshot = self.dir.join(format!("snapshot_{}", index));
+        let start = Instant::now();
+        std::fs::create_dir(&snapshot).context("Failed to create snapshot directory")?;
+        for file in &self.files {
+            let name = file.file_name().context("Snapshot file has no name")?;
+            BenchmarkRunner::create_reflink_blocking(file, &snapshot.join(name))
+                .context(format!("Failed to snapshot {}", file.display()))?;
+        }
+        Ok(OpTiming {
+            reflink: start.elapsed(),
+            write: Duration::ZERO,
+        })
+    }
+}
+
+/// Reads the first region of clones whose first block was CoW'd, so each
+/// read spans a private block and extents still shared with the source.
+struct ReadBack {
+    dir: PathBuf,
+    sources: Vec<PathBuf>,
+    count: u32,
+    read_len: u64,
+}
+
+impl ReadBack {
+    fn target(&self, index: u32) -> PathBuf {
+        self.dir.join(format!("read_{}.dat", index))
+    }
+}
+
+impl Workload for ReadBack {
+    fn name(&self) -> &str {
+        "read"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["read"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create read directory")?;
+        let file_len = std::fs::metadata(&self.sources[0])
+            .context("Failed to stat source file")?
+            .len();
+        self.read_len = FALLOCATE_REGION_BYTES.min(file_len);
+        for i in 0..self.count {
+            let source = &self.sources[i as usize % self.sources.len()];
+            BenchmarkRunner::create_reflink_and_write_blocking(source, &self.target(i))
+                .context(format!("Failed to prepare read target {}", i))?;
+        }
+        filesystem::sync_filesystem(&self.dir)
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        use std::os::unix::fs::FileExt;
+
+        let mut buf = vec![0u8; self.read_len as usize];
+        let start = Instant::now();
+        let file =
+            std::fs::File::open(self.target(index)).context("Failed to open clone for reading")?;
+        file.read_exact_at(&mut buf, 0)
+            .context("Failed to read from clone")?;
+        Ok(OpTiming {
+            reflink: start.elapsed(),
+            write: Duration::ZERO,
+        })
+    }
+}
+
+/// Clones the source, dirties the first block, then punches a hole over the
+/// whole clone. Only the CoW'd blocks are exclusive to each clone, so that is
+/// all the space that should come back.
+fn run_punch_hole(
+    dir: &Path,
+    source_file: &Path,
+    config: &BenchmarkConfig,
+) -> Result<WorkloadResult> {
+    let file_len = std::fs::metadata(source_file)
+        .context("Failed to stat source file")?
+        .len();
+
+    let mut targets = Vec::with_capacity(config.reflink_count as usize);
+    for i in 0..config.reflink_count {
+        let target = dir.join(format!("punch_{}.dat", i));
+        BenchmarkRunner::create_reflink_and_write_blocking(source_file, &target)
+            .context(format!("Failed to prepare punch-hole target {}", i))?;
+        targets.push(target);
+    }
+
+    filesystem::sync_filesystem(dir)?;
+    let free_before = filesystem::free_space_bytes(dir)?;
+
+    let start = Instant::now();
+    for target in &targets {
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(target)
+            .context("Failed to open punch-hole target")?;
+
+        fallocate(
+            &file,
+            libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+            0,
+            file_len,
+        )
+        .context("Hole punch failed")?;
+    }
+    let duration = start.elapsed();
+
+    filesystem::sync_filesystem(dir)?;
+    let free_after = filesystem::free_space_bytes(dir)?;
+    let reclaimed = free_after.saturating_sub(free_before);
+
+    Ok(
+        WorkloadResult::new(WorkloadKind::PunchHole, config.reflink_count, duration)
+            .with_metric("reclaimed_mb", reclaimed as f64 / (1024.0 * 1024.0))
+            .with_metric(
+                "reclaimed_kb_per_op",
+                reclaimed as f64 / 1024.0 / config.reflink_count.max(1) as f64,
+            ),
+    )
+}
+
+/// Size of the region unshared or rewritten by the fallocate workload.
+const FALLOCATE_REGION_BYTES: u64 = 1024 * 1024;
+
+/// Times three ways of getting a private copy of the first region of a clone:
+/// lazy CoW by overwriting it, explicit `FALLOC_FL_UNSHARE_RANGE` before the
+/// overwrite, and pre-allocating the target before cloning into it.
+fn run_fallocate(
+    dir: &Path,
+    source_file: &Path,
+    config: &BenchmarkConfig,
+) -> Result<WorkloadResult> {
+    let file_len = std::fs::metadata(source_file)
+        .context("Failed to stat source file")?
+        .len();
+    let region_len = FALLOCATE_REGION_BYTES.min(file_len);
+    let write_data = vec![WRITE_PATTERN; region_len as usize];
+
+    let mut lazy_time = Duration::ZERO;
+    let mut unshare_time = Duration::ZERO;
+    let mut prealloc_time = Duration::ZERO;
+    let mut unshare_supported = true;
+
+    for i in 0..config.reflink_count {
+        // Lazy CoW: clone, then let the overwrite unshare the region.
+        let target = dir.join(format!("lazy_{}.dat", i));
+        let start = Instant::now();
+        BenchmarkRunner::create_reflink_blocking(source_file, &target)?;
+        overwrite_region(&target, &write_data)?;
+        lazy_time += start.elapsed();
+
+        // Explicit unshare: clone, unshare the region, then overwrite it.
+        if unshare_supported {
+            let target = dir.join(format!("unshare_{}.dat", i));
+            let start = Instant::now();
+            BenchmarkRunner::create_reflink_blocking(source_file, &target)?;
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .open(&target)
+                .context("Failed to open unshare target")?;
+            match fallocate(&file, libc::FALLOC_FL_UNSHARE_RANGE, 0, region_len) {
+                Ok(()) => {
+                    overwrite_region(&target, &write_data)?;
+                    unshare_time += start.elapsed();
+                }
+                Err(e) if is_unsupported(&e) => {
+                    println!("    FALLOC_FL_UNSHARE_RANGE not supported, skipping unshare variant");
+                    unshare_supported = false;
+                }
+                Err(e) => return Err(e.context("Unshare failed")),
+            }
+        }
+
+        // Pre-allocate the target, then clone over it and overwrite.
+        let target = dir.join(format!("prealloc_{}.dat", i));
+        let start = Instant::now();
+        {
+            let file =
+                std::fs::File::create(&target).context("Failed to create prealloc target")?;
+            fallocate(&file, 0, 0, region_len).context("Pre-allocation failed")?;
+        }
+        BenchmarkRunner::create_reflink_blocking(source_file, &target)?;
+        overwrite_region(&target, &write_data)?;
+        prealloc_time += start.elapsed();
+
+        // Drop this iteration's clones so their unshared regions don't fill the filesystem
+        for variant in ["lazy", "unshare", "prealloc"] {
+            let _ = std::fs::remove_file(dir.join(format!("{}_{}.dat", variant, i)));
+        }
+    }
+
+    let count = config.reflink_count.max(1) as f64;
+    let avg_ms = |d: Duration| d.as_secs_f64() * 1000.0 / count;
+
+    let mut result = WorkloadResult::new(WorkloadKind::Fallocate, config.reflink_count, lazy_time)
+        .with_metric("lazy_cow_avg_ms", avg_ms(lazy_time))
+        .with_metric("prealloc_before_avg_ms", avg_ms(prealloc_time))
+        .with_metric(
+            "unshare_supported",
+            if unshare_supported { 1.0 } else { 0.0 },
+        );
+    if unshare_supported {
+        result = result.with_metric("unshare_avg_ms", avg_ms(unshare_time));
+    }
+
+    Ok(result)
+}
+
+/// Creates many small, independent source files and clones each one once, so
+/// the cost is dominated by inode and extent metadata rather than data size.
+fn run_small_files(dir: &Path, config: &BenchmarkConfig) -> Result<WorkloadResult> {
+    use rand::Rng;
+
+    let sources_dir = dir.join("sources");
+    let targets_dir = dir.join("targets");
+    std::fs::create_dir_all(&sources_dir).context("Failed to create sources directory")?;
+    std::fs::create_dir_all(&targets_dir).context("Failed to create targets directory")?;
+
+    let mut rng = benchmark::seeded_rng(config.seed, "small-files");
+    let mut total_bytes = 0u64;
+    for i in 0..config.small_file_count {
+        // 4-64 KB in whole 4 KB blocks
+        let size = rng.gen_range(1..=16) * 4096;
+        let mut data = vec![0u8; size];
+        rng.fill(&mut data[..]);
+        std::fs::write(sources_dir.join(format!("small_{}.dat", i)), &data)
+            .context(format!("Failed to create small source file {}", i))?;
+        total_bytes += size as u64;
+    }
+    filesystem::sync_filesystem(dir)?;
+
+    let start = Instant::now();
+    for i in 0..config.small_file_count {
+        let name = format!("small_{}.dat", i);
+        BenchmarkRunner::create_reflink_blocking(
+            &sources_dir.join(&name),
+            &targets_dir.join(&name),
+        )
+        .context(format!("Failed to reflink small file {}", i))?;
+    }
+    let duration = start.elapsed();
+
+    Ok(
+        WorkloadResult::new(WorkloadKind::SmallFiles, config.small_file_count, duration)
+            .with_metric("total_source_mb", total_bytes as f64 / (1024.0 * 1024.0))
+            .with_metric(
+                "avg_file_kb",
+                total_bytes as f64 / 1024.0 / config.small_file_count.max(1) as f64,
+            ),
+    )
+}
+
+#[derive(Default)]
+struct MixedStats {
+    reflinks: u32,
+    writes: u32,
+    reads: u32,
+    reflink_time: Duration,
+    write_time: Duration,
+    read_time: Duration,
+}
+
+/// Runs `mixed_workers` threads that each pick reflink, CoW write, or read
+/// operations according to the configured mix until they have done their
+/// share of `reflink_count` operations.
+fn run_mixed(dir: &Path, source_file: &Path, config: &BenchmarkConfig) -> Result<WorkloadResult> {
+    let file_len = std::fs::metadata(source_file)
+        .context("Failed to stat source file")?
+        .len();
+    let workers = config.mixed_workers.max(1);
+    let ops_per_worker = config.reflink_count.div_ceil(workers);
+    let mix = config.mix;
+
+    let start = Instant::now();
+    let worker_stats = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|worker| {
+                scope.spawn(move || {
+                    run_mixed_worker(
+                        dir,
+                        source_file,
+                        file_len,
+                        worker,
+                        ops_per_worker,
+                        mix,
+                        config.seed,
+                    )
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .map_err(|_| anyhow::anyhow!("Mixed worker panicked"))?
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+    let duration = start.elapsed();
+
+    let mut stats = MixedStats::default();
+    for worker in worker_stats {
+        stats.reflinks += worker.reflinks;
+        stats.writes += worker.writes;
+        stats.reads += worker.reads;
+        stats.reflink_time += worker.reflink_time;
+        stats.write_time += worker.write_time;
+        stats.read_time += worker.read_time;
+    }
+
+    let avg_us = |total: Duration, count: u32| total.as_secs_f64() * 1e6 / count.max(1) as f64;
+    let operations = stats.reflinks + stats.writes + stats.reads;
+
+    Ok(
+        WorkloadResult::new(WorkloadKind::Mixed, operations, duration)
+            .with_metric("workers", workers as f64)
+            .with_metric("reflinks", stats.reflinks as f64)
+            .with_metric("writes", stats.writes as f64)
+            .with_metric("reads", stats.reads as f64)
+            .with_metric("reflink_avg_us", avg_us(stats.reflink_time, stats.reflinks))
+            .with_metric("write_avg_us", avg_us(stats.write_time, stats.writes))
+            .with_metric("read_avg_us", avg_us(stats.read_time, stats.reads)),
+    )
+}
+
+fn run_mixed_worker(
+    dir: &Path,
+    source_file: &Path,
+    file_len: u64,
+    worker: u32,
+    operations: u32,
+    mix: OperationMix,
+    seed: u64,
+) -> Result<MixedStats> {
+    use rand::Rng;
+    use std::os::unix::fs::FileExt;
+
+    let mut rng = benchmark::seeded_rng(seed, &format!("mixed-worker-{}", worker));
+    let mut stats = MixedStats::default();
+    let mut clones: Vec<PathBuf> = Vec::new();
+    let blocks = (file_len / 4096).max(1);
+    let mut buf = vec![WRITE_PATTERN; 4096];
+
+    for _ in 0..operations {
+        let roll = rng.gen_range(0..100);
+        let offset = rng.gen_range(0..blocks) * 4096;
+
+        // Writes and reads need a clone to work on, so start with a reflink
+        if roll < mix.reflink || clones.is_empty() {
+            let target = dir.join(format!("mixed_{}_{}.dat", worker, clones.len()));
+            let start = Instant::now();
+            BenchmarkRunner::create_reflink_blocking(source_file, &target)?;
+            stats.reflink_time += start.elapsed();
+            stats.reflinks += 1;
+            clones.push(target);
+        } else if roll < mix.reflink + mix.write {
+            let target = &clones[rng.gen_range(0..clones.len())];
+            let start = Instant::now();
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .open(target)
+                .context("Failed to open clone for writing")?;
+            file.write_all_at(&buf, offset)
+                .context("Failed to write to clone")?;
+            stats.write_time += start.elapsed();
+            stats.writes += 1;
+        } else {
+            let target = &clones[rng.gen_range(0..clones.len())];
+            let start = Instant::now();
+            let file = std::fs::File::open(target).context("Failed to open clone for reading")?;
+            file.read_exact_at(&mut buf, offset)
+                .context("Failed to read from clone")?;
+            stats.read_time += start.elapsed();
+            stats.reads += 1;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Amount of existing data written into each target before it is cloned over.
+const OVERWRITE_PREFILL_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Pre-fills targets with their own data, then replaces it by cloning: the
+/// whole file with FICLONE, and a range in the middle with FICLONERANGE.
+/// Both have to drop the target's existing extents, unlike a fresh clone.
+fn run_overwrite(
+    dir: &Path,
+    source_file: &Path,
+    config: &BenchmarkConfig,
+) -> Result<WorkloadResult> {
+    use rand::Rng;
+
+    let file_len = std::fs::metadata(source_file)
+        .context("Failed to stat source file")?
+        .len();
+    let prefill_len = OVERWRITE_PREFILL_BYTES.min(file_len);
+    let range_len = FALLOCATE_REGION_BYTES.min(prefill_len / 2);
+    let mut prefill = vec![0u8; prefill_len as usize];
+    benchmark::seeded_rng(config.seed, "overwrite-prefill").fill(&mut prefill[..]);
+
+    let source = std::fs::File::open(source_file).context("Failed to open source file")?;
+    let mut clone_time = Duration::ZERO;
+    let mut clone_range_time = Duration::ZERO;
+
+    for i in 0..config.reflink_count {
+        let whole = dir.join(format!("whole_{}.dat", i));
+        let target = create_prefilled(&whole, &prefill)?;
+        let start = Instant::now();
+        clone_file(&source, &target).context(format!("Failed to clone over target {}", i))?;
+        clone_time += start.elapsed();
+
+        let ranged = dir.join(format!("range_{}.dat", i));
+        let target = create_prefilled(&ranged, &prefill)?;
+        let start = Instant::now();
+        clone_range(&source, 0, range_len, &target, prefill_len / 2)
+            .context(format!("Failed to clone range into target {}", i))?;
+        clone_range_time += start.elapsed();
+
+        let _ = std::fs::remove_file(&whole);
+        let _ = std::fs::remove_file(&ranged);
+    }
+
+    let count = config.reflink_count.max(1) as f64;
+    let avg_ms = |d: Duration| d.as_secs_f64() * 1000.0 / count;
+
+    Ok(WorkloadResult::new(
+        WorkloadKind::Overwrite,
+        config.reflink_count * 2,
+        clone_time + clone_range_time,
+    )
+    .with_metric("prefill_mb", prefill_len as f64 / (1024.0 * 1024.0))
+    .with_metric("ficlone_avg_ms", avg_ms(clone_time))
+    .with_metric("ficlonerange_avg_ms", avg_ms(clone_range_time)))
+}
+
+/// Clones one chunk of a file onto another chunk of the same file, so every
+/// operation remaps extents within a single inode.
+fn run_same_file(
+    dir: &Path,
+    source_file: &Path,
+    config: &BenchmarkConfig,
+) -> Result<WorkloadResult> {
+    use rand::Rng;
+
+    let file_len = std::fs::metadata(source_file)
+        .context("Failed to stat source file")?
+        .len();
+    // Two non-overlapping, block-aligned chunks at minimum
+    let chunk_len = (FALLOCATE_REGION_BYTES.min(file_len / 2) / 4096) * 4096;
+    if chunk_len == 0 {
+        anyhow::bail!("Source file is too small for the same-file workload");
+    }
+    let chunks = file_len / chunk_len;
+
+    // Work on a private copy so the source keeps its original layout
+    let work_path = dir.join("same_file.dat");
+    BenchmarkRunner::create_reflink_blocking(source_file, &work_path)?;
+    let work = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&work_path)
+        .context("Failed to open same-file target")?;
+
+    let mut rng = benchmark::seeded_rng(config.seed, "same-file");
+    let start = Instant::now();
+    for i in 0..config.reflink_count {
+        let src_chunk = i as u64 % chunks;
+        let dest_chunk = (src_chunk + rng.gen_range(1..chunks)) % chunks;
+        clone_range(
+            &work,
+            src_chunk * chunk_len,
+            chunk_len,
+            &work,
+            dest_chunk * chunk_len,
+        )
+        .context(format!("Failed to clone range {} within file", i))?;
+    }
+    let duration = start.elapsed();
+
+    Ok(
+        WorkloadResult::new(WorkloadKind::SameFile, config.reflink_count, duration)
+            .with_metric("chunk_kb", chunk_len as f64 / 1024.0)
+            .with_metric("chunks", chunks as f64),
+    )
+}
+
+/// Fills a directory with reflinked files, then times `stat`, `open`, and a
+/// full `readdir` pass over it.
+fn run_metadata(
+    dir: &Path,
+    source_file: &Path,
+    config: &BenchmarkConfig,
+) -> Result<WorkloadResult> {
+    let mut targets = Vec::with_capacity(config.reflink_count as usize);
+    for i in 0..config.reflink_count {
+        let target = dir.join(format!("meta_{}.dat", i));
+        BenchmarkRunner::create_reflink_and_write_blocking(source_file, &target)
+            .context(format!("Failed to prepare metadata target {}", i))?;
+        targets.push(target);
+    }
+    filesystem::sync_filesystem(dir)?;
+
+    let start = Instant::now();
+    for target in &targets {
+        std::fs::metadata(target).context("Failed to stat reflinked file")?;
+    }
+    let stat_time = start.elapsed();
+
+    let start = Instant::now();
+    for target in &targets {
+        std::fs::File::open(target).context("Failed to open reflinked file")?;
+    }
+    let open_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut entries = 0u32;
+    for entry in std::fs::read_dir(dir).context("Failed to read metadata directory")? {
+        entry.context("Failed to read directory entry")?;
+        entries += 1;
+    }
+    let readdir_time = start.elapsed();
+
+    let count = config.reflink_count.max(1) as f64;
+    let avg_us = |d: Duration| d.as_secs_f64() * 1e6 / count;
+
+    Ok(WorkloadResult::new(
+        WorkloadKind::Metadata,
+        config.reflink_count * 2 + entries,
+        stat_time + open_time + readdir_time,
+    )
+    .with_metric("stat_avg_us", avg_us(stat_time))
+    .with_metric("open_avg_us", avg_us(open_time))
+    .with_metric("readdir_ms", readdir_time.as_secs_f64() * 1000.0)
+    .with_metric("readdir_entries", entries as f64))
+}
+
+/// Bytes in each database page write.
+const DATABASE_PAGE_BYTES: u64 = 8 * 1024;
+
+/// Page writes, each made durable with fdatasync, between checkpoints.
+const DATABASE_WRITES_PER_CHECKPOINT: u32 = 100;
+
+/// Checkpoints kept; older ones are deleted untimed, as backups rotate.
+const DATABASE_CHECKPOINTS_KEPT: usize = 2;
+
+/// Copies the source into a database file with its own extents, then per
+/// operation does a random page write followed by fdatasync like a commit,
+/// and clones the whole file to a checkpoint every
+/// [`DATABASE_WRITES_PER_CHECKPOINT`] writes. Writes right after a
+/// checkpoint land on shared extents and pay for CoW.
+struct Database {
+    dir: PathBuf,
+    source: PathBuf,
+    count: u32,
+    seed: u64,
+    db: Option<std::fs::File>,
+    pages: u64,
+    checkpoints: Mutex<std::collections::VecDeque<PathBuf>>,
+    /// The first write after each checkpoint
+    first_writes: Mutex<Vec<Duration>>,
+}
+
+impl Workload for Database {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["write", "checkpoint"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create database directory")?;
+        let db_path = self.dir.join("database.db");
+        copy_without_reflink(&self.source, &db_path)?;
+        let db = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&db_path)
+            .context("Failed to open database file")?;
+        self.pages =
+            db.metadata().context("Failed to stat database file")?.len() / DATABASE_PAGE_BYTES;
+        if self.pages == 0 {
+            anyhow::bail!("Source file is too small for the database workload");
+        }
+        self.db = Some(db);
+        filesystem::sync_filesystem(&self.dir)
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        use rand::Rng;
+        use std::os::unix::fs::FileExt;
+
+        let db = self.db.as_ref().context("Database file isn't open")?;
+        let offset = benchmark::seeded_rng(self.seed, &format!("database-{}", index))
+            .gen_range(0..self.pages)
+            * DATABASE_PAGE_BYTES;
+        let page = [WRITE_PATTERN; DATABASE_PAGE_BYTES as usize];
+
+        let start = Instant::now();
+        db.write_all_at(&page, offset)
+            .context("Failed to write database page")?;
+        db.sync_data()
+            .context("Failed to fdatasync database file")?;
+        let write_time = start.elapsed();
+        if index.is_multiple_of(DATABASE_WRITES_PER_CHECKPOINT) && index > 0 {
+            self.first_writes.lock().unwrap().push(write_time);
+        }
+
+        let mut checkpoint_time = Duration::ZERO;
+        if (index + 1).is_multiple_of(DATABASE_WRITES_PER_CHECKPOINT) {
+            let number = index / DATABASE_WRITES_PER_CHECKPOINT;
+            let checkpoint = self.dir.join(format!("checkpoint_{}.db", number));
+            let start = Instant::now();
+            let target =
+                std::fs::File::create(&checkpoint).context("Failed to create checkpoint")?;
+            clone_file(db, &target).context(format!("Failed to checkpoint {}", number))?;
+            checkpoint_time = start.elapsed();
+
+            let mut checkpoints = self.checkpoints.lock().unwrap();
+            checkpoints.push_back(checkpoint);
+            if checkpoints.len() > DATABASE_CHECKPOINTS_KEPT {
+                if let Some(old) = checkpoints.pop_front() {
+                    let _ = std::fs::remove_file(old);
+                }
+            }
+        }
+
+        Ok(OpTiming {
+            reflink: write_time,
+            write: checkpoint_time,
+        })
+    }
+
+    fn metrics(&self, timings: &[OpTiming]) -> Vec<(String, f64)> {
+        let checkpoints = timings.iter().filter(|t| !t.write.is_zero()).count();
+        let mut metrics = vec![("checkpoints".to_string(), checkpoints as f64)];
+        let first_writes = self.first_writes.lock().unwrap();
+        if !first_writes.is_empty() {
+            let first = LatencyStats::from_samples(&first_writes);
+            metrics.push((
+                "post_checkpoint_write_p50_us".to_string(),
+                first.p50.as_secs_f64() * 1e6,
+            ));
+        }
+        metrics
+    }
+}
+
+/// Directories in the container workload's image layer.
+const CONTAINER_LAYER_DIRS: u32 = 10;
+
+/// Files in each directory of the container workload's image layer.
+const CONTAINER_FILES_PER_DIR: u32 = 10;
+
+/// Scattered 4 KB writes into each container's clone of the layer.
+const CONTAINER_WRITES: u32 = 20;
+
+/// Files in the container workload's image layer.
+const CONTAINER_LAYER_FILES: u32 = CONTAINER_LAYER_DIRS * CONTAINER_FILES_PER_DIR;
+
+/// Writes an image layer of small (4-64 KB) files in a few directories,
+/// then per container clones the whole tree, as a reflink snapshotter
+/// prepares a container's root, and makes scattered 4 KB writes into it.
+/// Runs `reflink_count / files in the layer` containers so the total number
+/// of clones matches the other phases.
+struct Container {
+    dir: PathBuf,
+    count: u32,
+    seed: u64,
+    /// Each layer file's path within the layer, and its size
+    files: Vec<(PathBuf, u64)>,
+    /// Each container's clone plus its first write
+    first_writes: Mutex<Vec<Duration>>,
+    writes: Mutex<Vec<Duration>>,
+}
+
+impl Workload for Container {
+    fn name(&self) -> &str {
+        "container"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["layer_clone", "writes"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        use rand::Rng;
+
+        let mut rng = benchmark::seeded_rng(self.seed, "container");
+        let layer = self.dir.join("layer");
+        for d in 0..CONTAINER_LAYER_DIRS {
+            let subdir = PathBuf::from(format!("dir_{}", d));
+            std::fs::create_dir_all(layer.join(&subdir))
+                .context("Failed to create layer directory")?;
+            for f in 0..CONTAINER_FILES_PER_DIR {
+                let size = rng.gen_range(1..=16) * 4096;
+                let mut data = vec![0u8; size];
+                rng.fill(&mut data[..]);
+                let file = subdir.join(format!("file_{}", f));
+                std::fs::write(layer.join(&file), &data)
+                    .context(format!("Failed to create layer file {}", file.display()))?;
+                self.files.push((file, size as u64));
+            }
+        }
+        filesystem::sync_filesystem(&self.dir)
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        use rand::Rng;
+        use std::os::unix::fs::FileExt;
+
+        let mut rng = benchmark::seeded_rng(self.seed, &format!("container-{}", index));
+        let page = [WRITE_PATTERN; 4096];
+        let layer = self.dir.join("layer");
+        let root = self.dir.join(format!("container_{}", index));
+
+        let start = Instant::now();
+        for d in 0..CONTAINER_LAYER_DIRS {
+            std::fs::create_dir_all(root.join(format!("dir_{}", d)))
+                .context("Failed to create container directory")?;
+        }
+        for (file, _) in &self.files {
+            BenchmarkRunner::create_reflink_blocking(&layer.join(file), &root.join(file))
+                .context(format!("Failed to clone layer file {}", file.display()))?;
+        }
+        let clone_time = start.elapsed();
+
+        let mut write_times = Vec::with_capacity(CONTAINER_WRITES as usize);
+        for _ in 0..CONTAINER_WRITES {
+            let (file, size) = &self.files[rng.gen_range(0..self.files.len())];
+            let offset = rng.gen_range(0..size / 4096) * 4096;
+            let start = Instant::now();
+            let target = std::fs::OpenOptions::new()
+                .write(true)
+                .open(root.join(file))
+                .context("Failed to open container file")?;
+            target
+                .write_all_at(&page, offset)
+                .context("Failed to write to container file")?;
+            write_times.push(start.elapsed());
+        }
+
+        let writes = write_times.iter().sum();
+        self.first_writes
+            .lock()
+            .unwrap()
+            .push(clone_time + write_times[0]);
+        self.writes.lock().unwrap().extend(write_times);
+        Ok(OpTiming {
+            reflink: clone_time,
+            write: writes,
+        })
+    }
+
+    fn metrics(&self, _timings: &[OpTiming]) -> Vec<(String, f64)> {
+        let first_write = LatencyStats::from_samples(&self.first_writes.lock().unwrap());
+        let writes = LatencyStats::from_samples(&self.writes.lock().unwrap());
+        let us = |d: Duration| d.as_secs_f64() * 1e6;
+        vec![
+            ("layer_files".to_string(), CONTAINER_LAYER_FILES as f64),
+            (
+                "time_to_first_write_p50_ms".to_string(),
+                first_write.p50.as_secs_f64() * 1000.0,
+            ),
+            ("write_p50_us".to_string(), us(writes.p50)),
+            ("write_p99_us".to_string(), us(writes.p99)),
+        ]
+    }
+}
+
+/// Apparent size of the vm-image workload's sparse base image.
+const VM_IMAGE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// The base image has this much data written at the start of every
+/// [`VM_IMAGE_EXTENT_STRIDE`], like an installed guest with free space.
+const VM_IMAGE_EXTENT_BYTES: u64 = 1024 * 1024;
+const VM_IMAGE_EXTENT_STRIDE: u64 = 64 * 1024 * 1024;
+
+/// Bytes in each guest write.
+const VM_WRITE_BYTES: u64 = 64 * 1024;
+
+/// Random writes into each VM's image.
+const VM_WRITES: u32 = 256;
+
+/// Writes at the start of each VM that break up freshly shared extents;
+/// they are left out of the steady-state IOPS.
+const VM_WARMUP_WRITES: u32 = 32;
+
+/// Writes a sparse [`VM_IMAGE_BYTES`] base image, then per VM clones it
+/// whole, as libvirt would instead of creating a qcow2 overlay, and makes
+/// [`VM_WRITES`] random 64 KB writes into the clone with O_DIRECT, as a
+/// guest with `cache=none` does. Runs `reflink_count / 100` VMs.
+struct VmImage {
+    dir: PathBuf,
+    count: u32,
+    seed: u64,
+    base: Option<std::fs::File>,
+    /// Holds a [`VM_WRITE_BYTES`] block aligned for O_DIRECT
+    buf: Vec<u8>,
+    writes: Mutex<Vec<Duration>>,
+    /// Writes past each VM's first [`VM_WARMUP_WRITES`], and their total time
+    steady: Mutex<(u32, Duration)>,
+}
+
+impl VmImage {
+    fn data(&self) -> &[u8] {
+        let align = self.buf.as_ptr().align_offset(4096);
+        &self.buf[align..align + VM_WRITE_BYTES as usize]
+    }
+}
+
+impl Workload for VmImage {
+    fn name(&self) -> &str {
+        "vm-image"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["clone", "writes"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        use std::os::unix::fs::FileExt;
+
+        std::fs::create_dir_all(&self.dir).context("Failed to create vm-image directory")?;
+        // FICLONE needs its source open for reading
+        let base = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(self.dir.join("base.img"))
+            .context("Failed to create base image")?;
+        base.set_len(VM_IMAGE_BYTES)
+            .context("Failed to size base image")?;
+        let extent = vec![WRITE_PATTERN; VM_IMAGE_EXTENT_BYTES as usize];
+        for offset in (0..VM_IMAGE_BYTES).step_by(VM_IMAGE_EXTENT_STRIDE as usize) {
+            base.write_all_at(&extent, offset)
+                .context("Failed to write base image")?;
+        }
+        base.sync_all().context("Failed to sync base image")?;
+        self.base = Some(base);
+
+        // O_DIRECT needs a buffer aligned to the logical block size
+        self.buf = vec![WRITE_PATTERN; (VM_WRITE_BYTES + 4096) as usize];
+        Ok(())
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        use rand::Rng;
+        use std::os::unix::fs::{FileExt, OpenOptionsExt};
+
+        let base = self.base.as_ref().context("Base image isn't open")?;
+        let data = self.data();
+        let mut rng = benchmark::seeded_rng(self.seed, &format!("vm-image-{}", index));
+        let image_path = self.dir.join(format!("vm_{}.img", index));
+
+        let start = Instant::now();
+        let image = std::fs::File::create(&image_path).context("Failed to create VM image")?;
+        clone_file(base, &image).context(format!("Failed to clone image for VM {}", index))?;
+        let clone_time = start.elapsed();
+        drop(image);
+
+        let image = std::fs::OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(&image_path)
+            .context("Failed to open VM image with O_DIRECT")?;
+        let mut write_times = Vec::with_capacity(VM_WRITES as usize);
+        for _ in 0..VM_WRITES {
+            let offset = rng.gen_range(0..VM_IMAGE_BYTES / VM_WRITE_BYTES) * VM_WRITE_BYTES;
+            let start = Instant::now();
+            image
+                .write_all_at(data, offset)
+                .context("Failed to write VM image")?;
+            write_times.push(start.elapsed());
+        }
+
+        let writes = write_times.iter().sum();
+        {
+            let mut steady = self.steady.lock().unwrap();
+            let warm = &write_times[VM_WARMUP_WRITES as usize..];
+            steady.0 += warm.len() as u32;
+            steady.1 += warm.iter().sum::<Duration>();
+        }
+        self.writes.lock().unwrap().extend(write_times);
+        Ok(OpTiming {
+            reflink: clone_time,
+            write: writes,
+        })
+    }
+
+    fn metrics(&self, _timings: &[OpTiming]) -> Vec<(String, f64)> {
+        let writes = LatencyStats::from_samples(&self.writes.lock().unwrap());
+        let (steady_writes, steady_time) = *self.steady.lock().unwrap();
+        let us = |d: Duration| d.as_secs_f64() * 1e6;
+        vec![
+            ("vms".to_string(), self.count as f64),
+            ("write_p50_us".to_string(), us(writes.p50)),
+            ("write_p99_us".to_string(), us(writes.p99)),
+            (
+                "steady_write_iops".to_string(),
+                steady_writes as f64 / steady_time.as_secs_f64().max(f64::EPSILON),
+            ),
+        ]
+    }
+}
+
+/// Block size the backup workload rewrites in.
+const BACKUP_BLOCK_BYTES: u64 = 4096;
+
+/// Copies the source into a private dataset and reflinks it to generation
+/// 0, then per operation rewrites `backup_change_percent` of its blocks,
+/// chosen at random, and reflinks it to the next generation, keeping every
+/// generation like rsnapshot or borg. Runs `reflink_count / 50`
+/// generations. Free space is measured after each generation, so growth
+/// can be compared with the bytes changed.
+struct Backup {
+    dir: PathBuf,
+    source: PathBuf,
+    count: u32,
+    seed: u64,
+    change_percent: u32,
+    dataset: Option<std::fs::File>,
+    blocks: u64,
+    changed: usize,
+    /// Free space after generation 0 and after the latest generation
+    free: Mutex<(u64, u64)>,
+    /// Free space each generation took
+    growth: Mutex<Vec<u64>>,
+}
+
+impl Backup {
+    fn snapshot(&self, dataset: &std::fs::File, generation: u32) -> Result<Duration> {
+        let start = Instant::now();
+        let target = std::fs::File::create(self.dir.join(format!("generation_{}.dat", generation)))
+            .context("Failed to create backup generation")?;
+        clone_file(dataset, &target)
+            .context(format!("Failed to clone backup generation {}", generation))?;
+        Ok(start.elapsed())
+    }
+}
+
+impl Workload for Backup {
+    fn name(&self) -> &str {
+        "backup"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["rewrite", "clone"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create backup directory")?;
+        let dataset_path = self.dir.join("dataset.dat");
+        copy_without_reflink(&self.source, &dataset_path)?;
+        // FICLONE needs its source open for reading
+        let dataset = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&dataset_path)
+            .context("Failed to open backup dataset")?;
+        self.blocks = dataset
+            .metadata()
+            .context("Failed to stat backup dataset")?
+            .len()
+            / BACKUP_BLOCK_BYTES;
+        if self.blocks == 0 {
+            anyhow::bail!("Source file is too small for the backup workload");
+        }
+        self.changed = (self.blocks * self.change_percent as u64 / 100).max(1) as usize;
+
+        self.snapshot(&dataset, 0)?;
+        self.dataset = Some(dataset);
+        filesystem::sync_filesystem(&self.dir)?;
+        let free = filesystem::free_space_bytes(&self.dir)?;
+        *self.free.lock().unwrap() = (free, free);
+        Ok(())
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        use std::os::unix::fs::FileExt;
+
+        let dataset = self.dataset.as_ref().context("Backup dataset isn't open")?;
+        let generation = index + 1;
+        let mut rng = benchmark::seeded_rng(self.seed, &format!("backup-{}", generation));
+        let blocks = rand::seq::index::sample(&mut rng, self.blocks as usize, self.changed);
+        let block = [WRITE_PATTERN; BACKUP_BLOCK_BYTES as usize];
+
+        let start = Instant::now();
+        for changed in blocks {
+            dataset
+                .write_all_at(&block, changed as u64 * BACKUP_BLOCK_BYTES)
+                .context("Failed to modify backup dataset")?;
+        }
+        let rewrite_time = start.elapsed();
+        let clone_time = self.snapshot(dataset, generation)?;
+
+        filesystem::sync_filesystem(&self.dir)?;
+        let free_after = filesystem::free_space_bytes(&self.dir)?;
+        let mut free = self.free.lock().unwrap();
+        self.growth
+            .lock()
+            .unwrap()
+            .push(free.1.saturating_sub(free_after));
+        free.1 = free_after;
+
+        Ok(OpTiming {
+            reflink: rewrite_time,
+            write: clone_time,
+        })
+    }
+
+    fn metrics(&self, timings: &[OpTiming]) -> Vec<(String, f64)> {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mb = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+        let (free_start, free_end) = *self.free.lock().unwrap();
+        let growth = self.growth.lock().unwrap();
+        let generations = growth.len().max(1) as f64;
+        let total_growth = free_start.saturating_sub(free_end);
+        let changed_bytes = (self.changed as u64 * BACKUP_BLOCK_BYTES) as f64 * generations;
+        let mut metrics = vec![
+            ("change_percent".to_string(), self.change_percent as f64),
+            (
+                "growth_per_generation_mb".to_string(),
+                mb(total_growth) / generations,
+            ),
+            (
+                "growth_max_mb".to_string(),
+                mb(growth.iter().copied().max().unwrap_or(0)),
+            ),
+            ("total_growth_mb".to_string(), mb(total_growth)),
+            (
+                "space_amplification".to_string(),
+                total_growth as f64 / changed_bytes,
+            ),
+        ];
+        if let (Some(first), Some(last)) = (timings.first(), timings.last()) {
+            metrics.push(("clone_first_ms".to_string(), ms(first.write)));
+            metrics.push(("clone_last_ms".to_string(), ms(last.write)));
+        }
+        metrics
+    }
+}
+
+/// Directories in the build-cache workload's cache.
+const BUILD_CACHE_DIRS: u32 = 40;
+
+/// Files in each directory of the build-cache workload's cache.
+const BUILD_CACHE_FILES_PER_DIR: u32 = 50;
+
+/// Percentage of a build directory's files that get their first half
+/// rewritten, as a rebuild replaces some outputs.
+const BUILD_CACHE_REWRITE_PERCENT: usize = 10;
+
+/// Writes a cache of 4-256 KB files across [`BUILD_CACHE_DIRS`]
+/// directories, then per build clones the whole tree into a fresh build
+/// directory and rewrites the first half of [`BUILD_CACHE_REWRITE_PERCENT`]
+/// of the files. Runs `reflink_count / 200` builds. The directory clone
+/// time is the headline: it is what a build waits for before starting.
+struct BuildCache {
+    dir: PathBuf,
+    count: u32,
+    seed: u64,
+    /// Each cache file's path within the cache, and its size
+    files: Vec<(PathBuf, u64)>,
+    cache_bytes: u64,
+    /// Half the largest file, sliced for each rewrite
+    data: Vec<u8>,
+}
+
+impl Workload for BuildCache {
+    fn name(&self) -> &str {
+        "build-cache"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["dir_clone", "rewrite"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        use rand::Rng;
+
+        let mut rng = benchmark::seeded_rng(self.seed, "build-cache");
+        let cache = self.dir.join("cache");
+        for d in 0..BUILD_CACHE_DIRS {
+            let subdir = PathBuf::from(format!("dir_{}", d));
+            std::fs::create_dir_all(cache.join(&subdir))
+                .context("Failed to create cache directory")?;
+            for f in 0..BUILD_CACHE_FILES_PER_DIR {
+                let size = 4096usize << rng.gen_range(0..=6);
+                let mut data = vec![0u8; size];
+                rng.fill(&mut data[..]);
+                let file = subdir.join(format!("file_{}.o", f));
+                std::fs::write(cache.join(&file), &data)
+                    .context(format!("Failed to create cache file {}", file.display()))?;
+                self.files.push((file, size as u64));
+                self.cache_bytes += size as u64;
+            }
+        }
+        let largest = self.files.iter().map(|(_, size)| *size).max().unwrap_or(0);
+        self.data = vec![WRITE_PATTERN; (largest / 2).max(4096) as usize];
+        filesystem::sync_filesystem(&self.dir)
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        use std::os::unix::fs::FileExt;
+
+        let mut rng = benchmark::seeded_rng(self.seed, &format!("build-cache-{}", index));
+        let rewrites = (self.files.len() * BUILD_CACHE_REWRITE_PERCENT / 100).max(1);
+        let rewritten = rand::seq::index::sample(&mut rng, self.files.len(), rewrites);
+        let cache = self.dir.join("cache");
+        let build = self.dir.join(format!("build_{}", index));
+
+        let start = Instant::now();
+        for d in 0..BUILD_CACHE_DIRS {
+            std::fs::create_dir_all(build.join(format!("dir_{}", d)))
+                .context("Failed to create build directory")?;
+        }
+        for (file, _) in &self.files {
+            BenchmarkRunner::create_reflink_blocking(&cache.join(file), &build.join(file))
+                .context(format!("Failed to clone cache file {}", file.display()))?;
+        }
+        let clone_time = start.elapsed();
+
+        let start = Instant::now();
+        for file in rewritten {
+            let (file, size) = &self.files[file];
+            let target = std::fs::OpenOptions::new()
+                .write(true)
+                .open(build.join(file))
+                .context("Failed to open build output")?;
+            target
+                .write_all_at(&self.data[..(*size / 2).max(4096) as usize], 0)
+                .context("Failed to rewrite build output")?;
+        }
+        Ok(OpTiming {
+            reflink: clone_time,
+            write: start.elapsed(),
+        })
+    }
+
+    fn metrics(&self, timings: &[OpTiming]) -> Vec<(String, f64)> {
+        let clones =
+            LatencyStats::from_samples(&timings.iter().map(|t| t.reflink).collect::<Vec<_>>());
+        vec![
+            (
+                "clone_per_file_us".to_string(),
+                clones.p50.as_secs_f64() * 1e6 / self.files.len().max(1) as f64,
+            ),
+            ("files".to_string(), self.files.len() as f64),
+            (
+                "cache_mb".to_string(),
+                self.cache_bytes as f64 / 1024.0 / 1024.0,
+            ),
+        ]
+    }
+}
+
+/// Bytes in each of the clone-while-writing workload's writes; each is
+/// filled with one byte value, so a clone holding part of one is torn.
+const MUTATION_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// Chunks the clone-while-writing source starts with (16 MB).
+const MUTATION_INITIAL_CHUNKS: u64 = 256;
+
+/// The writer stops appending, and only overwrites, past this many chunks.
+const MUTATION_MAX_CHUNKS: u64 = 4 * MUTATION_INITIAL_CHUNKS;
+
+/// Threads cloning the source while it is written.
+const MUTATION_CLONERS: u32 = 4;
+
+/// Writes a source of uniformly filled chunks and times 32 clones of it at
+/// rest. Then [`MUTATION_CLONERS`] threads clone it `reflink_count` times
+/// in total while a writer thread appends chunks (a quarter of the time)
+/// and overwrites random ones. `FICLONE` flushes and locks the source
+/// against writes differently on each filesystem; however it does, every
+/// clone must be a whole number of chunks, each with a single byte value.
+/// Clones are checked untimed and deleted as they go.
+fn run_clone_while_writing(dir: &Path, config: &BenchmarkConfig) -> Result<WorkloadResult> {
+    use rand::Rng;
+    use std::os::unix::fs::FileExt;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    let source_path = dir.join("mutating.dat");
+    let source = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .read(true)
+        .write(true)
+        .open(&source_path)
+        .context("Failed to create mutating source")?;
+    for chunk in 0..MUTATION_INITIAL_CHUNKS {
+        source
+            .write_all_at(
+                &vec![chunk as u8; MUTATION_CHUNK_BYTES as usize],
+                chunk * MUTATION_CHUNK_BYTES,
+            )
+            .context("Failed to write mutating source")?;
+    }
+    source
+        .sync_all()
+        .context("Failed to sync mutating source")?;
+
+    let mut idle_times = Vec::new();
+    for i in 0..32 {
+        let target = dir.join(format!("idle_{}.dat", i));
+        let start = Instant::now();
+        BenchmarkRunner::create_reflink_blocking(&source_path, &target)?;
+        idle_times.push(start.elapsed());
+        let _ = std::fs::remove_file(target);
+    }
+
+    let done = AtomicBool::new(false);
+    let torn = AtomicU32::new(0);
+    let per_cloner = config.reflink_count.div_ceil(MUTATION_CLONERS);
+
+    let start = Instant::now();
+    let (write_times, clone_times) = std::thread::scope(|scope| -> Result<_> {
+        let writer = scope.spawn(|| -> Result<Vec<Duration>> {
+            let mut rng = benchmark::seeded_rng(config.seed, "clone-while-writing");
+            let mut chunks = MUTATION_INITIAL_CHUNKS;
+            let mut times = Vec::new();
+            while !done.load(Ordering::Relaxed) {
+                let chunk = if chunks < MUTATION_MAX_CHUNKS && rng.gen_range(0..4) == 0 {
+                    chunks += 1;
+                    chunks - 1
+                } else {
+                    rng.gen_range(0..chunks)
+                };
+                let data = vec![rng.gen::<u8>(); MUTATION_CHUNK_BYTES as usize];
+                let write_start = Instant::now();
+                source
+                    .write_all_at(&data, chunk * MUTATION_CHUNK_BYTES)
+                    .context("Failed to write mutating source")?;
+                times.push(write_start.elapsed());
+            }
+            Ok(times)
+        });
+
+        let cloners: Vec<_> = (0..MUTATION_CLONERS)
+            .map(|cloner| {
+                let (source_path, torn) = (&source_path, &torn);
+                scope.spawn(move || -> Result<Vec<Duration>> {
+                    let mut times = Vec::with_capacity(per_cloner as usize);
+                    for i in 0..per_cloner {
+                        let target = dir.join(format!("clone_{}_{}.dat", cloner, i));
+                        let clone_start = Instant::now();
+                        BenchmarkRunner::create_reflink_blocking(source_path, &target)?;
+                        times.push(clone_start.elapsed());
+                        if !clone_is_whole_chunks(&target)? {
+                            torn.fetch_add(1, Ordering::Relaxed);
+                        }
+                        let _ = std::fs::remove_file(&target);
+                    }
+                    Ok(times)
+                })
+            })
+            .collect();
+
+        let mut clone_times = Vec::new();
+        let mut result = Ok(());
+        for cloner in cloners {
+            // Keep joining so the writer is always told to stop
+            match cloner.join() {
+                Ok(Ok(times)) => clone_times.extend(times),
+                Ok(Err(e)) => result = Err(e),
+                Err(_) => result = Err(anyhow::anyhow!("Cloner panicked")),
+            }
+        }
+        done.store(true, Ordering::Relaxed);
+        let write_times = writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("Writer panicked"))??;
+        result?;
+        Ok((write_times, clone_times))
+    })?;
+    let duration = start.elapsed();
+
+    let idle = LatencyStats::from_samples(&idle_times);
+    let clones = LatencyStats::from_samples(&clone_times);
+    let writes = LatencyStats::from_samples(&write_times);
+    let us = |d: Duration| d.as_secs_f64() * 1e6;
+    Ok(WorkloadResult::new(
+        WorkloadKind::CloneWhileWriting,
+        clone_times.len() as u32,
+        duration,
+    )
+    .with_metric("idle_clone_p50_us", us(idle.p50))
+    .with_metric("clone_p50_us", us(clones.p50))
+    .with_metric("clone_p99_us", us(clones.p99))
+    .with_metric("source_writes", write_times.len() as f64)
+    .with_metric("source_write_p99_us", us(writes.p99))
+    .with_metric("torn_clones", torn.load(Ordering::Relaxed) as f64))
+}
+
+/// Whether a clone from [`run_clone_while_writing`] is a whole number of
+/// chunks, each filled with a single byte value.
+fn clone_is_whole_chunks(path: &Path) -> Result<bool> {
+    let data = std::fs::read(path).context("Failed to read clone")?;
+    Ok((data.len() as u64).is_multiple_of(MUTATION_CHUNK_BYTES)
+        && data
+            .chunks(MUTATION_CHUNK_BYTES as usize)
+            .all(|chunk| chunk.iter().all(|&byte| byte == chunk[0])))
+}
+
+/// Clones of the source that the contention workload reads and writes.
+const CONTENTION_FILES: u32 = 8;
+
+/// Threads reading the clones in the contention workload.
+const CONTENTION_READERS: u32 = 4;
+
+/// Threads unsharing the clones while they are read.
+const CONTENTION_WRITERS: u32 = 2;
+
+/// Bytes in each read and write of the contention workload.
+const CONTENTION_IO_BYTES: u64 = 64 * 1024;
+
+/// The writers fdatasync after this many writes, so extents are actually
+/// unshared while the readers run rather than at some later writeback.
+const CONTENTION_WRITES_PER_SYNC: u32 = 16;
+
+/// Clones the source [`CONTENTION_FILES`] times, then times
+/// `reflink_count` random 64 KB reads spread over [`CONTENTION_READERS`]
+/// threads twice: first with the clones at rest, then while
+/// [`CONTENTION_WRITERS`] threads overwrite random 64 KB ranges of the same
+/// clones. Reads after the first are mostly from page cache, so the
+/// difference is how long reads wait on the locks and extent map updates
+/// that unsharing takes.
+fn run_contention(
+    dir: &Path,
+    source_file: &Path,
+    config: &BenchmarkConfig,
+) -> Result<WorkloadResult> {
+    use rand::Rng;
+    use std::os::unix::fs::FileExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let mut files = Vec::with_capacity(CONTENTION_FILES as usize);
+    for i in 0..CONTENTION_FILES {
+        let target = dir.join(format!("shared_{}.dat", i));
+        BenchmarkRunner::create_reflink_blocking(source_file, &target)?;
+        files.push(
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&target)
+                .context("Failed to open clone")?,
+        );
+    }
+    let chunks = files[0].metadata().context("Failed to stat clone")?.len() / CONTENTION_IO_BYTES;
+    if chunks == 0 {
+        anyhow::bail!("Source file is too small for the contention workload");
+    }
+    let per_reader = config.reflink_count.div_ceil(CONTENTION_READERS);
+
+    let read = |phase: &str, reader: u32| -> Result<Vec<Duration>> {
+        let mut rng = benchmark::seeded_rng(
+            config.seed,
+            &format!("contention-{}-reader-{}", phase, reader),
+        );
+        let mut buf = vec![0u8; CONTENTION_IO_BYTES as usize];
+        let mut times = Vec::with_capacity(per_reader as usize);
+        for _ in 0..per_reader {
+            let file = &files[rng.gen_range(0..files.len())];
+            let offset = rng.gen_range(0..chunks) * CONTENTION_IO_BYTES;
+            let start = Instant::now();
+            file.read_exact_at(&mut buf, offset)
+                .context("Failed to read clone")?;
+            times.push(start.elapsed());
+        }
+        Ok(times)
+    };
+    let read_all = |phase: &str| -> Result<Vec<Duration>> {
+        std::thread::scope(|scope| {
+            let readers: Vec<_> = (0..CONTENTION_READERS)
+                .map(|reader| scope.spawn(move || read(phase, reader)))
+                .collect();
+            let mut times = Vec::new();
+            for reader in readers {
+                times.extend(
+                    reader
+                        .join()
+                        .map_err(|_| anyhow::anyhow!("Reader panicked"))??,
+                );
+            }
+            Ok(times)
+        })
+    };
+
+    let idle_times = read_all("idle")?;
+
+    let done = AtomicBool::new(false);
+    let start = Instant::now();
+    let (read_times, write_times) = std::thread::scope(|scope| -> Result<_> {
+        let writers: Vec<_> = (0..CONTENTION_WRITERS)
+            .map(|writer| {
+                let (files, done) = (&files, &done);
+                scope.spawn(move || -> Result<Vec<Duration>> {
+                    let mut rng = benchmark::seeded_rng(
+                        config.seed,
+                        &format!("contention-writer-{}", writer),
+                    );
+                    let data = vec![WRITE_PATTERN; CONTENTION_IO_BYTES as usize];
+                    let mut times = Vec::new();
+                    while !done.load(Ordering::Relaxed) {
+                        let file = &files[rng.gen_range(0..files.len())];
+                        let offset = rng.gen_range(0..chunks) * CONTENTION_IO_BYTES;
+                        let write_start = Instant::now();
+                        file.write_all_at(&data, offset)
+                            .context("Failed to write clone")?;
+                        if times.len() as u32 % CONTENTION_WRITES_PER_SYNC
+                            == CONTENTION_WRITES_PER_SYNC - 1
+                        {
+                            file.sync_data().context("Failed to fdatasync clone")?;
+                        }
+                        times.push(write_start.elapsed());
+                    }
+                    Ok(times)
+                })
+            })
+            .collect();
+
+        let read_times = read_all("contended");
+        done.store(true, Ordering::Relaxed);
+        let mut write_times = Vec::new();
+        for writer in writers {
+            write_times.extend(
+                writer
+                    .join()
+                    .map_err(|_| anyhow::anyhow!("Writer panicked"))??,
+            );
+        }
+        Ok((read_times?, write_times))
+    })?;
+    let duration = start.elapsed();
+
+    let idle = LatencyStats::from_samples(&idle_times);
+    let reads = LatencyStats::from_samples(&read_times);
+    let writes = LatencyStats::from_samples(&write_times);
+    let us = |d: Duration| d.as_secs_f64() * 1e6;
+    Ok(
+        WorkloadResult::new(WorkloadKind::Contention, read_times.len() as u32, duration)
+            .with_metric("idle_read_p50_us", us(idle.p50))
+            .with_metric("idle_read_p99_us", us(idle.p99))
+            .with_metric("read_p50_us", us(reads.p50))
+            .with_metric("read_p99_us", us(reads.p99))
+            .with_metric(
+                "read_p99_slowdown",
+                reads.p99.as_secs_f64() / idle.p99.as_secs_f64().max(f64::EPSILON),
+            )
+            .with_metric("writes", write_times.len() as f64)
+            .with_metric("write_p50_us", us(writes.p50)),
+    )
+}
+
+/// Clones sharing each reclaim workload copy's extents, besides the copy.
+const RECLAIM_CLONES: u32 = 32;
+
+/// How often the reclaim workload polls free space.
+const RECLAIM_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// How long the reclaim workload waits for space to come back.
+const RECLAIM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How the reclaim workload frees a file.
+#[derive(Debug, Clone, Copy)]
+enum ReclaimMethod {
+    Truncate,
+    Unlink,
+}
+
+impl Display for ReclaimMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReclaimMethod::Truncate => write!(f, "truncate"),
+            ReclaimMethod::Unlink => write!(f, "unlink"),
+        }
+    }
+}
+
+/// The reclaim workload's operations, in order.
+const RECLAIM_METHODS: [ReclaimMethod; 2] = [ReclaimMethod::Truncate, ReclaimMethod::Unlink];
+
+/// For `truncate(0)` and then `unlink`, one operation each: copies the
+/// source to a file with its own extents and clones it [`RECLAIM_CLONES`]
+/// times, syncs, then frees the copy and every clone, so the extents lose
+/// their last owner. Besides the calls themselves, times how long until
+/// statfs shows 90% of the file's size free again, polling without syncing:
+/// XFS frees unlinked inodes in background inode GC and btrfs only after a
+/// transaction commits, so the space can take far longer to come back than
+/// the calls take to return.
+struct Reclaim {
+    dir: PathBuf,
+    source: PathBuf,
+    file_len: u64,
+    metrics: Mutex<Vec<(String, f64)>>,
+}
+
+impl Reclaim {
+    fn files(&self, method: ReclaimMethod) -> Vec<PathBuf> {
+        std::iter::once(self.dir.join(format!("{}_base.dat", method)))
+            .chain((0..RECLAIM_CLONES).map(|i| self.dir.join(format!("{}_{}.dat", method, i))))
+            .collect()
+    }
+}
+
+impl Workload for Reclaim {
+    fn name(&self) -> &str {
+        "reclaim"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["free", "reclaim"]
+    }
+
+    fn operations(&self) -> u32 {
+        RECLAIM_METHODS.len() as u32
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create reclaim directory")?;
+        self.file_len = std::fs::metadata(&self.source)
+            .context("Failed to stat source file")?
+            .len();
+        for method in RECLAIM_METHODS {
+            let files = self.files(method);
+            copy_without_reflink(&self.source, &files[0])?;
+            for target in &files[1..] {
+                BenchmarkRunner::create_reflink_blocking(&files[0], target)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        let method = RECLAIM_METHODS[index as usize];
+        let files = self.files(method);
+        filesystem::sync_filesystem(&self.dir)?;
+        let free_before = filesystem::free_space_bytes(&self.dir)?;
+        let wanted = free_before + self.file_len * 9 / 10;
+
+        let start = Instant::now();
+        for file in &files {
+            match method {
+                ReclaimMethod::Truncate => std::fs::OpenOptions::new()
+                    .write(true)
+                    .open(file)
+                    .and_then(|f| f.set_len(0))
+                    .context("Failed to truncate shared file")?,
+                ReclaimMethod::Unlink => {
+                    std::fs::remove_file(file).context("Failed to unlink shared file")?
+                }
+            }
+        }
+        let call_time = start.elapsed();
+
+        let mut free = filesystem::free_space_bytes(&self.dir)?;
+        while free < wanted && start.elapsed() < RECLAIM_TIMEOUT {
+            std::thread::sleep(RECLAIM_POLL_INTERVAL);
+            free = filesystem::free_space_bytes(&self.dir)?;
+        }
+        let reclaim_time = start.elapsed();
+
+        let mut metrics = self.metrics.lock().unwrap();
+        metrics.push((format!("{}_ms", method), call_time.as_secs_f64() * 1000.0));
+        metrics.push((
+            format!("{}_reclaim_ms", method),
+            reclaim_time.as_secs_f64() * 1000.0,
+        ));
+        metrics.push((
+            format!("{}_reclaimed_mb", method),
+            free.saturating_sub(free_before) as f64 / 1024.0 / 1024.0,
+        ));
+        if free < wanted {
+            metrics.push((format!("{}_timed_out", method), 1.0));
+        }
+        for file in &files {
+            let _ = std::fs::remove_file(file);
+        }
+
+        Ok(OpTiming {
+            reflink: call_time,
+            write: reclaim_time - call_time,
+        })
+    }
+
+    fn metrics(&self, _timings: &[OpTiming]) -> Vec<(String, f64)> {
+        self.metrics.lock().unwrap().clone()
+    }
+}
+
+/// Copies the source into a document, then per operation updates it
+/// atomically: clones it to a temp file, overwrites a random 4 KB block of
+/// the temp file, fsyncs it, renames it over the document, and fsyncs the
+/// directory so the rename is durable. Each update's clone shares extents
+/// with every earlier version's.
+struct AtomicReplace {
+    dir: PathBuf,
+    source: PathBuf,
+    count: u32,
+    seed: u64,
+    blocks: u64,
+    /// Each update's fsync, and rename + directory fsync, times
+    commits: Mutex<Vec<(Duration, Duration)>>,
+}
+
+impl AtomicReplace {
+    fn document(&self) -> PathBuf {
+        self.dir.join("document.dat")
+    }
+}
+
+impl Workload for AtomicReplace {
+    fn name(&self) -> &str {
+        "atomic-replace"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["clone", "commit"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.count
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create atomic-replace directory")?;
+        let document = self.document();
+        copy_without_reflink(&self.source, &document)?;
+        self.blocks = std::fs::metadata(&document)
+            .context("Failed to stat document")?
+            .len()
+            / 4096;
+        if self.blocks == 0 {
+            anyhow::bail!("Source file is too small for the atomic-replace workload");
+        }
+        filesystem::sync_filesystem(&self.dir)
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        use rand::Rng;
+        use std::os::unix::fs::FileExt;
+
+        let document = self.document();
+        let temp = self.dir.join(format!("document_{}.tmp", index));
+        let offset = benchmark::seeded_rng(self.seed, &format!("atomic-replace-{}", index))
+            .gen_range(0..self.blocks)
+            * 4096;
+        let block = [WRITE_PATTERN; 4096];
+        let dir = std::fs::File::open(&self.dir).context("Failed to open workload directory")?;
+
+        let start = Instant::now();
+        BenchmarkRunner::create_reflink_blocking(&document, &temp)
+            .context(format!("Failed to clone document for update {}", index))?;
+        let cloned = Instant::now();
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&temp)
+            .context("Failed to open temp file")?;
+        file.write_all_at(&block, offset)
+            .context("Failed to modify temp file")?;
+        let written = Instant::now();
+        file.sync_all().context("Failed to fsync temp file")?;
+        drop(file);
+        let synced = Instant::now();
+        std::fs::rename(&temp, &document).context("Failed to rename over document")?;
+        dir.sync_all()
+            .context("Failed to fsync workload directory")?;
+        let renamed = Instant::now();
+
+        self.commits
+            .lock()
+            .unwrap()
+            .push((synced - written, renamed - synced));
+        Ok(OpTiming {
+            reflink: cloned - start,
+            write: renamed - cloned,
+        })
+    }
+
+    fn metrics(&self, timings: &[OpTiming]) -> Vec<(String, f64)> {
+        let commits = self.commits.lock().unwrap();
+        let total =
+            LatencyStats::from_samples(&timings.iter().map(OpTiming::total).collect::<Vec<_>>());
+        let fsyncs = LatencyStats::from_samples(&commits.iter().map(|c| c.0).collect::<Vec<_>>());
+        let renames = LatencyStats::from_samples(&commits.iter().map(|c| c.1).collect::<Vec<_>>());
+        let us = |d: Duration| d.as_secs_f64() * 1e6;
+        vec![
+            ("update_p50_us".to_string(), us(total.p50)),
+            ("update_p99_us".to_string(), us(total.p99)),
+            ("update_max_us".to_string(), us(total.max)),
+            ("fsync_p50_us".to_string(), us(fsyncs.p50)),
+            ("rename_p50_us".to_string(), us(renames.p50)),
+        ]
+    }
+}
+
+/// The unaligned workload's writes as (name, offset, length): a whole
+/// aligned block as the baseline, a 512-byte sector, a 100-byte record at
+/// an odd offset, and a block straddling two blocks.
+const UNALIGNED_PATTERNS: [(&str, u64, usize); 4] = [
+    ("aligned_4k", 0, 4096),
+    ("sector_512", 0, 512),
+    ("record_100", 1, 100),
+    ("straddle_4k", 2048, 4096),
+];
+
+/// Splits its operations between [`UNALIGNED_PATTERNS`], in order. Each
+/// operation clones the source (untimed) and makes one write of its pattern
+/// into the clone, then fdatasyncs it. A fresh clone has nothing in the
+/// page cache, so a write that doesn't cover whole blocks has to read them
+/// from the shared extent first: the read-modify-write small-record
+/// databases pay.
+struct Unaligned {
+    dir: PathBuf,
+    source: PathBuf,
+    per_pattern: u32,
+}
+
+impl Workload for Unaligned {
+    fn name(&self) -> &str {
+        "unaligned"
+    }
+
+    fn steps(&self) -> &'static [&'static str] {
+        &["write", "sync"]
+    }
+
+    fn operations(&self) -> u32 {
+        self.per_pattern * UNALIGNED_PATTERNS.len() as u32
+    }
+
+    fn prepare(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create unaligned directory")
+    }
+
+    fn run(&self, index: u32) -> Result<OpTiming> {
+        use std::os::unix::fs::FileExt;
+
+        let (name, offset, len) = UNALIGNED_PATTERNS[(index / self.per_pattern) as usize];
+        let data = vec![WRITE_PATTERN; len];
+        let target = self
+            .dir
+            .join(format!("{}_{}.dat", name, index % self.per_pattern));
+        BenchmarkRunner::create_reflink_blocking(&self.source, &target)?;
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&target)
+            .context("Failed to open clone")?;
+
+        let start = Instant::now();
+        file.write_all_at(&data, offset)
+            .context("Failed to write to clone")?;
+        let written = Instant::now();
+        file.sync_data().context("Failed to fdatasync clone")?;
+        let synced = Instant::now();
+        Ok(OpTiming {
+            reflink: written - start,
+            write: synced - written,
+        })
+    }
+
+    fn metrics(&self, timings: &[OpTiming]) -> Vec<(String, f64)> {
+        let us = |d: Duration| d.as_secs_f64() * 1e6;
+        let mut metrics = Vec::new();
+        for ((name, _, _), samples) in UNALIGNED_PATTERNS
+            .iter()
+            .zip(timings.chunks(self.per_pattern as usize))
+        {
+            let writes =
+                LatencyStats::from_samples(&samples.iter().map(|t| t.reflink).collect::<Vec<_>>());
+            let syncs =
+                LatencyStats::from_samples(&samples.iter().map(|t| t.write).collect::<Vec<_>>());
+            metrics.push((format!("{}_write_p50_us", name), us(writes.p50)));
+            metrics.push((format!("{}_write_p99_us", name), us(writes.p99)));
+            metrics.push((format!("{}_sync_p50_us", name), us(syncs.p50)));
+        }
+        metrics
+    }
+}
+
+/// Copies `source` to a new `target` by reading and writing, since
+/// `std::fs::copy` may reflink and leave the copy sharing extents.
+fn copy_without_reflink(source: &Path, target: &Path) -> Result<()> {
+    let mut reader = std::fs::File::open(source).context("Failed to open source file")?;
+    let mut writer = std::fs::File::create(target)
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = std::io::Read::read(&mut reader, &mut buf).context("Failed to read source file")?;
+        if n == 0 {
+            break;
+        }
+        std::io::Write::write_all(&mut writer, &buf[..n])
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+    }
+    writer
+        .sync_all()
+        .with_context(|| format!("Failed to sync {}", target.display()))
+}
+
+/// Writes `data` to a new file and syncs it so the target has real extents.
+fn create_prefilled(path: &Path, data: &[u8]) -> Result<std::fs::File> {
+    use std::io::Write;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .context("Failed to create prefilled target")?;
+    file.write_all(data).context("Failed to prefill target")?;
+    file.sync_all().context("Failed to sync prefilled target")?;
+
+    Ok(file)
+}
+
+/// Clones all of `source` over `target` without truncating it first.
+fn clone_file(source: &std::fs::File, target: &std::fs::File) -> Result<()> {
+    ioctl::ficlone(target, source).context("Reflink operation failed")
+}
+
+/// Clones `len` bytes of `source` at `src_offset` into `target` at `dest_offset`.
+fn clone_range(
+    source: &std::fs::File,
+    src_offset: u64,
+    len: u64,
+    target: &std::fs::File,
+    dest_offset: u64,
+) -> Result<()> {
+    ioctl::ficlonerange(target, dest_offset, source, src_offset, len)
+        .context("Range reflink operation failed")
+}
+
+/// Shares the first `len` bytes of `target` with `source` if they match.
+fn dedupe_range(source: &std::fs::File, target: &std::fs::File, len: u64) -> Result<()> {
+    match ioctl::fideduperange(source, 0, len, target, 0).context("Dedupe operation failed")? {
+        Dedupe::Differs => anyhow::bail!("Dedupe operation failed: data differs from source"),
+        Dedupe::Shared(shared) if shared != len => {
+            anyhow::bail!("Dedupe operation only shared {} of {} bytes", shared, len)
+        }
+        Dedupe::Shared(_) => Ok(()),
+    }
+}
+
+fn overwrite_region(target: &Path, data: &[u8]) -> Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(target)
+        .context("Failed to open target file")?;
+    file.write_all_at(data, 0)
+        .context("Failed to write to target file")?;
+
+    Ok(())
+}
+
+fn fallocate(file: &std::fs::File, mode: libc::c_int, offset: u64, len: u64) -> Result<()> {
+    let result = unsafe {
+        libc::fallocate(
+            file.as_raw_fd(),
+            mode,
+            offset as libc::off_t,
+            len as libc::off_t,
+        )
+    };
+
+    if result != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(())
+}
+
+fn is_unsupported(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .and_then(|e| e.raw_os_error())
+        .is_some_and(|code| code == libc::EOPNOTSUPP || code == libc::EINVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operation_mix_parses_percentages() {
+        let mix: OperationMix = "60/30/10".parse().unwrap();
+        assert_eq!(
+            mix,
+            OperationMix {
+                reflink: 60,
+                write: 30,
+                read: 10
+            }
+        );
+        assert_eq!(mix.to_string(), "60/30/10");
+        assert_eq!(OperationMix::default().to_string(), "70/20/10");
+    }
+
+    #[test]
+    fn samples_report_latency_per_step() {
+        let workload = CloneOnly {
+            dir: PathBuf::new(),
+            sources: Vec::new(),
+            count: 4,
+        };
+        let timings: Vec<OpTiming> = (1..=4)
+            .map(|ms| OpTiming {
+                reflink: Duration::from_millis(ms),
+                write: Duration::ZERO,
+            })
+            .collect();
+
+        let result = WorkloadResult::from_samples(&workload, &timings, Duration::from_millis(10));
+        assert_eq!(result.workload, "clone-only");
+        assert_eq!(result.operations, 4);
+        assert_eq!(result.operations_per_sec, 400.0);
+        assert!(result.metrics.contains_key("reflink_p99_us"));
+        assert!(!result.metrics.keys().any(|key| key.starts_with("write")));
+    }
+
+    #[test]
+    fn samples_include_workload_metrics() {
+        let workload = AtomicReplace {
+            dir: PathBuf::new(),
+            source: PathBuf::new(),
+            count: 2,
+            seed: 0,
+            blocks: 1,
+            commits: Mutex::new(vec![
+                (Duration::from_millis(1), Duration::from_millis(2));
+                2
+            ]),
+        };
+        let timing = OpTiming {
+            reflink: Duration::from_millis(1),
+            write: Duration::from_millis(3),
+        };
+
+        let result =
+            WorkloadResult::from_samples(&workload, &[timing; 2], Duration::from_millis(8));
+        assert_eq!(result.metrics["clone_p50_us"], 1000.0);
+        assert_eq!(result.metrics["commit_p99_us"], 3000.0);
+        assert_eq!(result.metrics["update_p50_us"], 4000.0);
+        assert_eq!(result.metrics["rename_p50_us"], 2000.0);
+    }
+
+    #[test]
+    fn skipped_steps_are_left_out_of_percentiles() {
+        let workload = Database {
+            dir: PathBuf::new(),
+            source: PathBuf::new(),
+            count: 4,
+            seed: 0,
+            db: None,
+            pages: 1,
+            checkpoints: Mutex::default(),
+            first_writes: Mutex::default(),
+        };
+        let timings: Vec<OpTiming> = [0, 0, 0, 5]
+            .into_iter()
+            .map(|ms| OpTiming {
+                reflink: Duration::from_millis(1),
+                write: Duration::from_millis(ms),
+            })
+            .collect();
+
+        let result = WorkloadResult::from_samples(&workload, &timings, Duration::from_millis(10));
+        assert_eq!(result.metrics["checkpoint_p50_us"], 5000.0);
+        assert_eq!(result.metrics["checkpoints"], 1.0);
+        assert!(!result.metrics.contains_key("post_checkpoint_write_p50_us"));
+    }
+
+    #[test]
+    fn operation_mix_rejects_bad_input() {
+        assert!("70/20".parse::<OperationMix>().is_err());
+        assert!("70/20/20".parse::<OperationMix>().is_err());
+        assert!("a/b/c".parse::<OperationMix>().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Drives the library the way other tools would. Benchmarks themselves need
+//! root and real filesystems, so those tests are ignored by default; run
+//! them with `sudo -E cargo test -- --ignored`.
+
+use reflink_bench::{
+    compare, BenchmarkConfig, BenchmarkResult, BenchmarkRunner, FilesystemManager, FilesystemType,
+    ResultsReporter,
+};
+use std::path::PathBuf;
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "reflink-bench-api-test-{}-{}",
+        std::process::id(),
+        name
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn saved_results_load_back() {
+    let dir = scratch("results");
+    let path = dir.join("results.json");
+    let result = BenchmarkResult {
+        filesystem: "XFS".to_string(),
+        reflink_count: 10,
+        operations_per_sec: 250.0,
+        ..Default::default()
+    };
+
+    ResultsReporter::new(vec![(FilesystemType::Xfs, result)])
+        .with_seed(7)
+        .save_to_file(&path)
+        .unwrap();
+    let loaded = compare::load_results(&path).unwrap();
+
+    assert_eq!(loaded.seed, Some(7));
+    assert_eq!(loaded.results.len(), 1);
+    assert_eq!(loaded.results[0].1.operations_per_sec, 250.0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs root, losetup, and mkfs.xfs"]
+async fn benchmarks_a_loopback_xfs() {
+    let workdir = scratch("xfs");
+    let mut fs = FilesystemManager::new(FilesystemType::Xfs, 1, &workdir).unwrap();
+    fs.setup().await.unwrap();
+
+    let config = BenchmarkConfig {
+        file_size_mb: 4,
+        reflink_count: 20,
+        thread_counts: vec![1, 2],
+        verify: true,
+        ..BenchmarkConfig::default()
+    };
+    let result = BenchmarkRunner::new(fs.mount_point(), config)
+        .run_benchmark()
+        .await;
+    fs.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&workdir).unwrap();
+
+    let result = result.unwrap();
+    assert_eq!(result.reflink_latency.count, 20);
+    assert_eq!(result.concurrent_results.thread_counts, vec![1, 2]);
+    assert_eq!(result.verification.unwrap().files, 20 * 4);
+}
+
+#[tokio::test]
+#[ignore = "needs root, losetup, and mkfs.xfs"]
+async fn verifies_a_write_size_sweep() {
+    let workdir = scratch("xfs-write-sizes");
+    let mut fs = FilesystemManager::new(FilesystemType::Xfs, 1, &workdir).unwrap();
+    fs.setup().await.unwrap();
+
+    let config = BenchmarkConfig {
+        file_size_mb: 4,
+        reflink_count: 20,
+        thread_counts: vec![1],
+        write_sizes: vec![512, 65536],
+        verify: true,
+        ..BenchmarkConfig::default()
+    };
+    let result = BenchmarkRunner::new(fs.mount_point(), config)
+        .run_benchmark()
+        .await;
+    fs.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&workdir).unwrap();
+
+    let result = result.unwrap();
+    assert_eq!(result.write_size_results.len(), 2);
+    // Sequential, concurrent, and concurrent_1, then both write sizes
+    assert_eq!(result.verification.unwrap().files, 20 * 3 + 20 * 2);
+}